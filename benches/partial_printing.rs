@@ -0,0 +1,131 @@
+//! Benchmarks for printing part of a large document, and for comparison, all of it.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use partial_pretty_printer::testing::{make_deep_doc, make_flow_doc, make_wide_doc};
+use partial_pretty_printer::{
+    pretty_print, pretty_print_to_string, DownwardPrinter, FocusTarget, Line, PrettyDoc,
+    PrintingError, UpwardPrinter, Width,
+};
+
+const WIDTH: Width = 80;
+
+/// Print `num_lines` lines centered on the start of the node at `path`.
+fn print_lines<'d, D: PrettyDoc<'d>>(doc: D, path: &[usize], num_lines: usize) -> usize {
+    let (upward_printer, focused_line, downward_printer) =
+        pretty_print(doc, WIDTH, path, FocusTarget::Start, None).unwrap();
+    let mut width = focused_line.width() as usize;
+    for line in upward_printer.take(num_lines / 2) {
+        width += line.unwrap().width() as usize;
+    }
+    for line in downward_printer.take(num_lines - num_lines / 2 - 1) {
+        width += line.unwrap().width() as usize;
+    }
+    width
+}
+
+/// Seek to the start of the node at `path`, and return the printer for the lines above it.
+fn seek_up<'d, D: PrettyDoc<'d>>(doc: D, path: &[usize]) -> UpwardPrinter<'d, D> {
+    pretty_print(doc, WIDTH, path, FocusTarget::Start, None)
+        .unwrap()
+        .0
+}
+
+/// Seek to the start of the node at `path`, and return the printer for the lines below it.
+fn seek_down<'d, D: PrettyDoc<'d>>(doc: D, path: &[usize]) -> DownwardPrinter<'d, D> {
+    pretty_print(doc, WIDTH, path, FocusTarget::Start, None)
+        .unwrap()
+        .2
+}
+
+/// Print `num_lines` lines from one of the printers returned by `seek_up` or `seek_down`.
+fn print_lines_from<'d, D: PrettyDoc<'d>>(
+    printer: impl Iterator<Item = Result<Line<'d, D>, PrintingError<D::Error>>>,
+    num_lines: usize,
+) -> usize {
+    printer
+        .take(num_lines)
+        .map(|line| line.unwrap().width() as usize)
+        .sum()
+}
+
+fn bench_wide(c: &mut Criterion) {
+    let doc = make_wide_doc(100_000);
+    c.bench_function("50 lines from the middle of a 100k line doc", |b| {
+        b.iter(|| print_lines(black_box(&doc), &[50_000], 50))
+    });
+    c.bench_function("50 lines from the end of a 100k line doc", |b| {
+        b.iter(|| print_lines(black_box(&doc), &[99_999], 50))
+    });
+
+    let doc = make_wide_doc(10_000);
+    c.bench_function("all of a 10k line doc", |b| {
+        b.iter(|| pretty_print_to_string(black_box(&doc), WIDTH).unwrap())
+    });
+}
+
+fn bench_deep(c: &mut Criterion) {
+    let depth = 1_000;
+    let doc = make_deep_doc(depth);
+    let path = vec![1; 2 * depth];
+    c.bench_function("50 lines from the bottom of a 1k deep doc", |b| {
+        b.iter(|| print_lines(black_box(&doc), &path, 50))
+    });
+}
+
+fn bench_flow(c: &mut Criterion) {
+    let doc = make_flow_doc(10_000);
+    c.bench_function("50 lines from the start of a 10k word flow-wrapped doc", |b| {
+        b.iter(|| print_lines(black_box(&doc), &[], 50))
+    });
+}
+
+/// Compare the throughput of printing upward and downward from the same focus. Only printing the
+/// lines is timed: seeking to the focus happens in the untimed setup of each iteration.
+fn bench_up_vs_down(c: &mut Criterion) {
+    let doc = make_wide_doc(100_000);
+    c.bench_function("2000 lines up from the middle of a 100k line doc", |b| {
+        b.iter_batched(
+            || seek_up(&doc, &[50_000]),
+            |printer| print_lines_from(printer, 2000),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("2000 lines down from the middle of a 100k line doc", |b| {
+        b.iter_batched(
+            || seek_down(&doc, &[50_000]),
+            |printer| print_lines_from(printer, 2000),
+            BatchSize::SmallInput,
+        )
+    });
+
+    // Half as many ones as the path to the innermost object leads to the object nested halfway
+    // down. A third of the doc's lines (1000 of them) are above its start, and the rest below.
+    let depth = 1_000;
+    let doc = make_deep_doc(depth);
+    let path = vec![1; depth];
+    c.bench_function("1000 lines up from halfway into a 1k deep doc", |b| {
+        b.iter_batched(
+            || seek_up(&doc, &path),
+            |printer| print_lines_from(printer, 1000),
+            BatchSize::SmallInput,
+        )
+    });
+    c.bench_function("1000 lines down from halfway into a 1k deep doc", |b| {
+        b.iter_batched(
+            || seek_down(&doc, &path),
+            |printer| print_lines_from(printer, 1000),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_wide,
+    bench_deep,
+    bench_flow,
+    bench_up_vs_down
+);
+criterion_main!(benches);
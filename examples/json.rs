@@ -52,31 +52,77 @@ fn pretty_print_json(doc: &Json, width: Width) -> Vec<Line<&Json>> {
 
 fn lines_to_string<'d>(lines: Vec<Line<'d, &'d Json>>) -> Result<String, fmt::Error> {
     use std::fmt::Write;
-    use termion::color;
-    use Color::*;
+    use termion::{color, style};
 
     let mut string = String::new();
     let w = &mut string;
     for line in lines {
+        let mut current_link = None;
         for segment in line.segments {
-            match segment.style.color {
-                White => write!(w, "{}", color::Fg(color::White))?,
-                Black => write!(w, "{}", color::Fg(color::Black))?,
-                Red => write!(w, "{}", color::Fg(color::Red))?,
-                Green => write!(w, "{}", color::Fg(color::Green))?,
-                Yellow => write!(w, "{}", color::Fg(color::Yellow))?,
-                Blue => write!(w, "{}", color::Fg(color::Blue))?,
-                Magenta => write!(w, "{}", color::Fg(color::Magenta))?,
-                Cyan => write!(w, "{}", color::Fg(color::Cyan))?,
-            };
+            if segment.link != current_link {
+                write_link(w, segment.link)?;
+                current_link = segment.link;
+            }
+            write!(w, "{}", style::Reset)?;
+            write_color(w, segment.style.color, false)?;
+            if let Some(background) = segment.style.background {
+                write_color(w, background, true)?;
+            }
+            if segment.style.bold {
+                write!(w, "{}", style::Bold)?;
+            }
+            if segment.style.italic {
+                write!(w, "{}", style::Italic)?;
+            }
+            if segment.style.strikethrough {
+                write!(w, "{}", style::CrossedOut)?;
+            }
             write!(w, "{}", segment.str)?;
         }
+        if current_link.is_some() {
+            write_link(w, None)?;
+        }
         writeln!(w)?;
     }
-    write!(w, "{}", color::Fg(color::Reset))?;
+    write!(w, "{}{}", style::Reset, color::Fg(color::Reset))?;
     Ok(string)
 }
 
+/// Write the OSC 8 terminal escape code for starting a hyperlink to `target`, or for ending the
+/// current hyperlink if `target` is `None`.
+fn write_link(w: &mut String, target: Option<&str>) -> fmt::Result {
+    use std::fmt::Write;
+
+    write!(w, "\x1b]8;;{}\x1b\\", target.unwrap_or(""))
+}
+
+/// Write the terminal escape code for setting the foreground (or background) color.
+fn write_color(w: &mut String, color: Color, is_background: bool) -> Result<(), fmt::Error> {
+    use std::fmt::Write;
+    use termion::color as c;
+    use Color::*;
+
+    fn write<C: c::Color>(w: &mut String, color: C, is_background: bool) -> fmt::Result {
+        if is_background {
+            write!(w, "{}", c::Bg(color))
+        } else {
+            write!(w, "{}", c::Fg(color))
+        }
+    }
+
+    match color {
+        White => write(w, c::White, is_background),
+        Black => write(w, c::Black, is_background),
+        Red => write(w, c::Red, is_background),
+        Green => write(w, c::Green, is_background),
+        Yellow => write(w, c::Yellow, is_background),
+        Blue => write(w, c::Blue, is_background),
+        Magenta => write(w, c::Magenta, is_background),
+        Cyan => write(w, c::Cyan, is_background),
+        Rgb(r, g, b) => write(w, c::Rgb(r, g, b), is_background),
+    }
+}
+
 /// Like `.unwrap()`, but prints errors with Display.
 fn unwrap<T, E: fmt::Display>(result: Result<T, E>) -> T {
     match result {
@@ -0,0 +1,171 @@
+//! A small JSON formatter: read JSON from stdin, and pretty print it to stdout.
+//!
+//! ```text
+//! > cargo run --example ppp-json -- --width 60 --color < examples/pokemon.json
+//! > cargo run --example ppp-json -- --focus 0.3 --context 5 < examples/pokemon.json
+//! ```
+
+use clap::Parser;
+use partial_pretty_printer::doc_examples::json::{
+    json_array, json_bool, json_null, json_number, json_object, json_object_pair, json_string, Json,
+};
+use partial_pretty_printer::doc_examples::Color;
+use partial_pretty_printer::{pretty_print, FocusTarget, Line, Width};
+use std::fmt;
+use std::io;
+use std::process;
+
+/// Pretty print JSON read from stdin.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct CommandLineArgs {
+    /// Maximum line width.
+    #[arg(short, long, default_value_t = 80)]
+    width: Width,
+    /// Color the output using ANSI escape codes.
+    #[arg(short, long)]
+    color: bool,
+    /// Only print the lines around the start of this node, given as a sequence of child indices
+    /// separated by dots, like `2.0.1`. (Object entries are key/value pairs, so `0.1` is the value
+    /// of the first entry of an object.)
+    #[arg(short, long)]
+    focus: Option<String>,
+    /// With `--focus`, how many lines to print above and below the focused line.
+    #[arg(long, default_value_t = 10)]
+    context: usize,
+}
+
+fn json_to_doc(json: serde_json::Value) -> Json {
+    use serde_json::Value::{Array, Bool, Null, Number, Object, String};
+
+    match json {
+        Null => json_null(),
+        Bool(b) => json_bool(b),
+        Number(n) => json_number(n.as_f64().expect("Failed to convert number to f64")),
+        String(s) => json_string(&s),
+        Array(elems) => json_array(elems.into_iter().map(json_to_doc).collect::<Vec<_>>()),
+        Object(entries) => json_object(
+            entries
+                .into_iter()
+                .map(|(key, val)| json_object_pair(&key, json_to_doc(val)))
+                .collect::<Vec<_>>(),
+        ),
+    }
+}
+
+/// Parse a path like `2.0.1`. The empty string is the path to the root.
+fn parse_path(path: &str) -> Result<Vec<usize>, String> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    path.split('.')
+        .map(|index| {
+            index
+                .parse::<usize>()
+                .map_err(|_| format!("Invalid child index '{}' in path '{}'", index, path))
+        })
+        .collect()
+}
+
+/// Print `context` lines above and below the start of the node at `path` (or the whole document,
+/// if `context` is `None`).
+fn print_lines<'d>(
+    doc: &'d Json,
+    width: Width,
+    path: &[usize],
+    context: Option<usize>,
+) -> Vec<Line<'d, &'d Json>> {
+    let (prev_lines, focused_line, next_lines) =
+        unwrap(pretty_print(doc, width, path, FocusTarget::Start, None));
+    let context = context.unwrap_or(usize::MAX);
+    let mut lines = prev_lines.take(context).map(unwrap).collect::<Vec<_>>();
+    lines.reverse();
+    lines.push(Line::from(focused_line));
+    lines.extend(next_lines.take(context).map(unwrap));
+    lines
+}
+
+fn write_line<'d>(w: &mut String, line: Line<'d, &'d Json>, color: bool) -> fmt::Result {
+    use std::fmt::Write;
+    use termion::style;
+
+    if !color {
+        w.push_str(&line.to_string());
+        w.push('\n');
+        return Ok(());
+    }
+    for segment in line.segments {
+        write!(w, "{}", style::Reset)?;
+        write_color(w, segment.style.color, false)?;
+        if let Some(background) = segment.style.background {
+            write_color(w, background, true)?;
+        }
+        if segment.style.bold {
+            write!(w, "{}", style::Bold)?;
+        }
+        if segment.style.italic {
+            write!(w, "{}", style::Italic)?;
+        }
+        if segment.style.strikethrough {
+            write!(w, "{}", style::CrossedOut)?;
+        }
+        write!(w, "{}", segment.str)?;
+    }
+    writeln!(w, "{}", style::Reset)
+}
+
+/// Write the terminal escape code for setting the foreground (or background) color.
+fn write_color(w: &mut String, color: Color, is_background: bool) -> fmt::Result {
+    use std::fmt::Write;
+    use termion::color as c;
+    use Color::*;
+
+    fn write<C: c::Color>(w: &mut String, color: C, is_background: bool) -> fmt::Result {
+        if is_background {
+            write!(w, "{}", c::Bg(color))
+        } else {
+            write!(w, "{}", c::Fg(color))
+        }
+    }
+
+    match color {
+        White => write(w, c::White, is_background),
+        Black => write(w, c::Black, is_background),
+        Red => write(w, c::Red, is_background),
+        Green => write(w, c::Green, is_background),
+        Yellow => write(w, c::Yellow, is_background),
+        Blue => write(w, c::Blue, is_background),
+        Magenta => write(w, c::Magenta, is_background),
+        Cyan => write(w, c::Cyan, is_background),
+        Rgb(r, g, b) => write(w, c::Rgb(r, g, b), is_background),
+    }
+}
+
+/// Like `.unwrap()`, but prints errors with Display.
+fn unwrap<T, E: fmt::Display>(result: Result<T, E>) -> T {
+    match result {
+        Ok(ok) => ok,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn main() {
+    let args = CommandLineArgs::parse();
+    let path = match &args.focus {
+        Some(path) => unwrap(parse_path(path)),
+        None => Vec::new(),
+    };
+    let context = args.focus.as_ref().map(|_| args.context);
+
+    let json = unwrap(serde_json::from_reader(io::stdin().lock()));
+    let doc = json_to_doc(json);
+
+    let mut output = String::new();
+    for line in print_lines(&doc, args.width, &path, context) {
+        unwrap(write_line(&mut output, line, args.color));
+    }
+    print!("{}", output);
+}
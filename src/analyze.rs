@@ -0,0 +1,368 @@
+//! Static analysis of a single notation, for auditing complex (often generated) notations without
+//! needing a document to print.
+
+use crate::geometry::str_width;
+use crate::{Condition, Notation, StyleLabel, ValidNotation, Width};
+use alloc::vec::Vec;
+
+/// What [`analyze()`] found out about a notation. This only covers the notation itself: the
+/// notations of its children are unknown, so it assumes that they could print anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotationReport {
+    /// One entry for every [`Notation::Choice`], in the order they appear in the notation (a
+    /// pre-order traversal, with each choice's first option before its second).
+    pub choices: Vec<ChoiceReport>,
+    /// The largest number of nested [`Notation::Indent`]s, or `None` if it's unbounded (because a
+    /// `Fold`'s `join` indents its [`Left`](Notation::Left)).
+    pub max_indent_depth: Option<usize>,
+    /// The largest total width of nested [`Notation::Indent`] strings, or `None` if it's
+    /// unbounded.
+    pub max_indent_width: Option<Width>,
+    /// Whether an [`EndOfLine`](Notation::EndOfLine) can be followed by a child. If that child's
+    /// first line contains text, printing will fail with
+    /// [`PrintingError::TextAfterEndOfLine`](crate::PrintingError::TextAfterEndOfLine).
+    pub eol_before_child: bool,
+    /// Whether the notation can end with an [`EndOfLine`](Notation::EndOfLine). If so, it will
+    /// conflict with any text that follows it in its parent's notation.
+    pub may_end_with_eol: bool,
+}
+
+/// What [`analyze()`] found out about a single [`Notation::Choice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChoiceReport {
+    /// The smallest printing width at which the first option could ever be chosen, or `None` if
+    /// it never can be (because the choice is itself inside an option that's never chosen). Inside
+    /// a [`Notation::Flat`], the first option is always chosen, so this is `Some(0)`.
+    pub first_min_width: Option<Width>,
+    /// Whether the second option could ever be chosen. It can't be if the choice is inside a
+    /// [`Notation::Flat`], or inside an option that's never chosen.
+    pub second_reachable: bool,
+}
+
+/// Which option of a [`Notation::Choice`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChoiceOption {
+    First,
+    Second,
+}
+
+impl NotationReport {
+    /// The options that can never be chosen, as `(index into choices, option)`.
+    pub fn unreachable_options(&self) -> Vec<(usize, ChoiceOption)> {
+        let mut unreachable = Vec::new();
+        for (i, choice) in self.choices.iter().enumerate() {
+            if choice.first_min_width.is_none() {
+                unreachable.push((i, ChoiceOption::First));
+            }
+            if !choice.second_reachable {
+                unreachable.push((i, ChoiceOption::Second));
+            }
+        }
+        unreachable
+    }
+}
+
+/// Analyze a notation, to find out which options of its [`Notation::Choice`]s can be taken at
+/// which widths, how deeply it indents, and whether its [`EndOfLine`](Notation::EndOfLine)s can
+/// cause printing errors. All widths are lower bounds, computed by assuming that every text and
+/// child is empty.
+pub fn analyze<L: StyleLabel, C: Condition>(notation: &ValidNotation<L, C>) -> NotationReport {
+    let mut analyzer = Analyzer {
+        choices: Vec::new(),
+        max_indent_depth: Some(0),
+        max_indent_width: Some(0),
+        eol_before_child: false,
+    };
+    let ctx = Context {
+        flat: false,
+        reachable: true,
+        indent_depth: 0,
+        indent_width: 0,
+        indents_in_join: false,
+    };
+    let may_end_with_eol = analyzer.walk(&notation.0, ctx, Some(0), Some(0), false);
+    NotationReport {
+        choices: analyzer.choices,
+        max_indent_depth: analyzer.max_indent_depth,
+        max_indent_width: analyzer.max_indent_width,
+        eol_before_child: analyzer.eol_before_child,
+        may_end_with_eol,
+    }
+}
+
+struct Analyzer {
+    choices: Vec<ChoiceReport>,
+    max_indent_depth: Option<usize>,
+    max_indent_width: Option<Width>,
+    eol_before_child: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Context {
+    /// Whether we're inside a `Flat`.
+    flat: bool,
+    /// Whether this part of the notation can ever be displayed.
+    reachable: bool,
+    indent_depth: usize,
+    indent_width: Width,
+    /// Whether there's an `Indent` between here and the enclosing `Fold.join`.
+    indents_in_join: bool,
+}
+
+/// Lower bounds on the widths of a notation's lines. Each is `None` if the notation can't be laid
+/// out that way.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    /// The width, if it's laid out on a single line.
+    flat: Option<Width>,
+    /// The width of the first line, if it's laid out on multiple lines.
+    first: Option<Width>,
+    /// The width of the last line, if it's laid out on multiple lines.
+    last: Option<Width>,
+}
+
+fn add(a: Option<Width>, b: Option<Width>) -> Option<Width> {
+    Some(a?.saturating_add(b?))
+}
+
+fn min(a: Option<Width>, b: Option<Width>) -> Option<Width> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+impl Span {
+    fn flat(width: Width) -> Span {
+        Span {
+            flat: Some(width),
+            first: None,
+            last: None,
+        }
+    }
+
+    /// Could be anything, like a child.
+    fn unknown() -> Span {
+        Span {
+            flat: Some(0),
+            first: Some(0),
+            last: Some(0),
+        }
+    }
+
+    fn concat(self, other: Span) -> Span {
+        Span {
+            flat: add(self.flat, other.flat),
+            first: min(self.first, add(self.flat, other.first)),
+            last: min(other.last, add(self.last, other.flat)),
+        }
+    }
+
+    fn choice(self, other: Span) -> Span {
+        Span {
+            flat: min(self.flat, other.flat),
+            first: min(self.first, other.first),
+            last: min(self.last, other.last),
+        }
+    }
+
+    /// The width of the first line, if it's followed by `suffix` more columns.
+    fn first_line(self, suffix: Option<Width>) -> Option<Width> {
+        min(self.first, add(self.flat, suffix))
+    }
+
+    /// The width of the last line, if it's preceded by `prefix` more columns.
+    fn last_line(self, prefix: Option<Width>) -> Option<Width> {
+        min(self.last, add(prefix, self.flat))
+    }
+}
+
+fn span<L: StyleLabel, C: Condition>(
+    notation: &Notation<L, C>,
+    flat: bool,
+    indent_width: Width,
+) -> Span {
+    use Notation::*;
+
+    match notation {
+        Empty | EndOfLine | FocusMark | Space | PadTo(_) => Span::flat(0),
+        Literal(lit) => Span::flat(str_width(lit)),
+        Newline | VerticalSpace(..) => Span {
+            flat: None,
+            first: Some(0),
+            last: Some(indent_width),
+        },
+        Text | Placeholder(_) => Span::flat(0),
+        BreakableText { indent, .. } => Span::flat(0).choice(Span {
+            flat: None,
+            first: Some(0),
+            last: Some(indent_width.saturating_add(str_width(indent))),
+        }),
+        HiddenCount => Span::flat(1),
+        Child(_) | Left | Right | Fold { .. } | Columns { .. } => Span::unknown(),
+        Flat(note) => span(note, true, indent_width),
+        Indent(prefix, _, note) => span(note, flat, indent_width.saturating_add(str_width(prefix))),
+        Style(_, note)
+        | DepthStyle(_, note)
+        | Link(_, note)
+        | MapText(_, note)
+        | Cache(note)
+        | WithWidth(_, note) => span(note, flat, indent_width),
+        Concat(note1, note2) => {
+            span(note1, flat, indent_width).concat(span(note2, flat, indent_width))
+        }
+        Choice(note1, _) if flat => span(note1, flat, indent_width),
+        Choice(note1, note2)
+        | Check(_, _, note1, note2)
+        | CheckJoin(_, note1, note2)
+        | CheckExpr(_, note1, note2) => {
+            span(note1, flat, indent_width).choice(span(note2, flat, indent_width))
+        }
+        Summarize {
+            notation, summary, ..
+        } => {
+            let span1 = span(notation, flat, indent_width);
+            span1.choice(span1.concat(span(summary, flat, indent_width)))
+        }
+        Count { zero, one, many } => span(zero, flat, indent_width)
+            .choice(span(one, flat, indent_width))
+            .choice(span(many, flat, indent_width)),
+        Arity { cases, fallback } => cases.iter().fold(
+            span(fallback, flat, indent_width),
+            |span_so_far, (_, note)| span_so_far.choice(span(note, flat, indent_width)),
+        ),
+    }
+}
+
+impl Analyzer {
+    /// Walk the notation, where `prefix` is the least width of its line that comes before it,
+    /// `suffix` is the least width of its line that comes after it, and `eol` is whether it could
+    /// be preceded by an `EndOfLine`. Returns whether it could end with an `EndOfLine`.
+    fn walk<L: StyleLabel, C: Condition>(
+        &mut self,
+        notation: &Notation<L, C>,
+        ctx: Context,
+        prefix: Option<Width>,
+        suffix: Option<Width>,
+        eol: bool,
+    ) -> bool {
+        use Notation::*;
+
+        match notation {
+            Empty | FocusMark | Space | PadTo(_) => eol,
+            EndOfLine => true,
+            Literal(_)
+            | Text
+            | BreakableText { .. }
+            | Placeholder(_)
+            | HiddenCount
+            | Newline
+            | VerticalSpace(..) => false,
+            Child(_) | Right | Columns { .. } => {
+                self.eol_before_child |= eol && ctx.reachable;
+                false
+            }
+            Left => {
+                self.eol_before_child |= eol && ctx.reachable;
+                if ctx.indents_in_join {
+                    self.max_indent_depth = None;
+                    self.max_indent_width = None;
+                }
+                false
+            }
+            Flat(note) => {
+                let ctx = Context { flat: true, ..ctx };
+                self.walk(note, ctx, prefix, suffix, eol)
+            }
+            Indent(string, _, note) => {
+                let ctx = Context {
+                    indent_depth: ctx.indent_depth + 1,
+                    indent_width: ctx.indent_width.saturating_add(str_width(string)),
+                    indents_in_join: true,
+                    ..ctx
+                };
+                if ctx.reachable {
+                    self.max_indent_depth = self.max_indent_depth.map(|d| d.max(ctx.indent_depth));
+                    self.max_indent_width = self.max_indent_width.map(|w| w.max(ctx.indent_width));
+                }
+                self.walk(note, ctx, prefix, suffix, eol)
+            }
+            Style(_, note)
+            | DepthStyle(_, note)
+            | Link(_, note)
+            | MapText(_, note)
+            | Cache(note)
+            | WithWidth(_, note) => self.walk(note, ctx, prefix, suffix, eol),
+            Concat(note1, note2) => {
+                let span1 = span(note1, ctx.flat, ctx.indent_width);
+                let span2 = span(note2, ctx.flat, ctx.indent_width);
+                let eol = self.walk(note1, ctx, prefix, span2.first_line(suffix), eol);
+                self.walk(note2, ctx, span1.last_line(prefix), suffix, eol)
+            }
+            Choice(note1, note2) => {
+                let first_min_width = if ctx.reachable && ctx.flat {
+                    Some(0)
+                } else if ctx.reachable {
+                    let span1 = span(note1, ctx.flat, ctx.indent_width);
+                    add(prefix, span1.first_line(suffix))
+                } else {
+                    None
+                };
+                let second_reachable = ctx.reachable && !ctx.flat;
+                self.choices.push(ChoiceReport {
+                    first_min_width,
+                    second_reachable,
+                });
+                let ctx1 = Context {
+                    reachable: first_min_width.is_some(),
+                    ..ctx
+                };
+                let ctx2 = Context {
+                    reachable: second_reachable,
+                    ..ctx
+                };
+                let eol1 = self.walk(note1, ctx1, prefix, suffix, eol);
+                let eol2 = self.walk(note2, ctx2, prefix, suffix, eol);
+                (eol1 && ctx1.reachable) || (eol2 && ctx2.reachable)
+            }
+            Check(_, _, note1, note2) | CheckJoin(_, note1, note2) | CheckExpr(_, note1, note2) => {
+                let eol1 = self.walk(note1, ctx, prefix, suffix, eol);
+                let eol2 = self.walk(note2, ctx, prefix, suffix, eol);
+                eol1 || eol2
+            }
+            Summarize {
+                notation, summary, ..
+            } => {
+                let span1 = span(notation, ctx.flat, ctx.indent_width);
+                let eol1 = self.walk(notation, ctx, prefix, suffix, eol);
+                let eol2 = self.walk(summary, ctx, span1.last_line(prefix), suffix, eol1);
+                eol1 || eol2
+            }
+            Count { zero, one, many } => {
+                let eol1 = self.walk(zero, ctx, prefix, suffix, eol);
+                let eol2 = self.walk(one, ctx, prefix, suffix, eol);
+                let eol3 = self.walk(many, ctx, prefix, suffix, eol);
+                eol1 || eol2 || eol3
+            }
+            Arity { cases, fallback } => {
+                let mut eol_any = self.walk(fallback, ctx, prefix, suffix, eol);
+                for (_, note) in cases {
+                    eol_any |= self.walk(note, ctx, prefix, suffix, eol);
+                }
+                eol_any
+            }
+            Fold { first, join } => {
+                // The fold could have no children, and both `first` and `join` can be followed by
+                // more `join`s, which could start with anything.
+                let eol1 = self.walk(first, ctx, prefix, Some(0), eol);
+                let join_ctx = Context {
+                    indents_in_join: false,
+                    ..ctx
+                };
+                let eol2 = self.walk(join, join_ctx, prefix, Some(0), eol);
+                eol || eol1 || eol2
+            }
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Assertions for testing notations, exported from the `testing` module. They panic with a
+//! description of the difference if the printed document isn't as expected.
+
+use crate::{
+    oracle::oracular_pretty_print, pretty_print, pretty_print_to_string, FocusTarget, PrettyDoc,
+    Width,
+};
+
+#[track_caller]
+fn compare_lines(message: &str, expected: (&'static str, String), actual: (&'static str, String)) {
+    if actual.1 != expected.1 {
+        eprintln!(
+            "{}\n{}:\n{}\n{}:\n{}\n=========",
+            message, expected.0, expected.1, actual.0, actual.1,
+        );
+        assert_eq!(actual, expected);
+    }
+}
+
+fn print_above_and_below<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+) -> (Vec<String>, String, String, Vec<String>) {
+    let (upward_printer, focused_line, downward_printer) =
+        pretty_print(doc, width, path, focus_target, None).expect("Failed to pretty print");
+    let mut lines_above = upward_printer
+        .map(|line| line.expect("Failed to pretty print").to_string())
+        .collect::<Vec<_>>();
+    lines_above.reverse();
+    let left_string = focused_line.to_left_string();
+    let right_string = focused_line.to_right_string();
+    let lines_below = downward_printer
+        .map(|line| line.expect("Failed to pretty print").to_string())
+        .collect::<Vec<_>>();
+    (lines_above, left_string, right_string, lines_below)
+}
+
+fn concat_lines(
+    lines_above: Vec<String>,
+    left_string: String,
+    right_string: String,
+    lines_below: Vec<String>,
+) -> Vec<String> {
+    let mut lines = lines_above;
+    let mut center_line = left_string;
+    center_line.push_str(&right_string);
+    lines.push(center_line);
+    lines.extend(lines_below);
+    lines
+}
+
+/// The path to every node in the document, in depth-first order, starting with the root's (empty)
+/// path.
+pub fn all_paths<'d, D: PrettyDoc<'d>>(doc: D) -> Vec<Vec<usize>> {
+    fn recur<'d, D: PrettyDoc<'d>>(doc: D, path: &mut Vec<usize>, paths: &mut Vec<Vec<usize>>) {
+        paths.push(path.clone());
+        let num_children = doc
+            .num_children()
+            .expect("Failed to get num_children")
+            .unwrap_or(0);
+        for i in 0..num_children {
+            path.push(i);
+            recur(
+                doc.unwrap_child(i).expect("Failed to get child"),
+                path,
+                paths,
+            );
+            path.pop();
+        }
+    }
+    let mut paths = vec![];
+    recur(doc, &mut vec![], &mut paths);
+    paths
+}
+
+/// Print `rows` lines of the document around the focus: `rows / 2` lines above the focus line (or
+/// as many as there are), the focus line itself, and the rest below it.
+pub fn print_region<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    rows: usize,
+) -> Vec<String> {
+    let (upward_printer, focused_line, downward_printer) =
+        pretty_print(doc, width, path, focus_target, None).expect("Failed to pretty print");
+    let mut lines = upward_printer
+        .map(|line| line.expect("Failed to pretty print").to_string())
+        .take(rows / 2)
+        .collect::<Vec<_>>();
+    lines.reverse();
+    lines.push(focused_line.to_string());
+    let mut lines_below = downward_printer
+        .map(|line| line.expect("Failed to pretty print").to_string())
+        .take(rows.saturating_sub(rows / 2 + 1))
+        .collect::<Vec<_>>();
+    lines.append(&mut lines_below);
+    lines
+}
+
+/// Assert that the document prints as `expected_lines` at the given `width`. This checks the
+/// expectation against a simple (but slow) reference implementation of the printer first, so a
+/// wrong expectation is reported as such. Then it checks that printing the whole document agrees,
+/// and that so does printing it with the focus at the start and at the end of every node.
+#[track_caller]
+pub fn assert_pp<'d, D: PrettyDoc<'d>>(doc: D, width: Width, expected_lines: &[&str]) {
+    assert_pp_impl(doc, width, Some(expected_lines));
+}
+
+/// Like [`assert_pp()`], but without an expectation: just check that the printer agrees with the
+/// reference implementation, everywhere. Useful for randomized testing.
+#[track_caller]
+pub fn assert_pp_without_expectation<'d, D: PrettyDoc<'d>>(doc: D, width: Width) {
+    assert_pp_impl(doc, width, None)
+}
+
+#[track_caller]
+fn assert_pp_impl<'d, D: PrettyDoc<'d>>(doc: D, width: Width, expected_lines: Option<&[&str]>) {
+    let oracle_result = oracular_pretty_print(doc, width);
+    if let Some(expected_lines) = expected_lines {
+        compare_lines(
+            "ORACLE DISAGREES WITH TEST CASE, SO TEST CASE MUST BE WRONG",
+            ("ORACLE", oracle_result.clone()),
+            ("TEST CASE", expected_lines.join("\n")),
+        );
+    }
+    let lines = pretty_print_to_string(doc, width)
+        .expect("Failed to pretty print")
+        .split('\n')
+        .map(|s| s.to_owned())
+        .collect::<Vec<_>>();
+    if expected_lines.is_none() {
+        compare_lines(
+            &format!(
+                "IN PRETTY PRINTING WITH WIDTH {}\nNOTATION\n{}",
+                width,
+                doc.notation().expect("Failed to get notation")
+            ),
+            ("ORACLE", oracle_result.clone()),
+            ("ACTUAL", lines.join("\n")),
+        );
+    } else {
+        compare_lines(
+            &format!("IN PRETTY PRINTING WITH WIDTH {}", width),
+            ("EXPECTED", oracle_result.clone()),
+            ("ACTUAL", lines.join("\n")),
+        );
+    }
+    for path in all_paths(doc) {
+        for focus_target in [FocusTarget::Start, FocusTarget::End] {
+            let (lines_above, left_string, right_string, lines_below) =
+                print_above_and_below(doc, width, &path, focus_target);
+            let lines = concat_lines(lines_above, left_string, right_string, lines_below);
+            compare_lines(
+                &format!(
+                    "IN PRETTY PRINTING AT PATH {:?} (focus_target={:?})",
+                    path, focus_target
+                ),
+                ("EXPECTED", oracle_result.clone()),
+                ("ACTUAL", lines.join("\n")),
+            );
+        }
+    }
+}
+
+/// Assert where the node at `path` is in the printed document. The `expected_lines` are the
+/// printed document with a `(` inserted at the start of the node and a `)` at its end. This also
+/// checks that focusing on the start and the end of the node prints the same document.
+#[track_caller]
+pub fn assert_pp_seek<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    expected_lines: &[&str],
+) {
+    let (lines_above_1, left_string_1, right_string_1, lines_below_1) =
+        print_above_and_below(doc, width, path, FocusTarget::Start);
+    let (lines_above_2, left_string_2, right_string_2, lines_below_2) =
+        print_above_and_below(doc, width, path, FocusTarget::End);
+    let start_row = lines_above_1.len();
+    let end_row = lines_above_2.len();
+    let start_col = left_string_1.len();
+    let end_col = left_string_2.len();
+
+    let lines_1 = concat_lines(lines_above_1, left_string_1, right_string_1, lines_below_1);
+    let lines_2 = concat_lines(lines_above_2, left_string_2, right_string_2, lines_below_2);
+    compare_lines(
+        &format!("IN PRINTING AT PATH {:?}", path),
+        ("SEEK_START", lines_1.join("\n")),
+        ("SEEK_END", lines_2.join("\n")),
+    );
+
+    let mut lines_with_focus = lines_1;
+    lines_with_focus[end_row].insert(end_col, ')');
+    lines_with_focus[start_row].insert(start_col, '(');
+    compare_lines(
+        &format!("IN PRINTING AT PATH {:?}", path),
+        ("EXPECTED", expected_lines.join("\n")),
+        ("ACTUAL", lines_with_focus.join("\n")),
+    );
+}
+
+/// Assert where the focus is, when focusing on `focus_target` of the node at `path`. The
+/// `expected_lines` are the printed document with a `|` inserted at the focus point.
+#[track_caller]
+pub fn assert_pp_focus<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    expected_lines: &[&str],
+) {
+    let (lines_above, mut left_string, right_string, lines_below) =
+        print_above_and_below(doc, width, path, focus_target);
+    left_string.push('|');
+    let lines_with_focus = concat_lines(lines_above, left_string, right_string, lines_below);
+
+    compare_lines(
+        &format!("IN PRINTING AT PATH {:?}", path),
+        ("EXPECTED", expected_lines.join("\n")),
+        ("ACTUAL", lines_with_focus.join("\n")),
+    );
+}
+
+/// Assert that printing `rows` lines around the focus gives `expected_lines`, as in
+/// [`print_region()`]. This is for checking part of a document that's too large to print in full.
+#[track_caller]
+pub fn assert_pp_region<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    rows: usize,
+    expected_lines: &[&str],
+) {
+    let lines = print_region(doc, width, path, focus_target, rows);
+    compare_lines(
+        &format!("IN PRINTING {} ROWS AT PATH {:?}", rows, path),
+        ("EXPECTED", expected_lines.join("\n")),
+        ("ACTUAL", lines.join("\n")),
+    );
+}
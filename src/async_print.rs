@@ -0,0 +1,210 @@
+use crate::{
+    pretty_print_with_options, DownwardPrinter, FocusTarget, Line, PrettyDoc, PrintOptions,
+    PrintingError, Width,
+};
+use alloc::boxed::Box;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use futures_core::Stream;
+
+/// Pretty print a document whose nodes are loaded on demand (for example from a remote server or
+/// paged in from a large file), without blocking while they load.
+///
+/// The [`PrettyDoc`] methods stay synchronous. Instead, when one of them needs a node that hasn't
+/// been loaded yet, it should fail with an error saying so. `pretty_print_async` then awaits
+/// `load(&error)`, which should load that node (and ideally its neighbors, such as all of its
+/// siblings) into the document's cache, and then it calls `print` again from scratch. This repeats
+/// until `print` succeeds, or fails with some other kind of [`PrintingError`], or `load` fails
+/// (for example because the error wasn't about a missing node after all). If `print` fails with
+/// the same error twice in a row, then `load` didn't make any progress, so that error is returned
+/// instead of retrying forever.
+///
+/// `print` can be any of the other printing functions, such as
+/// `|doc| pretty_print_to_string(doc, 80)`. If it uses [`pretty_print()`](crate::pretty_print),
+/// it should take only the lines it needs from the iterators and propagate their errors, since a
+/// missing node can be discovered partway through either iterator.
+///
+/// The cache must use interior mutability (`doc` is borrowed for the whole call), and must not
+/// invalidate anything `print` returned when `load` adds to it. Each retry re-prints, so the more
+/// `load` fetches at once, the fewer retries there will be. To print lines from the top of the
+/// document without re-printing the lines before each missing node, use
+/// [`pretty_print_stream()`] instead.
+pub async fn pretty_print_async<'d, D, T, P, L, F>(
+    doc: D,
+    mut print: P,
+    mut load: L,
+) -> Result<T, PrintingError<D::Error>>
+where
+    D: PrettyDoc<'d>,
+    D::Error: PartialEq,
+    P: FnMut(D) -> Result<T, PrintingError<D::Error>>,
+    L: FnMut(&D::Error) -> F,
+    F: Future<Output = Result<(), D::Error>>,
+{
+    let mut last_error = None;
+    loop {
+        match print(doc) {
+            Err(PrintingError::PrettyDoc(err)) => {
+                if last_error.as_ref() == Some(&err) {
+                    return Err(PrintingError::PrettyDoc(err));
+                }
+                load(&err).await.map_err(PrintingError::PrettyDoc)?;
+                last_error = Some(err);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Pretty print a whole document whose nodes are loaded on demand, as a [`Stream`] of its lines
+/// from the top. This is like [`pretty_print_async()`], except that when printing a line runs
+/// into a node that isn't loaded, it awaits `load(&error)` and then retries just that line. So
+/// each load costs one line of printing, instead of a full re-print.
+///
+/// The stream ends after the first error. Like in `pretty_print_async()`, if printing a line fails
+/// with the same error twice in a row then `load` didn't make any progress, and that error is
+/// returned instead of retrying forever.
+pub fn pretty_print_stream<'d, D, L, F>(
+    doc: D,
+    width: Width,
+    options: PrintOptions<'d, D>,
+    load: L,
+) -> LineStream<'d, D, L, F>
+where
+    D: PrettyDoc<'d>,
+    D::Error: PartialEq,
+    L: FnMut(&D::Error) -> F,
+    F: Future<Output = Result<(), D::Error>>,
+{
+    LineStream {
+        doc,
+        width,
+        options,
+        load,
+        state: StreamState::Start,
+        last_error: None,
+    }
+}
+
+/// The lines of a document, printed as its nodes load. Returned by [`pretty_print_stream()`].
+pub struct LineStream<'d, D: PrettyDoc<'d>, L, F> {
+    doc: D,
+    width: Width,
+    options: PrintOptions<'d, D>,
+    load: L,
+    state: StreamState<'d, D, F>,
+    /// The error that started the most recent load, if no line has been printed since.
+    last_error: Option<D::Error>,
+}
+
+enum StreamState<'d, D: PrettyDoc<'d>, F> {
+    /// Haven't printed the first line yet.
+    Start,
+    /// Printing the lines after the first.
+    Printing(DownwardPrinter<'d, D>),
+    /// Waiting for a node to load, before retrying the line that needed it (or the first line, if
+    /// there's no printer yet).
+    Loading(Pin<Box<F>>, Option<DownwardPrinter<'d, D>>),
+    /// Reached the end of the document, or an error.
+    Done,
+}
+
+// The future is pinned in its own box, and nothing else needs to be pinned.
+impl<'d, D: PrettyDoc<'d>, L, F> Unpin for LineStream<'d, D, L, F> {}
+
+impl<'d, D, L, F> LineStream<'d, D, L, F>
+where
+    D: PrettyDoc<'d>,
+    D::Error: PartialEq,
+    L: FnMut(&D::Error) -> F,
+    F: Future<Output = Result<(), D::Error>>,
+{
+    /// Start loading the node that `err` says is missing, and then resume with `printer`. Fails
+    /// if it's the same error as last time.
+    fn start_loading(
+        &mut self,
+        err: D::Error,
+        printer: Option<DownwardPrinter<'d, D>>,
+    ) -> Result<(), PrintingError<D::Error>> {
+        if self.last_error.as_ref() == Some(&err) {
+            return Err(PrintingError::PrettyDoc(err));
+        }
+        self.state = StreamState::Loading(Box::pin((self.load)(&err)), printer);
+        self.last_error = Some(err);
+        Ok(())
+    }
+}
+
+impl<'d, D, L, F> Stream for LineStream<'d, D, L, F>
+where
+    D: PrettyDoc<'d>,
+    D::Error: PartialEq,
+    L: FnMut(&D::Error) -> F,
+    F: Future<Output = Result<(), D::Error>>,
+{
+    type Item = Result<Line<'d, D>, PrintingError<D::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            let result = match mem::replace(&mut this.state, StreamState::Done) {
+                StreamState::Done => return Poll::Ready(None),
+                StreamState::Start => {
+                    let printed = pretty_print_with_options(
+                        this.doc,
+                        this.width,
+                        &[],
+                        FocusTarget::Start,
+                        None,
+                        &this.options,
+                    );
+                    match printed {
+                        Ok((_, focused_line, printer)) => {
+                            this.state = StreamState::Printing(printer);
+                            this.last_error = None;
+                            return Poll::Ready(Some(Ok(Line::from(focused_line))));
+                        }
+                        Err(PrintingError::PrettyDoc(err)) => this.start_loading(err, None),
+                        Err(err) => Err(err),
+                    }
+                }
+                StreamState::Printing(mut printer) => {
+                    let checkpoint = printer.save_state();
+                    match printer.next() {
+                        None => return Poll::Ready(None),
+                        Some(Ok(line)) => {
+                            this.state = StreamState::Printing(printer);
+                            this.last_error = None;
+                            return Poll::Ready(Some(Ok(line)));
+                        }
+                        Some(Err(PrintingError::PrettyDoc(err))) => {
+                            printer.restore_state(checkpoint);
+                            this.start_loading(err, Some(printer))
+                        }
+                        Some(Err(err)) => Err(err),
+                    }
+                }
+                StreamState::Loading(mut loading, printer) => match loading.as_mut().poll(cx) {
+                    Poll::Pending => {
+                        this.state = StreamState::Loading(loading, printer);
+                        return Poll::Pending;
+                    }
+                    Poll::Ready(Ok(())) => {
+                        this.state = match printer {
+                            None => StreamState::Start,
+                            Some(printer) => StreamState::Printing(printer),
+                        };
+                        Ok(())
+                    }
+                    Poll::Ready(Err(err)) => Err(PrintingError::PrettyDoc(err)),
+                },
+            };
+            if let Err(err) = result {
+                this.state = StreamState::Done;
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! Simplified support for bidirectional text, that mixes left-to-right scripts (like Latin) with
+//! right-to-left scripts (like Hebrew and Arabic).
+
+use crate::geometry::char_width;
+use alloc::vec::Vec;
+
+/// The direction that a piece of text is written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Left-to-right, like Latin, Cyrillic, or CJK text, and digits.
+    Ltr,
+    /// Right-to-left, like Hebrew or Arabic text.
+    Rtl,
+    /// No inherent direction, like spaces and punctuation. Neutral text takes on the direction of
+    /// the text around it.
+    Neutral,
+}
+
+impl Direction {
+    /// The direction of a character, based on its Unicode bidi class. Strong right-to-left
+    /// characters are `Rtl`, other letters and digits are `Ltr`, and everything else (whitespace,
+    /// punctuation, symbols, combining marks) is `Neutral`.
+    pub fn of_char(ch: char) -> Direction {
+        match ch {
+            // Digits in right-to-left scripts are still written left-to-right.
+            '\u{0660}'..='\u{0669}' | '\u{06F0}'..='\u{06F9}' => Direction::Ltr,
+            '\u{200E}' => Direction::Ltr,
+            '\u{200F}' => Direction::Rtl,
+            '\u{0590}'..='\u{08FF}'
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+                if ch.is_alphanumeric() =>
+            {
+                Direction::Rtl
+            }
+            _ if ch.is_alphanumeric() => Direction::Ltr,
+            _ => Direction::Neutral,
+        }
+    }
+
+    /// The direction of the first character in `s` that is not `Neutral`, or `Neutral` if there
+    /// isn't one.
+    pub fn of_str(s: &str) -> Direction {
+        s.chars()
+            .map(Direction::of_char)
+            .find(|dir| *dir != Direction::Neutral)
+            .unwrap_or(Direction::Neutral)
+    }
+}
+
+/// Resolve the direction of each char in a line, assuming the line as a whole is left-to-right.
+/// Every resolved direction is either `Ltr` or `Rtl`:
+///
+/// - Neutral chars are `Rtl` if the nearest non-neutral chars on both sides are `Rtl`, and `Ltr`
+///   otherwise.
+/// - Zero-width chars (like combining marks) take the direction of the char before them.
+pub(crate) fn resolve_directions(chars: impl Iterator<Item = char>) -> Vec<Direction> {
+    let chars = chars.collect::<Vec<_>>();
+    let mut directions = chars
+        .iter()
+        .map(|ch| Direction::of_char(*ch))
+        .collect::<Vec<_>>();
+
+    let mut prev_strong = Direction::Ltr;
+    let mut i = 0;
+    while i < directions.len() {
+        if directions[i] != Direction::Neutral {
+            prev_strong = directions[i];
+            i += 1;
+            continue;
+        }
+        let run_end = (i..directions.len())
+            .find(|j| directions[*j] != Direction::Neutral)
+            .unwrap_or(directions.len());
+        let next_strong = directions.get(run_end).copied().unwrap_or(Direction::Ltr);
+        let resolved = if prev_strong == Direction::Rtl && next_strong == Direction::Rtl {
+            Direction::Rtl
+        } else {
+            Direction::Ltr
+        };
+        for dir in &mut directions[i..run_end] {
+            *dir = resolved;
+        }
+        i = run_end;
+    }
+
+    for i in 1..chars.len() {
+        if char_width(chars[i]) == 0 {
+            directions[i] = directions[i - 1];
+        }
+    }
+    directions
+}
@@ -0,0 +1,175 @@
+//! Check a whole document against its notations up front, instead of discovering mismatches
+//! partway through printing.
+
+use crate::notation::normalize_child_index;
+use crate::{CheckPos, Condition, Notation, PrettyDoc, StyleLabel};
+use alloc::vec::Vec;
+
+/// A place where a document node doesn't match its notation, found by [`check_doc()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocNotationMismatch {
+    /// The path to the node, as a sequence of child indices starting from the root.
+    pub path: Vec<usize>,
+    /// What's wrong with it.
+    pub kind: MismatchKind,
+}
+
+/// The ways that a document node can fail to match its notation. These mirror the notation/doc
+/// mismatch variants of [`PrintingError`](crate::PrintingError).
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum MismatchKind {
+    #[error("Notation was Text but doc node did not contain text.")]
+    TextNotationOnTextlessDoc,
+    #[error("Notation was Child({index}) but doc node only had {len} children.")]
+    ChildIndexOutOfBounds { index: isize, len: usize },
+    #[error("Notation was Child but doc node contained text instead.")]
+    ChildNotationOnChildlessDoc,
+    #[error("Notation contained CheckPos::Child({index}) but doc node only had {len} children.")]
+    CheckPosChildIndexOutOfBounds { index: isize, len: usize },
+    #[error("Notation contained CheckPos::Child(_) but doc node contained text instead.")]
+    CheckPosChildOnChildlessDoc,
+    #[error("Notation was Count, Arity, Fold, or Summarize but doc node contained text instead of children.")]
+    CountNotationOnChildlessDoc,
+}
+
+/// Check every node of `doc` against its notation, and report all of the mismatches, such as a
+/// [`Notation::Child`] whose index is out of bounds. This doesn't lay anything out, and it checks
+/// nodes whether or not they would be displayed.
+///
+/// [`Notation::Count`], [`Notation::Arity`], and [`Notation::Fold`] are only checked in the case
+/// that applies to the node's number of children. Both options of every [`Notation::Choice`],
+/// [`Notation::Check`], and [`Notation::CheckJoin`] are checked, since which one is displayed can
+/// depend on the layout. (So a `Check` that guards against a mismatch can still have it reported.)
+///
+/// Returns an error only if one of the [`PrettyDoc`] methods does.
+pub fn check_doc<'d, D: PrettyDoc<'d>>(doc: D) -> Result<Vec<DocNotationMismatch>, D::Error> {
+    let mut mismatches = Vec::new();
+    check_node(doc, &mut Vec::new(), &mut mismatches)?;
+    Ok(mismatches)
+}
+
+fn check_node<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    path: &mut Vec<usize>,
+    mismatches: &mut Vec<DocNotationMismatch>,
+) -> Result<(), D::Error> {
+    let num_children = doc.num_children()?;
+    let mut kinds = Vec::new();
+    check_notation(&doc.notation()?.0, num_children, &mut kinds);
+    mismatches.extend(kinds.into_iter().map(|kind| DocNotationMismatch {
+        path: path.clone(),
+        kind,
+    }));
+
+    for i in 0..num_children.unwrap_or(0) {
+        path.push(i);
+        check_node(doc.unwrap_child(i)?, path, mismatches)?;
+        path.pop();
+    }
+    Ok(())
+}
+
+fn check_notation<L: StyleLabel, C: Condition>(
+    notation: &Notation<L, C>,
+    num_children: Option<usize>,
+    kinds: &mut Vec<MismatchKind>,
+) {
+    use MismatchKind::*;
+    use Notation::*;
+
+    match notation {
+        Empty | Newline | VerticalSpace(..) | EndOfLine | Literal(_) | Space | PadTo(_)
+        | Placeholder(_) | HiddenCount | FocusMark | Left | Right => (),
+        Text | BreakableText { .. } => {
+            if num_children.is_some() {
+                kinds.push(TextNotationOnTextlessDoc);
+            }
+        }
+        Child(index) => match num_children {
+            None => kinds.push(ChildNotationOnChildlessDoc),
+            Some(len) if normalize_child_index(*index, len).is_none() => {
+                kinds.push(ChildIndexOutOfBounds { index: *index, len })
+            }
+            Some(_) => (),
+        },
+        Flat(note)
+        | Indent(_, _, note)
+        | Style(_, note)
+        | DepthStyle(_, note)
+        | Link(_, note)
+        | MapText(_, note)
+        | Cache(note)
+        | WithWidth(_, note) => check_notation(note, num_children, kinds),
+        Concat(note1, note2) | Choice(note1, note2) | CheckJoin(_, note1, note2) => {
+            check_notation(note1, num_children, kinds);
+            check_notation(note2, num_children, kinds);
+        }
+        Check(_, pos, note1, note2) => {
+            check_pos(pos, num_children, kinds);
+            check_notation(note1, num_children, kinds);
+            check_notation(note2, num_children, kinds);
+        }
+        CheckExpr(expr, note1, note2) => {
+            for pos in expr.positions() {
+                check_pos(pos, num_children, kinds);
+            }
+            check_notation(note1, num_children, kinds);
+            check_notation(note2, num_children, kinds);
+        }
+        Count { zero, one, many } => match num_children {
+            None => kinds.push(CountNotationOnChildlessDoc),
+            Some(0) => check_notation(zero, num_children, kinds),
+            Some(1) => check_notation(one, num_children, kinds),
+            Some(_) => check_notation(many, num_children, kinds),
+        },
+        Arity { cases, fallback } => match num_children {
+            None => kinds.push(CountNotationOnChildlessDoc),
+            Some(n) => {
+                let note = cases
+                    .iter()
+                    .find(|(arity, _)| *arity == n)
+                    .map(|(_, note)| note)
+                    .unwrap_or(fallback);
+                check_notation(note, num_children, kinds);
+            }
+        },
+        Summarize {
+            limit,
+            notation,
+            summary,
+        } => match num_children {
+            None => kinds.push(CountNotationOnChildlessDoc),
+            Some(n) if n > *limit => {
+                check_notation(notation, Some(*limit), kinds);
+                check_notation(summary, Some(*limit), kinds);
+            }
+            Some(_) => check_notation(notation, num_children, kinds),
+        },
+        Columns { .. } if num_children.is_none() => kinds.push(CountNotationOnChildlessDoc),
+        Columns { .. } => (),
+        Fold { first, join } => match num_children {
+            None => kinds.push(CountNotationOnChildlessDoc),
+            Some(0) => (),
+            Some(1) => check_notation(first, num_children, kinds),
+            Some(_) => {
+                check_notation(first, num_children, kinds);
+                check_notation(join, num_children, kinds);
+            }
+        },
+    }
+}
+
+/// Check that a [`CheckPos::Child`] refers to a child that exists.
+fn check_pos(pos: &CheckPos, num_children: Option<usize>, kinds: &mut Vec<MismatchKind>) {
+    use MismatchKind::*;
+
+    if let CheckPos::Child(index) = pos {
+        match num_children {
+            None => kinds.push(CheckPosChildOnChildlessDoc),
+            Some(len) if normalize_child_index(*index, len).is_none() => {
+                kinds.push(CheckPosChildIndexOutOfBounds { index: *index, len })
+            }
+            Some(_) => (),
+        }
+    }
+}
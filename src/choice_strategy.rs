@@ -0,0 +1,86 @@
+use crate::Width;
+use core::fmt;
+
+/// How the printer picks between the two options of a [`Notation::Choice`](crate::Notation::Choice).
+/// The default is [`FirstFits`]; supply another in
+/// [`PrintOptions::choice_strategy`](crate::PrintOptions::choice_strategy) to experiment with
+/// other layout policies.
+///
+/// A strategy can only pick which option to use. Choices inside a `Flat` always use their first
+/// option, and never reach the strategy.
+pub trait ChoiceStrategy: fmt::Debug {
+    /// Whether to pick the first option of the choice described by `choice`.
+    fn choose_first(&self, choice: &mut ChoiceContext) -> bool;
+}
+
+/// The greedy strategy: pick the first option if the rest of the line fits within the width
+/// when using it, and the second option otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FirstFits;
+
+impl ChoiceStrategy for FirstFits {
+    fn choose_first(&self, choice: &mut ChoiceContext) -> bool {
+        let width = choice.width();
+        choice.first_fits_within(width)
+    }
+}
+
+/// What to do when a [`ChoiceStrategy`] picks the second option of a choice, but neither option
+/// fits within the width. Supply one in
+/// [`PrintOptions::tie_break`](crate::PrintOptions::tie_break); the default is
+/// [`ChoiceTieBreak::PreferSecond`].
+///
+/// The other policies measure both options, so they make printing slower when lines don't fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChoiceTieBreak {
+    /// Use the second option, as the strategy picked.
+    #[default]
+    PreferSecond,
+    /// Use whichever option's line goes the fewest columns past the width, preferring the second
+    /// if they tie. Like when checking whether an option fits, this measures everything up to the
+    /// next newline, assuming that the choices in it pick their second option.
+    PreferFewestOverflowColumns,
+    /// Use whichever option could take the fewest lines, preferring the second if they tie. An
+    /// option's line count is the fewest newlines it could print, picking whichever option of
+    /// each choice inside it has fewer.
+    PreferFewestLines,
+}
+
+/// What a [`ChoiceStrategy`] can find out about a choice.
+pub struct ChoiceContext<'a> {
+    width: Width,
+    prefix_len: Width,
+    fits: &'a mut dyn FnMut(Width) -> bool,
+}
+
+impl<'a> ChoiceContext<'a> {
+    pub(crate) fn new(
+        width: Width,
+        prefix_len: Width,
+        fits: &'a mut dyn FnMut(Width) -> bool,
+    ) -> ChoiceContext<'a> {
+        ChoiceContext {
+            width,
+            prefix_len,
+            fits,
+        }
+    }
+
+    /// The preferred line width for this choice: the printing width, or the first option's
+    /// `max_width` if that's smaller.
+    pub fn width(&self) -> Width {
+        self.width
+    }
+
+    /// The width of what's already been printed on the line before the choice.
+    pub fn prefix_len(&self) -> Width {
+        self.prefix_len
+    }
+
+    /// Whether the line would fit within `width` columns if the first option was picked. This
+    /// measures everything up to the next newline, including what follows the choice, assuming
+    /// that any choices inside the first option (or after it) pick their second option.
+    pub fn first_fits_within(&mut self, width: Width) -> bool {
+        (self.fits)(width)
+    }
+}
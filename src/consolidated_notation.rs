@@ -1,11 +1,22 @@
 //! Walk along the notation tree, skipping the boring parts.
 
+use crate::infra::Mutex;
 use crate::{
-    geometry::str_width, notation::normalize_child_index, CheckPos, Notation, PrettyDoc, Style,
-    Width,
+    geometry::{char_width, str_width},
+    notation::normalize_child_index,
+    search::MatchStyle,
+    CheckPos, Col, Condition, ConditionExpr, Direction, JoinCondition, Notation, PrettyDoc, Style,
+    StyleLabel, UnicodeWidth, Width, WidthPolicy, ERROR_PLACEHOLDER,
 };
-use std::fmt;
-use std::rc::Rc;
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cell::Cell;
+use core::fmt;
 
 /// A `Notation` says how to print a _single_ node in a document. The _notation tree_ is what you
 /// get from gluing together the `Notation`s for every node in the document. A
@@ -31,6 +42,8 @@ pub enum ConsolidatedNotation<'d, D: PrettyDoc<'d>> {
     ),
     Child(usize, DelayedConsolidatedNotation<'d, D>),
     FocusMark,
+    /// From a `Notation::Cache`. Identical to its contents, except that it's ok to memoize.
+    Cache(DelayedConsolidatedNotation<'d, D>),
 }
 
 // A fully resolved piece of text.
@@ -41,17 +54,92 @@ pub struct Segment<'d, D: PrettyDoc<'d>> {
     /// The width of `str` in columns.
     pub width: Width,
     pub style: D::Style,
+    /// What sort of notation produced this segment.
+    pub kind: SegmentKind,
+    /// Which direction the text in this segment is written in. When the segment is printed, this
+    /// is the direction of its first non-neutral character (see [`Direction::of_str()`]). After
+    /// [`Line::into_visual_order()`](crate::Line::into_visual_order), it's the segment's resolved
+    /// direction, which is never `Neutral`.
+    pub direction: Direction,
+    /// The link target of this segment, from an enclosing [`Notation::Link`].
+    pub link: Option<&'d str>,
+    /// The label of the innermost [`Notation::Style`] (or [`Notation::Indent`] style) that this
+    /// segment is inside of, if any.
+    pub style_label: Option<&'d D::StyleLabel>,
+}
+
+/// What sort of notation produced a [`Segment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentKind {
+    /// A [`Notation::Literal`], [`Notation::Placeholder`], or [`Notation::HiddenCount`] (or
+    /// [`ERROR_PLACEHOLDER`]).
+    Literal,
+    /// The document's text, from a [`Notation::Text`] or [`Notation::BreakableText`].
+    Text,
+    /// An indentation prefix, from a [`Notation::Indent`] or [`Notation::BreakableText`].
+    Indentation,
+    /// A soft space, from a [`Notation::Space`].
+    Separator,
+    /// Not from the notation at all: added to the line by a
+    /// [`LineDecorator`](crate::LineDecorator).
+    Decoration,
+}
+
+/// How to split a [`Segment`] or [`Line`](crate::Line) when the requested column falls in the
+/// middle of a full-width character.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitRounding {
+    /// Refuse to split: the split functions return `None`.
+    Exact,
+    /// Put the full-width character on the right side, so that the left side is one column
+    /// narrower than requested. This matches how panes truncate lines that are too wide.
+    Floor,
+    /// Put the full-width character on the left side, so that the left side is one column wider
+    /// than requested.
+    Ceil,
+}
+
+/// Which side of a split a full-width character that straddled the split column ended up on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitSide {
+    Left,
+    Right,
+}
+
+/// The result of splitting a [`Segment`] or [`Line`](crate::Line) at a column.
+#[derive(Debug)]
+pub struct Split<T> {
+    /// Everything before the split column.
+    pub left: T,
+    /// Everything after the split column.
+    pub right: T,
+    /// If a full-width character straddled the split column, which side it was placed on.
+    pub straddler: Option<SplitSide>,
 }
 
-/// A styled piece of text from `Notation::Literal` or `Notation::Text` or `Notation::Indent`.
+/// A styled piece of text from `Notation::Literal`, `Notation::Text`, `Notation::Space`, or
+/// `Notation::Indent`.
 #[derive(Debug)]
 pub struct Textual<'d, D: PrettyDoc<'d>> {
     pub str: &'d str,
     /// The unicode width of `str`, stored for performance.
     pub width: Width,
     pub style: D::Style,
-    /// Whether this came from a `Notation::Text` (true) or a `Notation::Literal` (false).
+    /// Whether this came from a `Notation::Text` or `Notation::BreakableText` (true) or a
+    /// `Notation::Literal` (false).
     pub is_from_text: bool,
+    /// Whether this is a piece of a `Notation::BreakableText` that's followed by more of the same
+    /// text.
+    pub text_continues: bool,
+    /// Whether this came from a `Notation::Space`, and so should collapse with neighbouring soft
+    /// spaces and be dropped at the start and end of lines.
+    pub is_soft_space: bool,
+    /// If this came from a `Notation::PadTo`, the column to pad to. Its `str` is empty.
+    pub pad_to: Option<Col>,
+    /// The link target from an enclosing `Notation::Link`, if any.
+    pub link: Option<&'d str>,
+    /// The label of the innermost enclosing `Notation::Style`, if any.
+    pub style_label: Option<&'d D::StyleLabel>,
 }
 
 // Performance Note: We've tested three implementations of indentation so far:
@@ -70,6 +158,37 @@ pub struct IndentNode<'d, D: PrettyDoc<'d>> {
     pub parent: Option<Rc<IndentNode<'d, D>>>,
 }
 
+impl<'d, D: PrettyDoc<'d>> IndentNode<'d, D> {
+    /// The number of levels of indentation in `indent`.
+    pub fn depth(mut indent: &Option<Rc<IndentNode<'d, D>>>) -> usize {
+        let mut depth = 0;
+        while let Some(node) = indent {
+            depth += 1;
+            indent = &node.parent;
+        }
+        depth
+    }
+}
+
+/// A child node that was entered to reach a notation, plus a reference to the node it was entered
+/// from. These are used to detect documents that contain themselves, which would otherwise make
+/// the printer loop forever.
+#[derive(Debug)]
+struct EnteredNode<Id> {
+    id: Id,
+    /// The address of the node's notation.
+    notation: usize,
+    /// Which child of its parent this node is.
+    index: usize,
+    /// The number of nodes entered to reach this one, including itself.
+    depth: usize,
+    parent: Option<Rc<EnteredNode<Id>>>,
+}
+
+/// Only look for a loop once the printer is this deep in the document (and then again every time
+/// the depth doubles), so that the search costs amortized constant time per node.
+const MIN_LOOP_CHECK_DEPTH: usize = 64;
+
 /// A `ConsolidatedNotation` that has not yet been evaluated, to prevent the entire notation tree
 /// from being in memory at once. Call `.eval()` to get a `ConsolidatedNotation`.
 #[derive(Debug)]
@@ -79,22 +198,86 @@ pub struct DelayedConsolidatedNotation<'d, D: PrettyDoc<'d>> {
     doc: D,
     /// Whether we are inside a `Notation::Flat`.
     flat: bool,
+    /// The smallest width of any `Notation::WithWidth` that we are inside of.
+    max_width: Option<Width>,
     /// The indentation that will be applied to any newlines inside of this notation.
     indent: Option<Rc<IndentNode<'d, D>>>,
     /// The style that will be applied to any text, literals, or indentation inside of this notation
     style: D::Style,
+    /// The link target that will be applied to any text or literals inside of this notation.
+    link: Option<&'d str>,
+    /// The label of the innermost `Notation::Style` that this notation is inside of.
+    style_label: Option<&'d D::StyleLabel>,
+    /// The label of the innermost `Notation::MapText` that this notation is inside of.
+    text_transform: Option<D::StyleLabel>,
+    /// If we are inside a `Notation::Summarize`'s `notation` case, pretend that the node has at
+    /// most this many children.
+    child_limit: Option<usize>,
+    /// If we are inside a `Notation::Summarize`'s `summary` case, the number of children it left
+    /// out.
+    hidden_count: Option<usize>,
+    /// If this is part of a `Notation::BreakableText`, which part.
+    text_piece: Option<TextPiece>,
     /// If we are inside a `Notation::Fold`'s `join` case, this stores context about the join.
     join_pos: Option<JoinPos<'d, D>>,
+    /// If this is part of a `Notation::Columns` layout, which part.
+    grid_pos: Option<GridPos<D>>,
+    /// If set, display `ERROR_PLACEHOLDER` in this style in place of any child node that can't be
+    /// accessed, instead of failing. See `ErrorPolicy::Substitute`.
+    error_style: Option<Rc<D::Style>>,
+    /// How to measure the width of text and indentation.
+    width_policy: &'d dyn WidthPolicy,
+    /// If set, the nodes to display in a "match" style. See `PrintOptions::matches`.
+    match_style: Option<Rc<MatchStyle<D::Id, D::Style>>>,
+    /// The child nodes that were entered to reach this notation, innermost first.
+    entered: Option<Rc<EnteredNode<D::Id>>>,
+    /// The notations displayed just before this one, nearest first, that a
+    /// `Notation::VerticalSpace` can collapse with.
+    left_neighbors: Option<Rc<Neighbor<'d, D>>>,
+    /// The notations displayed just after this one, nearest first.
+    right_neighbors: Option<Rc<Neighbor<'d, D>>>,
+    /// If set, we're only looking for `Notation::VerticalSpace`s, to see what they collapse with.
+    /// They put their `(min, max)` here instead of being displayed.
+    vertical_space_probe: Option<VerticalSpaceProbe>,
+    /// If this is (part of) a `Notation::VerticalSpace` whose collapsing has been resolved, how
+    /// many newlines it still has to display.
+    vertical_space_lines: Option<usize>,
+}
+
+/// Where a `Notation::VerticalSpace` reports its `(min, max)` when it's being looked for.
+type VerticalSpaceProbe = Rc<Cell<Option<(usize, usize)>>>;
+
+/// A list of neighboring notations, for `Notation::VerticalSpace` to look through.
+#[derive(Debug)]
+struct Neighbor<'d, D: PrettyDoc<'d>> {
+    note: DelayedConsolidatedNotation<'d, D>,
+    next: Option<Rc<Neighbor<'d, D>>>,
+}
+
+/// Part of a `Notation::BreakableText`'s text, given by byte positions. The text is split into
+/// pieces that each end with a break string (except for the last), with a choice between staying
+/// on the line and a newline between each pair of pieces.
+#[derive(Debug, Clone, Copy)]
+enum TextPiece {
+    /// The text from this position to the end.
+    From(usize),
+    /// The piece of text in this range.
+    Piece(usize, usize),
+    /// A choice of whether to break the line before the text from this position to the end.
+    Break(usize),
+    /// A newline, followed by the text from this position to the end.
+    NewlineThen(usize),
+    /// A newline.
+    Newline,
 }
 
 /// Position within a `Fold` notation.
 #[derive(Debug)]
 struct JoinPos<'d, D: PrettyDoc<'d>> {
-    /// The document node containing the `Notation::Fold`.
-    parent: D,
-    /// The next child to process.
-    child: D,
-    /// The index of `child`.
+    /// The children of the document node containing the `Notation::Fold`, fetched all at once with
+    /// `PrettyDoc::unwrap_children()`.
+    children: Rc<[D]>,
+    /// The index of the next child to process.
     index: usize,
     /// Notation::Fold.first
     first: &'d Notation<D::StyleLabel, D::Condition>,
@@ -102,10 +285,90 @@ struct JoinPos<'d, D: PrettyDoc<'d>> {
     join: &'d Notation<D::StyleLabel, D::Condition>,
 }
 
+impl<'d, D: PrettyDoc<'d>> Segment<'d, D> {
+    /// Whether this segment is (part of) an indentation prefix, rather than text from a literal or
+    /// the document.
+    pub fn is_indentation(&self) -> bool {
+        matches!(self.kind, SegmentKind::Indentation)
+    }
+
+    /// A segment containing `str`, for a [`LineDecorator`](crate::LineDecorator) to add to a line.
+    pub fn decoration(str: &'d str, style: D::Style) -> Segment<'d, D> {
+        Segment {
+            str,
+            width: str_width(str),
+            style,
+            kind: SegmentKind::Decoration,
+            direction: Direction::of_str(str),
+            link: None,
+            style_label: None,
+        }
+    }
+
+    /// Split this segment in two at the given column, measured from the start of the segment. Both
+    /// halves keep this segment's style. Zero-width characters stay with the character before
+    /// them. If `col` is past the end of the segment, the right half is empty.
+    ///
+    /// Returns `None` if `rounding` is [`SplitRounding::Exact`] and `col` falls in the middle of a
+    /// full-width character.
+    pub fn split_at_width(self, col: Col, rounding: SplitRounding) -> Option<Split<Self>> {
+        let mut width = 0;
+        for (byte_pos, ch) in self.str.char_indices() {
+            let ch_width = char_width(ch);
+            if width >= col && ch_width > 0 {
+                return Some(self.split_at_byte(byte_pos, None));
+            }
+            if width + ch_width > col {
+                return match rounding {
+                    SplitRounding::Exact => None,
+                    SplitRounding::Floor => {
+                        Some(self.split_at_byte(byte_pos, Some(SplitSide::Right)))
+                    }
+                    SplitRounding::Ceil => {
+                        Some(self.split_at_byte(byte_pos + ch.len_utf8(), Some(SplitSide::Left)))
+                    }
+                };
+            }
+            width += ch_width;
+        }
+        let len = self.str.len();
+        Some(self.split_at_byte(len, None))
+    }
+
+    fn split_at_byte(self, byte_pos: usize, straddler: Option<SplitSide>) -> Split<Self> {
+        let (left_str, right_str) = self.str.split_at(byte_pos);
+        Split {
+            left: Segment {
+                str: left_str,
+                width: str_width(left_str),
+                style: self.style.clone(),
+                kind: self.kind,
+                direction: Direction::of_str(left_str),
+                link: self.link,
+                style_label: self.style_label,
+            },
+            right: Segment {
+                str: right_str,
+                width: str_width(right_str),
+                style: self.style,
+                kind: self.kind,
+                direction: Direction::of_str(right_str),
+                link: self.link,
+                style_label: self.style_label,
+            },
+            straddler,
+        }
+    }
+}
+
 impl<'d, D: PrettyDoc<'d>> Textual<'d, D> {
     /// Split this text in two, at the given position between `char`s. If it's too large, split at
-    /// the end.
-    pub fn split_at(self, char_pos: usize) -> (Textual<'d, D>, Textual<'d, D>) {
+    /// the end. The widths of the halves are measured with `width_policy`.
+    pub fn split_at(
+        self,
+        char_pos: usize,
+        width_policy: &dyn WidthPolicy,
+    ) -> (Textual<'d, D>, Textual<'d, D>) {
         let byte_pos = self
             .str
             .char_indices()
@@ -115,15 +378,25 @@ impl<'d, D: PrettyDoc<'d>> Textual<'d, D> {
         let (left_str, right_str) = self.str.split_at(byte_pos);
         let left_textual = Textual {
             str: left_str,
-            width: str_width(left_str),
+            width: width_policy.str_width(left_str),
             style: self.style.clone(),
             is_from_text: self.is_from_text,
+            text_continues: self.is_from_text,
+            is_soft_space: self.is_soft_space,
+            pad_to: self.pad_to,
+            link: self.link,
+            style_label: self.style_label,
         };
         let right_textual = Textual {
             str: right_str,
-            width: str_width(right_str),
+            width: width_policy.str_width(right_str),
             style: self.style,
             is_from_text: self.is_from_text,
+            text_continues: self.text_continues,
+            is_soft_space: self.is_soft_space,
+            pad_to: self.pad_to,
+            link: self.link,
+            style_label: self.style_label,
         };
         (left_textual, right_textual)
     }
@@ -136,6 +409,11 @@ impl<'d, D: PrettyDoc<'d>> Clone for Textual<'d, D> {
             width: self.width,
             style: self.style.clone(),
             is_from_text: self.is_from_text,
+            text_continues: self.text_continues,
+            is_soft_space: self.is_soft_space,
+            pad_to: self.pad_to,
+            link: self.link,
+            style_label: self.style_label,
         }
     }
 }
@@ -146,6 +424,10 @@ impl<'d, D: PrettyDoc<'d>> Clone for Segment<'d, D> {
             str: self.str,
             width: self.width,
             style: self.style.clone(),
+            kind: self.kind,
+            direction: self.direction,
+            link: self.link,
+            style_label: self.style_label,
         }
     }
 }
@@ -163,6 +445,7 @@ impl<'d, D: PrettyDoc<'d>> Clone for ConsolidatedNotation<'d, D> {
             Choice(note1, note2) => Choice(note1.clone(), note2.clone()),
             Child(i, child) => Child(*i, child.clone()),
             FocusMark => FocusMark,
+            Cache(note) => Cache(note.clone()),
         }
     }
 }
@@ -173,25 +456,142 @@ impl<'d, D: PrettyDoc<'d>> Clone for DelayedConsolidatedNotation<'d, D> {
             doc: self.doc,
             notation: self.notation,
             flat: self.flat,
+            max_width: self.max_width,
             indent: self.indent.clone(),
-            join_pos: self.join_pos,
+            join_pos: self.join_pos.clone(),
+            grid_pos: self.grid_pos.clone(),
             style: self.style.clone(),
+            link: self.link,
+            style_label: self.style_label,
+            text_transform: self.text_transform.clone(),
+            child_limit: self.child_limit,
+            hidden_count: self.hidden_count,
+            text_piece: self.text_piece,
+            error_style: self.error_style.clone(),
+            width_policy: self.width_policy,
+            match_style: self.match_style.clone(),
+            entered: self.entered.clone(),
+            left_neighbors: self.left_neighbors.clone(),
+            right_neighbors: self.right_neighbors.clone(),
+            vertical_space_probe: self.vertical_space_probe.clone(),
+            vertical_space_lines: self.vertical_space_lines,
+        }
+    }
+}
+
+/// Identifies a `Notation::Cache` subtree, for the purpose of memoizing the measurements of its
+/// first line. Those depend only on these things.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey<Id> {
+    /// The id of the document node whose notation contains the `Cache`.
+    id: Id,
+    /// The address of the `Cache`'s contents within that notation.
+    notation: usize,
+    /// Whether we're inside a `Notation::Flat`.
+    flat: bool,
+    /// If we're inside a `Notation::Fold`'s `join` case, the index of the next child.
+    join_index: Option<usize>,
+}
+
+impl<Id: Copy> CacheKey<Id> {
+    /// The id of the document node whose notation contains the `Cache`.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+}
+
+/// Position within a `Notation::Columns` layout.
+#[derive(Debug)]
+struct GridPos<D> {
+    grid: Rc<Grid<D>>,
+    piece: GridPiece,
+}
+
+/// The children of a `Notation::Columns`, measured once when it's first evaluated.
+#[derive(Debug)]
+struct Grid<D> {
+    /// The children of the document node, fetched all at once with
+    /// `PrettyDoc::unwrap_children()`.
+    children: Vec<D>,
+    /// The width of each child when displayed flat, or `None` if some child can't be.
+    widths: Option<Vec<Width>>,
+    /// Notation::Columns.gap
+    gap: Width,
+}
+
+/// Part of a `Notation::Columns` layout. Cells are numbered in row-major order, and with `columns`
+/// columns and `rows` rows, the cell in row `r` and column `c` holds child `c * rows + r` (if
+/// there is one; only cells at the end of a row can be missing).
+#[derive(Debug, Clone, Copy)]
+enum GridPiece {
+    /// A choice between this many columns and fewer.
+    Columns(usize),
+    /// The cells of a layout with `columns` columns, from `cell` onwards.
+    Cells { columns: usize, cell: usize },
+    /// The padding or newline after `cell`, followed by the rest of the cells.
+    AfterCell { columns: usize, cell: usize },
+    /// The child with this index, displayed flat if `flat` is true.
+    Child { index: usize, flat: bool },
+    /// This many spaces.
+    Padding(Width),
+    /// A newline between rows.
+    Newline,
+}
+
+impl<D> Grid<D> {
+    fn num_rows(&self, columns: usize) -> usize {
+        self.children.len().div_ceil(columns)
+    }
+
+    /// The width of the widest child in the given column, or 0 if they weren't measured.
+    fn column_width(&self, columns: usize, col: usize) -> Width {
+        let rows = self.num_rows(columns);
+        let end = ((col + 1) * rows).min(self.children.len());
+        match &self.widths {
+            Some(widths) => widths[col * rows..end].iter().copied().max().unwrap_or(0),
+            None => 0,
+        }
+    }
+
+    fn width(&self, index: usize) -> Width {
+        self.widths.as_ref().map_or(0, |widths| widths[index])
+    }
+}
+
+impl<D> Clone for GridPos<D> {
+    fn clone(&self) -> Self {
+        GridPos {
+            grid: self.grid.clone(),
+            piece: self.piece,
         }
     }
 }
 
 impl<'d, D: PrettyDoc<'d>> Clone for JoinPos<'d, D> {
     fn clone(&self) -> Self {
-        *self
+        JoinPos {
+            children: self.children.clone(),
+            index: self.index,
+            first: self.first,
+            join: self.join,
+        }
     }
 }
-impl<'d, D: PrettyDoc<'d>> Copy for JoinPos<'d, D> {}
 
 /// An error that can occur while pretty printing the document.
 #[derive(thiserror::Error, Debug, Clone)]
-pub enum PrintingError<E: std::error::Error + 'static> {
-    #[error("Pretty printing path invalid at child index {0}.")]
-    InvalidPath(usize),
+pub enum PrintingError<E: core::error::Error + 'static> {
+    /// A path didn't lead to a displayed node: the node at `valid_prefix` has no child `index`
+    /// (`num_children` is what its [`PrettyDoc::num_children()`] returned), or its notation never
+    /// displays that child. See [`validate_path()`](crate::validate_path).
+    #[error(
+        "Pretty printing path invalid at child index {index} of the node at path {valid_prefix:?}."
+    )]
+    InvalidPath {
+        valid_prefix: Vec<usize>,
+        index: usize,
+        num_children: Option<usize>,
+    },
     #[error("Notation/doc mismatch: Notation was Text but doc node did not contain text.")]
     TextNotationOnTextlessDoc,
     #[error(
@@ -206,8 +606,12 @@ pub enum PrintingError<E: std::error::Error + 'static> {
     CheckPosChildIndexOutOfBounds { index: isize, len: usize },
     #[error("Notation/doc mismatch: Notation contained CheckPos::Child(_) but doc node contained text instead.")]
     CheckPosChildOnChildlessDoc,
-    #[error("Notation/doc mismatch: Notation was Count but doc node contained text instead of children.")]
+    #[error("Notation/doc mismatch: Notation was Count, Arity, Fold, Summarize, or Columns but doc node contained text instead of children.")]
     CountNotationOnChildlessDoc,
+    /// The node at `path` contains itself: following the child indices in `cycle` from it leads
+    /// back to the same node (with the same id and notation), so printing it would never finish.
+    #[error("Pretty printing looped: the node at path {path:?} contains itself at relative path {cycle:?}.")]
+    NotationLoop { path: Vec<usize>, cycle: Vec<usize> },
     #[error("Doc node's num_children() changed between invocations!")]
     NumChildrenChanged,
     #[error("Pretty printing encountered a Text or Literal after an EndOfLine.")]
@@ -233,8 +637,24 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
             doc,
             notation: &doc.notation()?.0,
             flat: false,
+            max_width: None,
             indent: None,
             join_pos: None,
+            grid_pos: None,
+            link: None,
+            style_label: None,
+            text_transform: None,
+            child_limit: None,
+            hidden_count: None,
+            text_piece: None,
+            error_style: None,
+            width_policy: &UnicodeWidth,
+            match_style: None,
+            entered: None,
+            left_neighbors: None,
+            right_neighbors: None,
+            vertical_space_probe: None,
+            vertical_space_lines: None,
             style: if let Some(style) = style {
                 D::Style::combine(style, &doc.node_style()?)
             } else {
@@ -247,6 +667,46 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
         &self.doc
     }
 
+    /// Display `ERROR_PLACEHOLDER` in this style in place of any child node that can't be accessed,
+    /// instead of failing.
+    pub fn set_error_style(&mut self, error_style: Option<Rc<D::Style>>) {
+        self.error_style = error_style;
+    }
+
+    /// Measure the width of text and indentation with this policy.
+    pub fn set_width_policy(&mut self, width_policy: &'d dyn WidthPolicy) {
+        self.width_policy = width_policy;
+    }
+
+    /// Display the nodes in `match_style` (including this one) in its style.
+    pub fn set_match_style(
+        &mut self,
+        match_style: Option<Rc<MatchStyle<D::Id, D::Style>>>,
+    ) -> Result<(), D::Error> {
+        if let Some(match_style) = &match_style {
+            if match_style.ids.contains(&self.doc.id()?) {
+                self.style = D::Style::combine(&self.style, &match_style.style);
+            }
+        }
+        self.match_style = match_style;
+        Ok(())
+    }
+
+    /// The smallest width of any `Notation::WithWidth` that this notation is inside of.
+    pub fn max_width(&self) -> Option<Width> {
+        self.max_width
+    }
+
+    /// The key to memoize this notation's first line under, if it came from a `Notation::Cache`.
+    pub fn cache_key(&self) -> Result<CacheKey<D::Id>, PrintingError<D::Error>> {
+        Ok(CacheKey {
+            id: self.doc.id()?,
+            notation: self.notation as *const _ as usize,
+            flat: self.flat,
+            join_index: self.join_pos.as_ref().map(|join_pos| join_pos.index),
+        })
+    }
+
     /// Expand this node to get a usable `ConsolidatedNotation`.
     pub fn eval(mut self) -> Result<ConsolidatedNotation<'d, D>, PrintingError<D::Error>> {
         use Notation::*;
@@ -255,25 +715,112 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
             Empty => Ok(ConsolidatedNotation::Empty),
             EndOfLine => Ok(ConsolidatedNotation::EndOfLine),
             Newline => Ok(ConsolidatedNotation::Newline(self.indent)),
+            VerticalSpace(min, max) => self.eval_vertical_space(*min, *max),
             Literal(str) => Ok(ConsolidatedNotation::Textual(Textual {
                 str,
-                width: str_width(str),
+                width: self.width_policy.str_width(str),
+                style: self.style,
+                is_from_text: false,
+                text_continues: false,
+                is_soft_space: false,
+                pad_to: None,
+                link: self.link,
+                style_label: self.style_label,
+            })),
+            PadTo(col) => Ok(ConsolidatedNotation::Textual(Textual {
+                str: "",
+                width: 0,
                 style: self.style,
                 is_from_text: false,
+                text_continues: false,
+                is_soft_space: false,
+                pad_to: Some(*col),
+                link: None,
+                style_label: self.style_label,
+            })),
+            Space => Ok(ConsolidatedNotation::Textual(Textual {
+                str: " ",
+                width: 1,
+                style: self.style,
+                is_from_text: false,
+                text_continues: false,
+                is_soft_space: true,
+                pad_to: None,
+                link: self.link,
+                style_label: self.style_label,
             })),
             Text => {
-                if self.doc.num_children()?.is_some() {
-                    Err(PrintingError::TextNotationOnTextlessDoc)
-                } else {
-                    let text = self.doc.unwrap_text()?;
-                    Ok(ConsolidatedNotation::Textual(Textual {
-                        str: text,
-                        width: str_width(text),
-                        style: self.style,
-                        is_from_text: true,
-                    }))
+                let text = self.text()?;
+                Ok(self.text_textual(text, false))
+            }
+            BreakableText { breaks, indent } => {
+                let text = self.text()?;
+                let piece = match self.text_piece {
+                    Some(piece) => piece,
+                    None => {
+                        let new_indent = Rc::new(IndentNode {
+                            segment: Segment {
+                                str: indent,
+                                width: self.width_policy.str_width(indent),
+                                style: self.style.clone(),
+                                kind: SegmentKind::Indentation,
+                                direction: Direction::of_str(indent),
+                                link: None,
+                                style_label: self.style_label,
+                            },
+                            parent: self.indent.take(),
+                        });
+                        self.indent = Some(new_indent);
+                        TextPiece::From(0)
+                    }
+                };
+                match piece {
+                    TextPiece::From(start) => match next_break(text, start, breaks) {
+                        None => Ok(self.text_textual(&text[start..], false)),
+                        Some(end) => {
+                            let mut cnote1 = self.clone();
+                            cnote1.text_piece = Some(TextPiece::Piece(start, end));
+                            let mut cnote2 = self;
+                            cnote2.text_piece = Some(TextPiece::Break(end));
+                            Ok(ConsolidatedNotation::Concat(cnote1, cnote2))
+                        }
+                    },
+                    TextPiece::Piece(start, end) => Ok(self.text_textual(&text[start..end], true)),
+                    TextPiece::Break(start) if self.flat => {
+                        self.text_piece = Some(TextPiece::From(start));
+                        self.eval()
+                    }
+                    TextPiece::Break(start) => {
+                        let mut cnote1 = self.clone();
+                        cnote1.text_piece = Some(TextPiece::From(start));
+                        let mut cnote2 = self;
+                        cnote2.text_piece = Some(TextPiece::NewlineThen(start));
+                        Ok(ConsolidatedNotation::Choice(cnote1, cnote2))
+                    }
+                    TextPiece::NewlineThen(start) => {
+                        let mut cnote1 = self.clone();
+                        cnote1.text_piece = Some(TextPiece::Newline);
+                        let mut cnote2 = self;
+                        cnote2.text_piece = Some(TextPiece::From(start));
+                        Ok(ConsolidatedNotation::Concat(cnote1, cnote2))
+                    }
+                    TextPiece::Newline => Ok(ConsolidatedNotation::Newline(self.indent)),
                 }
             }
+            Placeholder(label) => {
+                let text = self.doc.lookup_text(label.clone())?;
+                Ok(ConsolidatedNotation::Textual(Textual {
+                    str: text,
+                    width: self.width_policy.str_width(text),
+                    style: self.style,
+                    is_from_text: false,
+                    text_continues: false,
+                    is_soft_space: false,
+                    pad_to: None,
+                    link: self.link,
+                    style_label: self.style_label,
+                }))
+            }
             Flat(note) => {
                 self.flat = true;
                 self.notation = note;
@@ -281,15 +828,21 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
             }
             Indent(prefix, style_label, note) => {
                 let style = if let Some(label) = style_label {
-                    D::Style::combine(&self.style, &self.doc.lookup_style(label.clone())?)
+                    let depth = IndentNode::depth(&self.indent);
+                    let indent_style = self.doc.lookup_indent_style(label.clone(), depth)?;
+                    D::Style::combine(&self.style, &indent_style)
                 } else {
                     self.style.clone()
                 };
                 let new_indent = Rc::new(IndentNode {
                     segment: Segment {
                         str: prefix,
-                        width: str_width(prefix),
+                        width: self.width_policy.str_width(prefix),
                         style,
+                        kind: SegmentKind::Indentation,
+                        direction: Direction::of_str(prefix),
+                        link: None,
+                        style_label: style_label.as_ref().or(self.style_label),
                     },
                     parent: self.indent,
                 });
@@ -302,6 +855,7 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                 cnote1.notation = note1;
                 let mut cnote2 = self;
                 cnote2.notation = note2;
+                link_neighbors(&mut cnote1, &mut cnote2);
                 Ok(ConsolidatedNotation::Concat(cnote1, cnote2))
             }
             Choice(note1, _note2) if self.flat => {
@@ -309,6 +863,7 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                 self.eval()
             }
             Choice(note1, note2) => {
+                self.clear_neighbors();
                 let mut cnote1 = self.clone();
                 cnote1.notation = note1;
                 let mut cnote2 = self;
@@ -316,30 +871,47 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                 Ok(ConsolidatedNotation::Choice(cnote1, cnote2))
             }
             Check(cond, pos, note1, note2) => {
-                let doc_to_inspect = match pos {
-                    CheckPos::Here => self.doc,
-                    CheckPos::Child(i) => match self.doc.num_children()? {
-                        None => return Err(PrintingError::CheckPosChildOnChildlessDoc),
-                        Some(n) => match normalize_child_index(*i, n) {
-                            None => {
-                                return Err(PrintingError::CheckPosChildIndexOutOfBounds {
-                                    index: *i,
-                                    len: n,
-                                })
+                if self.check_condition(cond, pos)? {
+                    self.notation = note1;
+                    self.eval()
+                } else {
+                    self.notation = note2;
+                    self.eval()
+                }
+            }
+            CheckExpr(expr, note1, note2) => {
+                if self.check_expr(expr)? {
+                    self.notation = note1;
+                    self.eval()
+                } else {
+                    self.notation = note2;
+                    self.eval()
+                }
+            }
+            CheckJoin(cond, note1, note2) => {
+                // ValidNotation::validate() ensures this unwrap is safe
+                let join_pos = self.join_pos.as_ref().unwrap();
+                let holds = match cond {
+                    JoinCondition::IsFirstJoin => join_pos.index == 1,
+                    JoinCondition::IsLastJoin => join_pos.index + 1 == join_pos.children.len(),
+                    JoinCondition::IsLeftEmpty => {
+                        let (children, index) = (join_pos.children.clone(), join_pos.index);
+                        let mut all_empty = true;
+                        // Nearest first, since that's most likely to be nonempty.
+                        for i in (0..index).rev() {
+                            if !self.child_is_empty(children[i], i)? {
+                                all_empty = false;
+                                break;
                             }
-                            Some(index) => self.doc.unwrap_child(index)?,
-                        },
-                    },
-                    // ValidNotation::validate() ensures these unwraps are safe
-                    CheckPos::RightChild => self.join_pos.unwrap().child,
-                    CheckPos::LeftChild => {
-                        let join_pos = self.join_pos.unwrap();
-                        join_pos
-                            .child
-                            .unwrap_prev_sibling(join_pos.parent, join_pos.index - 1)?
+                        }
+                        all_empty
+                    }
+                    JoinCondition::IsRightEmpty => {
+                        let (child, index) = (join_pos.children[join_pos.index], join_pos.index);
+                        self.child_is_empty(child, index)?
                     }
                 };
-                if doc_to_inspect.condition(cond)? {
+                if holds {
                     self.notation = note1;
                     self.eval()
                 } else {
@@ -347,26 +919,64 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                     self.eval()
                 }
             }
-            Child(i) => match self.doc.num_children()? {
+            Child(i) => match self.num_children()? {
                 None => Err(PrintingError::ChildNotationOnChildlessDoc),
                 Some(n) => match normalize_child_index(*i, n) {
                     None => Err(PrintingError::ChildIndexOutOfBounds { index: *i, len: n }),
                     Some(index) => {
-                        self.doc = self.doc.unwrap_child(index)?;
-                        self.notation = &self.doc.notation()?.0;
-                        self.style = D::Style::combine(&self.style, &self.doc.node_style()?);
-                        Ok(ConsolidatedNotation::Child(index, self))
+                        let entered = self
+                            .doc
+                            .unwrap_child(index)
+                            .and_then(|child| self.enter_child(child, index));
+                        match entered {
+                            Ok(()) => {
+                                self.check_for_loop()?;
+                                Ok(ConsolidatedNotation::Child(index, self))
+                            }
+                            Err(err) => self.substitute_error(err),
+                        }
                     }
                 },
             },
             Style(style_label, note) => {
                 self.style =
                     D::Style::combine(&self.style, &self.doc.lookup_style(style_label.clone())?);
+                self.style_label = Some(style_label);
+                self.notation = note;
+                self.eval()
+            }
+            DepthStyle(style_label, note) => {
+                let depth = self.entered.as_ref().map_or(0, |node| node.depth);
+                let style = self.doc.lookup_depth_style(style_label.clone(), depth)?;
+                self.style = D::Style::combine(&self.style, &style);
+                self.style_label = Some(style_label);
+                self.notation = note;
+                self.eval()
+            }
+            MapText(text_label, note) => {
+                self.text_transform = Some(text_label.clone());
+                self.notation = note;
+                self.eval()
+            }
+            Link(link_label, note) => {
+                if let Some(link) = self.doc.lookup_link(link_label.clone())? {
+                    self.link = Some(link);
+                }
                 self.notation = note;
                 self.eval()
             }
             FocusMark => Ok(ConsolidatedNotation::FocusMark),
-            Count { zero, one, many } => match self.doc.num_children()? {
+            Cache(note) => {
+                self.notation = note;
+                self.clear_neighbors();
+                Ok(ConsolidatedNotation::Cache(self))
+            }
+            WithWidth(width, note) => {
+                self.max_width = Some(self.max_width.map_or(*width, |w| w.min(*width)));
+                self.notation = note;
+                self.eval()
+            }
+            Count { zero, one, many } => match self.num_children()? {
                 None => Err(PrintingError::CountNotationOnChildlessDoc),
                 Some(0) => {
                     self.notation = zero;
@@ -381,7 +991,54 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                     self.eval()
                 }
             },
-            Fold { first, join } => match self.doc.num_children()? {
+            Arity { cases, fallback } => match self.num_children()? {
+                None => Err(PrintingError::CountNotationOnChildlessDoc),
+                Some(n) => {
+                    self.notation = cases
+                        .iter()
+                        .find(|(arity, _)| *arity == n)
+                        .map(|(_, note)| note)
+                        .unwrap_or(fallback);
+                    self.eval()
+                }
+            },
+            Summarize {
+                limit,
+                notation,
+                summary,
+            } => match self.num_children()? {
+                None => Err(PrintingError::CountNotationOnChildlessDoc),
+                Some(n) if n <= *limit => {
+                    self.notation = notation;
+                    self.eval()
+                }
+                Some(n) => {
+                    let mut cnote1 = self.clone();
+                    cnote1.notation = notation;
+                    cnote1.child_limit = Some(*limit);
+                    let mut cnote2 = self;
+                    cnote2.notation = summary;
+                    cnote2.child_limit = Some(*limit);
+                    cnote2.hidden_count = Some(n - limit);
+                    link_neighbors(&mut cnote1, &mut cnote2);
+                    Ok(ConsolidatedNotation::Concat(cnote1, cnote2))
+                }
+            },
+            HiddenCount => {
+                let str = count_str(self.hidden_count.unwrap_or(0));
+                Ok(ConsolidatedNotation::Textual(Textual {
+                    str,
+                    width: self.width_policy.str_width(str),
+                    style: self.style,
+                    is_from_text: false,
+                    text_continues: false,
+                    is_soft_space: false,
+                    pad_to: None,
+                    link: self.link,
+                    style_label: self.style_label,
+                }))
+            }
+            Fold { first, join } => match self.num_children()? {
                 None => Err(PrintingError::NumChildrenChanged),
                 Some(0) => Ok(ConsolidatedNotation::Empty),
                 Some(1) => {
@@ -389,9 +1046,12 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                     self.eval()
                 }
                 Some(n) => {
+                    let children = match self.doc.unwrap_children(0..n) {
+                        Ok(children) => children,
+                        Err(err) => return self.substitute_error(err),
+                    };
                     self.join_pos = Some(JoinPos {
-                        parent: self.doc,
-                        child: self.doc.unwrap_last_child()?,
+                        children: children.into(),
                         index: n - 1,
                         first,
                         join,
@@ -400,23 +1060,51 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                     self.eval()
                 }
             },
+            Columns { max_columns, gap } => match self.grid_pos.take() {
+                None => {
+                    let n = match self.num_children()? {
+                        None => return Err(PrintingError::CountNotationOnChildlessDoc),
+                        Some(0) => return Ok(ConsolidatedNotation::Empty),
+                        Some(n) => n,
+                    };
+                    let children = match self.doc.unwrap_children(0..n) {
+                        Ok(children) => children,
+                        Err(err) => return self.substitute_error(err),
+                    };
+                    let mut widths = Some(Vec::with_capacity(n));
+                    for (index, child) in children.iter().enumerate() {
+                        match self.child_flat_width(*child, index)? {
+                            Some(width) => widths.as_mut().unwrap().push(width),
+                            None => {
+                                widths = None;
+                                break;
+                            }
+                        }
+                    }
+                    self.grid_pos = Some(GridPos {
+                        grid: Rc::new(Grid {
+                            children,
+                            widths,
+                            gap: *gap,
+                        }),
+                        piece: GridPiece::Columns((*max_columns).clamp(1, n)),
+                    });
+                    self.eval()
+                }
+                Some(grid_pos) => self.eval_grid(grid_pos),
+            },
             Left => match &mut self.join_pos {
                 None => {
                     panic!("Bug: Left used outside of fold; should have been caught by validation")
                 }
                 Some(JoinPos {
-                    parent,
-                    child,
-                    index,
-                    first,
-                    join,
+                    index, first, join, ..
                 }) => {
                     if *index == 1 {
                         self.notation = *first;
                         self.join_pos = None;
                         self.eval()
                     } else {
-                        *child = child.unwrap_prev_sibling(*parent, *index - 1)?;
                         *index -= 1;
                         self.notation = *join;
                         self.eval()
@@ -427,17 +1115,619 @@ impl<'d, D: PrettyDoc<'d>> DelayedConsolidatedNotation<'d, D> {
                 None => {
                     panic!("Bug: Right used outside of fold; should have been caught by validation")
                 }
-                Some(JoinPos { child, index, .. }) => {
-                    let index = *index;
-                    self.doc = *child;
-                    self.notation = &child.notation()?.0;
-                    self.style = D::Style::combine(&self.style, &self.doc.node_style()?);
+                Some(JoinPos {
+                    children, index, ..
+                }) => {
+                    let (child, index) = (children[*index], *index);
                     self.join_pos = None;
-                    Ok(ConsolidatedNotation::Child(index, self))
+                    match self.enter_child(child, index) {
+                        Ok(()) => {
+                            self.check_for_loop()?;
+                            Ok(ConsolidatedNotation::Child(index, self))
+                        }
+                        Err(err) => self.substitute_error(err),
+                    }
                 }
             },
         }
     }
+
+    /// The current node's text, transformed by the innermost `Notation::MapText` (if any).
+    fn text(&self) -> Result<&'d str, PrintingError<D::Error>> {
+        if self.doc.num_children()?.is_some() {
+            return Err(PrintingError::TextNotationOnTextlessDoc);
+        }
+        let text = self.doc.unwrap_text()?;
+        Ok(match self.text_transform.clone() {
+            Some(label) => self.doc.transform_text(label, text)?,
+            None => text,
+        })
+    }
+
+    /// Display `str`, which is (part of) the current node's text.
+    fn text_textual(self, str: &'d str, text_continues: bool) -> ConsolidatedNotation<'d, D> {
+        ConsolidatedNotation::Textual(Textual {
+            str,
+            width: self.width_policy.str_width(str),
+            style: self.style,
+            is_from_text: true,
+            text_continues,
+            is_soft_space: false,
+            pad_to: None,
+            link: self.link,
+            style_label: self.style_label,
+        })
+    }
+
+    /// The number of children the current node has, as far as its notation can tell: a
+    /// `Notation::Summarize` can hide some of them.
+    fn num_children(&self) -> Result<Option<usize>, D::Error> {
+        Ok(match (self.doc.num_children()?, self.child_limit) {
+            (Some(n), Some(limit)) => Some(n.min(limit)),
+            (num_children, _) => num_children,
+        })
+    }
+
+    /// Make this the notation of the `child` node, which is the `index`'th child of the current
+    /// node.
+    fn enter_child(&mut self, child: D, index: usize) -> Result<(), D::Error> {
+        let id = child.id()?;
+        self.notation = &child.notation()?.0;
+        self.style = D::Style::combine(&self.style, &child.node_style()?);
+        if let Some(match_style) = &self.match_style {
+            if match_style.ids.contains(&id) {
+                self.style = D::Style::combine(&self.style, &match_style.style);
+            }
+        }
+        self.doc = child;
+        self.child_limit = None;
+        self.hidden_count = None;
+        let parent = self.entered.take();
+        self.entered = Some(Rc::new(EnteredNode {
+            id,
+            notation: self.notation as *const _ as usize,
+            index,
+            depth: parent.as_ref().map_or(1, |parent| parent.depth + 1),
+            parent,
+        }));
+        Ok(())
+    }
+
+    /// Evaluate part of a `Notation::Columns` layout.
+    fn eval_grid(
+        mut self,
+        grid_pos: GridPos<D>,
+    ) -> Result<ConsolidatedNotation<'d, D>, PrintingError<D::Error>> {
+        let GridPos { grid, piece } = grid_pos;
+        let len = grid.children.len();
+        let with_piece = |note: &Self, piece: GridPiece| {
+            let mut note = note.clone();
+            note.grid_pos = Some(GridPos {
+                grid: grid.clone(),
+                piece,
+            });
+            note
+        };
+        match piece {
+            GridPiece::Columns(_) if self.flat => with_piece(
+                &self,
+                GridPiece::Cells {
+                    columns: len,
+                    cell: 0,
+                },
+            )
+            .eval(),
+            GridPiece::Columns(columns) if columns <= 1 || grid.widths.is_none() => with_piece(
+                &self,
+                GridPiece::Cells {
+                    columns: 1,
+                    cell: 0,
+                },
+            )
+            .eval(),
+            // Skip column counts that would leave the last column empty, since they have the same
+            // number of rows as fewer columns.
+            GridPiece::Columns(columns) if grid.num_rows(columns) * (columns - 1) >= len => {
+                with_piece(&self, GridPiece::Columns(columns - 1)).eval()
+            }
+            GridPiece::Columns(columns) => {
+                self.clear_neighbors();
+                Ok(ConsolidatedNotation::Choice(
+                    with_piece(&self, GridPiece::Cells { columns, cell: 0 }),
+                    with_piece(&self, GridPiece::Columns(columns - 1)),
+                ))
+            }
+            GridPiece::Cells { columns, cell } => {
+                let rows = grid.num_rows(columns);
+                let (row, col) = (cell / columns, cell % columns);
+                let index = col * rows + row;
+                let mut child = with_piece(
+                    &self,
+                    GridPiece::Child {
+                        index,
+                        flat: columns > 1,
+                    },
+                );
+                let row_continues = col + 1 < columns && index + rows < len;
+                if row_continues || row + 1 < rows {
+                    let mut rest = with_piece(&self, GridPiece::AfterCell { columns, cell });
+                    link_neighbors(&mut child, &mut rest);
+                    Ok(ConsolidatedNotation::Concat(child, rest))
+                } else {
+                    child.eval()
+                }
+            }
+            GridPiece::AfterCell { columns, cell } => {
+                let rows = grid.num_rows(columns);
+                let (row, col) = (cell / columns, cell % columns);
+                let index = col * rows + row;
+                let (mut before, next_cell) = if col + 1 < columns && index + rows < len {
+                    let padding = grid.column_width(columns, col) - grid.width(index) + grid.gap;
+                    (with_piece(&self, GridPiece::Padding(padding)), cell + 1)
+                } else {
+                    (with_piece(&self, GridPiece::Newline), (row + 1) * columns)
+                };
+                let mut rest = with_piece(
+                    &self,
+                    GridPiece::Cells {
+                        columns,
+                        cell: next_cell,
+                    },
+                );
+                link_neighbors(&mut before, &mut rest);
+                Ok(ConsolidatedNotation::Concat(before, rest))
+            }
+            GridPiece::Child { index, flat } => {
+                let child = grid.children[index];
+                self.flat |= flat;
+                match self.enter_child(child, index) {
+                    Ok(()) => {
+                        self.check_for_loop()?;
+                        Ok(ConsolidatedNotation::Child(index, self))
+                    }
+                    Err(err) => self.substitute_error(err),
+                }
+            }
+            GridPiece::Padding(width) if width as usize > SPACES.len() => {
+                Ok(ConsolidatedNotation::Concat(
+                    with_piece(&self, GridPiece::Padding(SPACES.len() as Width)),
+                    with_piece(&self, GridPiece::Padding(width - SPACES.len() as Width)),
+                ))
+            }
+            GridPiece::Padding(width) => {
+                let str = &SPACES[..width as usize];
+                Ok(ConsolidatedNotation::Textual(Textual {
+                    str,
+                    width,
+                    style: self.style,
+                    is_from_text: false,
+                    text_continues: false,
+                    is_soft_space: false,
+                    pad_to: None,
+                    link: None,
+                    style_label: self.style_label,
+                }))
+            }
+            GridPiece::Newline => Ok(ConsolidatedNotation::Newline(self.indent)),
+        }
+    }
+
+    /// The width of the `child` node, which is the `index`'th child of the current node, when it's
+    /// displayed flat, for `Notation::Columns`. Returns `None` if it can't be displayed flat
+    /// because it contains a `Newline` or `EndOfLine` that isn't inside a `Choice`.
+    fn child_flat_width(
+        &self,
+        child: D,
+        index: usize,
+    ) -> Result<Option<Width>, PrintingError<D::Error>> {
+        use ConsolidatedNotation::*;
+
+        let mut note = self.clone();
+        note.join_pos = None;
+        note.grid_pos = None;
+        note.flat = true;
+        note.clear_neighbors();
+        match note.enter_child(child, index) {
+            Ok(()) => note.check_for_loop()?,
+            Err(_) if self.error_style.is_some() => {
+                return Ok(Some(self.width_policy.str_width(ERROR_PLACEHOLDER)))
+            }
+            Err(err) => return Err(PrintingError::PrettyDoc(err)),
+        }
+        let mut width = 0;
+        let mut notes = vec![note];
+        while let Some(note) = notes.pop() {
+            match note.eval()? {
+                Empty | FocusMark => (),
+                Textual(textual) => width += textual.width,
+                EndOfLine | Newline(_) => return Ok(None),
+                Child(_, note) | Cache(note) => notes.push(note),
+                // Inside a `Flat`, only a `BreakableText` makes a choice, and its first option is
+                // the flat one.
+                Choice(note, _) => notes.push(note),
+                Concat(note1, note2) => {
+                    notes.push(note2);
+                    notes.push(note1);
+                }
+            }
+        }
+        Ok(Some(width))
+    }
+
+    /// Whether the condition holds for the document node at `pos`, for `Notation::Check`.
+    fn check_condition(
+        &self,
+        cond: &D::Condition,
+        pos: &CheckPos,
+    ) -> Result<bool, PrintingError<D::Error>> {
+        let doc_to_inspect = match pos {
+            CheckPos::Here => self.doc,
+            CheckPos::Child(i) => match self.num_children()? {
+                None => return Err(PrintingError::CheckPosChildOnChildlessDoc),
+                Some(n) => match normalize_child_index(*i, n) {
+                    None => {
+                        return Err(PrintingError::CheckPosChildIndexOutOfBounds {
+                            index: *i,
+                            len: n,
+                        })
+                    }
+                    Some(index) => self.doc.unwrap_child(index)?,
+                },
+            },
+            // ValidNotation::validate() ensures these unwraps are safe
+            CheckPos::RightChild => {
+                let join_pos = self.join_pos.as_ref().unwrap();
+                join_pos.children[join_pos.index]
+            }
+            CheckPos::LeftChild => {
+                let join_pos = self.join_pos.as_ref().unwrap();
+                join_pos.children[join_pos.index - 1]
+            }
+        };
+        match doc_to_inspect.condition(cond) {
+            Ok(holds) => Ok(holds),
+            // The node is broken, so it'll be displayed as a placeholder anyways.
+            Err(_) if self.error_style.is_some() => Ok(false),
+            Err(err) => Err(PrintingError::PrettyDoc(err)),
+        }
+    }
+
+    /// Whether the expression holds, for `Notation::CheckExpr`.
+    fn check_expr(
+        &self,
+        expr: &ConditionExpr<D::Condition>,
+    ) -> Result<bool, PrintingError<D::Error>> {
+        Ok(match expr {
+            ConditionExpr::Holds(cond, pos) => self.check_condition(cond, pos)?,
+            ConditionExpr::Not(expr) => !self.check_expr(expr)?,
+            ConditionExpr::All(exprs) => {
+                for expr in exprs {
+                    if !self.check_expr(expr)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            ConditionExpr::Any(exprs) => {
+                for expr in exprs {
+                    if self.check_expr(expr)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+        })
+    }
+
+    /// Whether the `child` node, which is the `index`'th child of the current node, displays as
+    /// nothing (no text and no newlines) in every layout, for `JoinCondition::IsLeftEmpty` and
+    /// `JoinCondition::IsRightEmpty`. A child that can't be accessed isn't empty, since it's either
+    /// an error or displayed as `ERROR_PLACEHOLDER`.
+    fn child_is_empty(&self, child: D, index: usize) -> Result<bool, PrintingError<D::Error>> {
+        use ConsolidatedNotation::*;
+
+        let mut note = self.clone();
+        note.join_pos = None;
+        note.clear_neighbors();
+        match note.enter_child(child, index) {
+            Ok(()) => note.check_for_loop()?,
+            Err(_) if self.error_style.is_some() => return Ok(false),
+            Err(err) => return Err(PrintingError::PrettyDoc(err)),
+        }
+        let mut notes = vec![note];
+        while let Some(note) = notes.pop() {
+            match note.eval()? {
+                Empty | EndOfLine | FocusMark => (),
+                Textual(textual) if textual.str.is_empty() => (),
+                Textual(_) | Newline(_) => return Ok(false),
+                Child(_, note) | Cache(note) => notes.push(note),
+                Concat(note1, note2) | Choice(note1, note2) => {
+                    notes.push(note2);
+                    notes.push(note1);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Display a `Notation::VerticalSpace(min, max)`. Only the last of a run of adjacent vertical
+    /// spaces is displayed, on behalf of the whole run; the others display nothing.
+    fn eval_vertical_space(
+        mut self,
+        min: usize,
+        max: usize,
+    ) -> Result<ConsolidatedNotation<'d, D>, PrintingError<D::Error>> {
+        if let Some(lines) = self.vertical_space_lines {
+            if lines <= 1 {
+                return Ok(ConsolidatedNotation::Newline(self.indent));
+            }
+            let mut first = self.clone();
+            first.vertical_space_lines = Some(1);
+            self.vertical_space_lines = Some(lines - 1);
+            return Ok(ConsolidatedNotation::Concat(first, self));
+        }
+        if let Some(probe) = &self.vertical_space_probe {
+            probe.set(Some((min, max)));
+            return Ok(ConsolidatedNotation::Empty);
+        }
+
+        let mut run = None;
+        scan_vertical_space(&self.right_neighbors, true, &mut run)?;
+        if run.is_some() {
+            return Ok(ConsolidatedNotation::Empty);
+        }
+        let mut run = Some((min, max));
+        scan_vertical_space(&self.left_neighbors, false, &mut run)?;
+        let (min, max) = run.unwrap();
+        self.clear_neighbors();
+        self.vertical_space_lines = Some(min.min(max) + 1);
+        self.eval_vertical_space(min, max)
+    }
+
+    fn clear_neighbors(&mut self) {
+        self.left_neighbors = None;
+        self.right_neighbors = None;
+    }
+
+    /// Fail with `PrintingError::NotationLoop` if the node that was just entered is its own
+    /// ancestor (with the same notation), since then the printer would never finish with it.
+    fn check_for_loop(&self) -> Result<(), PrintingError<D::Error>> {
+        let node = match &self.entered {
+            Some(node) => node,
+            None => return Ok(()),
+        };
+        if node.depth < MIN_LOOP_CHECK_DEPTH || !node.depth.is_power_of_two() {
+            return Ok(());
+        }
+
+        // Report the outermost occurrence of the node, and the path from it to the next one.
+        let mut chain = Vec::with_capacity(node.depth);
+        let mut ancestor = Some(node);
+        while let Some(anc) = ancestor {
+            chain.push(anc);
+            ancestor = anc.parent.as_ref();
+        }
+        chain.reverse();
+        let is_repeat =
+            |n: &&Rc<EnteredNode<D::Id>>| n.id == node.id && n.notation == node.notation;
+        let outer = chain.iter().position(is_repeat).unwrap();
+        if outer + 1 == chain.len() {
+            return Ok(());
+        }
+        let next = outer + 1 + chain[outer + 1..].iter().position(is_repeat).unwrap();
+        let indices = chain.iter().map(|n| n.index).collect::<Vec<_>>();
+        Err(PrintingError::NotationLoop {
+            path: indices[..=outer].to_vec(),
+            cycle: indices[outer + 1..=next].to_vec(),
+        })
+    }
+
+    /// Accessing a child node failed with `error`. Either display `ERROR_PLACEHOLDER` instead, or
+    /// fail, depending on whether there's an `error_style`.
+    fn substitute_error(
+        &self,
+        error: D::Error,
+    ) -> Result<ConsolidatedNotation<'d, D>, PrintingError<D::Error>> {
+        match &self.error_style {
+            None => Err(PrintingError::PrettyDoc(error)),
+            Some(error_style) => Ok(ConsolidatedNotation::Textual(Textual {
+                str: ERROR_PLACEHOLDER,
+                width: self.width_policy.str_width(ERROR_PLACEHOLDER),
+                style: D::Style::combine(&self.style, error_style),
+                is_from_text: false,
+                text_continues: false,
+                is_soft_space: false,
+                pad_to: None,
+                link: None,
+                style_label: None,
+            })),
+        }
+    }
+}
+
+/// The position just after the first occurrence of any of the `breaks` in `text` from `start`
+/// onwards, if there is one before the end of the text.
+/// Tell each of two notations that are about to be concatenated about the other, so that a
+/// `Notation::VerticalSpace` on one side can collapse with vertical space on the other side.
+fn link_neighbors<'d, D: PrettyDoc<'d>>(
+    note1: &mut DelayedConsolidatedNotation<'d, D>,
+    note2: &mut DelayedConsolidatedNotation<'d, D>,
+) {
+    if note1.vertical_space_probe.is_some() {
+        return;
+    }
+    // Most notations never display vertical space, so skip keeping track of their neighbors. And a
+    // notation that displays something visible at its edge hides everything beyond it, just like
+    // the edge of the document does.
+    let needs_left = uses_neighbors(note2.notation, note2.flat, true);
+    let needs_right = uses_neighbors(note1.notation, note1.flat, false);
+    if needs_left {
+        note2.left_neighbors = if is_barrier(note1.notation, note1.flat, false) {
+            None
+        } else {
+            let mut left = note1.clone();
+            left.clear_neighbors();
+            Some(Rc::new(Neighbor {
+                note: left,
+                next: note2.left_neighbors.take(),
+            }))
+        };
+    }
+    if needs_right {
+        note1.right_neighbors = if is_barrier(note2.notation, note2.flat, true) {
+            None
+        } else {
+            let mut right = note2.clone();
+            right.clear_neighbors();
+            Some(Rc::new(Neighbor {
+                note: right,
+                next: note1.right_neighbors.take(),
+            }))
+        };
+    }
+}
+
+/// Whether a `Notation::VerticalSpace` inside of `notation` (inside a `Flat` if `flat`) might
+/// look past its start (if `at_start`) or end at its neighbors.
+fn uses_neighbors<L: StyleLabel, C: Condition>(
+    notation: &Notation<L, C>,
+    flat: bool,
+    at_start: bool,
+) -> bool {
+    use Notation::*;
+
+    match notation {
+        Empty | EndOfLine | Newline | Literal(_) | Space | PadTo(_) | Text | FocusMark => false,
+        BreakableText { .. } | Placeholder(_) | HiddenCount | Cache(_) => false,
+        Choice(note, _) if flat => uses_neighbors(note, flat, at_start),
+        Choice(_, _) => false,
+        Flat(note) => uses_neighbors(note, true, at_start),
+        Indent(_, _, note)
+        | Style(_, note)
+        | DepthStyle(_, note)
+        | MapText(_, note)
+        | Link(_, note)
+        | WithWidth(_, note) => uses_neighbors(note, flat, at_start),
+        Concat(note1, note2) => {
+            let (near, far) = if at_start {
+                (note1, note2)
+            } else {
+                (note2, note1)
+            };
+            uses_neighbors(near, flat, at_start)
+                || (!is_barrier(near, flat, !at_start) && uses_neighbors(far, flat, at_start))
+        }
+        _ => true,
+    }
+}
+
+/// Whether `notation` (inside a `Flat` if `flat`) certainly displays something at its start (if
+/// `at_start`) or end, that vertical space can't collapse across.
+fn is_barrier<L: StyleLabel, C: Condition>(
+    notation: &Notation<L, C>,
+    flat: bool,
+    at_start: bool,
+) -> bool {
+    use Notation::*;
+
+    match notation {
+        Newline | Space | PadTo(_) | HiddenCount | Cache(_) => true,
+        Literal(str) => !str.is_empty(),
+        Choice(_, _) => !flat,
+        Flat(note) => is_barrier(note, true, at_start),
+        Indent(_, _, note)
+        | Style(_, note)
+        | DepthStyle(_, note)
+        | MapText(_, note)
+        | Link(_, note)
+        | WithWidth(_, note) => is_barrier(note, flat, at_start),
+        Concat(note1, _) if at_start => is_barrier(note1, flat, at_start),
+        Concat(_, note2) => is_barrier(note2, flat, at_start),
+        _ => false,
+    }
+}
+
+/// Look through `neighbors` (forwards or backwards) for `Notation::VerticalSpace`s that are only
+/// separated by things that display nothing, and merge their `(min, max)`s into `run`. Looking
+/// forwards, stop at the first one.
+fn scan_vertical_space<'d, D: PrettyDoc<'d>>(
+    neighbors: &Option<Rc<Neighbor<'d, D>>>,
+    forwards: bool,
+    run: &mut Option<(usize, usize)>,
+) -> Result<(), PrintingError<D::Error>> {
+    use ConsolidatedNotation::*;
+
+    let probe = Rc::new(Cell::new(None));
+    let mut neighbor = neighbors.as_deref();
+    while let Some(Neighbor { note, next }) = neighbor {
+        let mut note = note.clone();
+        note.vertical_space_probe = Some(probe.clone());
+        let mut notes = vec![note];
+        while let Some(note) = notes.pop() {
+            let cnote = note.eval()?;
+            if let Some((min, max)) = probe.take() {
+                *run = Some(match *run {
+                    None => (min, max),
+                    Some((run_min, run_max)) => (run_min.max(min), run_max.min(max)),
+                });
+                if forwards {
+                    return Ok(());
+                }
+                continue;
+            }
+            match cnote {
+                Empty | EndOfLine | FocusMark => (),
+                Textual(textual) if textual.str.is_empty() && textual.pad_to.is_none() => (),
+                // Vertical space doesn't collapse across a `Choice` or `Cache`.
+                Textual(_) | Newline(_) | Choice(_, _) | Cache(_) => return Ok(()),
+                Child(_, note) => notes.push(note),
+                Concat(note1, note2) if forwards => {
+                    notes.push(note2);
+                    notes.push(note1);
+                }
+                Concat(note1, note2) => {
+                    notes.push(note1);
+                    notes.push(note2);
+                }
+            }
+        }
+        neighbor = next.as_deref();
+    }
+    Ok(())
+}
+
+fn next_break(text: &str, start: usize, breaks: &[String]) -> Option<usize> {
+    breaks
+        .iter()
+        .filter(|brk| !brk.is_empty())
+        .filter_map(|brk| {
+            text[start..]
+                .find(brk.as_str())
+                .map(|i| start + i + brk.len())
+        })
+        .filter(|end| *end < text.len())
+        .min()
+}
+
+/// Spaces for padding the cells of a `Notation::Columns`.
+const SPACES: &str = "                                                                ";
+
+/// The decimal representation of `n`, for `Notation::HiddenCount`. Segments borrow their text for
+/// as long as the document, so each distinct number is allocated once and then kept.
+fn count_str(n: usize) -> &'static str {
+    static DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+    static COUNTS: Mutex<BTreeMap<usize, &'static str>> = Mutex::new(BTreeMap::new());
+
+    if n < DIGITS.len() {
+        return DIGITS[n];
+    }
+    #[cfg(feature = "std")]
+    let mut counts = COUNTS.lock().unwrap();
+    #[cfg(not(feature = "std"))]
+    let mut counts = COUNTS.lock();
+    counts
+        .entry(n)
+        .or_insert_with(|| Box::leak(n.to_string().into_boxed_str()))
 }
 
 // For debugging. Should match impl fmt::Display for Notation.
@@ -450,10 +1740,14 @@ impl<'d, D: PrettyDoc<'d>> fmt::Display for ConsolidatedNotation<'d, D> {
             EndOfLine => write!(f, "EOL"),
             FocusMark => write!(f, "MARK"),
             Newline(_) => write!(f, "↵"),
-            Textual(textual) => write!(f, "'{}'", textual.str),
+            Textual(textual) => match textual.pad_to {
+                Some(col) => write!(f, "PAD({})", col),
+                None => write!(f, "'{}'", textual.str),
+            },
             Concat(left, right) => write!(f, "{} + {}", left, right),
             Choice(opt1, opt2) => write!(f, "({} | {})", opt1, opt2),
             Child(i, _) => write!(f, "${}", i),
+            Cache(note) => write!(f, "Cache({})", note),
         }
     }
 }
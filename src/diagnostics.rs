@@ -0,0 +1,81 @@
+//! Render compiler-style messages that point at part of a document, like:
+//!
+//! ```text
+//! error: expected a number
+//!  |
+//!  |     "two",
+//!  |     ^^^^^
+//! ```
+//!
+//! The printer finds where the part is displayed by focusing on it, so this works no matter how
+//! the document's notations lay it out, and only prints the lines that are shown.
+
+use crate::{pretty_print, FocusTarget, PrettyDoc, PrintingError, Width};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+/// How serious a [`Diagnostic`] is. This is only used to label the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A message about part of a document, for [`render_diagnostic()`].
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// The path to the node that the message is about, as in [`pretty_print()`].
+    pub path: Vec<usize>,
+    /// Where in that node the underlined span starts.
+    pub focus_target: FocusTarget,
+    /// How many columns to underline, starting at the focus. The underline stops at the end of
+    /// the line, and is always at least one column wide.
+    pub span_len: Width,
+    pub message: String,
+    pub severity: Severity,
+}
+
+/// Render `diagnostic` as a snippet of `doc` printed at the given `width`: a header with its
+/// severity and message, then the line containing the focus (with up to `context` lines above it),
+/// then carets underlining the span. Every line of the snippet is prefixed with `" | "`.
+pub fn render_diagnostic<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    diagnostic: &Diagnostic,
+    context: usize,
+) -> Result<String, PrintingError<D::Error>> {
+    let (lines_above, focused_line, _) =
+        pretty_print(doc, width, &diagnostic.path, diagnostic.focus_target, None)?;
+    let mut context_lines = lines_above
+        .take(context)
+        .map(|line| Ok(line?.to_string()))
+        .collect::<Result<Vec<_>, PrintingError<D::Error>>>()?;
+    context_lines.reverse();
+
+    let mut output = format!("{}: {}\n |\n", diagnostic.severity, diagnostic.message);
+    for line in context_lines {
+        output.push_str(&format!(" | {}\n", line));
+    }
+    output.push_str(&format!(" | {}\n", focused_line.to_string()));
+    let underline_len = diagnostic.span_len.min(focused_line.right_width()).max(1);
+    output.push_str(&format!(
+        " | {}{}",
+        " ".repeat(focused_line.left_width() as usize),
+        "^".repeat(underline_len as usize)
+    ));
+    Ok(output)
+}
@@ -8,8 +8,8 @@ use super::{
     tree::{Tree, TreeCondition, TreeNotation},
 };
 use crate::notation_constructors::{
-    check, child, count, empty, eol, flat, fold, indent, left, lit, mark, nl, right, style, text,
-    Count, Fold,
+    cache, check, child, count, empty, eol, flat, fold, indent, left, lit, mark, nl, right, style,
+    text, Count, Fold,
 };
 use crate::CheckPos;
 use once_cell::sync::Lazy;
@@ -37,69 +37,72 @@ static JSON_STRING_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
 static JSON_NUMBER_NOTATION: Lazy<TreeNotation> =
     Lazy::new(|| style(NUMBER_STYLE, text()).validate().unwrap());
 
-static JSON_ARRAY_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
-    let single_seq = fold(Fold {
-        first: flat(child(0)),
-        join: left() + lit(", ") + flat(right()),
-    });
-    let single = style("open", lit("[")) + single_seq + style("close", lit("]"));
-
-    let separator = check(
-        TreeCondition::NeedsSeparator,
-        CheckPos::LeftChild,
-        lit(","),
-        empty(),
-    );
-    let multi_seq = 4
-        >> fold(Fold {
-            first: child(0),
-            join: left() + separator ^ right(),
-        });
-    let multi = style("open", lit("[")) + multi_seq ^ style("close", lit("]"));
-
-    let array = single | multi;
-
-    count(Count {
-        zero: style("open", lit("[")) + mark() + style("close", lit("]")),
-        one: array.clone(),
-        many: array,
-    })
-    .validate()
-    .unwrap()
-});
+static JSON_ARRAY_NOTATION: Lazy<TreeNotation> = Lazy::new(|| sequence_notation("[", "]"));
 
 static JSON_OBJECT_PAIR_NOTATION: Lazy<TreeNotation> =
     Lazy::new(|| (child(0) + lit(": ") + child(1)).validate().unwrap());
 
-static JSON_OBJECT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+static JSON_OBJECT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| sequence_notation("{", "}"));
+
+/// The notation for an array or object: its comma-separated elements on one line if they fit, and
+/// one per line otherwise. If its [`LayoutHint`](super::tree::LayoutHint) says that it was on multiple lines originally,
+/// it stays on multiple lines, and if it had a trailing comma originally, it keeps it (when on
+/// multiple lines).
+fn sequence_notation(open: &'static str, close: &'static str) -> TreeNotation {
     let single_seq = fold(Fold {
         first: flat(child(0)),
         join: left() + lit(", ") + flat(right()),
     });
-    let single = style("open", lit("{")) + single_seq + style("close", lit("}"));
-
+    let single = style("open", lit(open)) + single_seq + style("close", lit(close));
+
+    // The last non-comment element doesn't need a separator, but keeps a trailing one. Comments
+    // never get one.
+    let had_trailing_separator = |if_so| {
+        check(
+            TreeCondition::HadTrailingSeparator,
+            CheckPos::Here,
+            if_so,
+            empty(),
+        )
+    };
     let separator = check(
         TreeCondition::NeedsSeparator,
         CheckPos::LeftChild,
         lit(","),
-        empty(),
+        check(
+            TreeCondition::IsComment,
+            CheckPos::LeftChild,
+            empty(),
+            had_trailing_separator(lit(",")),
+        ),
     );
+    let trailing_separator = had_trailing_separator(check(
+        TreeCondition::IsComment,
+        CheckPos::Child(-1),
+        empty(),
+        lit(","),
+    ));
     let multi_seq = fold(Fold {
         first: child(0),
         join: left() + separator ^ right(),
-    });
-    let multi = style("open", lit("{")) + (4 >> multi_seq) ^ style("close", lit("}"));
-
-    let object = single | multi;
+    }) + trailing_separator;
+    let multi = style("open", lit(open)) + (4 >> multi_seq) ^ style("close", lit(close));
+
+    let seq = check(
+        TreeCondition::WasMultiline,
+        CheckPos::Here,
+        multi.clone(),
+        single | multi,
+    );
 
-    count(Count {
-        zero: style("open", lit("{")) + mark() + style("close", lit("}")),
-        one: object.clone(),
-        many: object,
-    })
+    cache(count(Count {
+        zero: style("open", lit(open)) + mark() + style("close", lit(close)),
+        one: seq.clone(),
+        many: seq,
+    }))
     .validate()
     .unwrap()
-});
+}
 
 static JSON_COMMENT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
     let comment_body = count(Count {
@@ -154,9 +157,56 @@ pub fn json_bool(b: bool) -> Json {
     }
 }
 
-/// Create a string.
+/// Create a string. Quotes, backslashes, and control characters in `s` are escaped (see
+/// [`escape_json_string()`]), so the node's text is the escaped string. (Keep this in mind when
+/// using [`FocusTarget::Text`](crate::FocusTarget::Text), which counts chars in the node's text.)
 pub fn json_string(s: &str) -> Json {
-    Tree::new_text(&JSON_STRING_NOTATION, s.to_owned())
+    Tree::new_text(&JSON_STRING_NOTATION, escape_json_string(s))
+}
+
+/// Create a string, like [`json_string()`], but escape every non-ASCII character too (see
+/// [`escape_json_string_ascii()`]).
+pub fn json_string_ascii(s: &str) -> Json {
+    Tree::new_text(&JSON_STRING_NOTATION, escape_json_string_ascii(s))
+}
+
+/// Escape `s` so that it can be placed between double quotes in a Json document. This escapes
+/// double quotes, backslashes, and control characters, and leaves all other characters as-is.
+pub fn escape_json_string(s: &str) -> String {
+    escape(s, false)
+}
+
+/// Escape `s` like [`escape_json_string()`], but additionally escape every non-ASCII character as
+/// `\uXXXX`. Characters outside of the Basic Multilingual Plane (like most emoji) are escaped as a
+/// UTF-16 surrogate pair, e.g. `"🦀"` becomes `"\ud83e\udd80"`. The result is pure ASCII, so each of
+/// its characters is one column wide.
+pub fn escape_json_string_ascii(s: &str) -> String {
+    escape(s, true)
+}
+
+fn escape(s: &str, ascii_only: bool) -> String {
+    use std::fmt::Write;
+
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            _ if ch.is_control() || (ascii_only && !ch.is_ascii()) => {
+                let mut utf16 = [0; 2];
+                for unit in ch.encode_utf16(&mut utf16) {
+                    write!(escaped, "\\u{:04x}", unit).unwrap();
+                }
+            }
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
 }
 
 /// Create a number.
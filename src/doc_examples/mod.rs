@@ -4,4 +4,6 @@ mod style;
 
 pub mod json;
 pub use style::{BasicStyle, Color};
+pub mod simple_tree;
 pub mod tree;
+pub mod xml;
@@ -0,0 +1,142 @@
+//! An anonymous document type, for one-off pretty printing without implementing [`PrettyDoc`].
+//!
+//! ```
+//! use partial_pretty_printer::doc_examples::simple_tree::{node, text};
+//! use partial_pretty_printer::notation_constructors::{child, lit};
+//! use partial_pretty_printer::pretty_print_to_string;
+//!
+//! let notation = (lit("f(") + child(0) + lit(", ") + child(1) + lit(")"))
+//!     | (lit("f(") + (4 >> child(0)) + lit(",") + (4 >> child(1)) ^ lit(")"));
+//! let doc = node(notation, vec![text("apple"), text("banana")]);
+//! assert_eq!(pretty_print_to_string(&doc, 80).unwrap(), "f(apple, banana)");
+//! assert_eq!(
+//!     pretty_print_to_string(&doc, 10).unwrap(),
+//!     "f(\n    apple,\n    banana\n)"
+//! );
+//! ```
+
+use super::tree::{next_id, TreeCondition, TreeNotation, TreeStyleLabel};
+use super::BasicStyle;
+use crate::{Notation, PrettyDoc};
+use std::convert::Infallible;
+use std::rc::Rc;
+use std::thread_local;
+
+thread_local! {
+    static TEXT_NOTATION: Rc<TreeNotation> = Rc::new(Notation::Text.validate().unwrap());
+}
+
+/// A document node that owns its notation. Each node either contains text, or contains a list of
+/// child nodes. Construct them with [`text()`] and [`node()`].
+///
+/// It's styled with [`BasicStyle`], which style labels are converted into, and it supports the
+/// same [`TreeCondition`]s as a [`Tree`](super::tree::Tree) (though it's never a comment).
+#[derive(Debug, Clone)]
+pub struct SimpleTree {
+    id: u32,
+    notation: Rc<TreeNotation>,
+    contents: SimpleContents,
+    needs_separator: bool,
+}
+
+#[derive(Debug, Clone)]
+enum SimpleContents {
+    Text(String),
+    Children(Vec<SimpleTree>),
+}
+
+/// Construct a node that displays the given text.
+pub fn text(text: impl Into<String>) -> SimpleTree {
+    SimpleTree {
+        id: next_id(),
+        notation: TEXT_NOTATION.with(Rc::clone),
+        contents: SimpleContents::Text(text.into()),
+        needs_separator: false,
+    }
+}
+
+/// Construct a node that displays its `children` using `notation`.
+///
+/// # Panics
+///
+/// Panics if the notation is invalid. To avoid that, or to avoid re-validating a notation that's
+/// shared by many nodes, use [`node_with()`] instead.
+pub fn node(
+    notation: Notation<TreeStyleLabel, TreeCondition>,
+    children: Vec<SimpleTree>,
+) -> SimpleTree {
+    let notation = notation.validate().expect("SimpleTree: invalid notation");
+    node_with(&Rc::new(notation), children)
+}
+
+/// Construct a node that displays its `children` using an already validated `notation`.
+pub fn node_with(notation: &Rc<TreeNotation>, mut children: Vec<SimpleTree>) -> SimpleTree {
+    if let Some((_, init)) = children.split_last_mut() {
+        for child in init {
+            child.needs_separator = true;
+        }
+    }
+    SimpleTree {
+        id: next_id(),
+        notation: notation.clone(),
+        contents: SimpleContents::Children(children),
+        needs_separator: false,
+    }
+}
+
+impl<'d> PrettyDoc<'d> for &'d SimpleTree {
+    type Id = u32;
+    type Style = BasicStyle;
+    type StyleLabel = TreeStyleLabel;
+    type Condition = TreeCondition;
+    type Error = Infallible;
+
+    fn id(self) -> Result<u32, Self::Error> {
+        Ok(self.id)
+    }
+
+    fn notation(self) -> Result<&'d TreeNotation, Self::Error> {
+        Ok(&self.notation)
+    }
+
+    fn node_style(self) -> Result<BasicStyle, Self::Error> {
+        Ok(BasicStyle::new())
+    }
+
+    fn lookup_style(self, label: TreeStyleLabel) -> Result<BasicStyle, Self::Error> {
+        Ok(BasicStyle::from(label))
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        Ok(match &self.contents {
+            SimpleContents::Text(_) => None,
+            SimpleContents::Children(children) => Some(children.len()),
+        })
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        Ok(match &self.contents {
+            SimpleContents::Text(text) => text,
+            SimpleContents::Children(_) => panic!("SimpleTree: invalid invocation of unwrap_text"),
+        })
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(match &self.contents {
+            SimpleContents::Text(_) => panic!("SimpleTree: invalid invocation of unwrap_child"),
+            SimpleContents::Children(children) => &children[i],
+        })
+    }
+
+    fn condition(self, condition: &TreeCondition) -> Result<bool, Self::Error> {
+        Ok(match condition {
+            TreeCondition::IsEmptyText => {
+                matches!(&self.contents, SimpleContents::Text(text) if text.is_empty())
+            }
+            TreeCondition::NeedsSeparator => self.needs_separator,
+            TreeCondition::IsComment
+            | TreeCondition::WasMultiline
+            | TreeCondition::HadTrailingSeparator => false,
+        })
+    }
+}
@@ -4,7 +4,11 @@ use crate::Style;
 #[derive(Debug, Clone, Copy, Default)]
 pub struct BasicStyle {
     pub color: Color,
+    /// The background color, or `None` to leave the background alone.
+    pub background: Option<Color>,
     pub bold: bool,
+    pub italic: bool,
+    pub strikethrough: bool,
 }
 
 /// The color used in [`BasicStyle`].
@@ -19,38 +23,63 @@ pub enum Color {
     Blue,
     Magenta,
     Cyan,
+    /// A 24-bit color, given as red, green, and blue components.
+    Rgb(u8, u8, u8),
 }
 
 impl BasicStyle {
-    /// Construct a default `BasicStyle` (white, not bold).
+    /// Construct a default `BasicStyle` (white, no background, and no bold, italic, or
+    /// strikethrough).
     pub fn new() -> BasicStyle {
         BasicStyle::default()
     }
 
     /// Change the color.
     pub fn color(self, color: Color) -> Self {
+        BasicStyle { color, ..self }
+    }
+
+    /// Change the background color.
+    pub fn background(self, color: Color) -> Self {
         BasicStyle {
-            color,
-            bold: self.bold,
+            background: Some(color),
+            ..self
         }
     }
 
     /// Make it bold.
     pub fn bold(self) -> Self {
+        BasicStyle { bold: true, ..self }
+    }
+
+    /// Make it italic.
+    pub fn italic(self) -> Self {
+        BasicStyle {
+            italic: true,
+            ..self
+        }
+    }
+
+    /// Strike it through.
+    pub fn strikethrough(self) -> Self {
         BasicStyle {
-            color: self.color,
-            bold: true,
+            strikethrough: true,
+            ..self
         }
     }
 }
 
 impl Style for BasicStyle {
-    /// The combined style has the color of `inner_style`, and will be bold if either `inner_style`
-    /// or `outer_style` is bold.
+    /// The combined style has the color of `inner_style`, and the background of `inner_style` if it
+    /// has one (otherwise that of `outer_style`). It will be bold, italic, or strikethrough if
+    /// either `inner_style` or `outer_style` is.
     fn combine(outer_style: &BasicStyle, inner_style: &BasicStyle) -> BasicStyle {
         BasicStyle {
             color: inner_style.color,
+            background: inner_style.background.or(outer_style.background),
             bold: outer_style.bold || inner_style.bold,
+            italic: outer_style.italic || inner_style.italic,
+            strikethrough: outer_style.strikethrough || inner_style.strikethrough,
         }
     }
 }
@@ -15,7 +15,7 @@ thread_local! {
     static ID_COUNTER: Cell<u32> = Cell::new(0);
 }
 
-fn next_id() -> u32 {
+pub(super) fn next_id() -> u32 {
     let id = ID_COUNTER.get();
     ID_COUNTER.set(id + 1);
     id
@@ -30,6 +30,25 @@ pub enum TreeCondition {
     IsEmptyText,
     /// Whether this node is marked as a comment (by [`Tree::into_comment`]).
     IsComment,
+    /// Whether this node was displayed on multiple lines in the source it was parsed from (see
+    /// [`LayoutHint`]).
+    WasMultiline,
+    /// Whether this node's last child was followed by a separator in the source it was parsed
+    /// from (see [`LayoutHint`]).
+    HadTrailingSeparator,
+}
+
+/// How a node was laid out in the source it was parsed from. Notations can check this with
+/// [`TreeCondition::WasMultiline`] and [`TreeCondition::HadTrailingSeparator`], to preserve the
+/// user's choices so that formatting is idempotent: for example, a list that the user split onto
+/// multiple lines stays split even if it would fit on one. See
+/// [`json_array()`](super::json::json_array) for an example.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutHint {
+    /// Whether the node was displayed on multiple lines.
+    pub was_multiline: bool,
+    /// Whether the node's last child was followed by a separator (such as a trailing comma).
+    pub had_trailing_separator: bool,
 }
 
 pub type TreeStyleLabel = &'static str;
@@ -56,6 +75,23 @@ where
     /// The style corresponding to each style label that could be applied to
     /// this node with [`Notation::Style`]. Used for [`PrettyDoc::lookup_style()`].
     pub style_overrides: Vec<(TreeStyleLabel, S)>,
+    /// The styles to cycle through, by depth, for each style label that could be applied to a
+    /// level of indentation with [`Notation::Indent`]. Used for
+    /// [`PrettyDoc::lookup_indent_style()`].
+    pub indent_styles: Vec<(TreeStyleLabel, Vec<S>)>,
+    /// The styles to cycle through, by the node's depth, for each style label that could be
+    /// applied to this node with [`Notation::DepthStyle`]. Used for
+    /// [`PrettyDoc::lookup_depth_style()`].
+    pub depth_styles: Vec<(TreeStyleLabel, Vec<S>)>,
+    /// The link target corresponding to each label that could be applied to this node with
+    /// [`Notation::Link`]. Used for [`PrettyDoc::lookup_link()`].
+    pub links: Vec<(TreeStyleLabel, String)>,
+    /// The text corresponding to each label that could be displayed in this node with
+    /// [`Notation::Placeholder`]. Used for [`PrettyDoc::lookup_text()`].
+    pub placeholders: Vec<(TreeStyleLabel, String)>,
+    /// The transformed text corresponding to each label that could be applied to this node with
+    /// [`Notation::MapText`]. Used for [`PrettyDoc::transform_text()`].
+    pub text_transforms: Vec<(TreeStyleLabel, String)>,
     /// For checking [`TreeCondition::IsComment`].
     pub is_comment: bool,
     /// For checking [`TreeCondition::NeedsSeparator`]. A child needs a
@@ -63,6 +99,10 @@ where
     /// This is automatically set when creating a branch node, and will become
     /// outdated if you manually modify the branch node's contents later.
     pub needs_separator: bool,
+    /// For checking [`TreeCondition::WasMultiline`] and [`TreeCondition::HadTrailingSeparator`].
+    pub layout_hint: LayoutHint,
+    /// Used for [`PrettyDoc::approx_height_hint()`].
+    pub height_hint: Option<usize>,
 }
 
 /// The contents of a [`Tree`].
@@ -87,8 +127,15 @@ where
             contents: Contents::Text(text),
             node_style: S::default(),
             style_overrides: Vec::new(),
+            indent_styles: Vec::new(),
+            depth_styles: Vec::new(),
+            links: Vec::new(),
+            placeholders: Vec::new(),
+            text_transforms: Vec::new(),
             is_comment: false,
             needs_separator: false,
+            layout_hint: LayoutHint::default(),
+            height_hint: None,
         }
     }
 
@@ -109,8 +156,15 @@ where
             contents: Contents::Children(children),
             node_style: S::default(),
             style_overrides: Vec::new(),
+            indent_styles: Vec::new(),
+            depth_styles: Vec::new(),
+            links: Vec::new(),
+            placeholders: Vec::new(),
+            text_transforms: Vec::new(),
             is_comment: false,
             needs_separator: false,
+            layout_hint: LayoutHint::default(),
+            height_hint: None,
         }
     }
 
@@ -120,6 +174,19 @@ where
         self
     }
 
+    /// Record how this node was laid out in the source it was parsed from.
+    pub fn with_layout_hint(mut self, layout_hint: LayoutHint) -> Self {
+        self.layout_hint = layout_hint;
+        self
+    }
+
+    /// Record roughly how many lines this node takes up when printed, for
+    /// [`PrettyDoc::approx_height_hint()`].
+    pub fn with_height_hint(mut self, height: usize) -> Self {
+        self.height_hint = Some(height);
+        self
+    }
+
     /// Apply the style to this node.
     pub fn with_style(mut self, style: S) -> Self {
         self.node_style = style;
@@ -132,6 +199,48 @@ where
         self
     }
 
+    /// Style the indentation with this label by depth: the level at depth `i` gets
+    /// `styles[i % styles.len()]`. `styles` must not be empty.
+    pub fn with_indent_styles(mut self, label: TreeStyleLabel, styles: Vec<S>) -> Self {
+        assert!(!styles.is_empty(), "Tree::with_indent_styles: no styles");
+        self.indent_styles.push((label, styles));
+        self
+    }
+
+    /// Style this node by its depth, for this label: at depth `i` it gets
+    /// `styles[i % styles.len()]`. `styles` must not be empty.
+    pub fn with_depth_styles(mut self, label: TreeStyleLabel, styles: Vec<S>) -> Self {
+        assert!(!styles.is_empty(), "Tree::with_depth_styles: no styles");
+        self.depth_styles.push((label, styles));
+        self
+    }
+
+    /// Add a label->link target lookup entry for this node.
+    pub fn with_link(mut self, label: TreeStyleLabel, target: &str) -> Self {
+        self.links.push((label, target.to_owned()));
+        self
+    }
+
+    /// Add a label->text lookup entry for this node.
+    pub fn with_placeholder(mut self, label: TreeStyleLabel, text: &str) -> Self {
+        self.placeholders.push((label, text.to_owned()));
+        self
+    }
+
+    /// Add a label->transformed text lookup entry for this node, by applying `transform` to its
+    /// text. Does nothing if the node contains children instead of text.
+    pub fn with_text_transform(
+        mut self,
+        label: TreeStyleLabel,
+        transform: impl FnOnce(&str) -> String,
+    ) -> Self {
+        if let Contents::Text(text) = &self.contents {
+            let transformed = transform(text);
+            self.text_transforms.push((label, transformed));
+        }
+        self
+    }
+
     /// Reset the global id counter, so that the next `Tree` that's created will
     /// have the id `0`. This is intended for use in unit tests that rely on
     /// nodes having particular ids. It must only be called between
@@ -173,6 +282,54 @@ where
         Ok(Self::Style::from(label))
     }
 
+    fn lookup_depth_style(self, label: TreeStyleLabel, depth: usize) -> Result<S, Self::Error> {
+        for (l, styles) in &self.depth_styles {
+            if *l == label {
+                return Ok(styles[depth % styles.len()].clone());
+            }
+        }
+        self.lookup_style(label)
+    }
+
+    fn lookup_indent_style(self, label: TreeStyleLabel, depth: usize) -> Result<S, Self::Error> {
+        for (l, styles) in &self.indent_styles {
+            if *l == label {
+                return Ok(styles[depth % styles.len()].clone());
+            }
+        }
+        self.lookup_style(label)
+    }
+
+    fn lookup_link(self, label: TreeStyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        Ok(self
+            .links
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, target)| target.as_str()))
+    }
+
+    fn lookup_text(self, label: TreeStyleLabel) -> Result<&'d str, Self::Error> {
+        Ok(self
+            .placeholders
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, text)| text.as_str())
+            .unwrap_or(""))
+    }
+
+    fn transform_text(self, label: TreeStyleLabel, text: &'d str) -> Result<&'d str, Self::Error> {
+        Ok(self
+            .text_transforms
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, text)| text.as_str())
+            .unwrap_or(text))
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        Ok(self.height_hint)
+    }
+
     fn num_children(self) -> Result<Option<usize>, Self::Error> {
         Ok(match &self.contents {
             Contents::Text(_) => None,
@@ -201,6 +358,8 @@ where
             }
             TreeCondition::NeedsSeparator => self.needs_separator,
             TreeCondition::IsComment => self.is_comment,
+            TreeCondition::WasMultiline => self.layout_hint.was_multiline,
+            TreeCondition::HadTrailingSeparator => self.layout_hint.had_trailing_separator,
         })
     }
 }
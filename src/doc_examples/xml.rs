@@ -0,0 +1,218 @@
+//! This uses XML as an example of how to lay out markup. Elements can wrap their attributes in
+//! several different ways (see [`AttributeLayout`]), elements without content are self-closing, and
+//! text content flows onto as many lines as it needs.
+
+#![allow(clippy::precedence)]
+
+use super::{
+    style::BasicStyle,
+    tree::{Tree, TreeCondition, TreeNotation},
+};
+use crate::notation_constructors::{
+    check, child, count, empty, flat, fold, indent, left, lit, nl, right, style, text, Count, Fold,
+};
+use crate::{geometry::str_width, CheckPos, Width};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const TAG_STYLE: &str = "blue";
+const ATTRIBUTE_NAME_STYLE: &str = "cyan";
+const ATTRIBUTE_VALUE_STYLE: &str = "magenta";
+
+/// How to display an element's attributes, if they don't all fit on the line with its tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeLayout {
+    /// Always keep every attribute on the line with the tag:
+    ///
+    /// ```text
+    /// <a href="https://example.com" title="Example">
+    /// ```
+    Inline,
+    /// Put one attribute per line, aligned with the first:
+    ///
+    /// ```text
+    /// <a href="https://example.com"
+    ///    title="Example">
+    /// ```
+    Aligned,
+    /// Put one attribute per line, with each attribute after the first indented:
+    ///
+    /// ```text
+    /// <a href="https://example.com"
+    ///     title="Example">
+    /// ```
+    Hanging,
+}
+
+static XML_ELEMENT_NOTATION: Lazy<TreeNotation> =
+    Lazy::new(|| (child(0) + child(1) + child(2)).validate().unwrap());
+
+static XML_START_TAG_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    (style(TAG_STYLE, lit("<") + child(0)) + child(1))
+        .validate()
+        .unwrap()
+});
+
+static XML_CONTENT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    let inline = fold(Fold {
+        first: child(0),
+        join: left() + right(),
+    });
+    let lines = fold(Fold {
+        first: child(0),
+        join: left() ^ right(),
+    });
+    let close_start_tag = style(TAG_STYLE, lit(">"));
+    count(Count {
+        zero: style(TAG_STYLE, lit("/>")),
+        one: close_start_tag.clone() + (flat(child(0)) | (4 >> child(0)) + nl()),
+        many: close_start_tag + (flat(inline) | (4 >> lines) + nl()),
+    })
+    .validate()
+    .unwrap()
+});
+
+static XML_END_TAG_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    let end_tag = style(TAG_STYLE, lit("</") + text() + lit(">"));
+    check(TreeCondition::IsEmptyText, CheckPos::Here, empty(), end_tag)
+        .validate()
+        .unwrap()
+});
+
+static XML_TAG_NAME_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+static XML_ATTRIBUTE_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    let name = style(ATTRIBUTE_NAME_STYLE, child(0));
+    let value = style(ATTRIBUTE_VALUE_STYLE, lit("\"") + child(1) + lit("\""));
+    (name + lit("=") + value).validate().unwrap()
+});
+
+static XML_TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    count(Count {
+        zero: empty(),
+        one: child(0),
+        many: fold(Fold {
+            first: child(0),
+            join: left() + (lit(" ") | nl()) + right(),
+        }),
+    })
+    .validate()
+    .unwrap()
+});
+
+static XML_WORD_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+static XML_INLINE_ATTRIBUTES_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    fold(Fold {
+        first: lit(" ") + child(0),
+        join: left() + lit(" ") + right(),
+    })
+    .validate()
+    .unwrap()
+});
+
+static XML_HANGING_ATTRIBUTES_NOTATION: Lazy<TreeNotation> =
+    Lazy::new(|| wrapped_attributes_notation("    "));
+
+/// Aligned attributes are indented by the width of the tag name, which is different for each
+/// element. So construct one notation for each width, and keep them around forever.
+static XML_ALIGNED_ATTRIBUTES_NOTATIONS: Lazy<Mutex<HashMap<Width, &'static TreeNotation>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Attributes that are either all on one line, or each on their own line with the given
+/// indentation (except for the first, which stays on the tag's line).
+fn wrapped_attributes_notation(indentation: &str) -> TreeNotation {
+    let inline = fold(Fold {
+        first: lit(" ") + child(0),
+        join: left() + lit(" ") + right(),
+    });
+    let wrapped = fold(Fold {
+        first: lit(" ") + child(0),
+        join: left() + indent(indentation, None, nl() + right()),
+    });
+    (flat(inline) | wrapped).validate().unwrap()
+}
+
+fn aligned_attributes_notation(tag_name: &str) -> &'static TreeNotation {
+    // Align with the first attribute, which comes after "<", the tag name, and " ".
+    let width = str_width(tag_name) + 2;
+    let mut notations = XML_ALIGNED_ATTRIBUTES_NOTATIONS.lock().unwrap();
+    notations.entry(width).or_insert_with(|| {
+        let indentation = " ".repeat(width as usize);
+        Box::leak(Box::new(wrapped_attributes_notation(&indentation)))
+    })
+}
+
+/// A [`Tree`] that stores an XML document.
+pub type Xml = Tree<BasicStyle>;
+
+/// Create an element with the given tag name, attributes, and content. If `content` is empty, it
+/// will be self-closing, like `<br/>`. The `attributes` must be [`xml_attribute`]s, and the
+/// `content` must be [`xml_element`]s or [`xml_text`]s.
+pub fn xml_element(
+    name: &str,
+    layout: AttributeLayout,
+    attributes: Vec<Xml>,
+    content: Vec<Xml>,
+) -> Xml {
+    let attributes_notation: &'static TreeNotation = match layout {
+        AttributeLayout::Inline => &XML_INLINE_ATTRIBUTES_NOTATION,
+        AttributeLayout::Aligned => aligned_attributes_notation(name),
+        AttributeLayout::Hanging => &XML_HANGING_ATTRIBUTES_NOTATION,
+    };
+    let end_tag_name = if content.is_empty() { "" } else { name };
+    let start_tag = Tree::new_branch(
+        &XML_START_TAG_NOTATION,
+        vec![
+            Tree::new_text(&XML_TAG_NAME_NOTATION, name.to_owned()),
+            Tree::new_branch(attributes_notation, attributes),
+        ],
+    );
+    Tree::new_branch(
+        &XML_ELEMENT_NOTATION,
+        vec![
+            start_tag,
+            Tree::new_branch(&XML_CONTENT_NOTATION, content),
+            Tree::new_text(&XML_END_TAG_NOTATION, end_tag_name.to_owned()),
+        ],
+    )
+}
+
+/// Create an attribute, like `href="https://example.com"`. Ampersands, angle brackets, and
+/// double quotes in the `value` are escaped.
+pub fn xml_attribute(name: &str, value: &str) -> Xml {
+    Tree::new_branch(
+        &XML_ATTRIBUTE_NOTATION,
+        vec![
+            Tree::new_text(&XML_WORD_NOTATION, name.to_owned()),
+            Tree::new_text(&XML_WORD_NOTATION, escape_xml(value)),
+        ],
+    )
+}
+
+/// Create text content. It will be split into words, which are separated by spaces if they fit on
+/// one line, and by newlines otherwise. Ampersands, angle brackets, and double quotes are
+/// escaped.
+pub fn xml_text(text: &str) -> Xml {
+    Tree::new_branch(
+        &XML_TEXT_NOTATION,
+        text.split_whitespace()
+            .map(|word| Tree::new_text(&XML_WORD_NOTATION, escape_xml(word)))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,993 @@
+//! Adapters that present a modified view of a [`PrettyDoc`], without copying it. Each one wraps a
+//! document node and implements [`PrettyDoc`] itself, so it can be passed to
+//! [`pretty_print()`](crate::pretty_print) or [`display_pane()`](crate::pane::display_pane) in
+//! place of the document. For example, an editor could show only the nodes that contain errors
+//! with [`FilterChildren`], or dim part of a document with [`MapStyle`]. There's also
+//! [`MultiDoc`], which combines several documents into one, and [`EmbedDocs`], which nests
+//! documents of another type inside of a document. To find out why part of a document is styled
+//! the way it is, print it through [`TraceStyles`].
+
+use crate::notation_constructors::{child, fold, left, right, Fold};
+use crate::{Notation, NotationError, PrettyDoc, Style, StyleLabel, ValidNotation};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+/// A view of a document in which only the children that satisfy a predicate are present. Construct
+/// one with [`FilterChildren::new()`].
+///
+/// The notations of the remaining nodes are unchanged, so this works best with notations that
+/// display children with [`Notation::Fold`] and [`Notation::Count`], rather than by fixed index.
+/// Accessing the `i`'th child takes time proportional to `i`.
+pub struct FilterChildren<'d, D: PrettyDoc<'d>, F: Fn(D) -> bool> {
+    doc: D,
+    /// The index of `doc` among all of its parent's children (not just the ones that were kept).
+    index: usize,
+    keep: &'d F,
+}
+
+impl<'d, D: PrettyDoc<'d>, F: Fn(D) -> bool> FilterChildren<'d, D, F> {
+    /// Wrap the root of a document so that, at every level, only the children for which `keep`
+    /// returns true are present. The root itself is always present.
+    pub fn new(doc: D, keep: &'d F) -> Self {
+        FilterChildren {
+            doc,
+            index: 0,
+            keep,
+        }
+    }
+
+    /// The underlying document node.
+    pub fn inner(self) -> D {
+        self.doc
+    }
+
+    fn wrap(self, doc: D, index: usize) -> Self {
+        FilterChildren {
+            doc,
+            index,
+            keep: self.keep,
+        }
+    }
+
+    /// Find the last kept child of `self.doc` whose index is less than `end`.
+    fn last_kept_before(self, end: usize) -> Result<Self, D::Error> {
+        for index in (0..end).rev() {
+            let child = self.doc.unwrap_child(index)?;
+            if (self.keep)(child) {
+                return Ok(self.wrap(child, index));
+            }
+        }
+        panic!("FilterChildren: child not found");
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>, F: Fn(D) -> bool> Clone for FilterChildren<'d, D, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'d, D: PrettyDoc<'d>, F: Fn(D) -> bool> Copy for FilterChildren<'d, D, F> {}
+
+impl<'d, D: PrettyDoc<'d>, F: Fn(D) -> bool> PrettyDoc<'d> for FilterChildren<'d, D, F> {
+    type Id = D::Id;
+    type Style = D::Style;
+    type StyleLabel = D::StyleLabel;
+    type Condition = D::Condition;
+    type Error = D::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        self.doc.id()
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        self.doc.notation()
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        self.doc.condition(condition)
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        self.doc.lookup_style(style_label)
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        self.doc.lookup_indent_style(style_label, depth)
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        self.doc.lookup_depth_style(style_label, depth)
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        self.doc.lookup_link(link_label)
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        self.doc.lookup_text(text_label)
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        self.doc.transform_text(text_label, text)
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        self.doc.node_style()
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        let num_children = match self.doc.num_children()? {
+            None => return Ok(None),
+            Some(n) => n,
+        };
+        let mut num_kept = 0;
+        for index in 0..num_children {
+            if (self.keep)(self.doc.unwrap_child(index)?) {
+                num_kept += 1;
+            }
+        }
+        Ok(Some(num_kept))
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        self.doc.unwrap_text()
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        let num_children = self.doc.num_children()?.unwrap_or(0);
+        let mut num_kept = 0;
+        for index in 0..num_children {
+            let child = self.doc.unwrap_child(index)?;
+            if (self.keep)(child) {
+                if num_kept == i {
+                    return Ok(self.wrap(child, index));
+                }
+                num_kept += 1;
+            }
+        }
+        panic!("FilterChildren: child index out of bounds");
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        let num_children = self.doc.num_children()?.unwrap_or(0);
+        self.last_kept_before(num_children)
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, _i: usize) -> Result<Self, Self::Error> {
+        parent.last_kept_before(self.index)
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        let num_children = self.doc.num_children()?.unwrap_or(0);
+        let mut children = Vec::with_capacity(range.len());
+        let mut num_kept = 0;
+        for (index, child) in self
+            .doc
+            .unwrap_children(0..num_children)?
+            .into_iter()
+            .enumerate()
+        {
+            if (self.keep)(child) {
+                if range.contains(&num_kept) {
+                    children.push(self.wrap(child, index));
+                }
+                num_kept += 1;
+            }
+        }
+        Ok(children)
+    }
+}
+
+/// A view of a document in which every node's styles are rewritten by a function. Construct one
+/// with [`MapStyle::new()`].
+pub struct MapStyle<'d, D: PrettyDoc<'d>, F: Fn(D, D::Style) -> D::Style> {
+    doc: D,
+    map: &'d F,
+}
+
+impl<'d, D: PrettyDoc<'d>, F: Fn(D, D::Style) -> D::Style> MapStyle<'d, D, F> {
+    /// Wrap the root of a document so that every style of every node is passed through `map`,
+    /// along with the node. This applies to both [`PrettyDoc::node_style()`] and
+    /// [`PrettyDoc::lookup_style()`].
+    pub fn new(doc: D, map: &'d F) -> Self {
+        MapStyle { doc, map }
+    }
+
+    /// The underlying document node.
+    pub fn inner(self) -> D {
+        self.doc
+    }
+
+    fn wrap(self, doc: D) -> Self {
+        MapStyle { doc, map: self.map }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>, F: Fn(D, D::Style) -> D::Style> Clone for MapStyle<'d, D, F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'d, D: PrettyDoc<'d>, F: Fn(D, D::Style) -> D::Style> Copy for MapStyle<'d, D, F> {}
+
+impl<'d, D: PrettyDoc<'d>, F: Fn(D, D::Style) -> D::Style> PrettyDoc<'d> for MapStyle<'d, D, F> {
+    type Id = D::Id;
+    type Style = D::Style;
+    type StyleLabel = D::StyleLabel;
+    type Condition = D::Condition;
+    type Error = D::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        self.doc.id()
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        self.doc.notation()
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        self.doc.condition(condition)
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        Ok((self.map)(self.doc, self.doc.lookup_style(style_label)?))
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        let style = self.doc.lookup_indent_style(style_label, depth)?;
+        Ok((self.map)(self.doc, style))
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        let style = self.doc.lookup_depth_style(style_label, depth)?;
+        Ok((self.map)(self.doc, style))
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        self.doc.lookup_link(link_label)
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        self.doc.lookup_text(text_label)
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        self.doc.transform_text(text_label, text)
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.approx_height_hint()
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        Ok((self.map)(self.doc, self.doc.node_style()?))
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.num_children()
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        self.doc.unwrap_text()
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_child(i)?))
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_last_child()?))
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_prev_sibling(parent.doc, i)?))
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        Ok(self
+            .doc
+            .unwrap_children(range)?
+            .into_iter()
+            .map(|child| self.wrap(child))
+            .collect())
+    }
+}
+
+/// Where one of the styles in a [`StyleTrace`] came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleSource<L, Id> {
+    /// The [`PrettyDoc::node_style()`] of the node with this id.
+    Node(Id),
+    /// A [`Notation::Style`] or [`Notation::DepthStyle`] with this label, in the notation of the
+    /// node with this id.
+    Label(L, Id),
+    /// The style of a [`Notation::Indent`] with this label, in the notation of the node with this
+    /// id.
+    Indent(L, Id),
+    /// A style that was given to the printer rather than looked up in the document, like the style
+    /// passed to [`display_pane()`](crate::pane::display_pane) or a `root_style`. The string says
+    /// which one it was.
+    Given(&'static str),
+}
+
+/// A style together with the chain of styles that were combined to produce it, for
+/// [`TraceStyles`].
+#[derive(Debug, Clone)]
+pub struct StyleTrace<S, L, Id> {
+    sources: Vec<(StyleSource<L, Id>, S)>,
+}
+
+impl<S: Style, L: StyleLabel, Id: fmt::Debug + Clone> StyleTrace<S, L, Id> {
+    /// A trace of a single style. Use this with [`StyleSource::Given`] to construct the styles
+    /// that you give to the printer, like a pane style.
+    pub fn new(source: StyleSource<L, Id>, style: S) -> Self {
+        StyleTrace {
+            sources: vec![(source, style)],
+        }
+    }
+
+    /// Each style that contributed, and where it came from, outermost first.
+    pub fn sources(&self) -> &[(StyleSource<L, Id>, S)] {
+        &self.sources
+    }
+
+    /// The style that the printer would have produced without tracing.
+    pub fn effective_style(&self) -> S {
+        let (_, first) = &self.sources[0];
+        self.sources[1..]
+            .iter()
+            .fold(first.clone(), |outer, (_, inner)| {
+                Style::combine(&outer, inner)
+            })
+    }
+}
+
+impl<S: Style, L: StyleLabel, Id: fmt::Debug + Clone> Style for StyleTrace<S, L, Id> {
+    fn combine(outer_style: &Self, inner_style: &Self) -> Self {
+        let mut sources = outer_style.sources.clone();
+        sources.extend(inner_style.sources.iter().cloned());
+        StyleTrace { sources }
+    }
+}
+
+/// A view of a document in which every style records where it came from, to debug why part of a
+/// document is (or isn't) styled a certain way. Construct one with [`TraceStyles::new()`].
+///
+/// Its style type is a [`StyleTrace`], so each [`Segment`](crate::Segment) that it prints lists
+/// the node styles, style labels, and indentation labels that were combined into its style. Use
+/// [`StyleTrace::effective_style()`] to get the style that the underlying document would have
+/// printed.
+pub struct TraceStyles<D> {
+    doc: D,
+}
+
+impl<'d, D: PrettyDoc<'d>> TraceStyles<D> {
+    /// Wrap the root of a document so that its styles are traced.
+    pub fn new(doc: D) -> Self {
+        TraceStyles { doc }
+    }
+
+    /// The underlying document node.
+    pub fn inner(self) -> D {
+        self.doc
+    }
+}
+
+impl<D: Clone> Clone for TraceStyles<D> {
+    fn clone(&self) -> Self {
+        TraceStyles {
+            doc: self.doc.clone(),
+        }
+    }
+}
+impl<D: Copy> Copy for TraceStyles<D> {}
+
+impl<'d, D: PrettyDoc<'d>> PrettyDoc<'d> for TraceStyles<D>
+where
+    D::Id: 'd,
+{
+    type Id = D::Id;
+    type Style = StyleTrace<D::Style, D::StyleLabel, D::Id>;
+    type StyleLabel = D::StyleLabel;
+    type Condition = D::Condition;
+    type Error = D::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        self.doc.id()
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        self.doc.notation()
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        self.doc.condition(condition)
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        let source = StyleSource::Label(style_label.clone(), self.doc.id()?);
+        Ok(StyleTrace::new(source, self.doc.lookup_style(style_label)?))
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        let source = StyleSource::Indent(style_label.clone(), self.doc.id()?);
+        let style = self.doc.lookup_indent_style(style_label, depth)?;
+        Ok(StyleTrace::new(source, style))
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        let source = StyleSource::Label(style_label.clone(), self.doc.id()?);
+        let style = self.doc.lookup_depth_style(style_label, depth)?;
+        Ok(StyleTrace::new(source, style))
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        self.doc.lookup_link(link_label)
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        self.doc.lookup_text(text_label)
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        self.doc.transform_text(text_label, text)
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.approx_height_hint()
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        let source = StyleSource::Node(self.doc.id()?);
+        Ok(StyleTrace::new(source, self.doc.node_style()?))
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.num_children()
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        self.doc.unwrap_text()
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(TraceStyles::new(self.doc.unwrap_child(i)?))
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        Ok(TraceStyles::new(self.doc.unwrap_last_child()?))
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
+        Ok(TraceStyles::new(
+            self.doc.unwrap_prev_sibling(parent.doc, i)?,
+        ))
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        Ok(self
+            .doc
+            .unwrap_children(range)?
+            .into_iter()
+            .map(TraceStyles::new)
+            .collect())
+    }
+}
+
+/// A node of either a host document or a document embedded in it, for [`EmbedDocs`]. Also used
+/// for the ids of [`EmbedDocs`] nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmbedNode<H, E> {
+    Host(H),
+    Embedded(E),
+}
+
+/// A view of a "host" document in which some nodes contain a document of a different type `E`,
+/// which is laid out with its own notations. For example, a Markdown document could embed a
+/// document for each of its code blocks. Construct one with [`EmbedDocs::new()`].
+///
+/// A host node that embeds a document gets the embedded document's root as an extra last child, so
+/// its notation should display that child (for example with [`Notation::Child`]). Only host nodes
+/// with children (possibly zero of them) can embed a document; host nodes that contain text are
+/// left as is.
+///
+/// The embedded document must use the same styles, style labels, conditions, and error type as the
+/// host document, but its nodes and ids can have different types. Node ids are
+/// `EmbedNode::Host(id)` or `EmbedNode::Embedded(id)`.
+pub struct EmbedDocs<'d, H: PrettyDoc<'d>, E: PrettyDoc<'d>, F: Fn(H) -> Option<E>> {
+    node: EmbedNode<H, E>,
+    lookup: &'d F,
+}
+
+impl<'d, H, E, F> EmbedDocs<'d, H, E, F>
+where
+    H: PrettyDoc<'d>,
+    E: PrettyDoc<
+        'd,
+        Style = H::Style,
+        StyleLabel = H::StyleLabel,
+        Condition = H::Condition,
+        Error = H::Error,
+    >,
+    F: Fn(H) -> Option<E>,
+{
+    /// Wrap the root of a host document, so that every host node for which `lookup` returns
+    /// `Some(doc)` contains `doc` as its last child.
+    pub fn new(doc: H, lookup: &'d F) -> Self {
+        EmbedDocs {
+            node: EmbedNode::Host(doc),
+            lookup,
+        }
+    }
+
+    /// The underlying node, from either the host document or an embedded document.
+    pub fn inner(self) -> EmbedNode<H, E> {
+        self.node
+    }
+
+    fn wrap(self, node: EmbedNode<H, E>) -> Self {
+        EmbedDocs {
+            node,
+            lookup: self.lookup,
+        }
+    }
+
+    /// The host node's number of children (not counting an embedded document), and the document
+    /// it embeds, if any.
+    fn host_children(host: H, lookup: &F) -> Result<(Option<usize>, Option<E>), H::Error> {
+        Ok(match host.num_children()? {
+            None => (None, None),
+            Some(n) => (Some(n), lookup(host)),
+        })
+    }
+}
+
+impl<'d, H, E, F> Clone for EmbedDocs<'d, H, E, F>
+where
+    H: PrettyDoc<'d>,
+    E: PrettyDoc<'d>,
+    F: Fn(H) -> Option<E>,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'d, H, E, F> Copy for EmbedDocs<'d, H, E, F>
+where
+    H: PrettyDoc<'d>,
+    E: PrettyDoc<'d>,
+    F: Fn(H) -> Option<E>,
+{
+}
+
+impl<'d, H, E, F> PrettyDoc<'d> for EmbedDocs<'d, H, E, F>
+where
+    H: PrettyDoc<'d>,
+    E: PrettyDoc<
+        'd,
+        Style = H::Style,
+        StyleLabel = H::StyleLabel,
+        Condition = H::Condition,
+        Error = H::Error,
+    >,
+    F: Fn(H) -> Option<E>,
+{
+    type Id = EmbedNode<H::Id, E::Id>;
+    type Style = H::Style;
+    type StyleLabel = H::StyleLabel;
+    type Condition = H::Condition;
+    type Error = H::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        Ok(match self.node {
+            EmbedNode::Host(host) => EmbedNode::Host(host.id()?),
+            EmbedNode::Embedded(doc) => EmbedNode::Embedded(doc.id()?),
+        })
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.notation(),
+            EmbedNode::Embedded(doc) => doc.notation(),
+        }
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.condition(condition),
+            EmbedNode::Embedded(doc) => doc.condition(condition),
+        }
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.lookup_style(style_label),
+            EmbedNode::Embedded(doc) => doc.lookup_style(style_label),
+        }
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.lookup_indent_style(style_label, depth),
+            EmbedNode::Embedded(doc) => doc.lookup_indent_style(style_label, depth),
+        }
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.lookup_depth_style(style_label, depth),
+            EmbedNode::Embedded(doc) => doc.lookup_depth_style(style_label, depth),
+        }
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.lookup_link(link_label),
+            EmbedNode::Embedded(doc) => doc.lookup_link(link_label),
+        }
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.lookup_text(text_label),
+            EmbedNode::Embedded(doc) => doc.lookup_text(text_label),
+        }
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.transform_text(text_label, text),
+            EmbedNode::Embedded(doc) => doc.transform_text(text_label, text),
+        }
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        match self.node {
+            // The host's hint doesn't know about the embedded document.
+            EmbedNode::Host(host) => match Self::host_children(host, self.lookup)? {
+                (_, Some(_)) => Ok(None),
+                (_, None) => host.approx_height_hint(),
+            },
+            EmbedNode::Embedded(doc) => doc.approx_height_hint(),
+        }
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.node_style(),
+            EmbedNode::Embedded(doc) => doc.node_style(),
+        }
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => {
+                let (num_children, embedded) = Self::host_children(host, self.lookup)?;
+                Ok(num_children.map(|n| n + embedded.is_some() as usize))
+            }
+            EmbedNode::Embedded(doc) => doc.num_children(),
+        }
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => host.unwrap_text(),
+            EmbedNode::Embedded(doc) => doc.unwrap_text(),
+        }
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => match Self::host_children(host, self.lookup)? {
+                (Some(n), Some(embedded)) if i == n => Ok(self.wrap(EmbedNode::Embedded(embedded))),
+                _ => Ok(self.wrap(EmbedNode::Host(host.unwrap_child(i)?))),
+            },
+            EmbedNode::Embedded(doc) => Ok(self.wrap(EmbedNode::Embedded(doc.unwrap_child(i)?))),
+        }
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => match Self::host_children(host, self.lookup)? {
+                (_, Some(embedded)) => Ok(self.wrap(EmbedNode::Embedded(embedded))),
+                (_, None) => Ok(self.wrap(EmbedNode::Host(host.unwrap_last_child()?))),
+            },
+            EmbedNode::Embedded(doc) => {
+                Ok(self.wrap(EmbedNode::Embedded(doc.unwrap_last_child()?)))
+            }
+        }
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
+        match (self.node, parent.node) {
+            (EmbedNode::Host(host), EmbedNode::Host(parent_host)) => {
+                Ok(self.wrap(EmbedNode::Host(host.unwrap_prev_sibling(parent_host, i)?)))
+            }
+            // The root of an embedded document. Its previous sibling is the host's last child.
+            (EmbedNode::Embedded(_), EmbedNode::Host(parent_host)) => {
+                Ok(self.wrap(EmbedNode::Host(parent_host.unwrap_child(i)?)))
+            }
+            (EmbedNode::Embedded(doc), EmbedNode::Embedded(parent_doc)) => {
+                Ok(self.wrap(EmbedNode::Embedded(doc.unwrap_prev_sibling(parent_doc, i)?)))
+            }
+            (EmbedNode::Host(_), EmbedNode::Embedded(_)) => {
+                panic!("EmbedDocs: host node inside of an embedded document")
+            }
+        }
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        match self.node {
+            EmbedNode::Host(host) => {
+                let (num_children, embedded) = Self::host_children(host, self.lookup)?;
+                let num_children = num_children.unwrap_or(0);
+                let host_range = range.start.min(num_children)..range.end.min(num_children);
+                let mut children = host
+                    .unwrap_children(host_range)?
+                    .into_iter()
+                    .map(|child| self.wrap(EmbedNode::Host(child)))
+                    .collect::<Vec<_>>();
+                if let Some(embedded) = embedded {
+                    if range.contains(&num_children) {
+                        children.push(self.wrap(EmbedNode::Embedded(embedded)));
+                    }
+                }
+                Ok(children)
+            }
+            EmbedNode::Embedded(doc) => Ok(doc
+                .unwrap_children(range)?
+                .into_iter()
+                .map(|child| self.wrap(EmbedNode::Embedded(child)))
+                .collect()),
+        }
+    }
+}
+
+/// A document made of a sequence of unrelated documents, displayed one after another with a
+/// separator between them (such as a blank line). This is useful for files that contain several
+/// top-level values.
+///
+/// To print it, pass [`MultiDoc::root()`] to the printer. The root is a node whose `i`'th child is
+/// the `i`'th document, so paths into the `MultiDoc` start with the index of a document. Node ids
+/// are `Some(id)` for the nodes of the documents, and `None` for the root.
+#[derive(Debug, Clone)]
+pub struct MultiDoc<'d, D: PrettyDoc<'d>> {
+    docs: Vec<D>,
+    notation: ValidNotation<D::StyleLabel, D::Condition>,
+}
+
+impl<'d, D: PrettyDoc<'d>> MultiDoc<'d, D> {
+    /// Construct a `MultiDoc` that displays `separator` between each pair of adjacent `docs`. For
+    /// example, `nl() + nl()` separates them by a blank line. The `separator` is displayed in the
+    /// context of the root node: it's inside of a [`Notation::Fold`]'s `join`, so it can use
+    /// [`CheckPos::LeftChild`](crate::CheckPos::LeftChild) and
+    /// [`CheckPos::RightChild`](crate::CheckPos::RightChild) to inspect the documents on either
+    /// side of it. Any other style labels and conditions are looked up in the first document.
+    pub fn new(
+        docs: Vec<D>,
+        separator: Notation<D::StyleLabel, D::Condition>,
+    ) -> Result<Self, NotationError> {
+        let notation = fold(Fold {
+            first: child(0),
+            join: left() + separator + right(),
+        })
+        .validate()?;
+        Ok(MultiDoc { docs, notation })
+    }
+
+    /// The documents, in order.
+    pub fn docs(&self) -> &[D] {
+        &self.docs
+    }
+
+    /// The root node, whose children are the documents.
+    pub fn root(&'d self) -> MultiDocNode<'d, D> {
+        MultiDocNode::Root(self)
+    }
+}
+
+/// A node of a [`MultiDoc`]: either its root, or a node in one of its documents.
+#[derive(Debug)]
+pub enum MultiDocNode<'d, D: PrettyDoc<'d>> {
+    Root(&'d MultiDoc<'d, D>),
+    Node(D),
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for MultiDocNode<'d, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'d, D: PrettyDoc<'d>> Copy for MultiDocNode<'d, D> {}
+
+impl<'d, D: PrettyDoc<'d>> MultiDocNode<'d, D> {
+    /// The node to delegate to. For the root, that's the root of the first document (which
+    /// exists, since otherwise nothing would be looked up on the root).
+    fn delegate(self) -> D {
+        match self {
+            MultiDocNode::Root(multi_doc) => multi_doc.docs[0],
+            MultiDocNode::Node(doc) => doc,
+        }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> PrettyDoc<'d> for MultiDocNode<'d, D>
+where
+    D::Style: Default,
+{
+    type Id = Option<D::Id>;
+    type Style = D::Style;
+    type StyleLabel = D::StyleLabel;
+    type Condition = D::Condition;
+    type Error = D::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        match self {
+            MultiDocNode::Root(_) => Ok(None),
+            MultiDocNode::Node(doc) => Ok(Some(doc.id()?)),
+        }
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        match self {
+            MultiDocNode::Root(multi_doc) => Ok(&multi_doc.notation),
+            MultiDocNode::Node(doc) => doc.notation(),
+        }
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        self.delegate().condition(condition)
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        self.delegate().lookup_style(style_label)
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        self.delegate().lookup_indent_style(style_label, depth)
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        self.delegate().lookup_depth_style(style_label, depth)
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        self.delegate().lookup_link(link_label)
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        self.delegate().lookup_text(text_label)
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        self.delegate().transform_text(text_label, text)
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        match self {
+            MultiDocNode::Root(_) => Ok(None),
+            MultiDocNode::Node(doc) => doc.approx_height_hint(),
+        }
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        match self {
+            MultiDocNode::Root(_) => Ok(D::Style::default()),
+            MultiDocNode::Node(doc) => doc.node_style(),
+        }
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        match self {
+            MultiDocNode::Root(multi_doc) => Ok(Some(multi_doc.docs.len())),
+            MultiDocNode::Node(doc) => doc.num_children(),
+        }
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        match self {
+            MultiDocNode::Root(_) => panic!("MultiDoc: the root has no text"),
+            MultiDocNode::Node(doc) => doc.unwrap_text(),
+        }
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        match self {
+            MultiDocNode::Root(multi_doc) => Ok(MultiDocNode::Node(multi_doc.docs[i])),
+            MultiDocNode::Node(doc) => Ok(MultiDocNode::Node(doc.unwrap_child(i)?)),
+        }
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        match self {
+            MultiDocNode::Root(multi_doc) => {
+                Ok(MultiDocNode::Node(*multi_doc.docs.last().unwrap()))
+            }
+            MultiDocNode::Node(doc) => Ok(MultiDocNode::Node(doc.unwrap_last_child()?)),
+        }
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
+        match (self, parent) {
+            (MultiDocNode::Node(doc), MultiDocNode::Node(parent)) => {
+                Ok(MultiDocNode::Node(doc.unwrap_prev_sibling(parent, i)?))
+            }
+            (_, parent) => parent.unwrap_child(i),
+        }
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        match self {
+            MultiDocNode::Root(multi_doc) => Ok(multi_doc.docs[range]
+                .iter()
+                .map(|doc| MultiDocNode::Node(*doc))
+                .collect()),
+            MultiDocNode::Node(doc) => Ok(doc
+                .unwrap_children(range)?
+                .into_iter()
+                .map(MultiDocNode::Node)
+                .collect()),
+        }
+    }
+}
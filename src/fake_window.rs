@@ -0,0 +1,246 @@
+use crate::{pane::PrettyWindow, Col, Pos, Row, Size, Style};
+use std::fmt;
+
+/// A call that was made to a [`FakeWindow`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WindowCall<S> {
+    DisplayChar {
+        ch: char,
+        pos: Pos,
+        style: S,
+        full_width: bool,
+    },
+    SetFocus(Pos),
+    /// Not a [`PrettyWindow`] method: the test called [`FakeWindow::resize()`].
+    Resize(Size),
+}
+
+/// An error from a [`FakeWindow`], for calls that a real terminal couldn't handle.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FakeWindowError {
+    #[error("Character displayed at {pos:?}, outside of the window of size {size:?}")]
+    OutOfBounds { pos: Pos, size: Size },
+    #[error("Focus set at {pos:?}, outside of the window of size {size:?}")]
+    FocusOutOfBounds { pos: Pos, size: Size },
+}
+
+/// A simulated terminal, for testing code that displays to a [`PrettyWindow`] (such as
+/// [`display_pane()`](crate::pane::display_pane)). It records every call made to it, can be
+/// resized between displays, and can render its contents either as plain text or with markup
+/// showing the style of each character.
+///
+/// Displaying a character outside of the window is an error, rather than being silently ignored.
+#[derive(Debug, Clone)]
+pub struct FakeWindow<S: Style> {
+    size: Size,
+    /// The contents of each cell, indexed by row and then column.
+    cells: Vec<Vec<Cell<S>>>,
+    calls: Vec<WindowCall<S>>,
+}
+
+#[derive(Debug, Clone)]
+enum Cell<S> {
+    /// Never written to.
+    Blank,
+    Char(char, S),
+    /// The second column of a full-width character.
+    WideRest,
+}
+
+impl<S: Style> FakeWindow<S> {
+    /// Construct a blank window of the given size.
+    pub fn new(size: Size) -> FakeWindow<S> {
+        FakeWindow {
+            size,
+            cells: blank_cells(size),
+            calls: Vec::new(),
+        }
+    }
+
+    /// Change the size of the window, like a terminal being resized. Like most terminals, this
+    /// keeps the contents that still fit, and the new cells are blank. The resize is recorded as
+    /// a [`WindowCall::Resize`].
+    pub fn resize(&mut self, size: Size) {
+        let mut cells = blank_cells(size);
+        for (new_line, old_line) in cells.iter_mut().zip(self.cells.iter()) {
+            for (new_cell, old_cell) in new_line.iter_mut().zip(old_line.iter()) {
+                *new_cell = old_cell.clone();
+            }
+            // Don't keep half of a full-width character.
+            if let Some(Cell::WideRest) = new_line.first() {
+                new_line[0] = Cell::Blank;
+            }
+            if let Some(Cell::Char(_, _)) = new_line.last() {
+                if let Some(Cell::WideRest) = old_line.get(new_line.len()) {
+                    *new_line.last_mut().unwrap() = Cell::Blank;
+                }
+            }
+        }
+        self.size = size;
+        self.cells = cells;
+        self.calls.push(WindowCall::Resize(size));
+    }
+
+    /// Every call made to this window (and every resize), in order, since it was constructed or
+    /// since the last [`FakeWindow::clear_calls()`].
+    pub fn calls(&self) -> &[WindowCall<S>] {
+        &self.calls
+    }
+
+    /// Forget the recorded calls, for example before displaying again. The window's contents
+    /// are kept.
+    pub fn clear_calls(&mut self) {
+        self.calls.clear();
+    }
+
+    /// The positions passed to `set_focus`, in order, among the recorded calls.
+    pub fn focus_points(&self) -> Vec<Pos> {
+        self.calls
+            .iter()
+            .filter_map(|call| match call {
+                WindowCall::SetFocus(pos) => Some(*pos),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The cells that weren't written exactly once among the recorded calls (the second column
+    /// of a full-width character counts as written with it), in order from top to bottom and
+    /// left to right. [`display_pane()`](crate::pane::display_pane) promises to write every cell
+    /// once, so this should be empty after clearing the calls and displaying a pane.
+    pub fn cells_not_written_once(&self) -> Vec<Pos> {
+        let mut counts = vec![vec![0; self.size.width as usize]; self.size.height as usize];
+        for call in &self.calls {
+            if let WindowCall::DisplayChar {
+                pos, full_width, ..
+            } = call
+            {
+                let width = if *full_width { 2 } else { 1 };
+                for col in pos.col..pos.col + width {
+                    if let Some(count) = counts
+                        .get_mut(pos.row as usize)
+                        .and_then(|line| line.get_mut(col as usize))
+                    {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+        let mut positions = Vec::new();
+        for (row, line) in counts.iter().enumerate() {
+            for (col, count) in line.iter().enumerate() {
+                if *count != 1 {
+                    positions.push(Pos {
+                        row: row as Row,
+                        col: col as Col,
+                    });
+                }
+            }
+        }
+        positions
+    }
+
+    /// Render the window's contents, marking up the characters with the name of their style.
+    /// Each maximal run of characters for which `style_name` returns the same `Some(name)` is
+    /// wrapped in `<name>` and `</name>`. Characters for which it returns `None` aren't marked
+    /// up. Blank cells are shown as spaces, and each row ends with a newline.
+    pub fn to_markup(&self, style_name: impl Fn(&S) -> Option<String>) -> String {
+        let mut string = String::new();
+        for line in &self.cells {
+            let mut open: Option<String> = None;
+            for cell in line {
+                let (ch, name) = match cell {
+                    Cell::Blank => (' ', None),
+                    Cell::Char(ch, style) => (*ch, style_name(style)),
+                    Cell::WideRest => continue,
+                };
+                if name != open {
+                    if let Some(open) = &open {
+                        string.push_str(&format!("</{}>", open));
+                    }
+                    if let Some(name) = &name {
+                        string.push_str(&format!("<{}>", name));
+                    }
+                    open = name;
+                }
+                string.push(ch);
+            }
+            if let Some(open) = &open {
+                string.push_str(&format!("</{}>", open));
+            }
+            string.push('\n');
+        }
+        string
+    }
+}
+
+fn blank_cells<S>(size: Size) -> Vec<Vec<Cell<S>>> {
+    (0..size.height)
+        .map(|_| (0..size.width).map(|_| Cell::Blank).collect())
+        .collect()
+}
+
+/// The window's contents as plain text. Blank cells are shown as spaces, and each row ends with a
+/// newline.
+impl<S: Style> fmt::Display for FakeWindow<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_markup(|_| None))
+    }
+}
+
+impl<S: Style> PrettyWindow for FakeWindow<S> {
+    type Error = FakeWindowError;
+    type Style = S;
+
+    fn size(&self) -> Result<Size, Self::Error> {
+        Ok(self.size)
+    }
+
+    fn display_char(
+        &mut self,
+        ch: char,
+        pos: Pos,
+        style: &Self::Style,
+        full_width: bool,
+    ) -> Result<(), Self::Error> {
+        let width = if full_width { 2 } else { 1 };
+        if pos.row >= self.size.height || pos.col + width > self.size.width {
+            return Err(FakeWindowError::OutOfBounds {
+                pos,
+                size: self.size,
+            });
+        }
+        self.calls.push(WindowCall::DisplayChar {
+            ch,
+            pos,
+            style: style.clone(),
+            full_width,
+        });
+        let line = &mut self.cells[pos.row as usize];
+        let col = pos.col as usize;
+        // Overwriting half of a full-width character erases the other half.
+        if let Cell::WideRest = line[col] {
+            line[col - 1] = Cell::Blank;
+        }
+        let end = col + width as usize;
+        if let Some(Cell::WideRest) = line.get(end) {
+            line[end] = Cell::Blank;
+        }
+        line[col] = Cell::Char(ch, style.clone());
+        if full_width {
+            line[col + 1] = Cell::WideRest;
+        }
+        Ok(())
+    }
+
+    fn set_focus(&mut self, pos: Pos) -> Result<(), Self::Error> {
+        if pos.row >= self.size.height || pos.col > self.size.width {
+            return Err(FakeWindowError::FocusOutOfBounds {
+                pos,
+                size: self.size,
+            });
+        }
+        self.calls.push(WindowCall::SetFocus(pos));
+        Ok(())
+    }
+}
@@ -0,0 +1,548 @@
+//! A C ABI for embedding the pretty printer in programs that aren't written in Rust, such as
+//! editor plugins. Enable it with the `ffi` feature.
+//!
+//! - Notations are built bottom-up from opaque [`FfiNotation`] handles (`ppp_lit`, `ppp_concat`,
+//!   ...). Combinators take ownership of their arguments. A finished notation is moved into a
+//!   [`FfiNotations`] table with [`ppp_notations_add`], which validates it and returns its index.
+//! - The document stays on the caller's side. Nodes are identified by `u64` handles of the
+//!   caller's choosing, and the printer asks about them through the [`FfiDocCallbacks`].
+//! - [`ppp_print`] prints a range of lines around a node into a caller-provided buffer.
+//!
+//! Every function that returns a status returns one of the `PPP_*` constants. Every function that
+//! returns a notation handle returns null if any of its arguments was null, or on failure. No
+//! function unwinds a panic into the caller: a panic is reported as [`PPP_PANIC`] or null instead.
+
+use crate::notation_constructors::{
+    check, child, count, empty, eol, flat, fold, indent, left, lit, nl, right, space, text, Count,
+    Fold,
+};
+use crate::{pretty_print_into, CheckPos, FocusTarget, Notation, PrettyDoc, ValidNotation, Width};
+use std::convert::TryFrom;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::{ptr, slice, str};
+
+/// Success.
+pub const PPP_OK: i32 = 0;
+/// [`ppp_notations_add`] was given an invalid notation.
+pub const PPP_INVALID_NOTATION: i32 = 1;
+/// Printing failed, for example because a node's notation didn't match its children.
+pub const PPP_PRINTING_ERROR: i32 = 2;
+/// The output didn't fit in the buffer. The required length was stored in `out_len`.
+pub const PPP_BUFFER_TOO_SMALL: i32 = 3;
+/// An argument was null when it shouldn't be.
+pub const PPP_NULL_ARGUMENT: i32 = 4;
+/// A number didn't fit in the type it had to be returned as, such as a notation index that
+/// doesn't fit in a `u32`.
+pub const PPP_OUT_OF_RANGE: i32 = 5;
+/// The printer panicked. This is a bug in the printer.
+pub const PPP_PANIC: i32 = 6;
+
+/// An opaque handle to a notation under construction.
+pub struct FfiNotation(Notation<(), u32>);
+
+/// An opaque handle to a table of validated notations, referred to by index.
+#[derive(Debug, Default)]
+pub struct FfiNotations(Vec<ValidNotation<(), u32>>);
+
+/// How the printer asks the caller about its document. Every callback is passed `user_data` and
+/// the handle of a node.
+#[repr(C)]
+pub struct FfiDocCallbacks {
+    /// Passed to every callback.
+    pub user_data: *mut c_void,
+    /// The index (in the [`FfiNotations`]) of the node's notation.
+    pub notation: extern "C" fn(user_data: *mut c_void, node: u64) -> u32,
+    /// The number of children the node has, or -1 if it contains text instead.
+    pub num_children: extern "C" fn(user_data: *mut c_void, node: u64) -> i64,
+    /// The node's UTF-8 text, with its length in bytes stored in `len`. It must stay valid until
+    /// [`ppp_print`] returns.
+    pub text: extern "C" fn(user_data: *mut c_void, node: u64, len: *mut usize) -> *const u8,
+    /// The handle of the node's `index`'th child.
+    pub child: extern "C" fn(user_data: *mut c_void, node: u64, index: usize) -> u64,
+    /// Whether the numbered condition (from [`ppp_check`]) holds for the node.
+    pub condition: extern "C" fn(user_data: *mut c_void, node: u64, condition: u32) -> bool,
+}
+
+/// Ways the caller's document can fail to make sense.
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum FfiDocError {
+    #[error("Node {node} has notation {index}, but there are only {len} notations.")]
+    UnknownNotation { node: u64, index: u32, len: usize },
+    #[error("Node {node} has text that isn't UTF-8.")]
+    InvalidUtf8 { node: u64 },
+    #[error("Node {node} has {num_children} children, which is too many.")]
+    TooManyChildren { node: u64, num_children: i64 },
+}
+
+#[derive(Clone, Copy)]
+struct FfiDoc<'d> {
+    notations: &'d FfiNotations,
+    callbacks: &'d FfiDocCallbacks,
+    node: u64,
+}
+
+impl<'d> PrettyDoc<'d> for FfiDoc<'d> {
+    type Id = u64;
+    type Style = ();
+    type StyleLabel = ();
+    type Condition = u32;
+    type Error = FfiDocError;
+
+    fn id(self) -> Result<u64, FfiDocError> {
+        Ok(self.node)
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<(), u32>, FfiDocError> {
+        let index = (self.callbacks.notation)(self.callbacks.user_data, self.node);
+        self.notations
+            .0
+            .get(index as usize)
+            .ok_or(FfiDocError::UnknownNotation {
+                node: self.node,
+                index,
+                len: self.notations.0.len(),
+            })
+    }
+
+    fn condition(self, condition: &u32) -> Result<bool, FfiDocError> {
+        Ok((self.callbacks.condition)(
+            self.callbacks.user_data,
+            self.node,
+            *condition,
+        ))
+    }
+
+    fn lookup_style(self, _style_label: ()) -> Result<(), FfiDocError> {
+        Ok(())
+    }
+
+    fn node_style(self) -> Result<(), FfiDocError> {
+        Ok(())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, FfiDocError> {
+        let num_children = (self.callbacks.num_children)(self.callbacks.user_data, self.node);
+        if num_children < 0 {
+            return Ok(None);
+        }
+        match usize::try_from(num_children) {
+            Ok(num_children) => Ok(Some(num_children)),
+            Err(_) => Err(FfiDocError::TooManyChildren {
+                node: self.node,
+                num_children,
+            }),
+        }
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, FfiDocError> {
+        let mut len = 0;
+        let text = (self.callbacks.text)(self.callbacks.user_data, self.node, &mut len);
+        if text.is_null() {
+            return Ok("");
+        }
+        // The caller promised that the text stays valid until `ppp_print` returns, which is
+        // longer than the printer uses it for.
+        let bytes = unsafe { slice::from_raw_parts(text, len) };
+        str::from_utf8(bytes).map_err(|_| FfiDocError::InvalidUtf8 { node: self.node })
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, FfiDocError> {
+        Ok(FfiDoc {
+            node: (self.callbacks.child)(self.callbacks.user_data, self.node, i),
+            ..self
+        })
+    }
+}
+
+/// Run the body of an `extern "C"` function, returning `on_panic` if it panics instead of
+/// unwinding into the caller.
+fn catch_panic<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(on_panic)
+}
+
+fn new_notation(notation: Notation<(), u32>) -> *mut FfiNotation {
+    Box::into_raw(Box::new(FfiNotation(notation)))
+}
+
+/// Take ownership of a notation handle. Returns `None` if it's null.
+///
+/// # Safety
+///
+/// `notation` must be null, or have come from one of the `ppp_` notation constructors and not
+/// have been used since.
+unsafe fn take_notation(notation: *mut FfiNotation) -> Option<Notation<(), u32>> {
+    if notation.is_null() {
+        None
+    } else {
+        Some(Box::from_raw(notation).0)
+    }
+}
+
+/// Read a NUL-terminated UTF-8 string. Returns `None` if it's null or isn't UTF-8.
+///
+/// # Safety
+///
+/// `s` must be null or a valid NUL-terminated string.
+unsafe fn read_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        None
+    } else {
+        CStr::from_ptr(s).to_str().ok()
+    }
+}
+
+/// Construct a notation that displays nothing.
+#[no_mangle]
+pub extern "C" fn ppp_empty() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(empty()))
+}
+
+/// Construct a newline notation.
+#[no_mangle]
+pub extern "C" fn ppp_nl() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(nl()))
+}
+
+/// Construct an end-of-line notation.
+#[no_mangle]
+pub extern "C" fn ppp_eol() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(eol()))
+}
+
+/// Construct a soft space notation.
+#[no_mangle]
+pub extern "C" fn ppp_space() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(space()))
+}
+
+/// Construct a notation that displays the node's text.
+#[no_mangle]
+pub extern "C" fn ppp_text() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(text()))
+}
+
+/// Construct a notation that displays the node's `index`'th child (counting from the end if
+/// negative).
+#[no_mangle]
+pub extern "C" fn ppp_child(index: isize) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(child(index)))
+}
+
+/// Construct the previous child notation, for use in [`ppp_fold`].
+#[no_mangle]
+pub extern "C" fn ppp_left() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(left()))
+}
+
+/// Construct the next child notation, for use in [`ppp_fold`].
+#[no_mangle]
+pub extern "C" fn ppp_right() -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || new_notation(right()))
+}
+
+/// Construct a notation that displays the NUL-terminated UTF-8 string `s`. Returns null if `s`
+/// is null or isn't UTF-8.
+///
+/// # Safety
+///
+/// `s` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_lit(s: *const c_char) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || match read_str(s) {
+        Some(s) => new_notation(lit(s)),
+        None => ptr::null_mut(),
+    })
+}
+
+/// Construct a notation that indents every newline in `notation` by the NUL-terminated UTF-8
+/// string `s`. Returns null if `s` is null or isn't UTF-8 (and frees `notation`).
+///
+/// # Safety
+///
+/// `s` must be null or a valid NUL-terminated string, and `notation` null or a notation handle,
+/// which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_indent(
+    s: *const c_char,
+    notation: *mut FfiNotation,
+) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || {
+        match (read_str(s), take_notation(notation)) {
+            (Some(s), Some(notation)) => new_notation(indent(s, None, notation)),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Construct a notation that displays `notation` all on one line.
+///
+/// # Safety
+///
+/// `notation` must be null or a notation handle, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_flat(notation: *mut FfiNotation) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || match take_notation(notation) {
+        Some(notation) => new_notation(flat(notation)),
+        None => ptr::null_mut(),
+    })
+}
+
+/// Construct a notation that displays `left` followed by `right`.
+///
+/// # Safety
+///
+/// `left` and `right` must be null or notation handles, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_concat(
+    left: *mut FfiNotation,
+    right: *mut FfiNotation,
+) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || {
+        match (take_notation(left), take_notation(right)) {
+            (Some(left), Some(right)) => new_notation(left + right),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Construct a notation that displays `first` if it fits, and `second` otherwise.
+///
+/// # Safety
+///
+/// `first` and `second` must be null or notation handles, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_choice(
+    first: *mut FfiNotation,
+    second: *mut FfiNotation,
+) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || {
+        match (take_notation(first), take_notation(second)) {
+            (Some(first), Some(second)) => new_notation(first | second),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Construct a notation that displays `then_notation` if the `condition` callback returns true
+/// for the node's `child_index`'th child (or for the node itself, if `child_index` is null), and
+/// `else_notation` otherwise.
+///
+/// # Safety
+///
+/// `child_index` must be null or point to an `isize`. `then_notation` and `else_notation` must be
+/// null or notation handles, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_check(
+    condition: u32,
+    child_index: *const isize,
+    then_notation: *mut FfiNotation,
+    else_notation: *mut FfiNotation,
+) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || {
+        let pos = if child_index.is_null() {
+            CheckPos::Here
+        } else {
+            CheckPos::Child(*child_index)
+        };
+        match (take_notation(then_notation), take_notation(else_notation)) {
+            (Some(then_notation), Some(else_notation)) => {
+                new_notation(check(condition, pos, then_notation, else_notation))
+            }
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Construct a notation that displays `zero`, `one`, or `many`, depending on how many children
+/// the node has.
+///
+/// # Safety
+///
+/// `zero`, `one`, and `many` must be null or notation handles, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_count(
+    zero: *mut FfiNotation,
+    one: *mut FfiNotation,
+    many: *mut FfiNotation,
+) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || {
+        match (take_notation(zero), take_notation(one), take_notation(many)) {
+            (Some(zero), Some(one), Some(many)) => new_notation(count(Count { zero, one, many })),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Construct a notation that displays the first child with `first`, and then joins on each
+/// following child with `join` (which can use [`ppp_left`] and [`ppp_right`]).
+///
+/// # Safety
+///
+/// `first` and `join` must be null or notation handles, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_fold(
+    first: *mut FfiNotation,
+    join: *mut FfiNotation,
+) -> *mut FfiNotation {
+    catch_panic(ptr::null_mut(), || {
+        match (take_notation(first), take_notation(join)) {
+            (Some(first), Some(join)) => new_notation(fold(Fold { first, join })),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Free a notation handle that wasn't passed to anything else.
+///
+/// # Safety
+///
+/// `notation` must be null or a notation handle, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_notation_free(notation: *mut FfiNotation) {
+    catch_panic((), || drop(take_notation(notation)))
+}
+
+/// Construct an empty table of notations.
+#[no_mangle]
+pub extern "C" fn ppp_notations_new() -> *mut FfiNotations {
+    catch_panic(ptr::null_mut(), || Box::into_raw(Box::default()))
+}
+
+/// Validate `notation` and add it to the table. On success, its index is stored in `index`.
+/// Returns [`PPP_OUT_OF_RANGE`] if the table already has `u32::MAX + 1` notations. Either way,
+/// this frees `notation` if it doesn't add it.
+///
+/// # Safety
+///
+/// `notations` must be null or a notations table, `notation` null or a notation handle (which
+/// this takes ownership of), and `index` null or a pointer to a `u32`.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_notations_add(
+    notations: *mut FfiNotations,
+    notation: *mut FfiNotation,
+    index: *mut u32,
+) -> i32 {
+    catch_panic(PPP_PANIC, || {
+        let notation = take_notation(notation);
+        if notations.is_null() || index.is_null() {
+            return PPP_NULL_ARGUMENT;
+        }
+        let notation = match notation {
+            Some(notation) => notation,
+            None => return PPP_NULL_ARGUMENT,
+        };
+        let notations = &mut (*notations).0;
+        let new_index = match u32::try_from(notations.len()) {
+            Ok(new_index) => new_index,
+            Err(_) => return PPP_OUT_OF_RANGE,
+        };
+        match notation.validate() {
+            Ok(notation) => {
+                *index = new_index;
+                notations.push(notation);
+                PPP_OK
+            }
+            Err(_) => PPP_INVALID_NOTATION,
+        }
+    })
+}
+
+/// Free a table of notations.
+///
+/// # Safety
+///
+/// `notations` must be null or a notations table, which this takes ownership of.
+#[no_mangle]
+pub unsafe extern "C" fn ppp_notations_free(notations: *mut FfiNotations) {
+    catch_panic((), || {
+        if !notations.is_null() {
+            drop(Box::from_raw(notations));
+        }
+    })
+}
+
+/// Print the lines around the start of the node at `path` (a sequence of `path_len` child indices
+/// from the `root` node): the line containing it, and up to `rows_above` lines above it and
+/// `rows_below` lines below it. They're written to `buffer` as UTF-8, separated by `\n`, with the
+/// number of bytes written stored in `out_len`, and the index of the line containing the node
+/// stored in `focus_row`.
+///
+/// If the lines are longer than `buffer_len`, returns [`PPP_BUFFER_TOO_SMALL`] and stores the
+/// required length in `out_len` (and `focus_row` as usual), without writing to `buffer`.
+///
+/// # Safety
+///
+/// `notations` and `callbacks` must be valid, `path` must point to `path_len` indices (or be null
+/// if `path_len` is 0), `buffer` must point to `buffer_len` writable bytes, and `out_len` and
+/// `focus_row` must point to `usize`s.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn ppp_print(
+    notations: *const FfiNotations,
+    callbacks: *const FfiDocCallbacks,
+    root: u64,
+    width: Width,
+    path: *const usize,
+    path_len: usize,
+    rows_above: usize,
+    rows_below: usize,
+    buffer: *mut u8,
+    buffer_len: usize,
+    out_len: *mut usize,
+    focus_row: *mut usize,
+) -> i32 {
+    catch_panic(PPP_PANIC, || {
+        if notations.is_null()
+            || callbacks.is_null()
+            || (path.is_null() && path_len > 0)
+            || (buffer.is_null() && buffer_len > 0)
+            || out_len.is_null()
+            || focus_row.is_null()
+        {
+            return PPP_NULL_ARGUMENT;
+        }
+        let doc = FfiDoc {
+            notations: &*notations,
+            callbacks: &*callbacks,
+            node: root,
+        };
+        let path = if path_len == 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(path, path_len)
+        };
+
+        let mut lines = Vec::new();
+        let row = match pretty_print_into(
+            doc,
+            width,
+            path,
+            FocusTarget::Start,
+            rows_above,
+            rows_below,
+            None,
+            &mut lines,
+        ) {
+            Ok((row, _col)) => row,
+            Err(_) => return PPP_PRINTING_ERROR,
+        };
+        let output = lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        *out_len = output.len();
+        *focus_row = row;
+        if output.len() > buffer_len {
+            return PPP_BUFFER_TOO_SMALL;
+        }
+        // `buffer` may be null if `buffer_len` is 0, and copying to null is UB even for 0 bytes.
+        if !output.is_empty() {
+            ptr::copy_nonoverlapping(output.as_ptr(), buffer, output.len());
+        }
+        PPP_OK
+    })
+}
@@ -1,5 +1,5 @@
-use std::fmt;
-use std::ops::Add;
+use core::fmt;
+use core::ops::Add;
 
 /// Line number, 0-indexed.
 pub type Row = u32;
@@ -35,7 +35,7 @@ pub struct Size {
 /// A rectangle, either on the window or on the document. Includes its upper-left, but excludes its
 /// lower-right.
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
-pub(crate) struct Rectangle {
+pub struct Rectangle {
     pub min_row: Row,
     pub max_row: Row,
     pub min_col: Col,
@@ -46,32 +46,98 @@ impl Pos {
     pub fn zero() -> Pos {
         Pos { row: 0, col: 0 }
     }
+
+    /// Move this position down by `offset.row` and right by `offset.col`. Saturates at the
+    /// largest representable row and column instead of overflowing.
+    pub fn offset(self, offset: Pos) -> Pos {
+        Pos {
+            row: self.row.saturating_add(offset.row),
+            col: self.col.saturating_add(offset.col),
+        }
+    }
+}
+
+impl Size {
+    /// Shrink this size so that it's no larger than `max` in either dimension.
+    pub fn clamp(self, max: Size) -> Size {
+        Size {
+            width: self.width.min(max.width),
+            height: self.height.min(max.height),
+        }
+    }
+
+    /// Subtract `other`'s width and height from this size's, saturating at zero instead of
+    /// underflowing.
+    pub fn saturating_sub(self, other: Size) -> Size {
+        Size {
+            width: self.width.saturating_sub(other.width),
+            height: self.height.saturating_sub(other.height),
+        }
+    }
 }
 
 impl Rectangle {
+    /// The rectangle with the given size whose upper-left is at the origin.
     pub fn from_size(size: Size) -> Rectangle {
+        Rectangle::new(Pos::zero(), size)
+    }
+
+    /// The rectangle with the given upper-left position and size. If it would extend past the
+    /// largest representable row or column, it's cut off there.
+    pub fn new(pos: Pos, size: Size) -> Rectangle {
         Rectangle {
-            min_row: 0,
-            min_col: 0,
-            max_row: size.height,
-            max_col: size.width,
+            min_row: pos.row,
+            min_col: pos.col,
+            max_row: pos.row.saturating_add(size.height),
+            max_col: pos.col.saturating_add(size.width),
+        }
+    }
+
+    /// The upper-left position of the rectangle.
+    pub fn pos(self) -> Pos {
+        Pos {
+            row: self.min_row,
+            col: self.min_col,
         }
     }
 
+    /// Shrink this rectangle to be no larger than `size`, keeping its upper-left in place.
+    pub fn shrink(self, size: Size) -> Rectangle {
+        Rectangle::new(self.pos(), self.size().clamp(size))
+    }
+
+    /// The width of the rectangle, or 0 if `max_col` is less than `min_col`.
     pub fn width(self) -> Width {
-        self.max_col - self.min_col
+        self.max_col.saturating_sub(self.min_col)
     }
 
+    /// The height of the rectangle, or 0 if `max_row` is less than `min_row`.
     pub fn height(self) -> Height {
-        self.max_row - self.min_row
+        self.max_row.saturating_sub(self.min_row)
     }
 
+    /// The width and height of the rectangle.
     pub fn size(self) -> Size {
         Size {
             height: self.height(),
             width: self.width(),
         }
     }
+
+    /// Whether this rectangle includes the cell at `pos`.
+    pub fn contains(self, pos: Pos) -> bool {
+        pos.row >= self.min_row
+            && pos.row < self.max_row
+            && pos.col >= self.min_col
+            && pos.col < self.max_col
+    }
+
+    /// Whether this rectangle and `other` share at least one cell. Empty rectangles don't
+    /// intersect anything.
+    pub fn intersects(self, other: Rectangle) -> bool {
+        self.min_row.max(other.min_row) < self.max_row.min(other.max_row)
+            && self.min_col.max(other.min_col) < self.max_col.min(other.max_col)
+    }
 }
 
 impl Add<Size> for Pos {
@@ -91,9 +157,59 @@ pub fn str_width(s: &str) -> Width {
     unicode_width::UnicodeWidthStr::width(s) as Width
 }
 
-/// Returns true if the char is 2 columns wide and false if its 1 column wide.
-pub fn is_char_full_width(ch: char) -> bool {
-    unicode_width::UnicodeWidthChar::width(ch) == Some(2)
+/// The width of a char in columns. Like [`str_width`], this is 0 for control characters.
+pub fn char_width(ch: char) -> Width {
+    unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0) as Width
+}
+
+/// How to measure the width of text in columns. Terminals and fonts don't all agree on this
+/// (for example on East Asian "ambiguous width" characters, or on emoji sequences), so you can
+/// supply your own measurement to
+/// [`PrintOptions::width_policy`](crate::PrintOptions::width_policy) or
+/// [`PrintingOptions::width_policy`](crate::pane::PrintingOptions::width_policy). The default is
+/// [`UnicodeWidth`].
+///
+/// The policy is used while printing. Methods that re-measure an already printed
+/// [`Line`](crate::Line), like [`Line::split_at_width()`](crate::Line::split_at_width), always use
+/// `UnicodeWidth`.
+pub trait WidthPolicy: fmt::Debug {
+    /// The width of a char in columns. It should be 0, 1, or 2.
+    fn char_width(&self, ch: char) -> Width;
+
+    /// The width of a string in columns. By default, this is the sum of the widths of its chars.
+    fn str_width(&self, s: &str) -> Width {
+        s.chars().map(|ch| self.char_width(ch)).sum()
+    }
+}
+
+/// Measure widths using the Unicode standard, where East Asian "ambiguous width" characters are
+/// 1 column wide. This is the default [`WidthPolicy`], and agrees with [`str_width`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeWidth;
+
+impl WidthPolicy for UnicodeWidth {
+    fn char_width(&self, ch: char) -> Width {
+        char_width(ch)
+    }
+
+    fn str_width(&self, s: &str) -> Width {
+        str_width(s)
+    }
+}
+
+/// Measure widths using the Unicode standard, but with East Asian "ambiguous width" characters
+/// (such as `·` and Greek letters) 2 columns wide, as many terminals display them in CJK locales.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnicodeWidthCjk;
+
+impl WidthPolicy for UnicodeWidthCjk {
+    fn char_width(&self, ch: char) -> Width {
+        unicode_width::UnicodeWidthChar::width_cjk(ch).unwrap_or(0) as Width
+    }
+
+    fn str_width(&self, s: &str) -> Width {
+        unicode_width::UnicodeWidthStr::width_cjk(s) as Width
+    }
 }
 
 impl fmt::Display for Pos {
@@ -14,3 +14,16 @@ macro_rules! __span {
 #[cfg(not(feature = "profile"))]
 #[doc(hidden)]
 pub use crate::__span as span;
+
+#[cfg(feature = "std")]
+pub use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub use std::sync::Mutex;
+
+#[cfg(not(feature = "std"))]
+pub use hashbrown::{HashMap, HashSet};
+#[cfg(not(feature = "std"))]
+pub use spin::Mutex;
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("partial-pretty-printer requires either the `std` or the `alloc` feature");
@@ -19,6 +19,10 @@
 //! - Print part of a [`PrettyDoc`] using [`pretty_print()`]. This lets you (i) use styles and (ii)
 //!   improve performance by only printing what you need.
 //! - Make a terminal UI with multiple side-by-side documents, using the [`pane`] module.
+//! - Render the same document in a different color scheme using a [`Theme`].
+//! - Point at part of a document in a compiler-style message, using the [`diagnostics`] module.
+//! - Print a one-off document without implementing [`PrettyDoc`], using
+//!   [`doc_examples::simple_tree`].
 //!
 //! Keep reading for details.
 //!
@@ -56,29 +60,110 @@
 //! Besides pretty printing a single document, this library has a mechanism for splitting a window
 //! into multiple rectangular panes, and displaying a different document in each one. This is meant
 //! for implementing terminal UIs. For more details see the [`pane`] module.
+//!
+//! ## `no_std`
+//!
+//! The `std` feature is on by default. To use this crate in a `no_std` environment that has
+//! `alloc`, turn off its default features and turn on the `alloc` feature instead. Everything
+//! still works except for the [`pane`] module, [`doc_examples`], [`testing`], and the `ffi`,
+//! `wasm`, and `profile` features, which all require `std`. This crate's minimum supported Rust
+//! version is 1.81, for `core::error::Error`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[macro_use]
+extern crate alloc;
 
+mod analyze;
+#[cfg(feature = "std")]
+mod assertions;
+#[cfg(feature = "async")]
+mod async_print;
+mod bidi;
+mod check_doc;
+mod choice_strategy;
 mod consolidated_notation;
+#[cfg(feature = "std")]
+mod fake_window;
 mod geometry;
 mod infra;
+mod line_decorator;
+#[cfg(feature = "macros")]
+mod macros;
 mod notation;
+#[cfg(feature = "std")]
 mod oracle;
+mod prefetch;
 mod pretty_doc;
 mod pretty_print;
+mod reanchor;
+mod search;
+#[cfg(feature = "std")]
+mod synthetic_docs;
+mod theme;
 mod valid_notation;
 
+pub mod diagnostics;
+#[cfg(feature = "std")]
 pub mod doc_examples;
+pub mod doc_view;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod notation_constructors;
+#[cfg(feature = "std")]
 pub mod pane;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub use consolidated_notation::{PrintingError, Segment};
-pub use geometry::{Col, Height, Pos, Row, Size, Width};
-pub use notation::{CheckPos, Condition, Notation, StyleLabel};
+pub use analyze::{analyze, ChoiceOption, ChoiceReport, NotationReport};
+#[cfg(feature = "async")]
+pub use async_print::{pretty_print_async, pretty_print_stream, LineStream};
+pub use bidi::Direction;
+pub use check_doc::{check_doc, DocNotationMismatch, MismatchKind};
+pub use choice_strategy::{ChoiceContext, ChoiceStrategy, ChoiceTieBreak, FirstFits};
+pub use consolidated_notation::{
+    PrintingError, Segment, SegmentKind, Split, SplitRounding, SplitSide,
+};
+pub use geometry::{
+    Col, Height, Pos, Rectangle, Row, Size, UnicodeWidth, UnicodeWidthCjk, Width, WidthPolicy,
+};
+pub use line_decorator::{LineDecorator, LineInfo};
+pub use notation::{CheckPos, Condition, ConditionExpr, JoinCondition, Notation, StyleLabel};
+pub use prefetch::Prefetcher;
 pub use pretty_doc::{PrettyDoc, Style};
-pub use pretty_print::{pretty_print, pretty_print_to_string, FocusTarget, FocusedLine, Line};
+pub use pretty_print::{
+    analyze_layout, debug_snapshot, fits_first_line, layout_fingerprint, pretty_print,
+    pretty_print_blocks, pretty_print_context, pretty_print_into, pretty_print_multi,
+    pretty_print_near_line, pretty_print_to_annotated_string, pretty_print_to_string,
+    pretty_print_to_tabbed_string, pretty_print_to_trimmed_string, pretty_print_with_options,
+    print_node_to_string, semantic_tokens, BlockPrintResult, DebugBlock, DebugSnapshot,
+    DownwardBlockPrinter, DownwardCheckpoint, DownwardPrinter, DownwardState, ErrorPolicy,
+    FocusTarget, FocusedBlock, FocusedLine, LayoutCache, LayoutMetrics, Line, MultiFocusPrint,
+    OffsetKind, PrintOptions, PrintResult, PrintStats, ResolvedBlock, SelectionSegment,
+    SemanticToken, SpanAnnotation, UpwardBlockPrinter, UpwardCheckpoint, UpwardPrinter,
+    UpwardState, ERROR_PLACEHOLDER,
+};
+#[allow(deprecated)]
+pub use pretty_print::{pretty_print_with_fallback, pretty_print_with_stats};
+pub use reanchor::{
+    id_at_path, path_near_line, path_of_id, reanchor, validate_path, PathEdit, PathFallback,
+    PathIndex,
+};
+pub use search::{cmp_layout_order, next_match_after, prev_match_before, Matches};
+pub use theme::{Theme, Themed};
 pub use valid_notation::{NotationError, ValidNotation};
 
-/// For testing only. Not core functionality.
+/// For testing only. Not core functionality. The `assert_pp` family of assertions are for testing
+/// your own notations: they compare the printer's output against a simple reference
+/// implementation, with the focus on every node of the document.
+#[cfg(feature = "std")]
 pub mod testing {
+    pub use super::assertions::{
+        all_paths, assert_pp, assert_pp_focus, assert_pp_region, assert_pp_seek,
+        assert_pp_without_expectation, print_region,
+    };
+    pub use super::fake_window::{FakeWindow, FakeWindowError, WindowCall};
     pub use super::geometry::str_width;
     pub use super::oracle::oracular_pretty_print;
+    pub use super::synthetic_docs::{make_deep_doc, make_flow_doc, make_wide_doc};
 }
@@ -0,0 +1,34 @@
+use crate::{PrettyDoc, Segment};
+use alloc::vec::Vec;
+
+/// Adds extra segments to the start or end of each printed line, such as a gutter with diagnostic
+/// markers or coverage signs. Supply one in
+/// [`PrintOptions::line_decorator`](crate::PrintOptions::line_decorator). It's called once for
+/// each line, as that line is printed, so decorations stay in sync with partial printing.
+///
+/// Decorations don't take part in layout: they aren't counted against the printing width.
+pub trait LineDecorator<'d, D: PrettyDoc<'d>> {
+    /// Decorate the line described by `line`, by pushing segments onto `prefix` (which will be
+    /// shown before the line's segments) and `suffix` (which will be shown after them). Both start
+    /// out empty.
+    fn decorate(
+        &self,
+        line: &LineInfo<'_, 'd, D>,
+        prefix: &mut Vec<Segment<'d, D>>,
+        suffix: &mut Vec<Segment<'d, D>>,
+    );
+}
+
+/// What a [`LineDecorator`] can find out about a line.
+pub struct LineInfo<'a, 'd, D: PrettyDoc<'d>> {
+    /// The position of the line relative to the focused line: `0` for the focused line itself,
+    /// `-1` for the line above it, `1` for the line below it, and so on.
+    pub row: isize,
+    /// The id of the document node whose notation contains the [`Notation::Newline`] that started
+    /// this line, or `None` for the first line of the document.
+    ///
+    /// [`Notation::Newline`]: crate::Notation::Newline
+    pub start_id: Option<D::Id>,
+    /// The line's segments, before decoration.
+    pub segments: &'a [Segment<'d, D>],
+}
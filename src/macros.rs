@@ -0,0 +1,96 @@
+/// Construct a [`Notation`](crate::Notation) with an expression-like syntax, instead of calling
+/// the [`notation_constructors`](crate::notation_constructors) directly.
+///
+/// ```
+/// # use partial_pretty_printer::nota;
+/// # use partial_pretty_printer::notation_constructors::{child, left, right, fold, Fold};
+/// # use partial_pretty_printer::doc_examples::BasicStyle;
+/// # type Notation = partial_pretty_printer::Notation<BasicStyle, ()>;
+/// let children: Notation = fold(Fold {
+///     first: child(0),
+///     join: left() + nota!{ "," ++ nl } + right(),
+/// });
+/// let list: Notation = nota!{
+///     "[" ++ (flat(children) | indent("    ", nl ++ children) ++ nl) ++ "]"
+/// };
+/// ```
+///
+/// The syntax is:
+///
+/// - `x | y` is [`Choice`](crate::Notation::Choice), and binds loosest.
+/// - `x ^ y` is `x`, a newline, then `y`.
+/// - `x ++ y` is [`Concat`](crate::Notation::Concat), and binds tightest.
+/// - `"str"` is a literal.
+/// - `nl`, `eol`, `empty`, `text`, `space`, `mark`, `left`, and `right` are the constructors of
+///   the same name.
+/// - `child(i)`, `flat(x)`, `cache(x)`, and `indent("str", x)` are the constructors of the same
+///   name. The arguments to `flat`, `cache`, and `indent` are themselves in `nota!` syntax.
+/// - `(x)` groups.
+/// - `{ expr }` is any Rust expression of type `Notation`.
+/// - Any other identifier is a variable holding a `Notation`. It gets cloned, so the same variable
+///   can be used more than once.
+#[macro_export]
+macro_rules! nota {
+    ($($tokens:tt)+) => {
+        $crate::__nota!(@choice [] [] $($tokens)+)
+    };
+}
+
+// Each operator level splits its tokens on its operator, accumulating each finished operand as a
+// parenthesized group in the first bracket and the operand in progress in the second bracket.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __nota {
+    (@choice [$(($($done:tt)*))*] [$($cur:tt)*] | $($rest:tt)*) => {
+        $crate::__nota!(@choice [$(($($done)*))* ($($cur)*)] [] $($rest)*)
+    };
+    (@choice [$($done:tt)*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__nota!(@choice [$($done)*] [$($cur)* $next] $($rest)*)
+    };
+    (@choice [$(($($done:tt)*))*] [$($cur:tt)*]) => {
+        ($($crate::__nota!(@newline [] [] $($done)*) | )* $crate::__nota!(@newline [] [] $($cur)*))
+    };
+
+    (@newline [$(($($done:tt)*))*] [$($cur:tt)*] ^ $($rest:tt)*) => {
+        $crate::__nota!(@newline [$(($($done)*))* ($($cur)*)] [] $($rest)*)
+    };
+    (@newline [$($done:tt)*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__nota!(@newline [$($done)*] [$($cur)* $next] $($rest)*)
+    };
+    (@newline [$(($($done:tt)*))*] [$($cur:tt)*]) => {
+        ($($crate::__nota!(@concat [] [] $($done)*) ^ )* $crate::__nota!(@concat [] [] $($cur)*))
+    };
+
+    (@concat [$(($($done:tt)*))*] [$($cur:tt)*] + + $($rest:tt)*) => {
+        $crate::__nota!(@concat [$(($($done)*))* ($($cur)*)] [] $($rest)*)
+    };
+    (@concat [$($done:tt)*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__nota!(@concat [$($done)*] [$($cur)* $next] $($rest)*)
+    };
+    (@concat [$(($($done:tt)*))*] [$($cur:tt)*]) => {
+        ($($crate::__nota!(@atom $($done)*) + )* $crate::__nota!(@atom $($cur)*))
+    };
+
+    (@atom $s:literal) => { $crate::notation_constructors::lit($s) };
+    (@atom nl) => { $crate::notation_constructors::nl() };
+    (@atom eol) => { $crate::notation_constructors::eol() };
+    (@atom empty) => { $crate::notation_constructors::empty() };
+    (@atom text) => { $crate::notation_constructors::text() };
+    (@atom space) => { $crate::notation_constructors::space() };
+    (@atom mark) => { $crate::notation_constructors::mark() };
+    (@atom left) => { $crate::notation_constructors::left() };
+    (@atom right) => { $crate::notation_constructors::right() };
+    (@atom child($i:expr)) => { $crate::notation_constructors::child($i) };
+    (@atom flat($($inner:tt)+)) => {
+        $crate::notation_constructors::flat($crate::nota!($($inner)+))
+    };
+    (@atom cache($($inner:tt)+)) => {
+        $crate::notation_constructors::cache($crate::nota!($($inner)+))
+    };
+    (@atom indent($s:literal, $($inner:tt)+)) => {
+        $crate::notation_constructors::indent($s, None, $crate::nota!($($inner)+))
+    };
+    (@atom ($($inner:tt)+)) => { $crate::nota!($($inner)+) };
+    (@atom { $e:expr }) => { $e };
+    (@atom $var:ident) => { $var.clone() };
+}
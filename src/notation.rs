@@ -1,6 +1,7 @@
-use crate::geometry::Width;
-use std::fmt;
-use std::ops::{Add, BitOr, BitXor, Shr};
+use crate::geometry::{Col, Width};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt;
+use core::ops::{Add, BitAnd, BitOr, BitXor, Not, Shr};
 
 #[cfg(feature = "serialization")]
 use serde::{Deserialize, Serialize};
@@ -30,6 +31,16 @@ pub enum Notation<L: StyleLabel, C: Condition> {
     Empty,
     /// Display a newline followed by the current indentation. (See [`Notation::Indent`]).
     Newline,
+    /// Display a newline followed by between `min` and `max` blank lines, where the arguments
+    /// are `(min, max)`. On its own, it displays `min` blank lines. Adjacent `VerticalSpace`s
+    /// (with nothing displayed in between) collapse into one, which displays the largest of their
+    /// `min`s, but no more than the smallest of their `max`s. So the notations on either side of a
+    /// boundary, like the end of a function and the start of the comment after it, can each ask
+    /// for some space without the requests adding up.
+    ///
+    /// Vertical space doesn't collapse across the edge of a [`Choice`](Notation::Choice) or a
+    /// [`Cache`](Notation::Cache).
+    VerticalSpace(usize, usize),
     /// The printer will try to resolve choices such that this `EndOfLine` is followed by a
     /// [`Newline`](Notation::Newline) (or the end of the document), and not by a
     /// [`Text`](Notation::Text) or [`Literal`](Notation::Literal). If that's not possible, it will
@@ -37,10 +48,36 @@ pub enum Notation<L: StyleLabel, C: Condition> {
     EndOfLine,
     /// Display this constant text. It must not contain a newline character.
     Literal(String),
+    /// Display a "soft" space: a single space that collapses with any adjacent soft spaces, and
+    /// disappears entirely at the start or end of a line (or after an
+    /// [`EndOfLine`](Notation::EndOfLine)). This is convenient for flow layouts, where
+    /// `lit(",") + space() + (empty() | nl())` never leaves a trailing space when it breaks the
+    /// line, and two neighbouring separators that each contribute a `Space` display only one.
+    Space,
+    /// Pad the line with spaces up to the given column, right before the next non-empty text on
+    /// the same line. If the line already reaches that column, nothing is added, and if no more
+    /// text follows on the line, the padding is left out. This lines up things like trailing
+    /// comments across lines: `code + pad_to(40) + lit("// comment") + eol()`.
+    ///
+    /// Padding doesn't count against the width when resolving [`Choice`](Notation::Choice)s, so
+    /// it can make a line overflow. If several are pending at once, the largest column wins. Like
+    /// a [`Space`](Notation::Space), it's dropped after an [`EndOfLine`](Notation::EndOfLine).
+    PadTo(Col),
     /// Display a dynamic piece of text from the document. It must not contain a newline character.
     /// It can only be used in the notation for a document node that contains text (indicated by
     /// [`PrettyDoc::num_children()`] returning `None`).
     Text,
+    /// Display the document node's text like [`Text`](Notation::Text), but if it doesn't fit on
+    /// the line, break it after occurrences of any of the `breaks` strings (such as `/` in a URL
+    /// or `::` in a path). The lines after a break are indented by `indent` (in addition to the
+    /// current indentation). Each break is taken only if the text up to the next one doesn't fit,
+    /// so that a long token is filled greedily.
+    BreakableText { breaks: Vec<String>, indent: String },
+    /// Display a piece of text that's computed when printing, by looking up the label in the
+    /// current document node (via [`PrettyDoc::lookup_text()`]). This lets values like line counts
+    /// or timestamps appear in otherwise static notations, without storing them in the document.
+    /// The text must not contain a newline character.
+    Placeholder(L),
     /// Pick the first option of every [`Choice`](Notation::Choice) in the contained notation.
     /// If the notation author followed the recommendation of not putting
     /// [`Newline`](Notation::Newline)s in the first options of [`Choice`](Notation::Choice)s,
@@ -79,6 +116,15 @@ pub enum Notation<L: StyleLabel, C: Condition> {
     /// Check whether the [`Condition`](PrettyDoc::Condition) `C` is true for the document node
     /// located at [`CheckPos`]. If so, display the first notation, otherwise display the second.
     Check(C, CheckPos, Box<Notation<L, C>>, Box<Notation<L, C>>),
+    /// Check whether the [`JoinCondition`] holds for the current position within a
+    /// [`Fold`](Notation::Fold). If so, display the first notation, otherwise display the second.
+    /// Unlike [`Check`](Notation::Check), this doesn't consult the document. May only be used
+    /// inside of `Fold`'s `join` case.
+    CheckJoin(JoinCondition, Box<Notation<L, C>>, Box<Notation<L, C>>),
+    /// Like [`Check`](Notation::Check), but check a boolean combination of conditions, each on
+    /// its own document node. If it holds, display the first notation, otherwise display the
+    /// second. The conditions are checked from left to right, and only until the result is known.
+    CheckExpr(ConditionExpr<C>, Box<Notation<L, C>>, Box<Notation<L, C>>),
     /// Display the i'th child of the current document node. If the index is negative, the number
     /// of children is added to it (so that -1 accesses the last child). Can only be used on a node
     /// for which [`PrettyDoc::num_children()`] returns `Some(n)`, with `-n <= i < n`.
@@ -88,14 +134,75 @@ pub enum Notation<L: StyleLabel, C: Condition> {
     /// other styles that were previously applied to this subtree using
     /// [`Style::combine()`](crate::Style::combine).
     Style(L, Box<Notation<L, C>>),
-    /// A location you can seek to while pretty printing, using [`FocusTarget::Mark`].
+    /// Like [`Style`](Notation::Style), but look up the style with
+    /// [`PrettyDoc::lookup_depth_style()`], which is also given the current node's depth in the
+    /// document (the number of [`Child`](Notation::Child)ren entered to reach it from the root).
+    /// This lets the style vary with nesting depth, as in "rainbow" brackets, without the document
+    /// needing to know the depth of each node.
+    DepthStyle(L, Box<Notation<L, C>>),
+    /// Look up the link target with the given label in the current document node (via
+    /// [`PrettyDoc::lookup_link()`]), and attach it to the text in this notation: each
+    /// [`Segment`](crate::Segment) printed from it will have that target as its `link`. If links
+    /// are nested, the innermost one wins. If the lookup returns `None`, the enclosing link (if
+    /// any) is kept. Links never affect layout.
+    Link(L, Box<Notation<L, C>>),
+    /// Transform the [`Text`](Notation::Text) of every node displayed inside the contained
+    /// notation, by passing the label and the text to [`PrettyDoc::transform_text()`] on the node
+    /// that the text belongs to. This can uppercase, escape, or truncate text at print time,
+    /// without changing the document. If these are nested, the innermost one wins. Literals and
+    /// placeholders are not transformed.
+    MapText(L, Box<Notation<L, C>>),
+    /// A location you can seek to while pretty printing, using [`FocusTarget::Mark`],
+    /// [`FocusTarget::NthMark`], or [`FocusTarget::LastMark`].
     FocusMark,
+    /// Display the contained notation, and additionally let the printer memoize how wide this
+    /// subtree's first line is. Checking whether a choice fits often has to measure the rest of the
+    /// line after it, so this can give big speedups for repeated structures like long homogeneous
+    /// lists. The measurements can be kept across prints with a
+    /// [`LayoutCache`](crate::LayoutCache).
+    ///
+    /// Only these measurements are memoized, not the subtree's resolved lines: those are laid out
+    /// lazily, one line at a time in either direction from the focus, and the layout of a
+    /// subtree's last line can depend on whatever follows it.
+    ///
+    /// This is purely a performance hint: it never changes what's displayed.
+    Cache(Box<Notation<L, C>>),
+    /// Display the contained notation as if the printing width were at most the given width. This
+    /// lets part of a document be laid out against a narrower limit than the rest of it, such as an
+    /// embedded code sample that should stay within 80 columns inside a 120 column document. The
+    /// width is measured from the start of the line (not from where the notation starts), and if
+    /// these are nested, the smallest width applies.
+    WithWidth(Width, Box<Notation<L, C>>),
     /// Display one of these notations, depending how many children the current document node has.
     Count {
         zero: Box<Notation<L, C>>,
         one: Box<Notation<L, C>>,
         many: Box<Notation<L, C>>,
     },
+    /// Display the notation paired with the number of children that the current document node
+    /// has, or `fallback` if none of them match. This is like [`Count`](Notation::Count), but for
+    /// layouts that depend on the exact arity, such as a `-` operator that can be either unary or
+    /// binary. If the same number appears more than once, the first one is used.
+    Arity {
+        cases: Vec<(usize, Notation<L, C>)>,
+        fallback: Box<Notation<L, C>>,
+    },
+    /// If the current document node has at most `limit` children, display `notation`. Otherwise,
+    /// display `notation` as if the node had only its first `limit` children, followed by
+    /// `summary`. The remaining children are never looked at. Within `summary`,
+    /// [`HiddenCount`](Notation::HiddenCount) displays how many children were left out.
+    ///
+    /// For example, to show at most 100 elements of a huge array, with `… 493 more` on the line
+    /// after them, use a `summary` of `nl() + lit("… ") + hidden_count() + lit(" more")`.
+    /// To summarize only when the user folds a node, put this inside a [`Check`](Notation::Check).
+    Summarize {
+        limit: usize,
+        notation: Box<Notation<L, C>>,
+        summary: Box<Notation<L, C>>,
+    },
+    /// Display the number of children that the enclosing [`Summarize`](Notation::Summarize) left
+    /// out, or `0` outside of a `Summarize`'s `summary`.
+    HiddenCount,
     /// [Left-fold](https://en.wikipedia.org/wiki/Fold_(higher-order_function)) over the node's
     /// children. This lets you specify how an indeterminate number of children should be
     /// displayed. For example, to separate the children by commas on a single line:
@@ -123,6 +230,17 @@ pub enum Notation<L: StyleLabel, C: Condition> {
         /// and [`Right`](Notation::Right) refers to the next child to be appended.
         join: Box<Notation<L, C>>,
     },
+    /// Display the node's children in a grid of at most `max_columns` columns, for lists of short
+    /// items like enum variants or imports. The children fill the columns top to bottom, and the
+    /// columns are as close to the same height as possible. Each child is displayed flat, and each
+    /// column is as wide as its widest child, followed by `gap` spaces.
+    ///
+    /// Every child is measured first, and the printer picks the most columns that fit. Like any
+    /// [`Choice`](Notation::Choice), this only checks that the first row fits, so a later row can
+    /// overflow if its last cell is wider. If there's only room for one column, or some child
+    /// can't be displayed flat, each child is displayed (not flat) on its own line instead. Inside
+    /// a [`Flat`](Notation::Flat), all the children are displayed on one line.
+    Columns { max_columns: usize, gap: Width },
     /// Used in [`Fold`](Notation::Fold)'s `join` case to refer to the accumulated notation. Illegal
     /// outside of `Fold`.
     Left,
@@ -146,6 +264,98 @@ pub enum CheckPos {
     RightChild,
 }
 
+/// A boolean combination of [`Condition`]s, for use with [`Notation::CheckExpr`]. Construct one
+/// with [`notation_constructors::holds()`], and combine them with `&`, `|`, and `!`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum ConditionExpr<C: Condition> {
+    /// Whether the condition holds for the document node at the [`CheckPos`], as in
+    /// [`Notation::Check`].
+    Holds(C, CheckPos),
+    /// Whether the expression doesn't hold.
+    Not(Box<ConditionExpr<C>>),
+    /// Whether every expression holds. True if there are none.
+    All(Vec<ConditionExpr<C>>),
+    /// Whether any expression holds. False if there are none.
+    Any(Vec<ConditionExpr<C>>),
+}
+
+impl<C: Condition> ConditionExpr<C> {
+    /// Every position that this expression checks a condition at.
+    pub(crate) fn positions(&self) -> Vec<&CheckPos> {
+        let mut positions = Vec::new();
+        let mut exprs = vec![self];
+        while let Some(expr) = exprs.pop() {
+            match expr {
+                ConditionExpr::Holds(_, pos) => positions.push(pos),
+                ConditionExpr::Not(expr) => exprs.push(expr),
+                ConditionExpr::All(list) | ConditionExpr::Any(list) => {
+                    exprs.extend(list.iter().rev())
+                }
+            }
+        }
+        positions
+    }
+}
+
+/// `a & b` holds if both `a` and `b` do.
+impl<C: Condition> BitAnd<ConditionExpr<C>> for ConditionExpr<C> {
+    type Output = ConditionExpr<C>;
+
+    fn bitand(self, other: ConditionExpr<C>) -> ConditionExpr<C> {
+        match self {
+            ConditionExpr::All(mut list) => {
+                list.push(other);
+                ConditionExpr::All(list)
+            }
+            _ => ConditionExpr::All(vec![self, other]),
+        }
+    }
+}
+
+/// `a | b` holds if either `a` or `b` does.
+impl<C: Condition> BitOr<ConditionExpr<C>> for ConditionExpr<C> {
+    type Output = ConditionExpr<C>;
+
+    fn bitor(self, other: ConditionExpr<C>) -> ConditionExpr<C> {
+        match self {
+            ConditionExpr::Any(mut list) => {
+                list.push(other);
+                ConditionExpr::Any(list)
+            }
+            _ => ConditionExpr::Any(vec![self, other]),
+        }
+    }
+}
+
+/// `!a` holds if `a` doesn't.
+impl<C: Condition> Not for ConditionExpr<C> {
+    type Output = ConditionExpr<C>;
+
+    fn not(self) -> ConditionExpr<C> {
+        ConditionExpr::Not(Box::new(self))
+    }
+}
+
+/// A condition on where a [`Notation::Fold`]'s `join` is being applied, for use with
+/// [`Notation::CheckJoin`]. If a node has `n` children, then `join` is applied `n - 1` times: the
+/// first time to join child 1 onto child 0, and the last time to join child `n - 1` onto the rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum JoinCondition {
+    /// Whether this join is between the first and second children.
+    IsFirstJoin,
+    /// Whether this join is between the second-to-last and last children.
+    IsLastJoin,
+    /// Whether every child before this join displays as nothing (no text and no newlines) in
+    /// every layout. Together with [`IsRightEmpty`](JoinCondition::IsRightEmpty), this lets a
+    /// separator be left out next to children that display as nothing.
+    IsLeftEmpty,
+    /// Whether the child after this join displays as nothing (no text and no newlines) in every
+    /// layout.
+    IsRightEmpty,
+}
+
 /// Normalizes the index so that negative indices count back from the end of the list.
 /// Returns `None` if the index would be out of bounds.
 pub fn normalize_child_index(signed_index: isize, num_children: usize) -> Option<usize> {
@@ -170,10 +380,17 @@ impl<L: StyleLabel, C: Condition> fmt::Display for Notation<L, C> {
         match self {
             Empty => write!(f, "ε"),
             Newline => write!(f, "↵"),
+            VerticalSpace(min, max) => write!(f, "VSPACE({}, {})", min, max),
             EndOfLine => write!(f, "EOL"),
+            PadTo(col) => write!(f, "PAD({})", col),
             FocusMark => write!(f, "MARK"),
             Text => write!(f, "TEXT"),
+            BreakableText { breaks, indent } => {
+                write!(f, "BreakableText({:?}, '{}')", breaks, indent)
+            }
             Literal(lit) => write!(f, "'{}'", lit),
+            Placeholder(label) => write!(f, "Placeholder({:?})", label),
+            Space => write!(f, "SPACE"),
             Flat(note) => write!(f, "Flat({})", note),
             Indent(lit, _style_label, note) => write!(f, "'{}'⇒({})", lit, note),
             Concat(left, right) => write!(f, "{} + {}", left, right),
@@ -181,12 +398,35 @@ impl<L: StyleLabel, C: Condition> fmt::Display for Notation<L, C> {
             Check(cond, pos, opt1, opt2) => {
                 write!(f, "({:?}@{:?} ? {} | {})", cond, pos, opt1, opt2)
             }
+            CheckJoin(cond, opt1, opt2) => write!(f, "({:?} ? {} | {})", cond, opt1, opt2),
+            CheckExpr(expr, opt1, opt2) => write!(f, "({:?} ? {} | {})", expr, opt1, opt2),
             Child(i) => write!(f, "${}", i),
             Style(style_label, note) => write!(f, "Style({:?}, {})", style_label, note),
+            DepthStyle(style_label, note) => {
+                write!(f, "DepthStyle({:?}, {})", style_label, note)
+            }
+            Link(link_label, note) => write!(f, "Link({:?}, {})", link_label, note),
+            MapText(text_label, note) => write!(f, "MapText({:?}, {})", text_label, note),
+            Cache(note) => write!(f, "Cache({})", note),
+            WithWidth(width, note) => write!(f, "WithWidth({}, {})", width, note),
             Count { zero, one, many } => {
                 write!(f, "Count(zero={}, one={}, many={})", zero, one, many)
             }
+            Arity { cases, fallback } => {
+                write!(f, "Arity(")?;
+                for (arity, note) in cases {
+                    write!(f, "{}={}, ", arity, note)?;
+                }
+                write!(f, "fallback={})", fallback)
+            }
+            Summarize {
+                limit,
+                notation,
+                summary,
+            } => write!(f, "Summarize({}, {}, summary={})", limit, notation, summary),
+            HiddenCount => write!(f, "HIDDEN"),
             Fold { first, join } => write!(f, "Fold(first={}, join={})", first, join),
+            Columns { max_columns, gap } => write!(f, "Columns({}, gap={})", max_columns, gap),
             Left => write!(f, "$Left"),
             Right => write!(f, "$Right"),
         }
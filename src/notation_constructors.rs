@@ -7,8 +7,12 @@
 //! - `x ^ y` is shorthand for `x + `[`Newline`](Notation::Newline)` + y`.
 //! - `i >> x` is shorthand for [`Indent`](Notation::Indent)`(i_spaces,
 //!   `[`Newline`](Notation::Newline)` + x)` (sometimes called "nesting").
+//!
+//! With the `macros` feature, the `nota!` macro offers a more compact syntax for the same
+//! constructors.
 
-use crate::{CheckPos, Condition, Notation, StyleLabel};
+use crate::{CheckPos, Col, Condition, ConditionExpr, JoinCondition, Notation, StyleLabel, Width};
+use alloc::{borrow::ToOwned, boxed::Box, vec::Vec};
 
 /// Construct a [`Notation::Empty`].
 pub fn empty<L: StyleLabel, C: Condition>() -> Notation<L, C> {
@@ -20,6 +24,11 @@ pub fn nl<L: StyleLabel, C: Condition>() -> Notation<L, C> {
     Notation::Newline
 }
 
+/// Construct a [`Notation::VerticalSpace`].
+pub fn vertical_space<L: StyleLabel, C: Condition>(min: usize, max: usize) -> Notation<L, C> {
+    Notation::VerticalSpace(min, max)
+}
+
 /// Construct a [`Notation::EndOfLine`].
 pub fn eol<L: StyleLabel, C: Condition>() -> Notation<L, C> {
     Notation::EndOfLine
@@ -35,21 +44,75 @@ pub fn style<L: StyleLabel, C: Condition>(style_label: L, n: Notation<L, C>) ->
     Notation::Style(style_label, Box::new(n))
 }
 
+/// Construct a [`Notation::DepthStyle`].
+pub fn depth_style<L: StyleLabel, C: Condition>(
+    style_label: L,
+    n: Notation<L, C>,
+) -> Notation<L, C> {
+    Notation::DepthStyle(style_label, Box::new(n))
+}
+
+/// Construct a [`Notation::Link`].
+pub fn link<L: StyleLabel, C: Condition>(link_label: L, n: Notation<L, C>) -> Notation<L, C> {
+    Notation::Link(link_label, Box::new(n))
+}
+
+/// Construct a [`Notation::MapText`].
+pub fn map_text<L: StyleLabel, C: Condition>(text_label: L, n: Notation<L, C>) -> Notation<L, C> {
+    Notation::MapText(text_label, Box::new(n))
+}
+
+/// Construct a [`Notation::Placeholder`].
+pub fn placeholder<L: StyleLabel, C: Condition>(label: L) -> Notation<L, C> {
+    Notation::Placeholder(label)
+}
+
+/// Construct a [`Notation::Cache`].
+pub fn cache<L: StyleLabel, C: Condition>(n: Notation<L, C>) -> Notation<L, C> {
+    Notation::Cache(Box::new(n))
+}
+
+/// Construct a [`Notation::WithWidth`].
+pub fn with_width<L: StyleLabel, C: Condition>(width: Width, n: Notation<L, C>) -> Notation<L, C> {
+    Notation::WithWidth(width, Box::new(n))
+}
+
 /// Construct a [`Notation::FocusMark`].
 pub fn mark<L: StyleLabel, C: Condition>() -> Notation<L, C> {
     Notation::FocusMark
 }
 
+/// Construct a [`Notation::PadTo`].
+pub fn pad_to<L: StyleLabel, C: Condition>(col: Col) -> Notation<L, C> {
+    Notation::PadTo(col)
+}
+
 /// Construct a [`Notation::Text`].
 pub fn text<L: StyleLabel, C: Condition>() -> Notation<L, C> {
     Notation::Text
 }
 
+/// Construct a [`Notation::BreakableText`].
+pub fn breakable_text<L: StyleLabel, C: Condition>(
+    breaks: &[&str],
+    indent: &str,
+) -> Notation<L, C> {
+    Notation::BreakableText {
+        breaks: breaks.iter().map(|s| (*s).to_owned()).collect(),
+        indent: indent.to_owned(),
+    }
+}
+
 /// Construct a [`Notation::Literal`].
 pub fn lit<L: StyleLabel, C: Condition>(s: &str) -> Notation<L, C> {
     Notation::Literal(s.to_owned())
 }
 
+/// Construct a [`Notation::Space`].
+pub fn space<L: StyleLabel, C: Condition>() -> Notation<L, C> {
+    Notation::Space
+}
+
 /// Construct a [`Notation::Flat`].
 pub fn flat<L: StyleLabel, C: Condition>(n: Notation<L, C>) -> Notation<L, C> {
     Notation::Flat(Box::new(n))
@@ -64,6 +127,16 @@ pub fn indent<L: StyleLabel, C: Condition>(
     Notation::Indent(s.to_owned(), style_label, Box::new(n))
 }
 
+/// Indent `n` by `i` spaces, starting on a new line. Same as `i >> n`.
+pub fn nest<L: StyleLabel, C: Condition>(i: Width, n: Notation<L, C>) -> Notation<L, C> {
+    i >> n
+}
+
+/// Either `n` or nothing, preferring `n`. Same as `n | empty()`.
+pub fn opt<L: StyleLabel, C: Condition>(n: Notation<L, C>) -> Notation<L, C> {
+    n | Notation::Empty
+}
+
 /// Construct a [`Notation::Check`].
 pub fn check<L: StyleLabel, C: Condition>(
     condition: C,
@@ -79,6 +152,30 @@ pub fn check<L: StyleLabel, C: Condition>(
     )
 }
 
+/// Construct a [`Notation::CheckJoin`].
+pub fn check_join<L: StyleLabel, C: Condition>(
+    condition: JoinCondition,
+    then_notation: Notation<L, C>,
+    else_notation: Notation<L, C>,
+) -> Notation<L, C> {
+    Notation::CheckJoin(condition, Box::new(then_notation), Box::new(else_notation))
+}
+
+/// Construct a [`Notation::CheckExpr`].
+pub fn check_expr<L: StyleLabel, C: Condition>(
+    expr: ConditionExpr<C>,
+    then_notation: Notation<L, C>,
+    else_notation: Notation<L, C>,
+) -> Notation<L, C> {
+    Notation::CheckExpr(expr, Box::new(then_notation), Box::new(else_notation))
+}
+
+/// Construct a [`ConditionExpr::Holds`], which can be combined with others using `&`, `|`, and
+/// `!`.
+pub fn holds<C: Condition>(condition: C, pos: CheckPos) -> ConditionExpr<C> {
+    ConditionExpr::Holds(condition, pos)
+}
+
 /// The arguments to [`count()`].
 pub struct Count<L: StyleLabel, C: Condition> {
     pub zero: Notation<L, C>,
@@ -95,6 +192,41 @@ pub fn count<L: StyleLabel, C: Condition>(count: Count<L, C>) -> Notation<L, C>
     }
 }
 
+/// The arguments to [`arity()`].
+pub struct Arity<L: StyleLabel, C: Condition> {
+    pub cases: Vec<(usize, Notation<L, C>)>,
+    pub fallback: Notation<L, C>,
+}
+
+/// Construct a [`Notation::Arity`].
+pub fn arity<L: StyleLabel, C: Condition>(arity: Arity<L, C>) -> Notation<L, C> {
+    Notation::Arity {
+        cases: arity.cases,
+        fallback: Box::new(arity.fallback),
+    }
+}
+
+/// The arguments to [`summarize()`].
+pub struct Summarize<L: StyleLabel, C: Condition> {
+    pub limit: usize,
+    pub notation: Notation<L, C>,
+    pub summary: Notation<L, C>,
+}
+
+/// Construct a [`Notation::Summarize`].
+pub fn summarize<L: StyleLabel, C: Condition>(summarize: Summarize<L, C>) -> Notation<L, C> {
+    Notation::Summarize {
+        limit: summarize.limit,
+        notation: Box::new(summarize.notation),
+        summary: Box::new(summarize.summary),
+    }
+}
+
+/// Construct a [`Notation::HiddenCount`].
+pub fn hidden_count<L: StyleLabel, C: Condition>() -> Notation<L, C> {
+    Notation::HiddenCount
+}
+
 /// The arguments to [`fold()`].
 pub struct Fold<L: StyleLabel, C: Condition> {
     pub first: Notation<L, C>,
@@ -109,6 +241,11 @@ pub fn fold<L: StyleLabel, C: Condition>(fold: Fold<L, C>) -> Notation<L, C> {
     }
 }
 
+/// Construct a [`Notation::Columns`].
+pub fn columns<L: StyleLabel, C: Condition>(max_columns: usize, gap: Width) -> Notation<L, C> {
+    Notation::Columns { max_columns, gap }
+}
+
 /// Construct a [`Notation::Left`].
 pub fn left<L: StyleLabel, C: Condition>() -> Notation<L, C> {
     Notation::Left
@@ -118,3 +255,74 @@ pub fn left<L: StyleLabel, C: Condition>() -> Notation<L, C> {
 pub fn right<L: StyleLabel, C: Condition>() -> Notation<L, C> {
     Notation::Right
 }
+
+/// Construct a notation that "flows" a node's children like the words in a paragraph, filling
+/// each line with as many of them as fit before starting a new line. Adjacent children are
+/// separated by `separator` (like `lit(",")`) followed by either `sep_width` spaces if they're on
+/// the same line, or a newline if they aren't. A node with no children is displayed as nothing.
+///
+/// This is shorthand for:
+///
+/// ```text
+/// count(Count {
+///     zero: empty(),
+///     one: child(0),
+///     many: fold(Fold {
+///         first: child(0),
+///         join: left() + separator + (lit(" ".repeat(sep_width)) | nl()) + right(),
+///     }),
+/// })
+/// ```
+pub fn flow<L: StyleLabel, C: Condition>(
+    sep_width: usize,
+    separator: Notation<L, C>,
+) -> Notation<L, C> {
+    let soft_break = lit(&" ".repeat(sep_width)) | nl();
+    count(Count {
+        zero: empty(),
+        one: child(0),
+        many: fold(Fold {
+            first: child(0),
+            join: left() + separator + soft_break + right(),
+        }),
+    })
+}
+
+/// Construct a notation that displays a node's children one after another, with `separator`
+/// between each pair of them, except that children that display as nothing are skipped: no
+/// separator is placed before or after them. This avoids doubled separators and dangling commas
+/// in lists where some children may be empty. A node with no children is displayed as nothing.
+///
+/// This is shorthand for:
+///
+/// ```text
+/// count(Count {
+///     zero: empty(),
+///     one: child(0),
+///     many: fold(Fold {
+///         first: child(0),
+///         join: left()
+///             + check_join(IsLeftEmpty, empty(), check_join(IsRightEmpty, empty(), separator))
+///             + right(),
+///     }),
+/// })
+/// ```
+pub fn join_present_children<L: StyleLabel, C: Condition>(
+    separator: Notation<L, C>,
+) -> Notation<L, C> {
+    use JoinCondition::{IsLeftEmpty, IsRightEmpty};
+
+    let separator = check_join(
+        IsLeftEmpty,
+        empty(),
+        check_join(IsRightEmpty, empty(), separator),
+    );
+    count(Count {
+        zero: empty(),
+        one: child(0),
+        many: fold(Fold {
+            first: child(0),
+            join: left() + separator + right(),
+        }),
+    })
+}
@@ -15,6 +15,24 @@ struct Layout {
     lines: Vec<String>,
     /// Whether there's an `EndOfLine` at the end of the last line of `lines`.
     ends_with_eol: bool,
+    /// Whether there's a soft space at the end of the last line, that hasn't been appended yet
+    /// because it's only displayed if more text follows.
+    pending_space: bool,
+    /// Whether any text (besides indentation) has been appended to the last line.
+    has_content: bool,
+    /// The column to pad the last line to before any more text is appended to it.
+    pending_pad: Option<Width>,
+}
+
+/// The first line of a notation and everything after it, up to the first newline.
+#[derive(Debug, Clone, Copy, Default)]
+struct FirstLine {
+    /// The width of the line, not counting a leading soft space.
+    width: Width,
+    /// Whether the line starts with a soft space (which is displayed only if it follows text).
+    leading_space: bool,
+    /// Whether the line contains any non-empty text.
+    has_content: bool,
 }
 
 /// Print the document using a simple but slow algorithm; the time is roughly exponential in the
@@ -26,8 +44,8 @@ pub fn oracular_pretty_print<'d, D: PrettyDoc<'d>>(doc: D, width: Width) -> Stri
         .unwrap()
         .eval()
         .expect("Notation mismatch in oracle test (root)");
-    let layout =
-        pp(Layout::empty(), note, Some(0), width).expect("Notation mismatch in oracle test");
+    let layout = pp(Layout::empty(), note, Some(FirstLine::default()), width)
+        .expect("Notation mismatch in oracle test");
     format!("{}", layout)
 }
 
@@ -36,9 +54,9 @@ fn pp<'d, D: PrettyDoc<'d>>(
     mut prefix: Layout,
     // The next notation to print
     note: ConsolidatedNotation<'d, D>,
-    // The number of columns known to follow `note`, or `None` if it's followed by `EndOfLine +
+    // What's known to follow `note` on its last line, or `None` if it's followed by `EndOfLine +
     // Text`.
-    suffix_len: Option<Width>,
+    suffix: Option<FirstLine>,
     // The printing width
     width: Width,
 ) -> Result<Layout, PrintingError<D::Error>> {
@@ -47,7 +65,7 @@ fn pp<'d, D: PrettyDoc<'d>>(
     assert!(width < MAX_WIDTH);
 
     if DEBUG_PRINT {
-        println!("==pp suffix_len:{:?} width:{}", suffix_len, width);
+        println!("==pp suffix:{:?} width:{}", suffix, width);
         println!("{}", prefix);
         println!("{}", note);
         println!("==");
@@ -55,7 +73,11 @@ fn pp<'d, D: PrettyDoc<'d>>(
 
     match note {
         Empty | FocusMark => Ok(prefix),
-        Textual(textual) => Ok(prefix.append_text(textual.str)),
+        Textual(textual) if textual.is_soft_space => Ok(prefix.append_soft_space()),
+        Textual(textual) => match textual.pad_to {
+            Some(col) => Ok(prefix.append_pad(col)),
+            None => Ok(prefix.append_text(textual.str)),
+        },
         EndOfLine => {
             prefix.ends_with_eol = true;
             Ok(prefix)
@@ -70,64 +92,85 @@ fn pp<'d, D: PrettyDoc<'d>>(
             indent_strings.reverse();
             Ok(prefix.append_newline(indent_strings.join("")))
         }
-        Child(_, x) => pp(prefix, x.eval()?, suffix_len, width),
+        Child(_, x) | Cache(x) => pp(prefix, x.eval()?, suffix, width),
         Concat(x, y) => {
             let x = x.eval()?;
             let y = y.eval()?;
-            let x_suffix_len = first_line_len(y.clone(), suffix_len)?.map(|w| w.min(MAX_WIDTH));
-            let y_prefix = pp(prefix, x, x_suffix_len, width)?;
-            pp(y_prefix, y, suffix_len, width)
+            let x_suffix = first_line(y.clone(), suffix)?;
+            let y_prefix = pp(prefix, x, x_suffix, width)?;
+            pp(y_prefix, y, suffix, width)
         }
         Choice(x, y) => {
+            let choice_width = x
+                .max_width()
+                .map_or(width, |max_width| width.min(max_width));
             let x = x.eval()?;
             let last_len = prefix.last_line_len();
-            let fits = match first_line_len(x.clone(), suffix_len)? {
+            let fits = match first_line(x.clone(), suffix)? {
                 None => false,
-                Some(first_len) => {
-                    if prefix.ends_with_eol && first_len > 0 {
+                Some(first) => {
+                    if prefix.ends_with_eol && first.width > 0 {
                         false
                     } else {
-                        last_len + first_len <= width
+                        let space = first.has_content
+                            && (prefix.pending_space
+                                || (first.leading_space && prefix.has_content));
+                        last_len + space as Width + first.width <= choice_width
                     }
                 }
             };
             if DEBUG_PRINT {
-                println!("fits: {:?} + ? <= {} ? {}", last_len, width, fits);
+                println!("fits: {:?} + ? <= {} ? {}", last_len, choice_width, fits);
             }
             let z = if fits { x } else { y.eval()? };
-            pp(prefix, z, suffix_len, width)
+            pp(prefix, z, suffix, width)
         }
     }
 }
 
-/// Compute the smallest possible first line length of `note`, given that its last line will have an
-/// additional `suffix_len` columns after it (where `None` means the suffix contains `EndOfLine +
-/// Textual`). Assumes the rule that "in (x | y), if x fits then y fits", and so always chooses y.
-/// Returns `None` if the first line contains `EndOfLine + Textual`.
-fn first_line_len<'d, D: PrettyDoc<'d>>(
+/// Compute the smallest possible first line of `note`, given that its last line will be followed
+/// by `suffix` (where `None` means the suffix contains `EndOfLine + Textual`). Assumes the rule
+/// that "in (x | y), if x fits then y fits", and so always chooses y. Returns `None` if the first
+/// line contains `EndOfLine + Textual`.
+fn first_line<'d, D: PrettyDoc<'d>>(
     note: ConsolidatedNotation<'d, D>,
-    suffix_len: Option<Width>,
-) -> Result<Option<Width>, PrintingError<D::Error>> {
+    suffix: Option<FirstLine>,
+) -> Result<Option<FirstLine>, PrintingError<D::Error>> {
     use ConsolidatedNotation::*;
 
     match note {
-        Empty | FocusMark => Ok(suffix_len),
-        Textual(textual) => Ok(suffix_len.map(|w| textual.width + w)),
-        EndOfLine => match suffix_len {
+        // Padding isn't counted.
+        Empty | FocusMark => Ok(suffix),
+        Textual(textual) if textual.pad_to.is_some() => Ok(suffix),
+        Textual(textual) if textual.is_soft_space => Ok(suffix.map(|first| FirstLine {
+            leading_space: true,
+            ..first
+        })),
+        Textual(textual) if textual.str.is_empty() => Ok(suffix),
+        Textual(textual) => Ok(suffix.map(|first| {
+            let space = first.leading_space && first.has_content;
+            FirstLine {
+                width: (textual.width + space as Width + first.width).min(MAX_WIDTH),
+                leading_space: false,
+                has_content: true,
+            }
+        })),
+        EndOfLine => match suffix {
             None => Ok(None),
-            Some(0) => Ok(Some(0)), // Followed by a newline, good
-            Some(_) => Ok(None),    // Followed by text, bad
+            // Followed by a newline, good. Any soft space is dropped.
+            Some(first) if first.width == 0 => Ok(Some(FirstLine::default())),
+            Some(_) => Ok(None), // Followed by text, bad
         },
-        Newline(_) => Ok(Some(0)),
-        Child(_, x) => first_line_len(x.eval()?, suffix_len),
+        Newline(_) => Ok(Some(FirstLine::default())),
+        Child(_, x) | Cache(x) => first_line(x.eval()?, suffix),
         Concat(x, y) => {
-            let suffix_len = first_line_len(y.eval()?, suffix_len)?.map(|w| w.min(MAX_WIDTH));
-            first_line_len(x.eval()?, suffix_len)
+            let suffix = first_line(y.eval()?, suffix)?;
+            first_line(x.eval()?, suffix)
         }
         Choice(_, y) => {
             // Wouldn't see a choice if we were flat, so use y.
             // Relies on the rule that in (x | y), y's first line is no longer than x's.
-            first_line_len(y.eval()?, suffix_len)
+            first_line(y.eval()?, suffix)
         }
     }
 }
@@ -137,12 +180,32 @@ impl Layout {
         Layout {
             lines: vec![String::new()],
             ends_with_eol: false,
+            pending_space: false,
+            has_content: false,
+            pending_pad: None,
         }
     }
 
     fn append_newline(mut self, indentation: String) -> Layout {
         self.lines.push(indentation);
         self.ends_with_eol = false;
+        self.pending_space = false;
+        self.has_content = false;
+        self.pending_pad = None;
+        self
+    }
+
+    fn append_pad(mut self, col: Width) -> Layout {
+        if !self.ends_with_eol {
+            self.pending_pad = Some(self.pending_pad.map_or(col, |pad| pad.max(col)));
+        }
+        self
+    }
+
+    fn append_soft_space(mut self) -> Layout {
+        if self.has_content && !self.ends_with_eol {
+            self.pending_space = true;
+        }
         self
     }
 
@@ -150,7 +213,22 @@ impl Layout {
         if self.ends_with_eol {
             panic!("Oracle: encountered EOL + text");
         }
-        self.lines.last_mut().unwrap().push_str(text); // relies on invariant
+        if text.is_empty() {
+            return self;
+        }
+        let last_line = self.lines.last_mut().unwrap(); // relies on invariant
+        if self.pending_space {
+            last_line.push(' ');
+            self.pending_space = false;
+        }
+        if let Some(col) = self.pending_pad.take() {
+            let len = str_width(last_line);
+            if len < col {
+                last_line.push_str(&" ".repeat((col - len) as usize));
+            }
+        }
+        last_line.push_str(text);
+        self.has_content = true;
         self
     }
 
@@ -0,0 +1,164 @@
+use crate::pane::{DocLabel, PrintingOptions};
+use crate::{reanchor, FocusTarget, PathEdit};
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
+
+/// A saved focus position: which document, and where in it. See [`Bookmarks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct Bookmark<L: DocLabel> {
+    /// The document, as looked up by [`display_pane()`](super::display_pane).
+    pub label: L,
+    /// The path to the node, as in [`PrintingOptions::focus_path`].
+    pub path: Vec<usize>,
+    /// Where the focus is relative to that node, as in [`PrintingOptions::focus_target`].
+    pub focus_target: FocusTarget,
+}
+
+impl<L: DocLabel> Bookmark<L> {
+    /// Bookmark the focus of a document that's being displayed with these `options`.
+    pub fn from_options<S>(label: L, options: &PrintingOptions<S>) -> Bookmark<L> {
+        Bookmark {
+            label,
+            path: options.focus_path.clone(),
+            focus_target: options.focus_target,
+        }
+    }
+
+    /// Set the focus in `options` to this bookmark. (It's up to you to display the document with
+    /// the right label.) If the document might have changed since the bookmark was made, consider
+    /// setting [`PrintingOptions::fall_back_on_invalid_path`].
+    pub fn restore<S>(&self, options: &mut PrintingOptions<S>) {
+        options.focus_path = self.path.clone();
+        options.focus_target = self.focus_target;
+    }
+
+    /// Update the bookmark after its document is edited, using [`reanchor()`](crate::reanchor).
+    /// If the node it was on no longer exists, it moves to the start of the nearest surviving
+    /// ancestor.
+    fn reanchor(&mut self, edit: &PathEdit) {
+        let new_path = reanchor(&self.path, edit);
+        let was_replaced = match edit {
+            PathEdit::Replace { path } => self.path.starts_with(path),
+            PathEdit::Insert { .. } | PathEdit::Delete { .. } => new_path.len() < self.path.len(),
+        };
+        if was_replaced {
+            self.focus_target = FocusTarget::Start;
+        }
+        self.path = new_path;
+    }
+}
+
+/// Bookkeeping for an editor's jump list and named marks (like "last edit position").
+///
+/// The jump list is a history of focus positions that can be navigated with [`back()`] and
+/// [`forward()`], like a web browser's history. Named marks are individual bookmarks that can be
+/// set and looked up by name.
+///
+/// [`back()`]: Bookmarks::back
+/// [`forward()`]: Bookmarks::forward
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct Bookmarks<L: DocLabel> {
+    /// The jump list, from oldest to newest.
+    jumps: Vec<Bookmark<L>>,
+    /// While navigating with `back` and `forward`, the index into `jumps` of where we are. `None`
+    /// if we're past the newest entry.
+    current: Option<usize>,
+    /// The most entries to keep in `jumps`.
+    capacity: usize,
+    marks: BTreeMap<String, Bookmark<L>>,
+}
+
+impl<L: DocLabel + PartialEq> Bookmarks<L> {
+    /// Construct an empty set of bookmarks, whose jump list holds at most `capacity` entries.
+    pub fn new(capacity: usize) -> Bookmarks<L> {
+        Bookmarks {
+            jumps: Vec::new(),
+            current: None,
+            capacity,
+            marks: BTreeMap::new(),
+        }
+    }
+
+    /// Record a jump from `bookmark` to somewhere else. If you've gone [`back()`](Bookmarks::back),
+    /// this forgets the entries that you could have gone [`forward()`](Bookmarks::forward) to.
+    pub fn record_jump(&mut self, bookmark: Bookmark<L>) {
+        if let Some(current) = self.current.take() {
+            self.jumps.truncate(current + 1);
+        }
+        self.push(bookmark);
+    }
+
+    /// Go back to the previous entry in the jump list, if any. `here` is the current focus
+    /// position, which is recorded so that you can go [`forward()`](Bookmarks::forward) to it
+    /// again.
+    pub fn back(&mut self, here: Bookmark<L>) -> Option<&Bookmark<L>> {
+        let current = match self.current {
+            Some(current) => current,
+            None => {
+                self.push(here);
+                self.jumps.len().saturating_sub(1)
+            }
+        };
+        self.current = Some(current.saturating_sub(1));
+        if current == 0 {
+            None
+        } else {
+            Some(&self.jumps[current - 1])
+        }
+    }
+
+    /// Undo a [`back()`](Bookmarks::back), if there's anywhere to go forward to.
+    pub fn forward(&mut self) -> Option<&Bookmark<L>> {
+        let current = self.current?;
+        if current + 1 < self.jumps.len() {
+            self.current = Some(current + 1);
+            Some(&self.jumps[current + 1])
+        } else {
+            None
+        }
+    }
+
+    /// The jump list, from oldest to newest.
+    pub fn jumps(&self) -> &[Bookmark<L>] {
+        &self.jumps
+    }
+
+    /// Set the mark with the given name, replacing any previous mark with that name.
+    pub fn set_mark(&mut self, name: &str, bookmark: Bookmark<L>) {
+        self.marks.insert(name.to_owned(), bookmark);
+    }
+
+    /// Look up the mark with the given name.
+    pub fn mark(&self, name: &str) -> Option<&Bookmark<L>> {
+        self.marks.get(name)
+    }
+
+    /// Remove the mark with the given name, returning it.
+    pub fn remove_mark(&mut self, name: &str) -> Option<Bookmark<L>> {
+        self.marks.remove(name)
+    }
+
+    /// Update every jump and mark in the document with the given `label`, after that document is
+    /// edited. See [`reanchor()`](crate::reanchor).
+    pub fn reanchor(&mut self, label: &L, edit: &PathEdit) {
+        let bookmarks = self.jumps.iter_mut().chain(self.marks.values_mut());
+        for bookmark in bookmarks.filter(|bookmark| bookmark.label == *label) {
+            bookmark.reanchor(edit);
+        }
+    }
+
+    /// Add a jump to the end of the list, unless it's already there.
+    fn push(&mut self, bookmark: Bookmark<L>) {
+        if self.jumps.last() != Some(&bookmark) {
+            self.jumps.push(bookmark);
+        }
+        if self.jumps.len() > self.capacity {
+            let num_dropped = self.jumps.len() - self.capacity;
+            self.jumps.drain(..num_dropped);
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use crate::{
+    pane::{DocLabel, PaneNotation, PaneSize, PrintingOptions},
+    FocusTarget, PrettyDoc, Style, ValidNotation,
+};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::ptr;
+
+/// How a node differs between the two documents of a [`DocDiff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// The node is only in the new document.
+    Inserted,
+    /// The node is only in the old document.
+    Deleted,
+    /// The node is in both documents, but its text or its notation differs.
+    Changed,
+}
+
+/// Which of the two documents of a [`DocDiff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSide {
+    Old,
+    New,
+}
+
+/// The styles to display differing nodes with. Each is combined with the node's own style.
+#[derive(Debug, Clone)]
+pub struct DiffStyles<S> {
+    pub inserted: S,
+    pub deleted: S,
+    pub changed: S,
+}
+
+/// Two versions of a document, aligned by node id. Construct one with [`diff()`].
+///
+/// Display it with the [`PaneNotation`] from [`DocDiff::pane_notation()`], by returning
+/// [`DocDiff::content()`] for each side from the `get_content` function passed to
+/// [`display_pane()`](super::display_pane). The nodes that differ are displayed in their
+/// [`DiffStyles`], and both panes scroll together: the node at the focus of one side is displayed
+/// at the same height as the node with the same id in the other side.
+pub struct DocDiff<'d, D: PrettyDoc<'d>> {
+    old: D,
+    new: D,
+    styles: DiffStyles<D::Style>,
+    old_nodes: HashMap<D::Id, DiffNode>,
+    new_nodes: HashMap<D::Id, DiffNode>,
+}
+
+/// What's known about one node of one side of a `DocDiff`.
+struct DiffNode {
+    path: Vec<usize>,
+    kind: Option<DiffKind>,
+}
+
+/// Align the nodes of `old` and `new` by their [`PrettyDoc::id()`]s, and find which ones were
+/// inserted, deleted, or changed. This walks both documents in full.
+///
+/// A node that's only in one document is inserted or deleted. Its descendants are likewise
+/// inserted or deleted, unless they also appear somewhere in the other document (for example
+/// because they were moved). A node that's in both documents is changed if it contains different
+/// text, or has a different notation. Changes to a node's children aren't counted as changes to
+/// the node itself: the children are inserted or deleted instead.
+pub fn diff<'d, D: PrettyDoc<'d>>(
+    old: D,
+    new: D,
+    styles: DiffStyles<D::Style>,
+) -> Result<DocDiff<'d, D>, D::Error> {
+    let mut old_nodes = HashMap::new();
+    collect_nodes(old, &mut Vec::new(), &mut old_nodes)?;
+    let mut new_nodes = HashMap::new();
+    collect_nodes(new, &mut Vec::new(), &mut new_nodes)?;
+
+    let mut old_kinds = HashMap::new();
+    for (id, (old_node, _)) in &old_nodes {
+        let kind = match new_nodes.get(id) {
+            None => Some(DiffKind::Deleted),
+            Some((new_node, _)) if !same_content(*old_node, *new_node)? => Some(DiffKind::Changed),
+            Some(_) => None,
+        };
+        old_kinds.insert(*id, kind);
+    }
+    let new_nodes = new_nodes
+        .into_iter()
+        .map(|(id, (_, path))| {
+            let kind = match old_kinds.get(&id) {
+                None => Some(DiffKind::Inserted),
+                Some(kind) => *kind,
+            };
+            (id, DiffNode { path, kind })
+        })
+        .collect();
+    let old_nodes = old_nodes
+        .into_iter()
+        .map(|(id, (_, path))| {
+            (
+                id,
+                DiffNode {
+                    path,
+                    kind: old_kinds[&id],
+                },
+            )
+        })
+        .collect();
+
+    Ok(DocDiff {
+        old,
+        new,
+        styles,
+        old_nodes,
+        new_nodes,
+    })
+}
+
+/// Record every node under `node` in `nodes`, along with its path.
+#[allow(clippy::type_complexity)]
+fn collect_nodes<'d, D: PrettyDoc<'d>>(
+    node: D,
+    path: &mut Vec<usize>,
+    nodes: &mut HashMap<D::Id, (D, Vec<usize>)>,
+) -> Result<(), D::Error> {
+    nodes.insert(node.id()?, (node, path.clone()));
+    if let Some(num_children) = node.num_children()? {
+        for (i, child) in node
+            .unwrap_children(0..num_children)?
+            .into_iter()
+            .enumerate()
+        {
+            path.push(i);
+            collect_nodes(child, path, nodes)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Whether two nodes with the same id look the same, not counting their children.
+fn same_content<'d, D: PrettyDoc<'d>>(old: D, new: D) -> Result<bool, D::Error> {
+    if !ptr::eq(old.notation()?, new.notation()?) {
+        return Ok(false);
+    }
+    Ok(match (old.num_children()?, new.num_children()?) {
+        (None, None) => old.unwrap_text()? == new.unwrap_text()?,
+        (Some(_), Some(_)) => true,
+        _ => false,
+    })
+}
+
+impl<'d, D: PrettyDoc<'d>> DocDiff<'d, D> {
+    /// How the node with this id on this side differs from the other side, or `None` if it's the
+    /// same or isn't in the document.
+    pub fn kind(&self, side: DiffSide, id: D::Id) -> Option<DiffKind> {
+        self.nodes(side).get(&id).and_then(|node| node.kind)
+    }
+
+    /// The root of one side, styled to show how it differs from the other side.
+    pub fn doc(&'d self, side: DiffSide) -> DiffDoc<'d, D> {
+        let doc = match side {
+            DiffSide::Old => self.old,
+            DiffSide::New => self.new,
+        };
+        DiffDoc {
+            doc,
+            side,
+            diff: self,
+        }
+    }
+
+    /// Translate `path`, on side `from`, to the path of the same node on the other side. If that
+    /// node isn't on the other side, use its nearest ancestor that is, and return `false` as well.
+    /// Returns `None` if `path` doesn't exist, or if not even the root is on both sides.
+    #[allow(clippy::type_complexity)]
+    pub fn sync_path(
+        &self,
+        from: DiffSide,
+        path: &[usize],
+    ) -> Result<Option<(Vec<usize>, bool)>, D::Error> {
+        let to_nodes = self.nodes(from.other());
+        let mut node = match from {
+            DiffSide::Old => self.old,
+            DiffSide::New => self.new,
+        };
+        let mut synced = None;
+        for depth in 0..=path.len() {
+            let id = node.id()?;
+            if let Some(to_node) = to_nodes.get(&id) {
+                synced = Some((to_node.path.clone(), depth == path.len()));
+            }
+            if depth < path.len() {
+                match node.num_children()? {
+                    Some(len) if path[depth] < len => node = node.unwrap_child(path[depth])?,
+                    _ => return Ok(None),
+                }
+            }
+        }
+        Ok(synced)
+    }
+
+    /// The document and printing options to display for `side`, for use in the `get_content`
+    /// function passed to [`display_pane()`](super::display_pane). The `options` are for the
+    /// document on `focus_side`; for the other side, their focus is moved to the same node with
+    /// [`DocDiff::sync_path()`] (or to the start of its nearest ancestor, or to the start of the
+    /// document).
+    #[allow(clippy::type_complexity)]
+    pub fn content(
+        &'d self,
+        side: DiffSide,
+        focus_side: DiffSide,
+        options: &PrintingOptions<D::Style>,
+    ) -> Result<(DiffDoc<'d, D>, PrintingOptions<D::Style>), D::Error> {
+        let mut options = options.clone();
+        if side != focus_side {
+            match self.sync_path(focus_side, &options.focus_path)? {
+                Some((path, true)) => options.focus_path = path,
+                Some((path, false)) => {
+                    options.focus_path = path;
+                    options.focus_target = FocusTarget::Start;
+                }
+                None => {
+                    options.focus_path = Vec::new();
+                    options.focus_target = FocusTarget::Start;
+                }
+            }
+        }
+        Ok((self.doc(side), options))
+    }
+
+    /// Display the old document on the left and the new document on the right, with a vertical
+    /// line between them. The documents are looked up by `old_label` and `new_label`.
+    pub fn pane_notation<L: DocLabel>(old_label: L, new_label: L) -> PaneNotation<L, D::Style> {
+        PaneNotation::Horz(vec![
+            (
+                PaneSize::Proportional(1),
+                PaneNotation::Doc { label: old_label },
+            ),
+            (PaneSize::Fixed(1), PaneNotation::Fill { ch: '│' }),
+            (
+                PaneSize::Proportional(1),
+                PaneNotation::Doc { label: new_label },
+            ),
+        ])
+    }
+
+    fn nodes(&self, side: DiffSide) -> &HashMap<D::Id, DiffNode> {
+        match side {
+            DiffSide::Old => &self.old_nodes,
+            DiffSide::New => &self.new_nodes,
+        }
+    }
+
+    fn style(&self, kind: DiffKind) -> &D::Style {
+        match kind {
+            DiffKind::Inserted => &self.styles.inserted,
+            DiffKind::Deleted => &self.styles.deleted,
+            DiffKind::Changed => &self.styles.changed,
+        }
+    }
+}
+
+impl DiffSide {
+    fn other(self) -> DiffSide {
+        match self {
+            DiffSide::Old => DiffSide::New,
+            DiffSide::New => DiffSide::Old,
+        }
+    }
+}
+
+/// A node of one side of a [`DocDiff`], whose node style shows how it differs from the other side.
+/// Get the root with [`DocDiff::doc()`].
+pub struct DiffDoc<'d, D: PrettyDoc<'d>> {
+    doc: D,
+    side: DiffSide,
+    diff: &'d DocDiff<'d, D>,
+}
+
+impl<'d, D: PrettyDoc<'d>> DiffDoc<'d, D> {
+    /// The underlying document node.
+    pub fn inner(self) -> D {
+        self.doc
+    }
+
+    fn wrap(self, doc: D) -> Self {
+        DiffDoc { doc, ..self }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for DiffDoc<'d, D> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'d, D: PrettyDoc<'d>> Copy for DiffDoc<'d, D> {}
+
+impl<'d, D: PrettyDoc<'d>> PrettyDoc<'d> for DiffDoc<'d, D> {
+    type Id = D::Id;
+    type Style = D::Style;
+    type StyleLabel = D::StyleLabel;
+    type Condition = D::Condition;
+    type Error = D::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        self.doc.id()
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        self.doc.notation()
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        self.doc.condition(condition)
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        self.doc.lookup_style(style_label)
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        self.doc.lookup_indent_style(style_label, depth)
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        self.doc.lookup_depth_style(style_label, depth)
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        self.doc.lookup_link(link_label)
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        self.doc.lookup_text(text_label)
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        self.doc.transform_text(text_label, text)
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.approx_height_hint()
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        let style = self.doc.node_style()?;
+        Ok(match self.diff.kind(self.side, self.doc.id()?) {
+            Some(kind) => Style::combine(&style, self.diff.style(kind)),
+            None => style,
+        })
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.num_children()
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        self.doc.unwrap_text()
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_child(i)?))
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_last_child()?))
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_prev_sibling(parent.doc, i)?))
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        Ok(self
+            .doc
+            .unwrap_children(range)?
+            .into_iter()
+            .map(|child| self.wrap(child))
+            .collect())
+    }
+}
@@ -1,8 +1,16 @@
 use crate::{
-    geometry::{is_char_full_width, Rectangle},
-    pane::{divvy::Divvier, DocLabel, PaneNotation, PaneSize, PrettyWindow, PrintingOptions},
-    pretty_print, Height, Line, Pos, PrettyDoc, PrintingError, Row, Size, Width,
+    pane::{
+        divvy::{collapse_rect, interpolate_rect, Divvier},
+        DocLabel, GutterContent, OverlayAnchor, OverlayPlacement, PaneNotation, PaneSize,
+        PrettyWindow, PrintingOptions, Zoom,
+    },
+    pretty_print::pretty_print_multi_with_options,
+    pretty_print_with_options,
+    reanchor::find_path_fallback,
+    Col, FocusTarget, Height, Line, PathFallback, Pos, PrettyDoc, PrintOptions, PrintingError,
+    Rectangle, Row, Size, UnicodeWidth, Width, WidthPolicy,
 };
+use std::convert::TryFrom;
 use std::error::Error;
 
 /// Errors that can occur while displaying a pane.
@@ -11,7 +19,7 @@ use std::error::Error;
 #[derive(thiserror::Error, Debug)]
 pub enum PaneError<W: Error + 'static, E: Error + 'static> {
     #[error(
-        "Invalid pane notation: PaneSize::Dyanmic may only be used in a PaneNotation::Doc pane"
+        "Invalid pane notation: PaneSize::Dynamic may only be used in a PaneNotation::Doc pane"
     )]
     InvalidUseOfDynamic,
 
@@ -20,19 +28,131 @@ pub enum PaneError<W: Error + 'static, E: Error + 'static> {
 
     #[error("PrettyDoc printing error: {0}")]
     PrintingError(#[from] PrintingError<E>),
+
+    #[error("Failed to get pane content: {0}")]
+    ContentError(#[source] E),
+}
+
+/// Which pane is active (for example, which one has keyboard focus), and how to display it
+/// differently. Passed to [`display_pane`].
+pub struct ActivePane<'a, L: DocLabel, S> {
+    /// Whether the [`PaneNotation::Doc`] pane with this label is the active pane.
+    pub is_active: &'a dyn Fn(&L) -> bool,
+    /// Transform the style of the active pane. This is applied to the style that the pane would
+    /// otherwise have been displayed with.
+    pub style: &'a dyn Fn(&S) -> S,
+}
+
+/// Information about how [`display_pane`] laid out the window. `L` is the type of the
+/// [`DocLabel`]s in the [`PaneNotation`].
+#[derive(Debug, Clone)]
+pub struct PaneLayout<L: DocLabel> {
+    /// The position and size of the active pane, if it was displayed.
+    pub active: Option<(Pos, Size)>,
+    /// For each document whose `focus_path` didn't exist and that was displayed with
+    /// [`PrintingOptions::fall_back_on_invalid_path`], the position of its pane and what was
+    /// displayed instead.
+    pub path_fallbacks: Vec<(Pos, PathFallback)>,
+    /// For each [`PaneNotation::Image`] pane that was displayed with an image, where to draw the
+    /// image and the image itself. These rectangles were filled with blank cells, so draw the
+    /// images over them after displaying the panes.
+    pub images: Vec<(Rectangle, Image)>,
+    /// Where each document was displayed, in the order they were displayed. See
+    /// [`PaneLayout::hit_test()`].
+    pub docs: Vec<DocPane<L>>,
+}
+
+/// Where a document was displayed, as reported in [`PaneLayout::docs`].
+#[derive(Debug, Clone)]
+pub struct DocPane<L: DocLabel> {
+    /// The label of the document's [`PaneNotation::Doc`] or [`PaneNotation::Gutter`] pane.
+    pub label: L,
+    /// The part of the window that the document was displayed in, not including any gutter.
+    pub rect: Rectangle,
+    /// How zoomed in the document was displayed. See [`PrintingOptions::zoom`].
+    pub zoom: Zoom,
+}
+
+impl<L: DocLabel> Default for PaneLayout<L> {
+    fn default() -> Self {
+        PaneLayout {
+            active: None,
+            path_fallbacks: Vec::new(),
+            images: Vec::new(),
+            docs: Vec::new(),
+        }
+    }
+}
+
+impl<L: DocLabel> PaneLayout<L> {
+    /// Find which document is displayed at the window position `pos`, for example to handle a
+    /// mouse click. Returns the document's label, and the row and column of `pos` relative to the
+    /// upper-left of its pane, measured in cells of the document (so a zoomed document's cells
+    /// count once each). If several documents cover `pos` (such as one in a
+    /// [`PaneNotation::Overlay`]), the last one displayed wins. Only document panes are
+    /// considered, so a fill or image overlaid on a document doesn't hide it.
+    pub fn hit_test(&self, pos: Pos) -> Option<(L, Row, Col)> {
+        let doc = self.docs.iter().rev().find(|doc| doc.rect.contains(pos))?;
+        let scale = doc.zoom.scale();
+        let row = (pos.row - doc.rect.min_row) / scale.height;
+        let col = (pos.col - doc.rect.min_col) / scale.width;
+        Some((doc.label.clone(), row, col))
+    }
+}
+
+/// An image for a [`PaneNotation::Image`] pane, as returned by the `get_image` argument of
+/// [`display_pane_with_images`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// The raw image data, in whatever format the application draws it in.
+    pub bytes: Vec<u8>,
+    /// How large the image would like to be, in cells. If it doesn't fit in its pane, it's shrunk
+    /// until it does.
+    pub size: Size,
 }
 
 /// Display a [`PaneNotation`] to a [`PrettyWindow`].
 ///
 /// `style` is the initial style to use on the entire pane. `get_content` is a function to look up
 /// a document by [`DocLabel`]. It returns both the document and [extra
-/// information](PrintingOptions) about how to print it.
+/// information](PrintingOptions) about how to print it, or `None` to leave the pane blank. If it
+/// returns an error, displaying stops and the error is returned as [`PaneError::ContentError`].
+///
+/// If `active` is given, the pane it picks out is displayed with its style transformation, and
+/// its location is reported in the returned [`PaneLayout`].
+///
+/// [`PaneNotation::Image`] panes are left blank. Use [`display_pane_with_images`] to display them.
 pub fn display_pane<'d, L, D, W>(
     window: &mut W,
     notation: &PaneNotation<L, D::Style>,
     style: &D::Style,
-    get_content: &impl Fn(L) -> Option<(D, PrintingOptions)>,
-) -> Result<(), PaneError<W::Error, D::Error>>
+    get_content: &impl Fn(L) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error>,
+    active: Option<&ActivePane<L, D::Style>>,
+) -> Result<PaneLayout<L>, PaneError<W::Error, D::Error>>
+where
+    L: DocLabel,
+    D: PrettyDoc<'d>,
+    W: PrettyWindow<Style = D::Style>,
+{
+    display_pane_with_images(window, notation, style, get_content, &|_| Ok(None), active)
+}
+
+/// Like [`display_pane`], but also looks up the image for each [`PaneNotation::Image`] pane with
+/// `get_image`, which returns `None` to leave the pane blank. If it returns an error, displaying
+/// stops and the error is returned as [`PaneError::ContentError`].
+///
+/// Image panes are filled with blank placeholder cells in the pane's style. It's up to the
+/// application to draw each image over its placeholder, at the rectangle reported in
+/// [`PaneLayout::images`], for example by writing a Kitty or Sixel graphics escape sequence to
+/// the terminal after flushing the window.
+pub fn display_pane_with_images<'d, L, D, W>(
+    window: &mut W,
+    notation: &PaneNotation<L, D::Style>,
+    style: &D::Style,
+    get_content: &impl Fn(L) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error>,
+    get_image: &impl Fn(L) -> Result<Option<Image>, D::Error>,
+    active: Option<&ActivePane<L, D::Style>>,
+) -> Result<PaneLayout<L>, PaneError<W::Error, D::Error>>
 where
     L: DocLabel,
     D: PrettyDoc<'d>,
@@ -40,15 +160,160 @@ where
 {
     let size = window.size().map_err(PaneError::PrettyWindowError)?;
     let rect = Rectangle::from_size(size);
-    display_pane_rec(window, notation, style, get_content, rect)
+    let mut layout = PaneLayout::default();
+    display_pane_rec(
+        window,
+        notation,
+        style,
+        get_content,
+        get_image,
+        active,
+        &mut layout,
+        rect,
+        rect,
+    )?;
+    Ok(layout)
 }
 
+/// Like [`display_pane`], but only redraws the panes that intersect `region`, leaving the rest of
+/// the window untouched. Useful when only part of the window is stale, such as after a popup
+/// closes or a status bar changes.
+///
+/// Panes are always redrawn in full, so cells outside of `region` may be written too. The
+/// pane layout is still computed for the whole window, and the active pane's location is reported
+/// even if it wasn't redrawn, but `path_fallbacks` and `docs` only cover the documents that were
+/// displayed.
+/// [`PaneNotation::Image`] panes are left blank, as in [`display_pane`].
+pub fn display_pane_region<'d, L, D, W>(
+    window: &mut W,
+    notation: &PaneNotation<L, D::Style>,
+    style: &D::Style,
+    get_content: &impl Fn(L) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error>,
+    active: Option<&ActivePane<L, D::Style>>,
+    region: Rectangle,
+) -> Result<PaneLayout<L>, PaneError<W::Error, D::Error>>
+where
+    L: DocLabel,
+    D: PrettyDoc<'d>,
+    W: PrettyWindow<Style = D::Style>,
+{
+    let size = window.size().map_err(PaneError::PrettyWindowError)?;
+    let rect = Rectangle::from_size(size);
+    let mut layout = PaneLayout::default();
+    display_pane_rec(
+        window,
+        notation,
+        style,
+        get_content,
+        &|_| Ok(None),
+        active,
+        &mut layout,
+        rect,
+        region,
+    )?;
+    Ok(layout)
+}
+
+/// Display one frame of an animated transition between two pane layouts, for example to animate a
+/// split opening or closing. `old_layout` and `new_layout` are the [`PaneLayout`]s returned by
+/// displaying the panes before and after the change, and `t` says how far along the transition
+/// is, from 0 (the old layout) to 1 (the new layout).
+///
+/// Each document is displayed in a rectangle partway between where it was and where it will be,
+/// and is printed to fit that rectangle. Documents are matched between the layouts by label. A
+/// document that's only in the new layout grows out of the center of its new rectangle, and one
+/// that's only in the old layout shrinks into the center of its old rectangle, underneath the
+/// others. The rest of the window (including fills, gutters, and images) is left blank in
+/// `style`, so when the transition is done, display the new [`PaneNotation`] with
+/// [`display_pane`].
+///
+/// Returns where each document was displayed in this frame.
+pub fn display_pane_transition<'d, L, D, W>(
+    window: &mut W,
+    old_layout: &PaneLayout<L>,
+    new_layout: &PaneLayout<L>,
+    t: f32,
+    style: &D::Style,
+    get_content: &impl Fn(L) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error>,
+) -> Result<PaneLayout<L>, PaneError<W::Error, D::Error>>
+where
+    L: DocLabel + PartialEq,
+    D: PrettyDoc<'d>,
+    W: PrettyWindow<Style = D::Style>,
+{
+    let size = window.size().map_err(PaneError::PrettyWindowError)?;
+    let bounds = Rectangle::from_size(size);
+    for row in 0..bounds.height() {
+        display_blank_line::<D, W>(window, row, bounds, style)?;
+    }
+
+    // Each document's label, and the rectangles it moves from and to.
+    let mut moves = Vec::new();
+    for old in &old_layout.docs {
+        if !new_layout.docs.iter().any(|new| new.label == old.label) {
+            moves.push((&old.label, old.rect, collapse_rect(old.rect)));
+        }
+    }
+    for new in &new_layout.docs {
+        let from = match old_layout.docs.iter().find(|old| old.label == new.label) {
+            Some(old) => old.rect,
+            None => collapse_rect(new.rect),
+        };
+        moves.push((&new.label, from, new.rect));
+    }
+
+    let mut layout = PaneLayout::default();
+    for (label, from, to) in moves {
+        let rect = interpolate_rect(from, to, t, bounds);
+        if rect.width() == 0 || rect.height() == 0 {
+            continue;
+        }
+        if let Some((doc, options)) = get_content(label.clone()).map_err(PaneError::ContentError)? {
+            let printed_doc = PrintedDoc::new(doc, &options, rect.size(), style, false)?;
+            printed_doc.display(window, rect, label, &mut layout)?;
+        }
+    }
+    Ok(layout)
+}
+
+/// If the pane with `label` is active, record where it is in the `layout` and return its
+/// transformed style.
+fn activate<L: DocLabel, S: Clone>(
+    label: &L,
+    style: S,
+    active: Option<&ActivePane<L, S>>,
+    layout: &mut PaneLayout<L>,
+    rect: Rectangle,
+) -> S {
+    match record_if_active(label, active, layout, rect) {
+        Some(active) => (active.style)(&style),
+        None => style,
+    }
+}
+
+/// If the pane with `label` is active, record where it is in the `layout` and return `active`.
+fn record_if_active<'a, 'b, L: DocLabel, S>(
+    label: &L,
+    active: Option<&'a ActivePane<'b, L, S>>,
+    layout: &mut PaneLayout<L>,
+    rect: Rectangle,
+) -> Option<&'a ActivePane<'b, L, S>> {
+    let active = active.filter(|active| (active.is_active)(label))?;
+    layout.active = Some((rect.pos(), rect.size()));
+    Some(active)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn display_pane_rec<'d, L, D, W>(
     window: &mut W,
     notation: &PaneNotation<L, D::Style>,
     style: &D::Style,
-    get_content: &impl Fn(L) -> Option<(D, PrintingOptions)>,
+    get_content: &impl Fn(L) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error>,
+    get_image: &impl Fn(L) -> Result<Option<Image>, D::Error>,
+    active: Option<&ActivePane<L, D::Style>>,
+    layout: &mut PaneLayout<L>,
     rect: Rectangle,
+    region: Rectangle,
 ) -> Result<(), PaneError<W::Error, D::Error>>
 where
     L: DocLabel,
@@ -58,8 +323,19 @@ where
     use crate::pretty_doc::Style;
 
     match notation {
+        PaneNotation::Fill { .. }
+        | PaneNotation::Doc { .. }
+        | PaneNotation::Gutter { .. }
+        | PaneNotation::Image { .. }
+            if !rect.intersects(region) =>
+        {
+            // Skip this pane, but still report it if it's active.
+            if let PaneNotation::Doc { label } | PaneNotation::Gutter { label, .. } = notation {
+                activate(label, style.clone(), active, layout, rect);
+            }
+        }
         PaneNotation::Fill { ch } => {
-            let is_full_width = is_char_full_width(*ch);
+            let is_full_width = UnicodeWidth.char_width(*ch) == 2;
             let char_width = if is_full_width { 2 } else { 1 };
 
             for row in rect.min_row..rect.max_row {
@@ -77,10 +353,47 @@ where
                 }
             }
         }
+        PaneNotation::Image { label } => {
+            let image = get_image(label.clone()).map_err(PaneError::ContentError)?;
+            for row in 0..rect.height() {
+                display_blank_line::<D, W>(window, row, rect, style)?;
+            }
+            if let Some(image) = image {
+                layout.images.push((rect.shrink(image.size), image));
+            }
+        }
         PaneNotation::Doc { label } => {
-            if let Some((doc, options)) = get_content(label.clone()) {
-                let printed_doc = PrintedDoc::new(doc, &options, rect.size(), style)?;
-                printed_doc.display(window, rect)?;
+            let style = activate(label, style.clone(), active, layout, rect);
+            if let Some((doc, options)) =
+                get_content(label.clone()).map_err(PaneError::ContentError)?
+            {
+                let printed_doc = PrintedDoc::new(doc, &options, rect.size(), &style, false)?;
+                printed_doc.display(window, rect, label, layout)?;
+            }
+        }
+        PaneNotation::Gutter {
+            label,
+            width,
+            content,
+        } => {
+            let gutter_width = (*width).min(rect.width());
+            let gutter_rect = Rectangle {
+                max_col: rect.min_col + gutter_width,
+                ..rect
+            };
+            let doc_rect = Rectangle {
+                min_col: gutter_rect.max_col,
+                ..rect
+            };
+            let style = activate(label, style.clone(), active, layout, doc_rect);
+            if let Some((doc, options)) =
+                get_content(label.clone()).map_err(PaneError::ContentError)?
+            {
+                let count_rows = !matches!(content, GutterContent::RelativeLineNumbers);
+                let printed_doc =
+                    PrintedDoc::new(doc, &options, doc_rect.size(), &style, count_rows)?;
+                printed_doc.display_gutter(window, gutter_rect, content, &style)?;
+                printed_doc.display(window, doc_rect, label, layout)?;
             }
         }
         PaneNotation::Style {
@@ -88,7 +401,69 @@ where
             notation: inner_notation,
         } => {
             let combined_style = D::Style::combine(style, inner_style);
-            display_pane_rec(window, inner_notation, &combined_style, get_content, rect)?;
+            display_pane_rec(
+                window,
+                inner_notation,
+                &combined_style,
+                get_content,
+                get_image,
+                active,
+                layout,
+                rect,
+                region,
+            )?;
+        }
+        PaneNotation::IfFits {
+            min_width,
+            min_height,
+            then,
+            otherwise,
+        } => {
+            let branch = if rect.width() >= *min_width && rect.height() >= *min_height {
+                then
+            } else {
+                otherwise
+            };
+            display_pane_rec(
+                window,
+                branch,
+                style,
+                get_content,
+                get_image,
+                active,
+                layout,
+                rect,
+                region,
+            )?;
+        }
+        PaneNotation::Overlay {
+            base,
+            overlay,
+            placement,
+        } => {
+            display_pane_rec(
+                window,
+                base,
+                style,
+                get_content,
+                get_image,
+                active,
+                layout,
+                rect,
+                region,
+            )?;
+            let overlay_rect = place_overlay(rect, placement);
+            display_pane_rec(
+                window,
+                overlay,
+                style,
+                get_content,
+                get_image,
+                active,
+                layout,
+                overlay_rect,
+                region,
+            )?;
         }
         PaneNotation::Horz(panes) => {
             let pane_sizes = panes
@@ -103,21 +478,33 @@ where
             let mut dynamic_docs = Vec::new();
             let mut dynamic_widths: Vec<usize> = Vec::new();
             for (size, child_note) in panes {
-                if *size != PaneSize::Dynamic {
-                    continue;
-                }
+                let max = match size {
+                    PaneSize::Dynamic { max } => *max,
+                    _ => continue,
+                };
 
                 let (label, doc_style) = extract_doc::<L, D, W>(child_note, style.clone())?;
-                let printed_doc = if let Some((doc, options)) = get_content(label.clone()) {
-                    PrintedDoc::new(doc, &options, available_size, &doc_style)?
+                // Its rectangle isn't known yet. It gets recorded again below, once it is.
+                let doc_style = activate(&label, doc_style, active, layout, rect);
+                let max_size = Size {
+                    width: match max {
+                        Some(max) => max.min(available_size.width as usize) as Width,
+                        None => available_size.width,
+                    },
+                    height: available_size.height,
+                };
+                let printed_doc = if let Some((doc, options)) =
+                    get_content(label.clone()).map_err(PaneError::ContentError)?
+                {
+                    PrintedDoc::new(doc, &options, max_size, &doc_style, false)?
                 } else {
                     PrintedDoc::new_empty(&doc_style)
                 };
 
-                let width = printed_doc.width().min(available_size.width);
-                available_size.width -= width;
+                let width = printed_doc.width().min(max_size.width);
+                available_size = available_size.saturating_sub(Size { width, height: 0 });
                 dynamic_widths.push(width as usize);
-                dynamic_docs.push(printed_doc);
+                dynamic_docs.push((printed_doc, label));
             }
             let widths = divvier.finish(dynamic_widths);
 
@@ -134,11 +521,24 @@ where
                     max_row: rect.max_row,
                 };
 
-                if let PaneSize::Dynamic = size {
-                    let doc = dynamic_docs.next().unwrap();
-                    doc.display(window, child_rect)?;
+                if let PaneSize::Dynamic { .. } = size {
+                    let (doc, label) = dynamic_docs.next().unwrap();
+                    record_if_active(&label, active, layout, child_rect);
+                    if child_rect.intersects(region) {
+                        doc.display(window, child_rect, &label, layout)?;
+                    }
                 } else {
-                    display_pane_rec(window, child_note, style, get_content, child_rect)?;
+                    display_pane_rec(
+                        window,
+                        child_note,
+                        style,
+                        get_content,
+                        get_image,
+                        active,
+                        layout,
+                        child_rect,
+                        region,
+                    )?;
                 }
             }
         }
@@ -155,21 +555,33 @@ where
             let mut dynamic_docs = Vec::new();
             let mut dynamic_heights: Vec<usize> = Vec::new();
             for (size, child_note) in panes {
-                if *size != PaneSize::Dynamic {
-                    continue;
-                }
+                let max = match size {
+                    PaneSize::Dynamic { max } => *max,
+                    _ => continue,
+                };
 
                 let (label, doc_style) = extract_doc::<L, D, W>(child_note, style.clone())?;
-                let printed_doc = if let Some((doc, options)) = get_content(label.clone()) {
-                    PrintedDoc::new(doc, &options, available_size, &doc_style)?
+                // Its rectangle isn't known yet. It gets recorded again below, once it is.
+                let doc_style = activate(&label, doc_style, active, layout, rect);
+                let max_size = Size {
+                    width: available_size.width,
+                    height: match max {
+                        Some(max) => max.min(available_size.height as usize) as Height,
+                        None => available_size.height,
+                    },
+                };
+                let printed_doc = if let Some((doc, options)) =
+                    get_content(label.clone()).map_err(PaneError::ContentError)?
+                {
+                    PrintedDoc::new(doc, &options, max_size, &doc_style, false)?
                 } else {
                     PrintedDoc::new_empty(&doc_style)
                 };
 
-                let height = printed_doc.height();
-                available_size.height -= height;
+                let height = printed_doc.height().min(max_size.height);
+                available_size = available_size.saturating_sub(Size { width: 0, height });
                 dynamic_heights.push(height as usize);
-                dynamic_docs.push(printed_doc);
+                dynamic_docs.push((printed_doc, label));
             }
             let heights = divvier.finish(dynamic_heights);
 
@@ -186,11 +598,24 @@ where
                     max_row: row,
                 };
 
-                if let PaneSize::Dynamic = size {
-                    let doc = dynamic_docs.next().unwrap();
-                    doc.display(window, child_rect)?;
+                if let PaneSize::Dynamic { .. } = size {
+                    let (doc, label) = dynamic_docs.next().unwrap();
+                    record_if_active(&label, active, layout, child_rect);
+                    if child_rect.intersects(region) {
+                        doc.display(window, child_rect, &label, layout)?;
+                    }
                 } else {
-                    display_pane_rec(window, child_note, style, get_content, child_rect)?;
+                    display_pane_rec(
+                        window,
+                        child_note,
+                        style,
+                        get_content,
+                        get_image,
+                        active,
+                        layout,
+                        child_rect,
+                        region,
+                    )?;
                 }
             }
         }
@@ -198,6 +623,29 @@ where
     Ok(())
 }
 
+/// Determine where within `rect` to display an overlay.
+fn place_overlay(rect: Rectangle, placement: &OverlayPlacement) -> Rectangle {
+    let size = placement.size.clamp(rect.size());
+    let offset = match placement.anchor {
+        OverlayAnchor::Center => {
+            let slack = rect.size().saturating_sub(size);
+            Pos {
+                row: slack.height / 2,
+                col: slack.width / 2,
+            }
+        }
+        OverlayAnchor::At(pos) => pos,
+    };
+    let pos = rect.pos().offset(offset);
+    let remaining = Rectangle {
+        min_row: pos.row,
+        min_col: pos.col,
+        max_row: rect.max_row,
+        max_col: rect.max_col,
+    };
+    remaining.shrink(size)
+}
+
 #[allow(clippy::type_complexity)]
 fn extract_doc<'d, L, D, W>(
     mut notation: &PaneNotation<L, D::Style>,
@@ -227,6 +675,8 @@ where
 
 struct PrintedDoc<'d, D: PrettyDoc<'d>> {
     lines: Vec<Line<'d, D>>,
+    /// Lines to display over the top rows of the pane (see [`PrintingOptions::sticky_headers`]).
+    sticky_lines: Vec<Line<'d, D>>,
     /// Which line in `lines` is the focus line.
     focus_line_index: usize,
     /// Which row of the pane should the focus line be displayed on.
@@ -235,6 +685,14 @@ struct PrintedDoc<'d, D: PrettyDoc<'d>> {
     focus_point: Option<Pos>,
     /// Style to apply to blank space.
     blank_style: D::Style,
+    /// Set if the focus path didn't exist, and the printer fell back to an ancestor.
+    path_fallback: Option<PathFallback>,
+    /// Which row of the whole document the focus line is, if it was computed.
+    focus_line_doc_row: Option<Row>,
+    /// How to measure the width of characters when displaying them.
+    width_policy: &'static dyn WidthPolicy,
+    /// How many cells of the pane to display each cell of the document with.
+    zoom: Zoom,
 }
 
 impl<'d, D: PrettyDoc<'d>> PrintedDoc<'d, D> {
@@ -242,115 +700,372 @@ impl<'d, D: PrettyDoc<'d>> PrintedDoc<'d, D> {
     fn new_empty(root_style: &D::Style) -> Self {
         PrintedDoc {
             lines: Vec::new(),
+            sticky_lines: Vec::new(),
             focus_line_index: 0,
             focus_line_row: 0,
             focus_point: None,
             blank_style: root_style.clone(),
+            path_fallback: None,
+            focus_line_doc_row: None,
+            width_policy: &UnicodeWidth,
+            zoom: Zoom::Normal,
         }
     }
 
     /// Pretty-print the portion of document that would fit in the given `size`,
-    /// storing it as text in the `PrintedDoc`.
+    /// storing it as text in the `PrintedDoc`. If `count_rows` is set, also count the lines above
+    /// the focus line, so that it's known which row of the document each line is.
     fn new(
         doc: D,
-        options: &PrintingOptions,
+        options: &PrintingOptions<D::Style>,
         size: Size,
         root_style: &D::Style,
+        count_rows: bool,
     ) -> Result<Self, PrintingError<D::Error>> {
+        // Print the document as if the pane were as large as the zoomed view of it.
+        let scale = options.zoom.scale();
+        let size = Size {
+            width: size.width / scale.width,
+            height: size.height / scale.height,
+        };
         if size.height == 0 || size.width == 0 {
             return Ok(PrintedDoc::new_empty(root_style));
         }
 
         let printing_width = options.choose_width(size.width);
-        let focus_line_row = options.choose_focus_line_row(size.height);
-        let (mut upward_printer, focused_line, mut downward_printer) = pretty_print(
+        let mut focus_line_row = options.choose_focus_line_row(size.height);
+        let path_fallback = if options.fall_back_on_invalid_path {
+            find_path_fallback(doc, &options.focus_path)?
+        } else {
+            None
+        };
+        let (path, focus_target) = match &path_fallback {
+            Some(fallback) => (fallback.path.as_slice(), FocusTarget::Start),
+            None => (options.focus_path.as_slice(), options.focus_target),
+        };
+        // Also locate the start of each ancestor of the focused node, for sticky headers.
+        let mut focuses = vec![(path, focus_target)];
+        if options.sticky_headers > 0 {
+            focuses.extend((1..=path.len()).map(|len| (&path[..len], FocusTarget::Start)));
+        }
+        // When clamping, print enough lines on both sides of the focus line to fill the pane, and
+        // then move it once we know how many lines there are.
+        let (rows_above, rows_below) = if options.clamp_focus_to_content {
+            (size.height - 1, size.height - 1)
+        } else {
+            (focus_line_row, size.height - focus_line_row - 1)
+        };
+        let print_options = options.print_options();
+        let mut printed = pretty_print_multi_with_options(
             doc,
             printing_width,
-            &options.focus_path,
-            options.focus_target,
+            &focuses,
+            rows_above as usize,
+            rows_below as usize,
             Some(root_style),
+            &print_options,
         )?;
+        if options.clamp_focus_to_content {
+            focus_line_row = options.clamp_focus_line_row(
+                focus_line_row,
+                size.height,
+                printed.lines_above.len(),
+                printed.lines_below.len(),
+            );
+            let num_hidden_above = printed
+                .lines_above
+                .len()
+                .saturating_sub(focus_line_row as usize);
+            printed.lines_above.drain(..num_hidden_above);
+            printed
+                .lines_below
+                .truncate((size.height - focus_line_row - 1) as usize);
+        }
+
+        let focus_line_doc_row = if count_rows {
+            let (prev_lines, _, _) = pretty_print_with_options(
+                doc,
+                printing_width,
+                path,
+                focus_target,
+                Some(root_style),
+                &print_options,
+            )?;
+            let mut num_rows = 0;
+            for line in prev_lines {
+                line?;
+                num_rows += 1;
+            }
+            Some(num_rows)
+        } else {
+            None
+        };
 
         let focus_point = if options.set_focus {
             Some(Pos {
-                row: focus_line_row,
-                col: focused_line.left_width(),
+                row: focus_line_row * scale.height as Row,
+                col: printed.focused_line.left_width() * scale.width,
             })
         } else {
             None
         };
 
-        let mut lines = Vec::new();
-        for _ in 0..focus_line_row {
-            if let Some(line) = upward_printer.next() {
-                lines.push(line?);
-            }
-        }
-        lines.reverse();
+        let mut sticky_lines = if options.sticky_headers > 0 {
+            let ancestor_rows = printed.positions[1..]
+                .iter()
+                .map(|pos| {
+                    pos.and_then(|(row, _)| Row::try_from(focus_line_row as isize + row).ok())
+                })
+                .collect::<Vec<_>>();
+            let max_headers = options.sticky_headers.min(focus_line_row as usize);
+            sticky_header_lines(
+                doc,
+                printing_width,
+                path,
+                &ancestor_rows,
+                max_headers,
+                root_style,
+                &print_options,
+            )?
+        } else {
+            Vec::new()
+        };
+
+        let mut lines = printed.lines_above;
         let focus_line_index = lines.len();
-        lines.push(Line::from(focused_line));
-        for _ in (focus_line_row + 1)..size.height {
-            if let Some(line) = downward_printer.next() {
-                lines.push(line?);
-            }
+        lines.push(Line::from(printed.focused_line));
+        lines.extend(printed.lines_below);
+        if let Some(whitespace_style) = &options.visible_whitespace {
+            lines = lines
+                .into_iter()
+                .map(|line| line.with_visible_whitespace(whitespace_style))
+                .collect();
+            sticky_lines = sticky_lines
+                .into_iter()
+                .map(|line| line.with_visible_whitespace(whitespace_style))
+                .collect();
+        }
+        if options.reorder_bidi {
+            lines = lines.into_iter().map(Line::into_visual_order).collect();
+            sticky_lines = sticky_lines
+                .into_iter()
+                .map(Line::into_visual_order)
+                .collect();
         }
 
         Ok(PrintedDoc {
             lines,
+            sticky_lines,
             focus_line_index,
             focus_line_row,
             focus_point,
-            blank_style: root_style.clone(),
+            blank_style: match &options.blank_style {
+                Some(blank_style) => {
+                    use crate::pretty_doc::Style;
+                    D::Style::combine(root_style, blank_style)
+                }
+                None => root_style.clone(),
+            },
+            path_fallback,
+            focus_line_doc_row,
+            width_policy: options.width_policy,
+            zoom: options.zoom,
         })
     }
 
-    /// The number of lines in the document.
+    /// The number of pane rows that the lines of the document take up.
     fn height(&self) -> Height {
-        self.lines.len() as Height
+        self.lines.len() as Height * self.zoom.scale().height
     }
 
-    /// The number of columns in the widest line of the document.
+    /// The number of pane columns that the widest line of the document takes up.
     fn width(&self) -> Width {
         self.lines
             .iter()
             .map(|line| line.width() as Width)
             .max()
             .unwrap_or(0)
+            * self.zoom.scale().width
     }
 
-    /// Actually display the document to the PrettyWindow.
-    fn display<W>(
+    /// Display a gutter for the document to the PrettyWindow, in `rect`. Each row of the gutter
+    /// shows the `content` for the line that [`PrintedDoc::display()`] displays in the same row.
+    /// If the document is zoomed, the content is shown on only the first of the line's rows.
+    fn display_gutter<W>(
+        &self,
+        window: &mut W,
+        rect: Rectangle,
+        content: &GutterContent<D::Style>,
+        style: &D::Style,
+    ) -> Result<(), PaneError<W::Error, D::Error>>
+    where
+        W: PrettyWindow<Style = D::Style>,
+    {
+        use crate::pretty_doc::Style;
+
+        let number_width = rect.width().saturating_sub(1) as usize;
+        let first_row = self.focus_line_row - (self.focus_line_index as Row);
+        let last_row = first_row + self.lines.len() as Row;
+        let zoom_height = self.zoom.scale().height;
+        for pane_row in 0..rect.height() {
+            let row = pane_row / zoom_height;
+            let is_doc_line = row >= first_row && row < last_row && pane_row % zoom_height == 0;
+            let is_sticky = (row as usize) < self.sticky_lines.len();
+            let doc_row = match self.focus_line_doc_row {
+                Some(focus_doc_row) if is_doc_line && !is_sticky => {
+                    Some(focus_doc_row + row - self.focus_line_row)
+                }
+                _ => None,
+            };
+            let (text, text_style) = match content {
+                _ if !is_doc_line || is_sticky => (String::new(), style.clone()),
+                GutterContent::RelativeLineNumbers => {
+                    let distance = (row as i64 - self.focus_line_row as i64).abs();
+                    (format!("{:>1$} ", distance, number_width), style.clone())
+                }
+                GutterContent::LineNumbers => match doc_row {
+                    Some(doc_row) => (format!("{:>1$} ", doc_row + 1, number_width), style.clone()),
+                    None => (String::new(), style.clone()),
+                },
+                GutterContent::Markers(markers) => match doc_row.and_then(|r| markers.get(&r)) {
+                    Some((marker, marker_style)) => {
+                        (marker.to_string(), D::Style::combine(style, marker_style))
+                    }
+                    None => (String::new(), style.clone()),
+                },
+            };
+            display_text::<D, W>(
+                window,
+                &text,
+                pane_row,
+                rect,
+                &text_style,
+                style,
+                self.width_policy,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Actually display the document to the PrettyWindow, and record in the `layout` that the
+    /// document with `label` was displayed in `rect`.
+    fn display<L, W>(
         self,
         window: &mut W,
         rect: Rectangle,
+        label: &L,
+        layout: &mut PaneLayout<L>,
     ) -> Result<(), PaneError<W::Error, D::Error>>
     where
+        L: DocLabel,
         D: PrettyDoc<'d>,
         W: PrettyWindow<Style = D::Style>,
     {
+        layout.docs.push(DocPane {
+            label: label.clone(),
+            rect,
+            zoom: self.zoom,
+        });
+        if let Some(fallback) = self.path_fallback {
+            layout.path_fallbacks.push((rect.pos(), fallback));
+        }
         if let Some(focus_point) = self.focus_point {
             window
-                .set_focus(Pos {
-                    row: focus_point.row + rect.min_row,
-                    col: focus_point.col + rect.min_col,
-                })
+                .set_focus(rect.pos().offset(focus_point))
                 .map_err(PaneError::PrettyWindowError)?;
         }
 
         let first_row = self.focus_line_row - (self.focus_line_index as Row);
         let last_row = first_row + self.lines.len() as Row;
-        for row in 0..rect.size().height {
-            if row >= first_row && row < last_row {
+        let scale = self.zoom.scale();
+        for pane_row in 0..rect.size().height {
+            let row = pane_row / scale.height;
+            if (row as usize) < self.sticky_lines.len() {
+                let line = &self.sticky_lines[row as usize];
+                display_line(
+                    window,
+                    line,
+                    pane_row,
+                    rect,
+                    &self.blank_style,
+                    self.width_policy,
+                    scale.width,
+                )?;
+            } else if row >= first_row && row < last_row {
                 let line = &self.lines[(row - first_row) as usize];
-                display_line(window, line, row, rect, &self.blank_style)?;
+                display_line(
+                    window,
+                    line,
+                    pane_row,
+                    rect,
+                    &self.blank_style,
+                    self.width_policy,
+                    scale.width,
+                )?;
             } else {
-                display_blank_line::<D, W>(window, row, rect, &self.blank_style)?;
+                display_blank_line::<D, W>(window, pane_row, rect, &self.blank_style)?;
             }
         }
         Ok(())
     }
 }
 
+/// Get the sticky header lines for the node at `path`: the lines on which its ancestors start, if
+/// they're hidden above the top of the pane or underneath the sticky headers themselves.
+/// `ancestor_rows` are the pane rows on which `path[..1]`, `path[..2]`, etc. start, or `None` if
+/// they're above the pane. If several ancestors start on the same line, it's only included once.
+fn sticky_header_lines<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    ancestor_rows: &[Option<Row>],
+    max_headers: usize,
+    root_style: &D::Style,
+    print_options: &PrintOptions<'d, D>,
+) -> Result<Vec<Line<'d, D>>, PrintingError<D::Error>> {
+    let mut headers = Vec::new();
+    let mut num_hidden = 0;
+    loop {
+        // Ancestors start in order from top to bottom, so the hidden ones come first. Covering
+        // rows with headers can hide more of them.
+        let num_rows = headers.len().min(max_headers);
+        let new_num_hidden = ancestor_rows
+            .iter()
+            .take_while(|row| match row {
+                None => true,
+                Some(row) => (*row as usize) < num_rows,
+            })
+            .count();
+        if new_num_hidden == num_hidden {
+            break;
+        }
+        for len in (num_hidden + 1)..=new_num_hidden {
+            // Print just the line where this ancestor starts, and check whether its parent
+            // starts on it too.
+            let focuses: &[(&[usize], FocusTarget)] = &[
+                (&path[..len], FocusTarget::Start),
+                (&path[..len - 1], FocusTarget::Start),
+            ];
+            let printed = pretty_print_multi_with_options(
+                doc,
+                width,
+                focuses,
+                0,
+                0,
+                Some(root_style),
+                print_options,
+            )?;
+            let shares_line_with_parent = len > 1 && printed.positions[1].is_some();
+            if !shares_line_with_parent {
+                headers.push(Line::from(printed.focused_line));
+            }
+        }
+        num_hidden = new_num_hidden;
+    }
+    let num_dropped = headers.len().saturating_sub(max_headers);
+    Ok(headers.split_off(num_dropped))
+}
+
 /// Display a blank line in the given window, at the given row relative to the `rect`.
 /// Does not display anything that falls outside of the `rect`.
 fn display_blank_line<'d, D, W>(
@@ -381,14 +1096,60 @@ where
     Ok(())
 }
 
-/// Display the [`Line`] in the given window, at the given row relative to the `rect`.
-/// Does not display anything that falls outside of the `rect`.
+/// Display `text` in the given window, at the given row relative to the `rect`, followed by
+/// blank space to the end of the row. Does not display anything that falls outside of the `rect`.
+fn display_text<'d, D, W>(
+    window: &mut W,
+    text: &str,
+    relative_row: Row,
+    rect: Rectangle,
+    text_style: &D::Style,
+    blank_style: &D::Style,
+    width_policy: &dyn WidthPolicy,
+) -> Result<(), PaneError<W::Error, D::Error>>
+where
+    D: PrettyDoc<'d>,
+    W: PrettyWindow<Style = D::Style>,
+{
+    let mut pos = Pos {
+        row: rect.min_row + relative_row,
+        col: rect.min_col,
+    };
+    if pos.row >= rect.max_row {
+        return Ok(());
+    }
+
+    for ch in text.chars() {
+        let is_full_width = width_policy.char_width(ch) == 2;
+        let char_width = if is_full_width { 2 } else { 1 };
+        if pos.col + char_width > rect.max_col {
+            break;
+        }
+        window
+            .display_char(ch, pos, text_style, is_full_width)
+            .map_err(PaneError::PrettyWindowError)?;
+        pos.col += char_width;
+    }
+    while pos.col < rect.max_col {
+        window
+            .display_char(' ', pos, blank_style, false)
+            .map_err(PaneError::PrettyWindowError)?;
+        pos.col += 1;
+    }
+    Ok(())
+}
+
+/// Display the [`Line`] in the given window, at the given row relative to the `rect`, with each
+/// character repeated `zoom_width` times. Does not display anything that falls outside of the
+/// `rect`.
 fn display_line<'d, D, W>(
     window: &mut W,
     line: &Line<'d, D>,
     relative_row: Row,
     rect: Rectangle,
     blank_style: &D::Style,
+    width_policy: &dyn WidthPolicy,
+    zoom_width: Width,
 ) -> Result<(), PaneError<W::Error, D::Error>>
 where
     D: PrettyDoc<'d>,
@@ -406,15 +1167,17 @@ where
     // Display each segment
     'segments_loop: for segment in &line.segments {
         for ch in segment.str.chars() {
-            let is_full_width = is_char_full_width(ch);
+            let is_full_width = width_policy.char_width(ch) == 2;
             let char_width = if is_full_width { 2 } else { 1 };
-            if pos.col + char_width > rect.max_col {
+            if pos.col + char_width * zoom_width > rect.max_col {
                 break 'segments_loop;
             }
-            window
-                .display_char(ch, pos, &segment.style, is_full_width)
-                .map_err(PaneError::PrettyWindowError)?;
-            pos.col += char_width;
+            for _ in 0..zoom_width {
+                window
+                    .display_char(ch, pos, &segment.style, is_full_width)
+                    .map_err(PaneError::PrettyWindowError)?;
+                pos.col += char_width;
+            }
         }
     }
     while pos.col < rect.max_col {
@@ -1,4 +1,5 @@
 use crate::pane::PaneSize;
+use crate::{Col, Rectangle, Row};
 
 /// A utility for fairly dividing up space (either width or height) between multiple panes.
 pub struct Divvier {
@@ -40,10 +41,10 @@ impl Divvier {
     fn allocate_dynamic(&mut self, sizes: Vec<usize>) {
         let sum: usize = sizes.iter().sum();
         assert!(sum <= self.cookies);
-        self.cookies -= sum;
+        self.cookies = self.cookies.saturating_sub(sum);
         let mut sizes = sizes.into_iter();
         for (i, pane_size) in self.pane_sizes.iter().enumerate() {
-            if let PaneSize::Dynamic = pane_size {
+            if let PaneSize::Dynamic { .. } = pane_size {
                 self.allocations[i] = sizes
                     .next()
                     .expect("Divvier.allocate_dynamic(): not enough allocations");
@@ -63,7 +64,7 @@ impl Divvier {
         for (i, pane_size) in self.pane_sizes.iter().enumerate() {
             if let PaneSize::Fixed(hunger) = pane_size {
                 let cookies_given = self.cookies.min(*hunger);
-                self.cookies -= cookies_given;
+                self.cookies = self.cookies.saturating_sub(cookies_given);
                 self.allocations[i] = cookies_given;
             }
         }
@@ -81,6 +82,10 @@ impl Divvier {
             }
         }
         let total_hunger: usize = child_hungers.iter().sum();
+        if total_hunger == 0 {
+            // Nobody's hungry (or there's nobody), so nobody gets any cookies.
+            return;
+        }
         // Start by allocating each child a guaranteed minimum number of cookies,
         // found as the floor of the real number of cookies they deserve.
         let mut cookie_allocation: Vec<usize> = child_hungers
@@ -89,7 +94,7 @@ impl Divvier {
             .collect();
         // Compute the number of cookies still remaining.
         let allocated_cookies: usize = cookie_allocation.iter().sum();
-        let leftover: usize = self.cookies - allocated_cookies;
+        let leftover: usize = self.cookies.saturating_sub(allocated_cookies);
         // Determine what fraction of a cookie each child still deserves, found as
         // the remainder of the above division. Then hand out the remaining cookies
         // to the children with the largest remainders.
@@ -109,12 +114,52 @@ impl Divvier {
             if let PaneSize::Proportional(_) = pane_size {
                 let cookies = cookie_allocation.next().unwrap();
                 self.allocations[i] = cookies;
-                self.cookies -= cookies;
+                self.cookies = self.cookies.saturating_sub(cookies);
             }
         }
     }
 }
 
+/// The rectangle partway between `from` and `to`, for animating a pane that moves or resizes. `t`
+/// goes from 0 (giving `from`) to 1 (giving `to`), and is clamped to that range. Each edge moves
+/// independently and is rounded to the nearest cell. The result is clipped to `bounds`.
+pub fn interpolate_rect(from: Rectangle, to: Rectangle, t: f32, bounds: Rectangle) -> Rectangle {
+    let t = t.clamp(0.0, 1.0);
+    let lerp = |a: f32, b: f32| (a + (b - a) * t).round();
+    let rect = Rectangle {
+        min_row: lerp(from.min_row as f32, to.min_row as f32) as Row,
+        max_row: lerp(from.max_row as f32, to.max_row as f32) as Row,
+        min_col: lerp(from.min_col as f32, to.min_col as f32) as Col,
+        max_col: lerp(from.max_col as f32, to.max_col as f32) as Col,
+    };
+    clip_rect(rect, bounds)
+}
+
+/// The empty rectangle at the center of `rect`, for a pane to grow out of when it opens, or
+/// shrink into when it closes.
+pub fn collapse_rect(rect: Rectangle) -> Rectangle {
+    let row = rect.min_row + rect.height() / 2;
+    let col = rect.min_col + rect.width() / 2;
+    Rectangle {
+        min_row: row,
+        max_row: row,
+        min_col: col,
+        max_col: col,
+    }
+}
+
+/// The part of `rect` that's inside of `bounds`. If they don't overlap, the result is empty.
+fn clip_rect(rect: Rectangle, bounds: Rectangle) -> Rectangle {
+    let min_row = rect.min_row.max(bounds.min_row);
+    let min_col = rect.min_col.max(bounds.min_col);
+    Rectangle {
+        min_row,
+        max_row: rect.max_row.min(bounds.max_row).max(min_row),
+        min_col,
+        max_col: rect.max_col.min(bounds.max_col).max(min_col),
+    }
+}
+
 #[test]
 fn test_proportional_division() {
     fn proportionally_divide(cookies: usize, hungers: &[usize]) -> Vec<usize> {
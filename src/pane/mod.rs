@@ -12,16 +12,33 @@
 //! ignores style metadata and just writes the window contents to a string. You will need to provide
 //! your own implementation of [`PrettyWindow`] for whatever medium you want to display to (like a
 //! terminal window).
+//!
+//! If you display from an event loop, a [`PaneRunner`] can skip redisplaying when nothing changed.
+//! To animate a change in the layout, such as a split opening, display the frames in between with
+//! [`display_pane_transition()`].
+//!
+//! To compare two versions of a document side by side, use [`diff()`].
 
+mod bookmarks;
+mod diff;
 mod display_pane;
 mod divvy;
 mod pane_notation;
+mod pane_runner;
 mod plain_text;
 mod pretty_window;
 mod printing_options;
 
-pub use display_pane::{display_pane, PaneError};
-pub use pane_notation::{DocLabel, PaneNotation, PaneSize};
+pub use bookmarks::{Bookmark, Bookmarks};
+pub use diff::{diff, DiffDoc, DiffKind, DiffSide, DiffStyles, DocDiff};
+pub use display_pane::{
+    display_pane, display_pane_region, display_pane_transition, display_pane_with_images,
+    ActivePane, DocPane, Image, PaneError, PaneLayout,
+};
+pub use pane_notation::{
+    DocLabel, GutterContent, OverlayAnchor, OverlayPlacement, PaneNotation, PaneSize,
+};
+pub use pane_runner::PaneRunner;
 pub use plain_text::PlainText;
 pub use pretty_window::PrettyWindow;
-pub use printing_options::{PrintingOptions, WidthStrategy};
+pub use printing_options::{PrintingOptions, WidthStrategy, Zoom};
@@ -1,3 +1,5 @@
+use crate::{Height, Pos, Row, Size, Width};
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// A label that can be used to look up a document.
@@ -16,6 +18,20 @@ pub enum PaneNotation<L: DocLabel, S> {
     /// Pretty print a document and display it in this pane. The given [`DocLabel`] will be used to dynamically look up
     /// the [`PrettyDoc`](crate::PrettyDoc) when the pane is displayed.
     Doc { label: L },
+    /// Like [`PaneNotation::Doc`], but with a gutter `width` columns wide along the left side of the
+    /// pane. Each row of the gutter shows information about the line of the document that's
+    /// displayed in that row (or is blank if there is no such line, or if the row is covered by a
+    /// [sticky header](super::PrintingOptions::sticky_headers)).
+    Gutter {
+        label: L,
+        width: Width,
+        content: GutterContent<S>,
+    },
+    /// Reserve this pane for an image, to be drawn by the application with a terminal graphics
+    /// protocol like Kitty's or Sixel. The pane is filled with blank placeholder cells, and the
+    /// image is looked up by `label` and reported in [`PaneLayout::images`](super::PaneLayout),
+    /// along with where to draw it. See [`display_pane_with_images`](super::display_pane_with_images).
+    Image { label: L },
     /// Fill the entire pane by repeating the given character.
     Fill { ch: char },
     /// Apply the style to the contained notation.
@@ -23,6 +39,59 @@ pub enum PaneNotation<L: DocLabel, S> {
         style: S,
         notation: Box<PaneNotation<L, S>>,
     },
+    /// Display `then` if this pane is at least `min_width` wide and `min_height` tall, and
+    /// `otherwise` if it isn't. Useful for hiding side panels when the window is small. (The
+    /// second branch can't be called `else`, because that's a keyword.)
+    IfFits {
+        min_width: Width,
+        min_height: Height,
+        then: Box<PaneNotation<L, S>>,
+        otherwise: Box<PaneNotation<L, S>>,
+    },
+    /// Display `base` in this pane, then display `overlay` on top of it, in a sub-rectangle of
+    /// this pane determined by `placement`. Useful for popups like completion menus and dialogs.
+    Overlay {
+        base: Box<PaneNotation<L, S>>,
+        overlay: Box<PaneNotation<L, S>>,
+        placement: OverlayPlacement,
+    },
+}
+
+/// What to display in a [`PaneNotation::Gutter`].
+#[derive(Clone, Debug)]
+pub enum GutterContent<S> {
+    /// The line number of each line, counting from 1 at the top of the document, right-aligned and
+    /// followed by a space. This requires printing every line above the focus, so it's slower for
+    /// large documents than the other options.
+    LineNumbers,
+    /// How many lines above or below the focus line each line is, right-aligned and followed by a
+    /// space. The focus line shows 0.
+    RelativeLineNumbers,
+    /// Markers for particular lines, such as change markers or fold indicators. The keys are rows
+    /// of the document, where the document's first line is row 0. Each marker is displayed at the
+    /// left of the gutter, in its style combined with the pane's style. Like `LineNumbers`, this
+    /// requires printing every line above the focus.
+    Markers(BTreeMap<Row, (char, S)>),
+}
+
+/// Where to display the `overlay` of a [`PaneNotation::Overlay`], relative to its `base`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OverlayPlacement {
+    /// Where to put the overlay.
+    pub anchor: OverlayAnchor,
+    /// How large the overlay should be. If it doesn't fit in the base pane (after being
+    /// positioned), it's shrunk until it does.
+    pub size: Size,
+}
+
+/// How to position the `overlay` of a [`PaneNotation::Overlay`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayAnchor {
+    /// Center the overlay within the base pane (rounding up and to the left).
+    Center,
+    /// Put the upper-left corner of the overlay at this position, relative to the upper-left
+    /// corner of the base pane.
+    At(Pos),
 }
 
 /// Specify the size of a subpane within a vertically ([`PaneNotation::Vert`]) or horizontally
@@ -38,17 +107,22 @@ pub enum PaneSize {
     /// columns of width (for [`PaneNotation::Horz`]).
     Fixed(usize),
 
-    /// Try to give the subpane exactly the amount of height or width needed to fit its content. If
-    /// that's not possible, give it all of the remaining height or width. Note that documents are
-    /// typically very greedy for width, so you should only use `Dynamic` width in unusual
-    /// circumstances.
+    /// Try to give the subpane exactly the amount of height or width needed to fit its content, but
+    /// no more than `max` (if given). If that's not possible, give it all of the remaining height
+    /// or width. Note that documents are typically very greedy for width, so you should only use
+    /// `Dynamic` width without a `max` in unusual circumstances.
+    ///
+    /// The content is measured by printing just the part of the document that fits in the space
+    /// that's available to the subpane (and within `max`), which is then displayed as-is.
     ///
-    /// If there are multiple `Dynamic` subpanes and not enough space to satisfy all of them, the
-    /// ones earlier in the list get priority. `Dynamic` subpanes get priority over `Proportional`
-    /// subpanes, regardless of order.
+    /// `Dynamic` subpanes get priority over `Proportional` subpanes, regardless of order. If there
+    /// are multiple `Dynamic` subpanes, they're measured in order from first to last, and each one
+    /// can only use the space left over by the ones before it. So if there isn't enough space to
+    /// satisfy all of them, the ones earlier in the list get priority, and the later ones may be
+    /// given no space at all. Use `max` to leave room for them.
     ///
     /// A `Dynamic` subpane can only contain a [`PaneNotation::Doc`], not more nested subpanes.
-    Dynamic,
+    Dynamic { max: Option<usize> },
 
     /// After `Fixed` and `Dynamic` subpanes have been assigned a width/height, divide up the
     /// remaining available width/height between the `Proportional` subpanes according to their
@@ -0,0 +1,106 @@
+use crate::{
+    pane::{
+        display_pane, ActivePane, DocLabel, PaneError, PaneLayout, PaneNotation, PrettyWindow,
+        PrintingOptions,
+    },
+    PrettyDoc, Size,
+};
+
+/// Everything that determined the last display. If it's all unchanged, so is the window.
+#[derive(Debug, PartialEq, Eq)]
+struct Inputs {
+    /// The window's size.
+    size: Size,
+    /// The `Debug` rendering of the [`PaneNotation`]. Labels and styles are only required to be
+    /// `Debug`, so this is how they're compared.
+    notation: String,
+    /// The `Debug` rendering of the base style.
+    style: String,
+    /// The caller's fingerprint of the content.
+    content: u64,
+}
+
+/// Re-displays a [`PaneNotation`] only when its inputs have changed, so that an event loop can
+/// call [`PaneRunner::maybe_redraw()`] on every tick without re-printing every document each time.
+///
+/// The inputs are the window's size, the pane notation, the base style, and a "content
+/// fingerprint" that you supply. The fingerprint must change whenever anything that
+/// `get_content` returns does: the documents, their [`PrintingOptions`], or their themes. It must
+/// also change if the active pane changes. A revision counter that you bump on every change works
+/// well.
+///
+/// Skipping a redraw leaves the window as it is, so this relies on the [`PrettyWindow`] retaining
+/// what was last displayed (such as a terminal's screen, or a buffer that is diffed against the
+/// screen before flushing). If the window's contents are lost or overwritten by something else,
+/// call [`PaneRunner::invalidate()`] to force the next call to redraw.
+///
+/// `L` is the type of the [`DocLabel`]s in the [`PaneNotation`].
+#[derive(Debug)]
+pub struct PaneRunner<L: DocLabel> {
+    /// The inputs of the last successful display, if any.
+    last_inputs: Option<Inputs>,
+    /// The layout of the last successful display.
+    layout: PaneLayout<L>,
+}
+
+impl<L: DocLabel> Default for PaneRunner<L> {
+    fn default() -> Self {
+        PaneRunner {
+            last_inputs: None,
+            layout: PaneLayout::default(),
+        }
+    }
+}
+
+impl<L: DocLabel> PaneRunner<L> {
+    /// Construct a `PaneRunner` that hasn't displayed anything yet, so that the first call to
+    /// [`PaneRunner::maybe_redraw()`] will display.
+    pub fn new() -> PaneRunner<L> {
+        PaneRunner::default()
+    }
+
+    /// Forget the last display, so that the next call to [`PaneRunner::maybe_redraw()`] will
+    /// display even if nothing changed.
+    pub fn invalidate(&mut self) {
+        self.last_inputs = None;
+    }
+
+    /// The layout reported by the last display.
+    pub fn layout(&self) -> &PaneLayout<L> {
+        &self.layout
+    }
+
+    /// Call [`display_pane()`] with these arguments, unless the window's size, `notation`,
+    /// `style`, and `content_fingerprint` are all the same as the last time the pane was
+    /// displayed. Returns whether it was displayed.
+    ///
+    /// If displaying fails, the error is returned and the next call will try again.
+    pub fn maybe_redraw<'d, D, W>(
+        &mut self,
+        window: &mut W,
+        notation: &PaneNotation<L, D::Style>,
+        style: &D::Style,
+        content_fingerprint: u64,
+        get_content: &impl Fn(L) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error>,
+        active: Option<&ActivePane<L, D::Style>>,
+    ) -> Result<bool, PaneError<W::Error, D::Error>>
+    where
+        D: PrettyDoc<'d>,
+        W: PrettyWindow<Style = D::Style>,
+    {
+        let inputs = Inputs {
+            size: window.size().map_err(PaneError::PrettyWindowError)?,
+            notation: format!("{:?}", notation),
+            style: format!("{:?}", style),
+            content: content_fingerprint,
+        };
+        if self.last_inputs.as_ref() == Some(&inputs) {
+            return Ok(false);
+        }
+
+        self.last_inputs = None;
+        self.layout = display_pane(window, notation, style, get_content, active)?;
+        self.last_inputs = Some(inputs);
+        Ok(true)
+    }
+}
@@ -1,11 +1,15 @@
-use crate::{FocusTarget, Height, Row, Width};
+use crate::{
+    ErrorPolicy, FocusTarget, Height, Matches, PrettyDoc, PrintOptions, Row, Size, UnicodeWidth,
+    Width, WidthPolicy,
+};
 
 #[cfg(doc)]
 use super::pretty_window::PrettyWindow;
 
-/// Options for how to print a document within a pane.
+/// Options for how to print a document within a pane. `S` is the document's
+/// [`PrettyDoc::Style`](crate::PrettyDoc::Style).
 #[derive(Debug, Clone)]
-pub struct PrintingOptions {
+pub struct PrintingOptions<S> {
     /// Set the focus of the document to be at some target relative to the node at this path. Each
     /// `usize` is the index of a child node, starting from the root.
     pub focus_path: Vec<usize>,
@@ -18,6 +22,69 @@ pub struct PrintingOptions {
     pub width_strategy: WidthStrategy,
     /// Whether to invoke [`PrettyWindow::set_focus`] with the focus point of this document.
     pub set_focus: bool,
+    /// If set, show the spaces in indentation and the trailing spaces on each line as `·`, in this
+    /// style. See [`Line::with_visible_whitespace()`](crate::Line::with_visible_whitespace).
+    pub visible_whitespace: Option<S>,
+    /// Whether to reorder right-to-left text (like Hebrew or Arabic) so that it displays
+    /// correctly. See [`Line::into_visual_order()`](crate::Line::into_visual_order).
+    pub reorder_bidi: bool,
+    /// If `focus_path` doesn't exist in the document, focus on the start of its nearest existing
+    /// ancestor instead of failing with [`PrintingError::InvalidPath`](crate::PrintingError). This
+    /// is reported in [`PaneLayout::path_fallbacks`](super::PaneLayout::path_fallbacks). See
+    /// [`PrintOptions::fall_back_on_invalid_path`](crate::PrintOptions::fall_back_on_invalid_path).
+    pub fall_back_on_invalid_path: bool,
+    /// The most rows at the top of the pane to use for "sticky headers". If the line on which an
+    /// ancestor of the focused node starts has scrolled off the top of the pane, it's displayed in
+    /// the pane's top rows instead, outermost ancestor first. If there are too many such lines,
+    /// the innermost ancestors' are kept. The root doesn't count as an ancestor, and sticky headers
+    /// never cover the focus line. Set this to 0 to disable sticky headers.
+    pub sticky_headers: usize,
+    /// If the focus is near the start or end of the document, move the focus line away from
+    /// `focus_height` as far as needed for the document to fill the pane, instead of leaving blank
+    /// rows above or below it. (If the whole document fits in the pane, it starts on the top row.)
+    pub clamp_focus_to_content: bool,
+    /// What to do if the document returns an error while a child node is being printed. See
+    /// [`PrintOptions::on_error`].
+    pub on_error: ErrorPolicy<S>,
+    /// How to measure the width of text, both to lay out the document and to display it. This
+    /// should match how the [`PrettyWindow`] displays characters. Usually `&UnicodeWidth`. See
+    /// [`WidthPolicy`].
+    pub width_policy: &'static dyn WidthPolicy,
+    /// If set, display these nodes (such as the results of a search) in a "match" style. See
+    /// [`PrintOptions::matches`].
+    pub matches: Option<Matches<S>>,
+    /// If set, display the blank parts of the pane (such as the rows below the end of a short
+    /// document) in this style instead of the pane's style. It's combined with the pane's style
+    /// using [`Style::combine`](crate::Style::combine), so it only needs to set what's different,
+    /// like the background color.
+    pub blank_style: Option<S>,
+    /// Display the document zoomed in, with each character taking up several cells of the pane,
+    /// for low-vision users. The document is printed as if the pane were correspondingly smaller.
+    pub zoom: Zoom,
+}
+
+/// Focus on the start of the document, display it on the top row of the pane at the pane's full
+/// width, and leave every other option off.
+impl<S> Default for PrintingOptions<S> {
+    fn default() -> Self {
+        PrintingOptions {
+            focus_path: Vec::new(),
+            focus_target: FocusTarget::Start,
+            focus_height: 0.0,
+            width_strategy: WidthStrategy::Full,
+            set_focus: false,
+            visible_whitespace: None,
+            reorder_bidi: false,
+            fall_back_on_invalid_path: false,
+            sticky_headers: 0,
+            clamp_focus_to_content: false,
+            on_error: ErrorPolicy::Fail,
+            width_policy: &UnicodeWidth,
+            matches: None,
+            blank_style: None,
+            zoom: Zoom::Normal,
+        }
+    }
 }
 
 /// How to choose the document width, after learning the how much width is available.
@@ -32,12 +99,72 @@ pub enum WidthStrategy {
     NoMoreThan(Width),
 }
 
-impl PrintingOptions {
+/// How many cells of the pane to display each cell of the document with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Zoom {
+    /// Each cell of the document is one cell of the pane.
+    Normal,
+    /// Each cell of the document is two cells wide, by displaying each character twice.
+    Wide,
+    /// Each cell of the document is two cells wide and two cells tall, by displaying each
+    /// character twice and each line twice.
+    Double,
+}
+
+impl Zoom {
+    /// The size of the block of pane cells used to display one cell of the document.
+    pub fn scale(self) -> Size {
+        match self {
+            Zoom::Normal => Size {
+                width: 1,
+                height: 1,
+            },
+            Zoom::Wide => Size {
+                width: 2,
+                height: 1,
+            },
+            Zoom::Double => Size {
+                width: 2,
+                height: 2,
+            },
+        }
+    }
+}
+
+impl<S> PrintingOptions<S> {
     /// Choose which row of the pane the focus line should be displayed on.
     pub(crate) fn choose_focus_line_row(&self, pane_height: Height) -> Row {
         assert!(self.focus_height >= 0.0);
         assert!(self.focus_height <= 1.0);
-        f32::round((pane_height - 1) as f32 * self.focus_height) as Row
+        f32::round(pane_height.saturating_sub(1) as f32 * self.focus_height) as Row
+    }
+
+    /// Move the focus line from `focus_line_row` as little as possible so that there are no blank
+    /// rows at the top of the pane, or at the bottom of the pane unless the whole document fits in
+    /// it. There are `lines_above` lines of the document above the focus line, and `lines_below`
+    /// below it.
+    pub(crate) fn clamp_focus_line_row(
+        &self,
+        focus_line_row: Row,
+        pane_height: Height,
+        lines_above: usize,
+        lines_below: usize,
+    ) -> Row {
+        let min_row = (pane_height as usize).saturating_sub(1 + lines_below);
+        (focus_line_row as usize).max(min_row).min(lines_above) as Row
+    }
+
+    /// The options to pass to the printer.
+    pub(crate) fn print_options<'d, D: PrettyDoc<'d, Style = S>>(&self) -> PrintOptions<'d, D>
+    where
+        S: Clone,
+    {
+        PrintOptions {
+            on_error: self.on_error.clone(),
+            width_policy: self.width_policy,
+            matches: self.matches.clone(),
+            ..PrintOptions::default()
+        }
     }
 
     /// Choose what width to use when pretty-printing the document.
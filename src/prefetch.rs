@@ -0,0 +1,81 @@
+use alloc::collections::VecDeque;
+
+/// Wraps one of the printing iterators, such as the [`DownwardPrinter`](crate::DownwardPrinter)
+/// or [`UpwardPrinter`](crate::UpwardPrinter) returned by [`pretty_print()`](crate::pretty_print),
+/// so that upcoming lines can be printed ahead of time. For example, an editor can call
+/// [`Prefetcher::prefetch()`] while its event loop is idle, so that the lines are already printed
+/// when the user scrolls to them.
+///
+/// It's an iterator itself, which yields the prefetched lines first and then continues printing.
+/// An error is yielded in its place, and nothing past it is prefetched.
+pub struct Prefetcher<I: Iterator> {
+    printer: I,
+    /// Lines that have been printed but not yet yielded. The first element is the next line.
+    prefetched: VecDeque<I::Item>,
+    /// Whether the printer is done: it ran out of lines, or produced an error.
+    finished: bool,
+}
+
+impl<T, E, I: Iterator<Item = Result<T, E>>> Prefetcher<I> {
+    /// Wrap `printer`. Nothing is printed until [`Prefetcher::prefetch()`] or `next()` is called.
+    pub fn new(printer: I) -> Prefetcher<I> {
+        Prefetcher {
+            printer,
+            prefetched: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Print lines until at least `count` of them are ready, or the printer is finished.
+    pub fn prefetch(&mut self, count: usize) {
+        while self.prefetched.len() < count && !self.finished {
+            match self.printer.next() {
+                None => self.finished = true,
+                Some(item) => {
+                    self.finished = item.is_err();
+                    self.prefetched.push_back(item);
+                }
+            }
+        }
+    }
+
+    /// The number of lines that have been printed ahead of time and are ready to yield.
+    pub fn num_prefetched(&self) -> usize {
+        self.prefetched.len()
+    }
+
+    /// The `i`th upcoming line, if it has been prefetched.
+    pub fn get(&self, i: usize) -> Option<&I::Item> {
+        self.prefetched.get(i)
+    }
+
+    /// Whether every remaining line has been prefetched (or printing has failed).
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Throw away the prefetched lines, and continue with `printer` instead. Call this after the
+    /// document or the printing options change, with a printer for the new document. This is
+    /// cheap: nothing is printed until the next call to [`Prefetcher::prefetch()`] or `next()`.
+    pub fn invalidate(&mut self, printer: I) {
+        self.printer = printer;
+        self.prefetched.clear();
+        self.finished = false;
+    }
+}
+
+impl<T, E, I: Iterator<Item = Result<T, E>>> Iterator for Prefetcher<I> {
+    type Item = Result<T, E>;
+
+    fn next(&mut self) -> Option<Result<T, E>> {
+        if let Some(item) = self.prefetched.pop_front() {
+            return Some(item);
+        }
+        if self.finished {
+            return None;
+        }
+        let item = self.printer.next();
+        self.finished = !matches!(item, Some(Ok(_)));
+        item
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{Condition, StyleLabel, ValidNotation};
-use std::fmt;
-use std::hash::Hash;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::ops::Range;
 
 #[cfg(doc)]
 use crate::Notation; // for links in rustdocs
@@ -10,9 +12,10 @@ use crate::Notation; // for links in rustdocs
 ///
 /// A node is expected to contain either a piece of text, or 0 or more child nodes.
 ///
-/// Consider implementing [`PrettyDoc::unwrap_last_child()`] and [`PrettyDoc::unwrap_prev_sibling()`]
-/// even though default implementations are provided. Depending on your representation of documents,
-/// you may be able to write much more efficient implementations.
+/// Consider implementing [`PrettyDoc::unwrap_children()`], or [`PrettyDoc::unwrap_last_child()`] and
+/// [`PrettyDoc::unwrap_prev_sibling()`], even though default implementations are provided.
+/// Depending on your representation of documents, you may be able to write much more efficient
+/// implementations.
 pub trait PrettyDoc<'d>: Copy {
     /// Used to uniquely identify a node.
     type Id: Eq + Hash + Copy + fmt::Debug;
@@ -24,7 +27,7 @@ pub trait PrettyDoc<'d>: Copy {
     /// [`PrettyDoc::condition()`]/[`Notation::Check`].
     type Condition: Condition + 'd;
     /// An error that could occur when calling any of the trait methods.
-    type Error: std::error::Error + 'static;
+    type Error: core::error::Error + 'static;
 
     /// Get the id that uniquely identifies this node.
     fn id(self) -> Result<Self::Id, Self::Error>;
@@ -41,6 +44,74 @@ pub trait PrettyDoc<'d>: Copy {
     /// [`Notation::Style`].
     fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error>;
 
+    /// Get the style associated with this label, for a node at the given `depth` in the document:
+    /// the root has depth 0, its children depth 1, and so on. The pretty printer will only call
+    /// this method with labels that were used in [`Notation::DepthStyle`]. Returns
+    /// `lookup_style(style_label)` by default.
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        let _ = depth;
+        self.lookup_style(style_label)
+    }
+
+    /// Get the style of a level of indentation added by a [`Notation::Indent`] with this label, in
+    /// the context of this node. `depth` is the number of levels of indentation that the new level
+    /// is nested inside of, so the leftmost level has depth 0. This lets indentation guides
+    /// alternate colors by level, for example. Returns `lookup_style(style_label)` by default.
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        let _ = depth;
+        self.lookup_style(style_label)
+    }
+
+    /// Get the link target (such as a URL) associated with this label, in the context of this
+    /// node, or `None` if there isn't one. The pretty printer will only call this method with
+    /// labels that were used in [`Notation::Link`]. Returns `None` by default.
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        let _ = link_label;
+        Ok(None)
+    }
+
+    /// Get the text to display for this label, in the context of this node. The pretty printer
+    /// will only call this method with labels that were used in [`Notation::Placeholder`]. The
+    /// text must not contain a newline character. Returns the empty string by default.
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        let _ = text_label;
+        Ok("")
+    }
+
+    /// Transform this node's text for display, according to the label. The pretty printer will
+    /// only call this method on nodes that contain text, with labels that were used in
+    /// [`Notation::MapText`]. The result must not contain a newline character. Returns `text`
+    /// unchanged by default.
+    ///
+    /// Truncation can return a slice of `text`. A transformation that produces new text (such as
+    /// changing its case or escaping it) needs to keep its result alive for `'d`, for example in a
+    /// cache alongside the document.
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        let _ = text_label;
+        Ok(text)
+    }
+
+    /// Estimate how many lines this node takes up when printed, if that's cheap to know (for
+    /// example, if your document caches it for each subtree). It doesn't need to be exact: it's
+    /// only used by [`path_near_line()`](crate::path_near_line) and
+    /// [`pretty_print_near_line()`](crate::pretty_print_near_line) to skip over whole subtrees
+    /// instead of visiting every node in them. Returns `None` by default.
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        Ok(None)
+    }
+
     /// Get the style to apply to this node. This method is called once per document node and applies
     /// to the whole node. It will be [`combined`](Style::combine) with any overlapping styles.
     fn node_style(self) -> Result<Self::Style, Self::Error>;
@@ -85,6 +156,35 @@ pub trait PrettyDoc<'d>: Copy {
     fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
         parent.unwrap_child(i)
     }
+
+    /// Get the children of this node whose indices are in `range`, in order, or panic. The pretty
+    /// printer will only call this method if [`num_children()`](PrettyDoc::num_children) returns
+    /// `Some(n)` for `n >= range.end` - it is ok to make this method panic otherwise.
+    ///
+    /// The pretty printer uses this to fetch all of a [`Notation::Fold`]'s children at once. This
+    /// method is redundant with [`unwrap_child()`](PrettyDoc::unwrap_child), but if looking up a
+    /// child is expensive (for example if the document is stored in a database or parsed lazily),
+    /// you may be able to fetch many children in a single lookup. The default implementation
+    /// fetches the last child in the range and walks backwards with
+    /// [`unwrap_prev_sibling()`](PrettyDoc::unwrap_prev_sibling).
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        let mut children = Vec::with_capacity(range.len());
+        if range.is_empty() {
+            return Ok(children);
+        }
+        let mut child = if Some(range.end) == self.num_children()? {
+            self.unwrap_last_child()?
+        } else {
+            self.unwrap_child(range.end - 1)?
+        };
+        children.push(child);
+        for i in range.rev().skip(1) {
+            child = child.unwrap_prev_sibling(self, i)?;
+            children.push(child);
+        }
+        children.reverse();
+        Ok(children)
+    }
 }
 
 /// Styles are arbitrary metadata that are applied to regions of the document. When multiple styles
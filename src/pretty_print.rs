@@ -1,18 +1,59 @@
+use crate::infra::HashMap;
 use crate::{
+    bidi,
+    choice_strategy::{ChoiceContext, ChoiceStrategy, ChoiceTieBreak, FirstFits},
     consolidated_notation::{
-        ConsolidatedNotation, DelayedConsolidatedNotation, IndentNode, Textual,
+        CacheKey, ConsolidatedNotation, DelayedConsolidatedNotation, IndentNode, Textual,
     },
+    geometry::{char_width, str_width},
     infra::span,
-    PrettyDoc, PrintingError, Segment, Width,
+    line_decorator::{LineDecorator, LineInfo},
+    reanchor::{find_node, find_path_fallback, path_near_line, validate_path, PathFallback},
+    search::{MatchStyle, Matches},
+    Col, Direction, Pos, PrettyDoc, PrintingError, Row, Segment, SegmentKind, Split, SplitRounding,
+    UnicodeWidth, Width, WidthPolicy,
 };
-use std::convert::From;
-use std::iter::Iterator;
-use std::mem;
-use std::rc::Rc;
+use alloc::{
+    borrow::ToOwned,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cell::RefCell;
+use core::convert::From;
+use core::hash::Hash;
+use core::iter::{self, Iterator};
+use core::mem;
+use core::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Serialize};
 
 #[cfg(doc)]
 use crate::notation::Notation;
 
+/// The result of [`pretty_print()`]: an iterator over the lines above the focused line, the
+/// focused line itself, and an iterator over the lines below it.
+pub type PrintResult<'d, D> = Result<
+    (
+        UpwardPrinter<'d, D>,
+        FocusedLine<'d, D>,
+        DownwardPrinter<'d, D>,
+    ),
+    PrintingError<<D as PrettyDoc<'d>>::Error>,
+>;
+
+/// The result of [`pretty_print_blocks()`]: like [`PrintResult`], but with blocks instead of lines.
+pub type BlockPrintResult<'d, D> = Result<
+    (
+        UpwardBlockPrinter<'d, D>,
+        FocusedBlock<'d, D>,
+        DownwardBlockPrinter<'d, D>,
+    ),
+    PrintingError<<D as PrettyDoc<'d>>::Error>,
+>;
+
 /// Pretty print a document, focused relative to the node found by traversing `path`. The `path`
 /// is a sequence of child indices to follow starting from the root. The `focus_target` declares
 /// where the focus is relative to that node (e.g. before or after it).
@@ -35,114 +76,1583 @@ pub fn pretty_print<'d, D: PrettyDoc<'d>>(
     path: &[usize],
     focus_target: FocusTarget,
     root_style: Option<&D::Style>,
+) -> PrintResult<'d, D> {
+    pretty_print_with_options(
+        doc,
+        width,
+        path,
+        focus_target,
+        root_style,
+        &PrintOptions::default(),
+    )
+}
+
+/// Like [`pretty_print()`], but with [`PrintOptions`] that control how the document is printed.
+pub fn pretty_print_with_options<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    root_style: Option<&D::Style>,
+    options: &PrintOptions<'d, D>,
+) -> PrintResult<'d, D> {
+    span!("Pretty Print");
+
+    let origin = Origin {
+        path: path.to_vec(),
+        focus_target,
+        skipped_lines: 0,
+    };
+    let printer = seek_origin(doc, width, origin, root_style, options)?;
+    print_around_focus(printer)
+}
+
+/// Options for [`pretty_print_with_options()`], and the other printing functions that take them.
+/// The [`Default`] options print the same way as [`pretty_print()`], so set just the ones you need:
+///
+/// ```
+/// # use partial_pretty_printer::{ChoiceTieBreak, PrettyDoc, PrintOptions};
+/// # fn options<'d, D: PrettyDoc<'d>>() -> PrintOptions<'d, D> {
+/// PrintOptions {
+///     tie_break: ChoiceTieBreak::PreferFewestLines,
+///     collapse_blank_lines: Some(1),
+///     ..PrintOptions::default()
+/// }
+/// # }
+/// ```
+pub struct PrintOptions<'d, D: PrettyDoc<'d>> {
+    /// What to do if one of the [`PrettyDoc`] methods returns an error while accessing a child
+    /// node. With [`ErrorPolicy::Substitute`], the printer displays [`ERROR_PLACEHOLDER`] in place
+    /// of that child and keeps going, so that a partially broken document can still be displayed.
+    /// (Errors while seeking to the focus, or from any other [`PrettyDoc`] method, are still
+    /// returned.) Defaults to [`ErrorPolicy::Fail`].
+    pub on_error: ErrorPolicy<D::Style>,
+    /// How to measure the width of text. This affects how lines are laid out to fit within the
+    /// `width`, and the [`Segment::width`] of every printed segment. Defaults to [`UnicodeWidth`].
+    pub width_policy: &'d dyn WidthPolicy,
+    /// How to pick between the options of each choice. Defaults to [`FirstFits`].
+    pub choice_strategy: &'d dyn ChoiceStrategy,
+    /// How to pick between the options of a choice when neither of them fits within the width.
+    /// This can make overlong lines degrade more gracefully when printing to a narrow width.
+    /// Defaults to [`ChoiceTieBreak::PreferSecond`], which always takes the second option.
+    pub tie_break: ChoiceTieBreak,
+    /// If set, display these nodes (such as the results of a search) in their style. Use
+    /// [`next_match_after()`](crate::next_match_after) and
+    /// [`prev_match_before()`](crate::prev_match_before) to move the focus between them.
+    pub matches: Option<Matches<D::Style>>,
+    /// If set, let this add segments to the start and end of each line as it's printed, including
+    /// the focused line. Decorations are added after layout, so they don't count against the
+    /// `width`.
+    pub line_decorator: Option<Rc<dyn LineDecorator<'d, D> + 'd>>,
+    /// If `Some(n)`, print at most `n` blank lines in a row: the rest of each run of blank lines is
+    /// left out. A line is blank if it contains only whitespace (such as indentation). This is for
+    /// notations that can produce several empty lines in a row, for example from empty children.
+    ///
+    /// The focused line is always printed, even if it's blank and past the first `n` lines of its
+    /// run. Otherwise, printing with the focus anywhere gives the same lines.
+    pub collapse_blank_lines: Option<usize>,
+    /// If set, keep measurements of the document's [`Notation::Cache`] subtrees in this cache, and
+    /// reuse any that are already there. Passing the same [`LayoutCache`] to each redraw of a
+    /// document saves re-measuring the parts of it that haven't changed. See [`LayoutCache`] for
+    /// when it needs to be invalidated.
+    pub layout_cache: Option<LayoutCache<D::Id>>,
+    /// If set, whenever the printer checks whether a choice fits it looks at no more than this many
+    /// chunks of the rest of the document, and assumes that whatever it didn't look at fits. This
+    /// bounds the cost of each choice on pathological documents, at the expense of sometimes
+    /// picking a layout that overflows the `width`. See [`DownwardPrinter::stats()`].
+    pub max_lookahead: Option<usize>,
+    /// If `path` doesn't exist in the document (say because the document was edited since the path
+    /// was computed), put the focus at the start of the nearest ancestor that does exist instead of
+    /// returning [`PrintingError::InvalidPath`]. Use [`DownwardPrinter::path_fallback()`] to find
+    /// out whether this happened, and which ancestor was used. See also
+    /// [`reanchor()`](crate::reanchor), for updating paths as you edit the document.
+    pub fall_back_on_invalid_path: bool,
+}
+
+impl<'d, D: PrettyDoc<'d>> Default for PrintOptions<'d, D> {
+    fn default() -> Self {
+        PrintOptions {
+            on_error: ErrorPolicy::Fail,
+            width_policy: &UnicodeWidth,
+            choice_strategy: &FirstFits,
+            tie_break: ChoiceTieBreak::default(),
+            matches: None,
+            line_decorator: None,
+            collapse_blank_lines: None,
+            layout_cache: None,
+            max_lookahead: None,
+            fall_back_on_invalid_path: false,
+        }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for PrintOptions<'d, D> {
+    fn clone(&self) -> Self {
+        PrintOptions {
+            on_error: self.on_error.clone(),
+            width_policy: self.width_policy,
+            choice_strategy: self.choice_strategy,
+            tie_break: self.tie_break,
+            matches: self.matches.clone(),
+            line_decorator: self.line_decorator.clone(),
+            collapse_blank_lines: self.collapse_blank_lines,
+            layout_cache: self.layout_cache.clone(),
+            max_lookahead: self.max_lookahead,
+            fall_back_on_invalid_path: self.fall_back_on_invalid_path,
+        }
+    }
+}
+
+/// Like [`pretty_print()`], but print a fixed region into a buffer that you provide, reusing its
+/// memory. This is meant for programs that redraw many times a second, like terminal UIs: pass the
+/// same `lines` on every redraw, and the segment vectors of the lines already in it are reused for
+/// the new lines instead of being freed and allocated again.
+///
+/// The printed region contains the line with the focus, plus up to `rows_above` lines above it and
+/// `rows_below` lines below it. It replaces the contents of `lines`, from top to bottom. Returns
+/// the position of the focus: the index of its line within `lines`, and its column.
+#[allow(clippy::too_many_arguments)]
+pub fn pretty_print_into<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    rows_above: usize,
+    rows_below: usize,
+    root_style: Option<&D::Style>,
+    lines: &mut Vec<Line<'d, D>>,
+) -> Result<(usize, Col), PrintingError<D::Error>> {
+    span!("Pretty Print Into");
+
+    let mut printer = Printer::new(width)?;
+    printer.spare_segments = lines
+        .drain(..)
+        .map(|line| {
+            let mut segments = line.segments;
+            segments.clear();
+            segments
+        })
+        .collect();
+    printer.seek(doc, path, focus_target, root_style)?;
+
+    let num_left_segs = printer.next_blocks.last().unwrap().segments.len();
+    let focused_line = printer.print_next_line()?.unwrap();
+    let focus_col = focused_line.segments[..num_left_segs.min(focused_line.segments.len())]
+        .iter()
+        .map(|seg| seg.width)
+        .sum();
+
+    // The upward and downward printing don't interfere, so there's no need to `split()`.
+    for _ in 0..rows_above {
+        match printer.print_prev_line()? {
+            None => break,
+            Some(line) => lines.push(line),
+        }
+    }
+    lines.reverse();
+    let focus_row = lines.len();
+    lines.push(focused_line);
+    for _ in 0..rows_below {
+        match printer.print_next_line()? {
+            None => break,
+            Some(line) => lines.push(line),
+        }
+    }
+    Ok((focus_row, focus_col))
+}
+
+/// Construct a printer and seek to `origin`. If its path doesn't exist and the `options` say to,
+/// seek to the start of its nearest existing ancestor instead.
+fn seek_origin<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    origin: Origin,
+    root_style: Option<&D::Style>,
+    options: &PrintOptions<'d, D>,
+) -> Result<Printer<'d, D>, PrintingError<D::Error>> {
+    let mut printer = Printer::with_options(doc, width, options)?;
+    let path_fallback = if options.fall_back_on_invalid_path {
+        find_path_fallback(doc, &origin.path)?
+    } else {
+        None
+    };
+    match &path_fallback {
+        None => printer.seek(doc, &origin.path, origin.focus_target, root_style)?,
+        Some(fallback) => printer.seek(doc, &fallback.path, FocusTarget::Start, root_style)?,
+    }
+    printer.skip_lines(origin.skipped_lines)?;
+    printer.path_fallback = path_fallback;
+    printer.origin = Some(origin);
+    Ok(printer)
+}
+
+/// Split a `printer` that has seeked to the focus into the lines above it, the focused line, and
+/// the lines below it.
+fn print_around_focus<'d, D: PrettyDoc<'d>>(mut printer: Printer<'d, D>) -> PrintResult<'d, D> {
+    let num_left_segs = printer.next_blocks.last().unwrap().segments.len();
+    let start_id = printer.next_blocks.last().unwrap().start_id;
+    // Decorate the focused line here, so that the decorations go outside of the focus split.
+    // It's never collapsed, even if it's blank.
+    let line_decorator = printer.config.line_decorator.take();
+    let collapse_blank_lines = printer.config.collapse_blank_lines.take();
+    let mut line = printer.print_next_line()?.unwrap();
+    printer.config.line_decorator = line_decorator;
+    printer.config.collapse_blank_lines = collapse_blank_lines;
+    let focused_line_is_blank = line
+        .segments
+        .iter()
+        .all(|seg| seg.str.chars().all(char::is_whitespace));
+    let (prefix, suffix) = printer.decorations(0, start_id, &line.segments);
+    // If the focus was just after a soft space that ended the line, it was removed.
+    let num_left_segs = num_left_segs.min(line.segments.len());
+    let mut right_segments = line.segments.split_off(num_left_segs);
+    right_segments.extend(suffix);
+    let mut left_segments = prefix;
+    left_segments.extend(line.segments);
+    let focused_line = FocusedLine {
+        left_segments,
+        right_segments,
+    };
+
+    let (mut upward_printer, mut downward_printer) = printer.split();
+    if upward_printer.config.collapse_blank_lines.is_some() && focused_line_is_blank {
+        // The focused line continues the run of blank lines above it (if any).
+        downward_printer.blank_run = upward_printer.queue_prev_blocks()? + 1;
+    }
+    // When collapsing blank lines, `print_prev_line` fills the queue itself, a run at a time.
+    if upward_printer.config.collapse_blank_lines.is_none() {
+        upward_printer.queue_prev_blocks_eagerly(EAGER_PREV_LINES);
+    }
+    Ok((
+        UpwardPrinter(upward_printer),
+        focused_line,
+        DownwardPrinter(downward_printer),
+    ))
+}
+
+/// Like [`pretty_print()`], but produce [`ResolvedBlock`]s instead of [`Line`]s. Each block is one
+/// line, split into its indentation and its remaining segments, and labeled with the node whose
+/// [`Notation::Newline`] started it. This is for renderers that need to know how the lines were
+/// laid out, for example to cache lines per node or to implement sticky headers.
+///
+/// The [`PrintOptions::line_decorator`] and [`PrintOptions::collapse_blank_lines`] options don't
+/// apply to blocks, and are ignored.
+pub fn pretty_print_blocks<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    root_style: Option<&D::Style>,
+    options: &PrintOptions<'d, D>,
+) -> BlockPrintResult<'d, D> {
+    span!("Pretty Print Blocks");
+
+    let mut printer = Printer::with_options(doc, width, options)?;
+    printer.seek(doc, path, focus_target, root_style)?;
+
+    let num_left_segs = printer.next_blocks.last().unwrap().segments.len();
+    let block = printer.print_next_block()?.unwrap();
+    // If the focus was just after a soft space that ended the line, it was removed.
+    let num_left_segs = num_left_segs.min(block.indentation.len() + block.segments.len());
+    let focused_block = FocusedBlock {
+        focus_index: num_left_segs - block.indentation.len(),
+        block,
+    };
+
+    let (upward_printer, downward_printer) = printer.split();
+    Ok((
+        UpwardBlockPrinter(upward_printer),
+        focused_block,
+        DownwardBlockPrinter(downward_printer),
+    ))
+}
+
+/// Like [`pretty_print_with_options()`] with [`PrintOptions::fall_back_on_invalid_path`] set, but
+/// also return the [`PathFallback`] that says whether the path had to fall back to an ancestor.
+#[deprecated(
+    note = "use `PrintOptions::fall_back_on_invalid_path` and `DownwardPrinter::path_fallback()`"
+)]
+#[allow(clippy::type_complexity)]
+pub fn pretty_print_with_fallback<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    root_style: Option<&D::Style>,
+    options: &PrintOptions<'d, D>,
 ) -> Result<
     (
-        impl Iterator<Item = Result<Line<'d, D>, PrintingError<D::Error>>>,
+        UpwardPrinter<'d, D>,
         FocusedLine<'d, D>,
-        impl Iterator<Item = Result<Line<'d, D>, PrintingError<D::Error>>>,
+        DownwardPrinter<'d, D>,
+        Option<PathFallback>,
     ),
     PrintingError<D::Error>,
 > {
-    span!("Pretty Print");
+    let options = PrintOptions {
+        fall_back_on_invalid_path: true,
+        ..options.clone()
+    };
+    let (upward_printer, focused_line, downward_printer) =
+        pretty_print_with_options(doc, width, path, focus_target, root_style, &options)?;
+    let fallback = downward_printer.path_fallback().cloned();
+    Ok((upward_printer, focused_line, downward_printer, fallback))
+}
 
-    let mut printer = Printer::new(width)?;
-    printer.seek(doc, path, focus_target, root_style)?;
+/// Like [`pretty_print_with_options()`], but put the focus at the start of roughly the `line`'th
+/// line of the document (counting from 0), for example when the user drags a scrollbar. This
+/// finds the line with [`path_near_line()`](crate::path_near_line), which skips over the subtrees
+/// that have a [`PrettyDoc::approx_height_hint()`] instead of laying out every line above `line`.
+/// So the focus is only as close to `line` as those hints are accurate. If `line` is estimated to
+/// be past the end of the document, the focus is at the end of its last line.
+pub fn pretty_print_near_line<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    line: usize,
+    root_style: Option<&D::Style>,
+    options: &PrintOptions<'d, D>,
+) -> PrintResult<'d, D> {
+    span!("Pretty Print Near Line");
+
+    let (path, offset) = path_near_line(doc, line)?;
+    let origin = Origin {
+        path,
+        focus_target: FocusTarget::Start,
+        skipped_lines: offset,
+    };
+    let printer = seek_origin(doc, width, origin, root_style, options)?;
+    print_around_focus(printer)
+}
+
+/// Pretty print a document with several focus points at once, such as an editor's cursors. Each
+/// entry of `focuses` is a `path` and `focus_target`, with the same meaning as in
+/// [`pretty_print`]. The first entry is the _primary_ focus, which the printed region is centered
+/// on: it contains the line with the primary focus, plus up to `rows_above` lines above it and
+/// `rows_below` lines below it.
+///
+/// This seeks to the primary focus only once, and locates the other focuses while printing the
+/// region. The result reports, for every entry of `focuses`, its position relative to the primary
+/// focus, or `None` if it doesn't lie within the printed region. For [`FocusTarget::Mark`] and
+/// [`FocusTarget::NthMark`], the start of the node must also lie within the printed region (to
+/// count its marks), and for [`FocusTarget::LastMark`], the end of the node must.
+///
+/// # Panics
+///
+/// Panics if `focuses` is empty.
+pub fn pretty_print_multi<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    focuses: &[(&[usize], FocusTarget)],
+    rows_above: usize,
+    rows_below: usize,
+    root_style: Option<&D::Style>,
+) -> Result<MultiFocusPrint<'d, D>, PrintingError<D::Error>> {
+    pretty_print_multi_with_options(
+        doc,
+        width,
+        focuses,
+        rows_above,
+        rows_below,
+        root_style,
+        &PrintOptions::default(),
+    )
+}
+
+/// [`pretty_print_multi()`], with [`PrintOptions`].
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pretty_print_multi_with_options<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    focuses: &[(&[usize], FocusTarget)],
+    rows_above: usize,
+    rows_below: usize,
+    root_style: Option<&D::Style>,
+    options: &PrintOptions<'d, D>,
+) -> Result<MultiFocusPrint<'d, D>, PrintingError<D::Error>> {
+    span!("Pretty Print Multi");
+
+    let ((path, focus_target), secondary_focuses) = focuses
+        .split_first()
+        .expect("pretty_print_multi: no focuses");
+
+    let mut printer = Printer::with_options(doc, width, options)?;
+    for (secondary_path, secondary_target) in secondary_focuses {
+        let node = find_node(doc, secondary_path)?;
+        printer.targets.push(Target {
+            id: node.id()?,
+            focus_target: *secondary_target,
+            seen_text: false,
+        });
+    }
+    printer.seek(doc, path, *focus_target, root_style)?;
 
     let num_left_segs = printer.next_blocks.last().unwrap().segments.len();
     let mut line = printer.print_next_line()?.unwrap();
+    // If the focus was just after a soft space that ended the line, it was removed.
+    let num_left_segs = num_left_segs.min(line.segments.len());
     let focused_line = FocusedLine {
         right_segments: line.segments.split_off(num_left_segs),
         left_segments: line.segments,
     };
+    let mut landmarks = Vec::new();
+    landmarks.extend(
+        mem::take(&mut printer.landmarks)
+            .into_iter()
+            .map(|(target, landmark, col)| (target, 0, landmark, col)),
+    );
+
+    let (mut upward_printer, mut downward_printer) = printer.split();
+    let mut lines_above = Vec::new();
+    for row in 1..=rows_above {
+        match upward_printer.print_prev_line()? {
+            None => break,
+            Some(line) => lines_above.push(line),
+        }
+        landmarks.extend(
+            mem::take(&mut upward_printer.landmarks)
+                .into_iter()
+                .map(|(target, landmark, col)| (target, -(row as isize), landmark, col)),
+        );
+    }
+    lines_above.reverse();
+    let mut lines_below = Vec::new();
+    for row in 1..=rows_below {
+        match downward_printer.print_next_line()? {
+            None => break,
+            Some(line) => lines_below.push(line),
+        }
+        landmarks.extend(
+            mem::take(&mut downward_printer.landmarks)
+                .into_iter()
+                .map(|(target, landmark, col)| (target, row as isize, landmark, col)),
+        );
+    }
+    // Within a line, landmarks were found from left to right.
+    landmarks.sort_by_key(|(_, row, _, _)| *row);
+
+    let mut positions = vec![Some((0, focused_line.left_width()))];
+    for (i, target) in upward_printer.targets.iter().enumerate() {
+        let target_landmarks = landmarks
+            .iter()
+            .filter(|(t, _, _, _)| *t == i)
+            .map(|(_, row, landmark, col)| (*landmark, (*row, *col)))
+            .collect::<Vec<_>>();
+        positions.push(target.locate(&target_landmarks));
+    }
 
-    let upward_printer = UpwardPrinter(Printer {
-        width,
-        prev_blocks: printer.prev_blocks,
-        next_blocks: Vec::new(),
-    });
+    Ok(MultiFocusPrint {
+        lines_above,
+        focused_line,
+        lines_below,
+        positions,
+    })
+}
 
-    let downward_printer = DownwardPrinter(Printer {
-        width,
-        prev_blocks: Vec::new(),
-        next_blocks: printer.next_blocks,
-    });
+/// The printed region returned by [`pretty_print_multi`].
+pub struct MultiFocusPrint<'d, D: PrettyDoc<'d>> {
+    /// The lines above the focused line, from top to bottom.
+    pub lines_above: Vec<Line<'d, D>>,
+    /// The line containing the primary focus.
+    pub focused_line: FocusedLine<'d, D>,
+    /// The lines below the focused line, from top to bottom.
+    pub lines_below: Vec<Line<'d, D>>,
+    /// The position of each requested focus, in the same order as they were requested. Each
+    /// position is a row relative to the focused line (negative above it, positive below it), and
+    /// an absolute column. It's `None` if the focus isn't within the printed region.
+    pub positions: Vec<Option<(isize, Col)>>,
+}
+
+/// A [`Segment`] of printed text, and whether it's within a selection. Returned by
+/// [`MultiFocusPrint::into_selection()`].
+#[derive(Debug)]
+pub struct SelectionSegment<'d, D: PrettyDoc<'d>> {
+    pub segment: Segment<'d, D>,
+    pub selected: bool,
+}
+
+impl<'d, D: PrettyDoc<'d>> MultiFocusPrint<'d, D> {
+    /// Get all of the printed lines from top to bottom, with their segments marked as being inside
+    /// or outside of the selection that runs from focus `start` to focus `end`. These are indices
+    /// into the `focuses` passed to [`pretty_print_multi`], and `start` should not come after
+    /// `end` in the document. Segments that straddle either end of the selection are split in
+    /// two.
+    ///
+    /// If only one end of the selection is within the printed region, the selection extends to
+    /// the top or bottom of the region. If neither is, nothing is selected. (This can't happen if
+    /// either end is the primary focus.)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` or `end` is not a valid index into `positions`.
+    pub fn into_selection(self, start: usize, end: usize) -> Vec<Vec<SelectionSegment<'d, D>>> {
+        let first_row = -(self.lines_above.len() as isize);
+        let last_row = self.lines_below.len() as isize;
+        let (start, end) = match (self.positions[start], self.positions[end]) {
+            (Some(start), Some(end)) if end < start => (Some(end), Some(start)),
+            (Some(start), Some(end)) => (Some(start), Some(end)),
+            (None, Some(end)) => (Some((first_row, 0)), Some(end)),
+            (Some(start), None) => (Some(start), Some((last_row, Col::MAX))),
+            (None, None) => (None, None),
+        };
+
+        let focused_line = Line::from(self.focused_line);
+        let lines = self
+            .lines_above
+            .into_iter()
+            .chain(iter::once(focused_line))
+            .chain(self.lines_below);
+        let mut selection_lines = Vec::new();
+        for (line, row) in lines.zip(first_row..) {
+            let (lo, hi) = match (start, end) {
+                (Some((start_row, start_col)), Some((end_row, end_col)))
+                    if start_row <= row && row <= end_row =>
+                {
+                    let lo = if row == start_row { start_col } else { 0 };
+                    let hi = if row == end_row { end_col } else { Col::MAX };
+                    (lo, hi)
+                }
+                _ => (0, 0),
+            };
+            selection_lines.push(select_segments(line.segments, lo, hi));
+        }
+        selection_lines
+    }
+}
+
+/// Mark the segments that lie in the column range `lo..hi` as selected, splitting segments that
+/// straddle `lo` or `hi`.
+fn select_segments<'d, D: PrettyDoc<'d>>(
+    segments: Vec<Segment<'d, D>>,
+    lo: Col,
+    hi: Col,
+) -> Vec<SelectionSegment<'d, D>> {
+    let is_selected = |col: Col| lo <= col && col < hi;
+
+    let mut selection_segments = Vec::new();
+    let mut col = 0;
+    for mut segment in segments {
+        for boundary in [lo, hi] {
+            if col < boundary && boundary < col + segment.width {
+                let split = segment
+                    .split_at_width(boundary - col, SplitRounding::Floor)
+                    .unwrap();
+                let left_width = split.left.width;
+                selection_segments.push(SelectionSegment {
+                    segment: split.left,
+                    selected: is_selected(col),
+                });
+                col += left_width;
+                segment = split.right;
+            }
+        }
+        let width = segment.width;
+        selection_segments.push(SelectionSegment {
+            segment,
+            selected: is_selected(col),
+        });
+        col += width;
+    }
+    selection_segments
+}
+
+/// Print the entirety of the document to a single string, ignoring styles.
+///
+/// `width` is the desired line width. The algorithm will attempt to, but is not guaranteed to, find
+/// a layout that fits within that width.
+pub fn pretty_print_to_string<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+) -> Result<String, PrintingError<D::Error>> {
+    let (_, focused_line, lines_iter) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
+    let mut string = focused_line.to_string();
+    for line in lines_iter {
+        string.push('\n');
+        string.push_str(&line?.to_string());
+    }
+    Ok(string)
+}
+
+/// Print just the node at `path` to a string, as if it were the root of its own document: it
+/// starts at column 0 without its ancestors' indentation, and its layout is chosen afresh for
+/// `width` (so it isn't flattened just because an ancestor was). This is useful for copying a node,
+/// or for showing a preview of it.
+pub fn print_node_to_string<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+) -> Result<String, PrintingError<D::Error>> {
+    pretty_print_to_string(find_node(doc, path)?, width)
+}
+
+/// Print the entirety of the document to a single string, like [`pretty_print_to_string()`], but
+/// with trailing whitespace removed from every line (see [`Line::trim_trailing_whitespace()`]).
+/// Also returns the total width of whitespace that was removed.
+pub fn pretty_print_to_trimmed_string<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+) -> Result<(String, Width), PrintingError<D::Error>> {
+    let (_, focused_line, lines_iter) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
+    let mut string = String::new();
+    let mut trimmed = 0;
+    let lines = iter::once(Ok(Line::from(focused_line))).chain(lines_iter);
+    for (i, line) in lines.enumerate() {
+        if i > 0 {
+            string.push('\n');
+        }
+        let mut line = line?;
+        trimmed += line.trim_trailing_whitespace();
+        string.push_str(&line.to_string());
+    }
+    Ok((string, trimmed))
+}
+
+/// Print the entirety of the document to a single string, like [`pretty_print_to_string()`], but
+/// with the spaces at the start of each line's indentation replaced by tabs (see
+/// [`Line::with_tab_indentation()`]). The layout is chosen exactly as if every tab were
+/// `tab_width` spaces wide.
+pub fn pretty_print_to_tabbed_string<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    tab_width: Width,
+) -> Result<String, PrintingError<D::Error>> {
+    let (_, focused_line, lines_iter) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
+    let mut string = String::new();
+    let lines = iter::once(Ok(Line::from(focused_line))).chain(lines_iter);
+    for (i, line) in lines.enumerate() {
+        if i > 0 {
+            string.push('\n');
+        }
+        string.push_str(&line?.with_tab_indentation(tab_width).to_string());
+    }
+    Ok(string)
+}
+
+/// The style of a range of the string returned by [`pretty_print_to_annotated_string()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanAnnotation<S> {
+    /// The byte range of the string that the style applies to.
+    pub range: Range<usize>,
+    /// The style of the [`Segment`] that printed this range.
+    pub style: S,
+}
+
+/// Print the entirety of the document to a single string, like [`pretty_print_to_string()`], and
+/// also return the style of each part of it. There is one annotation per nonempty [`Segment`], in
+/// order. Annotations never overlap, and never include the newlines between lines.
+///
+/// `width` is the desired line width. The algorithm will attempt to, but is not guaranteed to, find
+/// a layout that fits within that width.
+#[allow(clippy::type_complexity)]
+pub fn pretty_print_to_annotated_string<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+) -> Result<(String, Vec<SpanAnnotation<D::Style>>), PrintingError<D::Error>> {
+    let (_, focused_line, lines_iter) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
+    let mut string = String::new();
+    let mut annotations = Vec::new();
+    let lines = iter::once(Ok(Line::from(focused_line))).chain(lines_iter);
+    for (i, line) in lines.enumerate() {
+        if i > 0 {
+            string.push('\n');
+        }
+        for segment in line?.segments {
+            if segment.str.is_empty() {
+                continue;
+            }
+            let start = string.len();
+            string.push_str(segment.str);
+            annotations.push(SpanAnnotation {
+                range: start..string.len(),
+                style: segment.style,
+            });
+        }
+    }
+    Ok((string, annotations))
+}
+
+/// Print just the lines around the start of the node at `path`, as a string without styles, for
+/// showing a snippet of a document (as in a compiler's error message). That's the line containing
+/// the start of the node, with `before` lines above it and `after` lines below it (or fewer, if
+/// the document runs out first). If the document continues past either end of the snippet, an
+/// `elision` line (such as `"⋯"`) is added there.
+///
+/// This only prints the lines it returns, so it's fast even for large documents.
+pub fn pretty_print_context<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    before: usize,
+    after: usize,
+    elision: &str,
+) -> Result<String, PrintingError<D::Error>> {
+    let (mut lines_above, focused_line, mut lines_below) =
+        pretty_print(doc, width, path, FocusTarget::Start, None)?;
+    let mut lines = Vec::new();
+    for line in lines_above.by_ref().take(before) {
+        lines.push(line?.to_string());
+    }
+    if lines_above.next().transpose()?.is_some() {
+        lines.push(elision.to_owned());
+    }
+    lines.reverse();
+    lines.push(focused_line.to_string());
+    for line in lines_below.by_ref().take(after) {
+        lines.push(line?.to_string());
+    }
+    if lines_below.next().transpose()?.is_some() {
+        lines.push(elision.to_owned());
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Counters describing how much work the printer did, from [`DownwardPrinter::stats()`]. Useful
+/// for finding documents whose notations make printing slow.
+///
+/// Each time the printer has to choose between the two options of a [`Notation::Choice`], it
+/// checks whether the first line of the first option (followed by whatever comes after it) fits.
+/// This can require looking far ahead through the rest of the document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrintStats {
+    /// How many times the printer checked whether a choice fits.
+    pub fits_calls: usize,
+    /// How many notations were examined while checking whether choices fit, in total.
+    pub fits_steps: usize,
+    /// How many chunks of the document after a choice were examined while checking whether it
+    /// fits, in total.
+    pub lookahead_chunks: usize,
+    /// The most chunks after a choice that were examined while checking whether it fits.
+    pub max_lookahead_chunks: usize,
+    /// How many times checking whether a choice fits was cut off by the lookahead limit.
+    pub lookahead_cutoffs: usize,
+}
+
+/// Print the entire document from the top, like [`pretty_print_to_string()`] but keeping styles,
+/// and also return [`PrintStats`] about how much work it took.
+#[deprecated(note = "use `pretty_print_with_options()` and `DownwardPrinter::stats()`")]
+#[allow(clippy::type_complexity)]
+pub fn pretty_print_with_stats<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    options: &PrintOptions<'d, D>,
+) -> Result<(Vec<Line<'d, D>>, PrintStats), PrintingError<D::Error>> {
+    let (_, focused_line, mut downward_printer) =
+        pretty_print_with_options(doc, width, &[], FocusTarget::Start, None, options)?;
+    let mut lines = vec![Line::from(focused_line)];
+    for line in downward_printer.by_ref() {
+        lines.push(line?);
+    }
+    Ok((lines, downward_printer.stats()))
+}
+
+/// Compute a hash of the layout of the entire document: which option the printer picks for each
+/// [`Notation::Choice`] it resolves, in order. The text itself is not hashed. This is a cheap way
+/// to check whether a change to a notation or document altered its layout, without comparing the
+/// whole printed output.
+///
+/// The fingerprint is deterministic: it's the same across runs, platforms, and versions of Rust.
+pub fn layout_fingerprint<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+) -> Result<u64, PrintingError<D::Error>> {
+    span!("Layout Fingerprint");
+
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+    let mut printer = Printer::new(width)?;
+    printer.choice_hash = Some(FNV_OFFSET_BASIS);
+    printer.seek(doc, &[], FocusTarget::Start, None)?;
+    while printer.print_next_block()?.is_some() {}
+    Ok(printer.choice_hash.unwrap())
+}
+
+/// Measurements of how well a document's layout uses the available width, from
+/// [`analyze_layout()`]. Useful for comparing notation designs, and for catching regressions in
+/// layout quality.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutMetrics {
+    /// The number of lines in the printed document.
+    pub num_lines: usize,
+    /// How many lines have each width: `width_counts[w]` is the number of lines that are `w`
+    /// columns wide. Its length is one more than the width of the widest line.
+    pub width_counts: Vec<usize>,
+    /// The number of lines that are wider than the printing width.
+    pub overflowing_lines: usize,
+    /// The width of the widest line.
+    pub max_line_width: Width,
+    /// The average over all lines of the line's width divided by the printing width (or `0.0` if
+    /// the printing width is 0). Lines that overflow count as more than `1.0`.
+    pub average_fill: f64,
+}
+
+/// Print the entire document at the given `width`, and measure its layout. See [`LayoutMetrics`].
+pub fn analyze_layout<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+) -> Result<LayoutMetrics, PrintingError<D::Error>> {
+    span!("Analyze Layout");
+
+    let mut printer = Printer::new(width)?;
+    printer.seek(doc, &[], FocusTarget::Start, None)?;
+    let mut width_counts = Vec::new();
+    let mut num_lines = 0;
+    let mut overflowing_lines = 0;
+    let mut total_width = 0;
+    while let Some(line) = printer.print_next_line()? {
+        let line_width = line.width();
+        if width_counts.len() <= line_width as usize {
+            width_counts.resize(line_width as usize + 1, 0);
+        }
+        width_counts[line_width as usize] += 1;
+        num_lines += 1;
+        if line_width > width {
+            overflowing_lines += 1;
+        }
+        total_width += line_width as u64;
+    }
+    let average_fill = if width == 0 || num_lines == 0 {
+        0.0
+    } else {
+        total_width as f64 / (num_lines as f64 * width as f64)
+    };
+    Ok(LayoutMetrics {
+        num_lines,
+        max_line_width: width_counts.len().saturating_sub(1) as Width,
+        width_counts,
+        overflowing_lines,
+        average_fill,
+    })
+}
+
+/// Seek to the focus like [`pretty_print()`], and take a [`DebugSnapshot`] of the printer's state
+/// right after, before any more lines are printed. Useful when a layout goes wrong, to see how the
+/// printer has split the document into lines around the focus.
+pub fn debug_snapshot<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    path: &[usize],
+    focus_target: FocusTarget,
+    root_style: Option<&D::Style>,
+) -> Result<DebugSnapshot, PrintingError<D::Error>> {
+    span!("Debug Snapshot");
+
+    let mut printer = Printer::new(width)?;
+    printer.seek(doc, path, focus_target, root_style)?;
+    Ok(printer.snapshot())
+}
+
+/// Determine whether the first line of `doc` could fit in the `width`, after a prefix that's
+/// `prefix_width` columns wide, without running a full print. This is the same check the printer
+/// does when it picks between the options of a [`Notation::Choice`], so it's optimistic about the
+/// choices inside `doc`: it's true if _some_ layout of `doc` has a first line that fits. If
+/// `at_eol` is true, the prefix ends with a [`Notation::EndOfLine`], so the first line only fits if
+/// it has no text at all.
+pub fn fits_first_line<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    prefix_width: Width,
+    at_eol: bool,
+) -> Result<bool, PrintingError<D::Error>> {
+    span!("Fits First Line");
+
+    let notation = DelayedConsolidatedNotation::new(doc)?.eval()?;
+    let mut block = Block::new(None, None, Vec::new(), Vec::new());
+    block.at_eol = at_eol;
+    fits(
+        width.saturating_sub(prefix_width),
+        &block,
+        notation,
+        &LayoutCache::new(),
+        None,
+        &mut PrintStats::default(),
+    )
+}
+
+/// A piece of printed text that was styled with a [`Notation::Style`], for use as a semantic token
+/// by editors and language servers. Its position is measured in columns, not bytes or UTF-16 code
+/// units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SemanticToken<L> {
+    /// The line the token is on, where the document's first line is row 0.
+    pub row: Row,
+    /// The column the token starts at.
+    pub col: Col,
+    /// The width of the token in columns.
+    pub length: Width,
+    /// The label of the innermost [`Notation::Style`] that the token is inside of.
+    pub token_type: L,
+}
+
+/// Print the given `rows` of the document, and return a [`SemanticToken`] for each nonempty
+/// [`Segment`] in them that has a style label. Tokens are ordered by position. Adjacent segments
+/// with the same label are not merged.
+///
+/// `width` is the desired line width. The algorithm will attempt to, but is not guaranteed to, find
+/// a layout that fits within that width.
+pub fn semantic_tokens<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    width: Width,
+    rows: Range<Row>,
+) -> Result<Vec<SemanticToken<D::StyleLabel>>, PrintingError<D::Error>> {
+    let (_, focused_line, lines_iter) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
+    let mut tokens = Vec::new();
+    let lines = iter::once(Ok(Line::from(focused_line))).chain(lines_iter);
+    for (row, line) in (0..).zip(lines).take(rows.end as usize) {
+        let line = line?;
+        if row >= rows.start {
+            tokens.extend(line.semantic_tokens(row));
+        }
+    }
+    Ok(tokens)
+}
+
+struct Chunk<'d, D: PrettyDoc<'d>> {
+    notation: ConsolidatedNotation<'d, D>,
+    id: D::Id,
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for Chunk<'d, D> {
+    fn clone(&self) -> Self {
+        Chunk {
+            id: self.id,
+            notation: self.notation.clone(),
+        }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Chunk<'d, D> {
+    fn new(notation: DelayedConsolidatedNotation<'d, D>) -> Result<Self, PrintingError<D::Error>> {
+        Ok(Chunk {
+            id: notation.doc().id()?,
+            notation: notation.eval()?,
+        })
+    }
+}
+
+/// Where to seek to, when calling [`pretty_print`], relative to the node at its `path` argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum FocusTarget {
+    /// Focus on the position just before the node.
+    Start,
+    /// Focus on the position just after the node.
+    End,
+    /// Focus on the first [`Notation::FocusMark`] in the node's notation (not including its childrens'
+    /// notations).
+    Mark,
+    /// Focus on the n'th [`Notation::FocusMark`] (counting from 0) in the node's notation (not
+    /// including its childrens' notations). `NthMark(0)` is the same as `Mark`.
+    NthMark(usize),
+    /// Focus on the last [`Notation::FocusMark`] in the node's notation (not including its
+    /// childrens' notations).
+    LastMark,
+    /// Focus before the n'th character in the node's text.
+    Text(usize),
+    /// Focus after the last character in the node's text.
+    TextEnd,
+    /// Focus before the given position in the node's text, counted in the given units. A position
+    /// in the middle of a character (or grapheme) focuses before it. `TextOffset(n,
+    /// OffsetKind::Chars)` is the same as `Text(n)`.
+    TextOffset(usize, OffsetKind),
+}
+
+impl FocusTarget {
+    /// The position in the node's text that this targets, if any, and how it's counted.
+    fn text_offset(self) -> Option<(usize, OffsetKind)> {
+        match self {
+            FocusTarget::Text(pos) => Some((pos, OffsetKind::Chars)),
+            FocusTarget::TextEnd => Some((usize::MAX, OffsetKind::Chars)),
+            FocusTarget::TextOffset(pos, kind) => Some((pos, kind)),
+            _ => None,
+        }
+    }
+}
+
+/// How to count positions in text, for [`FocusTarget::TextOffset`]. Different hosts count them
+/// differently: for example, language servers often use UTF-16 code units, while Rust strings use
+/// bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub enum OffsetKind {
+    /// Bytes of UTF-8.
+    Bytes,
+    /// Unicode code points (Rust `char`s), like [`FocusTarget::Text`].
+    Chars,
+    /// UTF-16 code units, as used by JavaScript.
+    Utf16,
+    /// Extended grapheme clusters, as defined by Unicode: what a user thinks of as a single
+    /// character, like a letter with combining accents, an emoji ZWJ sequence, or a flag.
+    Graphemes,
+}
+
+impl OffsetKind {
+    /// Convert `offset`, counted in this kind of unit, into the number of chars before it in
+    /// `text`. An offset in the middle of a character (or grapheme) is rounded down to its start,
+    /// and an offset past the end of the text is rounded down to the end.
+    pub fn to_char_offset(self, text: &str, offset: usize) -> usize {
+        if self == OffsetKind::Graphemes {
+            return text
+                .graphemes(true)
+                .take(offset)
+                .map(|grapheme| grapheme.chars().count())
+                .sum();
+        }
+        let mut units = 0;
+        for (char_offset, ch) in text.chars().enumerate() {
+            let len = self.char_len(ch);
+            if units + len > offset {
+                return char_offset;
+            }
+            units += len;
+        }
+        text.chars().count()
+    }
+
+    /// Convert a `char_offset` into `text` into this kind of unit. This is the inverse of
+    /// [`OffsetKind::to_char_offset()`], for reporting positions to a host.
+    pub fn from_char_offset(self, text: &str, char_offset: usize) -> usize {
+        if self == OffsetKind::Graphemes {
+            let mut chars_before = 0;
+            return text
+                .graphemes(true)
+                .take_while(|grapheme| {
+                    let starts_before = chars_before < char_offset;
+                    chars_before += grapheme.chars().count();
+                    starts_before
+                })
+                .count();
+        }
+        text.chars()
+            .take(char_offset)
+            .map(|ch| self.char_len(ch))
+            .sum()
+    }
+
+    /// The length of `ch` in this kind of unit. Not used for graphemes, which can span several
+    /// chars.
+    fn char_len(self, ch: char) -> usize {
+        match self {
+            OffsetKind::Bytes => ch.len_utf8(),
+            OffsetKind::Chars => 1,
+            OffsetKind::Utf16 => ch.len_utf16(),
+            OffsetKind::Graphemes => unreachable!("graphemes are counted by the caller"),
+        }
+    }
+}
+
+/// What to do if a [`PrettyDoc`] method returns an error while the printer is accessing a child
+/// node. See [`PrintOptions::on_error`].
+#[derive(Debug, Clone)]
+pub enum ErrorPolicy<S> {
+    /// Stop printing, and return the error.
+    Fail,
+    /// Display [`ERROR_PLACEHOLDER`] in place of the child, in this style (combined with the style
+    /// of the surrounding text), and keep printing. If the child was in a [`Notation::Fold`], the
+    /// placeholder may stand in for several of its children. A [`Notation::Check`] whose condition
+    /// can't be checked takes its second branch.
+    Substitute(S),
+}
+
+/// The text displayed in place of a child node that couldn't be accessed, when using
+/// [`ErrorPolicy::Substitute`].
+pub const ERROR_PLACEHOLDER: &str = "<error>";
+
+/// How many lines above the focus to finish right after seeking, while the blocks they're in are
+/// still small, so that the [`UpwardPrinter`] has them ready.
+const EAGER_PREV_LINES: usize = 16;
+
+impl<S> ErrorPolicy<S> {
+    fn into_error_style(self) -> Option<Rc<S>> {
+        match self {
+            ErrorPolicy::Fail => None,
+            ErrorPolicy::Substitute(style) => Some(Rc::new(style)),
+        }
+    }
+}
+
+/// The contents of a single pretty-printed line.
+pub struct Line<'d, D: PrettyDoc<'d>> {
+    /// A sequence of pieces of text to be displayed in order from left to right, with no spacing in
+    /// between.
+    pub segments: Vec<Segment<'d, D>>,
+}
+
+/// The contents of the pretty-printed line that contains the focus point.
+pub struct FocusedLine<'d, D: PrettyDoc<'d>> {
+    /// Pieces of text that appear before the focus point.
+    pub left_segments: Vec<Segment<'d, D>>,
+    /// Pieces of text that appear after the focus point.
+    pub right_segments: Vec<Segment<'d, D>>,
+}
+
+/// A single pretty-printed line, along with how it was laid out. Returned by
+/// [`pretty_print_blocks()`].
+pub struct ResolvedBlock<'d, D: PrettyDoc<'d>> {
+    /// The id of the document node whose notation contains the [`Notation::Newline`] that started
+    /// this line, or `None` for the first line of the document.
+    pub start_id: Option<D::Id>,
+    /// The indentation at the start of the line, with one segment per enclosing
+    /// [`Notation::Indent`], from outermost to innermost.
+    pub indentation: Vec<Segment<'d, D>>,
+    /// The rest of the line, after its indentation.
+    pub segments: Vec<Segment<'d, D>>,
+}
+
+/// The [`ResolvedBlock`] that contains the focus point.
+pub struct FocusedBlock<'d, D: PrettyDoc<'d>> {
+    pub block: ResolvedBlock<'d, D>,
+    /// How many of the block's `segments` (not counting its `indentation`) appear before the focus
+    /// point.
+    pub focus_index: usize,
+}
+
+impl<'d, D: PrettyDoc<'d>> ResolvedBlock<'d, D> {
+    /// The total width of the indentation.
+    pub fn indent_width(&self) -> Width {
+        self.indentation.iter().map(|seg| seg.width).sum()
+    }
+
+    /// The total width of the line, including its indentation.
+    pub fn width(&self) -> Width {
+        self.indent_width() + self.segments.iter().map(|seg| seg.width).sum::<Width>()
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> From<ResolvedBlock<'d, D>> for Line<'d, D> {
+    fn from(block: ResolvedBlock<'d, D>) -> Line<'d, D> {
+        let mut segments = block.indentation;
+        segments.extend(block.segments);
+        Line { segments }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> From<FocusedBlock<'d, D>> for FocusedLine<'d, D> {
+    fn from(focused_block: FocusedBlock<'d, D>) -> FocusedLine<'d, D> {
+        let num_left_segs = focused_block.block.indentation.len() + focused_block.focus_index;
+        let mut left_segments = Line::from(focused_block.block).segments;
+        let right_segments = left_segments.split_off(num_left_segs);
+        FocusedLine {
+            left_segments,
+            right_segments,
+        }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Line<'d, D> {
+    pub fn width(&self) -> Width {
+        self.segments.iter().map(|seg| seg.width).sum()
+    }
+
+    /// Split this line in two at the given column. Every segment keeps its style, and a segment
+    /// that spans the split column is divided using [`Segment::split_at_width()`]. If `col` is
+    /// past the end of the line, the right half is empty.
+    ///
+    /// Returns `None` if `rounding` is [`SplitRounding::Exact`] and `col` falls in the middle of a
+    /// full-width character.
+    pub fn split_at_width(self, col: Col, rounding: SplitRounding) -> Option<Split<Self>> {
+        let mut left_segments = Vec::new();
+        let mut right_segments = Vec::new();
+        let mut straddler = None;
+        let mut remaining = col;
+        let mut segments = self.segments.into_iter();
+        for segment in segments.by_ref() {
+            if segment.width <= remaining {
+                remaining -= segment.width;
+                left_segments.push(segment);
+            } else {
+                let split = segment.split_at_width(remaining, rounding)?;
+                straddler = split.straddler;
+                if !split.left.str.is_empty() {
+                    left_segments.push(split.left);
+                }
+                right_segments.push(split.right);
+                break;
+            }
+        }
+        right_segments.extend(segments);
+        Some(Split {
+            left: Line {
+                segments: left_segments,
+            },
+            right: Line {
+                segments: right_segments,
+            },
+            straddler,
+        })
+    }
+
+    /// Make the spaces in this line's indentation, and any trailing spaces at the end of it,
+    /// visible by replacing each of them with a `·`. The replacements are styled by combining
+    /// their segment's style with `whitespace_style`.
+    pub fn with_visible_whitespace(self, whitespace_style: &D::Style) -> Line<'d, D> {
+        use crate::Style;
+
+        // Everything at or past this (segment index, byte index) is trailing space.
+        let mut trailing_start = (0, 0);
+        for (i, segment) in self.segments.iter().enumerate() {
+            if let Some((j, ch)) = segment.str.char_indices().rfind(|(_, ch)| *ch != ' ') {
+                trailing_start = (i, j + ch.len_utf8());
+            }
+        }
+
+        let mut segments = Vec::new();
+        for (i, segment) in self.segments.into_iter().enumerate() {
+            let visible_style = Style::combine(&segment.style, whitespace_style);
+            let is_visible = |j: usize, ch: char| {
+                ch == ' ' && (segment.is_indentation() || (i, j) >= trailing_start)
+            };
+            let mut run_start = 0;
+            let mut run_is_visible = false;
+            let mut chars = segment.str.char_indices().peekable();
+            while let Some((j, ch)) = chars.next() {
+                if j == run_start {
+                    run_is_visible = is_visible(j, ch);
+                }
+                let run_end = match chars.peek() {
+                    Some((k, next_ch)) if is_visible(*k, *next_ch) == run_is_visible => continue,
+                    Some((k, _)) => *k,
+                    None => segment.str.len(),
+                };
+                let run = &segment.str[run_start..run_end];
+                if run_is_visible {
+                    for dots in visible_spaces(run.len()) {
+                        segments.push(Segment {
+                            str: dots,
+                            width: str_width(dots),
+                            style: visible_style.clone(),
+                            kind: segment.kind,
+                            direction: Direction::Neutral,
+                            link: segment.link,
+                            style_label: segment.style_label,
+                        });
+                    }
+                } else {
+                    segments.push(Segment {
+                        str: run,
+                        width: str_width(run),
+                        style: segment.style.clone(),
+                        kind: segment.kind,
+                        direction: Direction::of_str(run),
+                        link: segment.link,
+                        style_label: segment.style_label,
+                    });
+                }
+                run_start = run_end;
+            }
+        }
+        Line { segments }
+    }
+
+    /// Replace the spaces at the start of this line's indentation with one `\t` per `tab_width`
+    /// columns, followed by any leftover spaces. Each tab segment's `width` is the number of
+    /// columns it stands for, so the line's width doesn't change. Indentation that isn't made of
+    /// spaces (like a `// ` prefix) is kept as is, and so is everything after it.
+    ///
+    /// If `tab_width` is 0, the line is unchanged.
+    pub fn with_tab_indentation(self, tab_width: Width) -> Line<'d, D> {
+        if tab_width == 0 {
+            return self;
+        }
+
+        let mut segments = Vec::new();
+        // The total width of the leading spaces seen so far, and how much of it has been emitted.
+        let mut spaces_width = 0;
+        let mut emitted_width = 0;
+        let mut remaining = self.segments.into_iter().peekable();
+        while let Some(segment) = remaining.next_if(|seg| seg.is_indentation()) {
+            let rest = segment.str.trim_start_matches(' ');
+            spaces_width += (segment.str.len() - rest.len()) as Width;
+            let num_tabs = (spaces_width - emitted_width) / tab_width;
+            emitted_width += num_tabs * tab_width;
+            for tabs in repeat_static("\t\t\t\t\t\t\t\t", num_tabs as usize) {
+                segments.push(Segment {
+                    str: tabs,
+                    width: tabs.len() as Width * tab_width,
+                    style: segment.style.clone(),
+                    kind: segment.kind,
+                    direction: Direction::Neutral,
+                    link: segment.link,
+                    style_label: segment.style_label,
+                });
+            }
+            let is_last =
+                !rest.is_empty() || !remaining.peek().is_some_and(|seg| seg.is_indentation());
+            if is_last {
+                push_spaces(&mut segments, &segment, spaces_width - emitted_width);
+            }
+            if !rest.is_empty() {
+                segments.push(Segment {
+                    str: rest,
+                    width: str_width(rest),
+                    direction: Direction::of_str(rest),
+                    ..segment
+                });
+                break;
+            }
+        }
+        segments.extend(remaining);
+        Line { segments }
+    }
+
+    /// Style the words in this line that differ from the `old` version of it, for highlighting
+    /// what changed in a diff. Each line is split into words, runs of whitespace, and single
+    /// punctuation characters, and the ones that aren't part of a longest common subsequence with
+    /// `old` are restyled by combining their segment's style with `changed_style`. Segments are
+    /// split where a change starts or ends. Indentation is never restyled.
+    pub fn highlight_changes(self, old: &Line<'d, D>, changed_style: &D::Style) -> Line<'d, D> {
+        use crate::Style;
+
+        let old_text = old.segments.iter().map(|seg| seg.str).collect::<String>();
+        let new_text = self.segments.iter().map(|seg| seg.str).collect::<String>();
+        let changed = changed_words(&old_text, &new_text);
+        let is_changed = |pos: usize| changed.iter().any(|range| range.contains(&pos));
+
+        let mut segments = Vec::new();
+        let mut offset = 0;
+        for segment in self.segments {
+            let seg_offset = offset;
+            offset += segment.str.len();
+            if segment.is_indentation() {
+                segments.push(segment);
+                continue;
+            }
+            let mut run_start = 0;
+            let mut chars = segment.str.char_indices().peekable();
+            while chars.next().is_some() {
+                let run_is_changed = is_changed(seg_offset + run_start);
+                let run_end = match chars.peek() {
+                    Some((k, _)) if is_changed(seg_offset + *k) == run_is_changed => continue,
+                    Some((k, _)) => *k,
+                    None => segment.str.len(),
+                };
+                let run = &segment.str[run_start..run_end];
+                segments.push(Segment {
+                    str: run,
+                    width: str_width(run),
+                    style: if run_is_changed {
+                        Style::combine(&segment.style, changed_style)
+                    } else {
+                        segment.style.clone()
+                    },
+                    kind: segment.kind,
+                    direction: Direction::of_str(run),
+                    link: segment.link,
+                    style_label: segment.style_label,
+                });
+                run_start = run_end;
+            }
+        }
+        Line { segments }
+    }
 
-    Ok((upward_printer, focused_line, downward_printer))
-}
+    /// Remove any whitespace from the end of this line, including indentation on a line that has
+    /// nothing else. Segments that become empty are removed. Returns the width that was trimmed.
+    pub fn trim_trailing_whitespace(&mut self) -> Width {
+        let mut trimmed = 0;
+        while let Some(segment) = self.segments.last_mut() {
+            let str = segment.str.trim_end();
+            trimmed += segment.width - str_width(str);
+            if str.is_empty() {
+                self.segments.pop();
+            } else {
+                segment.str = str;
+                segment.width = str_width(str);
+                segment.direction = Direction::of_str(str);
+                break;
+            }
+        }
+        trimmed
+    }
 
-/// Print the entirety of the document to a single string, ignoring styles.
-///
-/// `width` is the desired line width. The algorithm will attempt to, but is not guaranteed to, find
-/// a layout that fits within that width.
-pub fn pretty_print_to_string<'d, D: PrettyDoc<'d>>(
-    doc: D,
-    width: Width,
-) -> Result<String, PrintingError<D::Error>> {
-    let (_, focused_line, lines_iter) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
-    let mut string = focused_line.to_string();
-    for line in lines_iter {
-        string.push('\n');
-        string.push_str(&line?.to_string());
+    /// A [`SemanticToken`] for each nonempty segment of this line that has a style label,
+    /// assuming that this line is at the given `row`.
+    pub fn semantic_tokens(&self, row: Row) -> Vec<SemanticToken<D::StyleLabel>> {
+        let mut tokens = Vec::new();
+        let mut col = 0;
+        for segment in &self.segments {
+            if let Some(label) = segment.style_label {
+                if segment.width > 0 {
+                    tokens.push(SemanticToken {
+                        row,
+                        col,
+                        length: segment.width,
+                        token_type: label.clone(),
+                    });
+                }
+            }
+            col += segment.width;
+        }
+        tokens
     }
-    Ok(string)
-}
 
-struct Chunk<'d, D: PrettyDoc<'d>> {
-    notation: ConsolidatedNotation<'d, D>,
-    id: D::Id,
-}
+    /// Reorder this line's segments from logical order (the order they were printed in) into
+    /// visual order (the order they should be displayed in, from left to right), so that
+    /// right-to-left text like Hebrew or Arabic displays correctly. Right-to-left runs are split
+    /// into one segment per character (keeping combining marks with the character they modify),
+    /// and then reversed. Each segment's `direction` is set to its resolved direction.
+    ///
+    /// This is a simplified version of the Unicode Bidirectional Algorithm, which assumes that the
+    /// line as a whole is left-to-right, and which does not mirror brackets. The line's width is
+    /// unchanged.
+    pub fn into_visual_order(self) -> Line<'d, D> {
+        let directions =
+            bidi::resolve_directions(self.segments.iter().flat_map(|seg| seg.str.chars()));
+        let mut directions = directions.into_iter();
+
+        let mut segments: Vec<Segment<'d, D>> = Vec::new();
+        for segment in self.segments {
+            let mut pieces = Vec::new();
+            for (j, ch) in segment.str.char_indices() {
+                let dir = directions.next().unwrap();
+                match pieces.last_mut() {
+                    Some((_, prev_dir)) if *prev_dir == dir && dir == Direction::Ltr => (),
+                    Some((_, prev_dir)) if *prev_dir == dir && char_width(ch) == 0 => (),
+                    _ => pieces.push((j, dir)),
+                }
+            }
+            for (i, (start, dir)) in pieces.iter().enumerate() {
+                let end = pieces
+                    .get(i + 1)
+                    .map(|(end, _)| *end)
+                    .unwrap_or(segment.str.len());
+                let str = &segment.str[*start..end];
+                segments.push(Segment {
+                    str,
+                    width: str_width(str),
+                    style: segment.style.clone(),
+                    kind: segment.kind,
+                    direction: *dir,
+                    link: segment.link,
+                    style_label: segment.style_label,
+                });
+            }
+        }
 
-impl<'d, D: PrettyDoc<'d>> Clone for Chunk<'d, D> {
-    fn clone(&self) -> Self {
-        Chunk {
-            id: self.id,
-            notation: self.notation.clone(),
+        let mut i = 0;
+        while i < segments.len() {
+            let run_len = segments[i..]
+                .iter()
+                .take_while(|seg| seg.direction == Direction::Rtl)
+                .count();
+            segments[i..i + run_len].reverse();
+            i += run_len.max(1);
         }
+        Line { segments }
     }
 }
 
-impl<'d, D: PrettyDoc<'d>> Chunk<'d, D> {
-    fn new(notation: DelayedConsolidatedNotation<'d, D>) -> Result<Self, PrintingError<D::Error>> {
-        Ok(Chunk {
-            id: notation.doc().id()?,
-            notation: notation.eval()?,
-        })
+/// Strings of `·`s that add up to `len` of them.
+/// Push segments of `len` spaces, in the style of `like`.
+fn push_spaces<'d, D: PrettyDoc<'d>>(
+    segments: &mut Vec<Segment<'d, D>>,
+    like: &Segment<'d, D>,
+    len: Width,
+) {
+    for spaces in repeat_static("        ", len as usize) {
+        segments.push(Segment {
+            str: spaces,
+            width: str_width(spaces),
+            style: like.style.clone(),
+            kind: like.kind,
+            direction: Direction::Neutral,
+            link: like.link,
+            style_label: like.style_label,
+        });
     }
 }
 
-/// Where to seek to, when calling [`pretty_print`], relative to the node at its `path` argument.
-#[derive(Debug, Clone, Copy)]
-pub enum FocusTarget {
-    /// Focus on the position just before the node.
-    Start,
-    /// Focus on the position just after the node.
-    End,
-    /// Focus on the first [`Notation::FocusMark`] in the node's notation (not including its childrens'
-    /// notations).
-    Mark,
-    /// Focus before the n'th character in the node's text.
-    Text(usize),
+/// Split `len` repetitions of the ASCII character in `chunk` into slices of `chunk`.
+fn repeat_static(chunk: &'static str, mut len: usize) -> impl Iterator<Item = &'static str> {
+    iter::from_fn(move || {
+        if len == 0 {
+            return None;
+        }
+        let n = len.min(chunk.len());
+        len -= n;
+        Some(&chunk[..n])
+    })
 }
 
-/// The contents of a single pretty-printed line.
-pub struct Line<'d, D: PrettyDoc<'d>> {
-    /// A sequence of pieces of text to be displayed in order from left to right, with no spacing in
-    /// between.
-    pub segments: Vec<Segment<'d, D>>,
+fn visible_spaces(mut len: usize) -> impl Iterator<Item = &'static str> {
+    const DOTS: &str = "················································";
+    const DOTS_LEN: usize = 48;
+
+    iter::from_fn(move || {
+        if len == 0 {
+            return None;
+        }
+        let n = len.min(DOTS_LEN);
+        len -= n;
+        Some(&DOTS[..n * '·'.len_utf8()])
+    })
 }
 
-/// The contents of the pretty-printed line that contains the focus point.
-pub struct FocusedLine<'d, D: PrettyDoc<'d>> {
-    /// Pieces of text that appear before the focus point.
-    pub left_segments: Vec<Segment<'d, D>>,
-    /// Pieces of text that appear after the focus point.
-    pub right_segments: Vec<Segment<'d, D>>,
+/// The byte ranges of the words of `new` that aren't in a longest common subsequence of the words
+/// of `old` and `new`.
+fn changed_words(old: &str, new: &str) -> Vec<Range<usize>> {
+    let old_words = split_words(old)
+        .into_iter()
+        .map(|range| &old[range])
+        .collect::<Vec<_>>();
+    let new_ranges = split_words(new);
+    let new_words = new_ranges
+        .iter()
+        .map(|range| &new[range.clone()])
+        .collect::<Vec<_>>();
+
+    // lcs[i][j] is the length of the longest common subsequence of old_words[i..] and
+    // new_words[j..].
+    let mut lcs = vec![vec![0; new_words.len() + 1]; old_words.len() + 1];
+    for i in (0..old_words.len()).rev() {
+        for j in (0..new_words.len()).rev() {
+            lcs[i][j] = if old_words[i] == new_words[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changed = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while j < new_words.len() {
+        if i < old_words.len() && old_words[i] == new_words[j] {
+            i += 1;
+            j += 1;
+        } else if i < old_words.len() && lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            changed.push(new_ranges[j].clone());
+            j += 1;
+        }
+    }
+    changed
 }
 
-impl<'d, D: PrettyDoc<'d>> Line<'d, D> {
-    pub fn width(&self) -> Width {
-        self.segments.iter().map(|seg| seg.width).sum()
+/// Split `text` into words (runs of alphanumeric characters and `_`), runs of whitespace, and
+/// single other characters, returning their byte ranges.
+fn split_words(text: &str) -> Vec<Range<usize>> {
+    let class = |ch: char| {
+        if ch.is_alphanumeric() || ch == '_' {
+            Some(0)
+        } else if ch.is_whitespace() {
+            Some(1)
+        } else {
+            None
+        }
+    };
+    let mut words: Vec<Range<usize>> = Vec::new();
+    let mut prev_class = None;
+    for (i, ch) in text.char_indices() {
+        let ch_class = class(ch);
+        match words.last_mut() {
+            Some(word) if ch_class.is_some() && ch_class == prev_class => {
+                word.end = i + ch.len_utf8()
+            }
+            _ => words.push(i..i + ch.len_utf8()),
+        }
+        prev_class = ch_class;
     }
+    words
 }
 
 impl<'d, D: PrettyDoc<'d>> FocusedLine<'d, D> {
@@ -159,6 +1669,45 @@ impl<'d, D: PrettyDoc<'d>> FocusedLine<'d, D> {
         segs.map(|seg| seg.width).sum()
     }
 
+    /// Where the focus would be if this line were wrapped onto rows that are `wrap_width` columns
+    /// wide: `row` counts rows down from the line's first row, and `col` is the column within
+    /// that row. As with [`Line::split_at_width()`] and [`SplitRounding::Floor`], a full-width
+    /// character that would straddle the end of a row starts the next row instead, and zero-width
+    /// characters stay with the character before them. A character wider than `wrap_width` gets a
+    /// row to itself. If the focus is at the end of a full row, it's at the start of the next
+    /// row, unless nothing visible follows it on the line. A `wrap_width` of 0 means no wrapping.
+    pub fn wrapped_focus_pos(&self, wrap_width: Width) -> Pos {
+        let mut pos = Pos::zero();
+        let wraps = |pos: Pos, ch_width: Width| {
+            wrap_width > 0 && pos.col > 0 && ch_width > 0 && pos.col + ch_width > wrap_width
+        };
+        for ch in self.left_segments.iter().flat_map(|seg| seg.str.chars()) {
+            let ch_width = char_width(ch);
+            if wraps(pos, ch_width) {
+                pos = Pos {
+                    row: pos.row + 1,
+                    col: 0,
+                };
+            }
+            pos.col += ch_width;
+        }
+        let next_width = self
+            .right_segments
+            .iter()
+            .flat_map(|seg| seg.str.chars())
+            .map(char_width)
+            .find(|ch_width| *ch_width > 0);
+        if let Some(ch_width) = next_width {
+            if wraps(pos, ch_width) {
+                pos = Pos {
+                    row: pos.row + 1,
+                    col: 0,
+                };
+            }
+        }
+        pos
+    }
+
     pub fn to_left_string(&self) -> String {
         let mut string = String::new();
         for segment in &self.left_segments {
@@ -219,22 +1768,52 @@ impl<'d, D: PrettyDoc<'d>> ToString for FocusedLine<'d, D> {
 /// prefix_len
 /// ```
 struct Block<'d, D: PrettyDoc<'d>> {
+    /// The id of the node whose `Newline` started this block, or `None` for the first block.
+    start_id: Option<D::Id>,
     /// Stack of resolved text. The last element is the _rightmost_ text.
     segments: Vec<Segment<'d, D>>,
     /// The sum of the segment string widths.
     prefix_len: Width,
     /// Whether there is an `EndOfLine` between the `segments` and the `chunks`.
     at_eol: bool,
+    /// Whether the last segment is a soft space (from a `Notation::Space`). It's removed if the
+    /// line ends before any more text is pushed.
+    trailing_space: bool,
+    /// Padding (from a `Notation::PadTo`) to add before the next non-empty text, if any.
+    pending_pad: Option<Textual<'d, D>>,
     /// Stack of unresolved notations. The last element is the _leftmost_ chunk.
     /// INVARIANT: These are "expanded chunks", meaning that their top-level notation may only be
-    /// `Textual`, `Choice`, `Child`, or `EndOfLine`.
+    /// `Textual`, `Choice`, `Child`, or `EndOfLine`. The one exception is that an `Empty` chunk
+    /// marks the end of the node with its id, for `pretty_print_multi` to find.
     chunks: Vec<Chunk<'d, D>>,
+    /// Positions within `segments` of secondary focus targets: `(target index, landmark, col)`.
+    landmarks: Vec<(usize, Landmark, Col)>,
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for Block<'d, D> {
+    fn clone(&self) -> Self {
+        Block {
+            start_id: self.start_id,
+            segments: self.segments.clone(),
+            prefix_len: self.prefix_len,
+            at_eol: self.at_eol,
+            trailing_space: self.trailing_space,
+            pending_pad: self.pending_pad.clone(),
+            chunks: self.chunks.clone(),
+            landmarks: self.landmarks.clone(),
+        }
+    }
 }
 
 impl<'d, D: PrettyDoc<'d>> Block<'d, D> {
-    fn new(indentation: Option<Rc<IndentNode<'d, D>>>, chunks: Vec<Chunk<'d, D>>) -> Block<'d, D> {
+    /// Construct a block, using `indent_segments` (which must be empty) to store its segments.
+    fn new(
+        start_id: Option<D::Id>,
+        indentation: Option<Rc<IndentNode<'d, D>>>,
+        chunks: Vec<Chunk<'d, D>>,
+        mut indent_segments: Vec<Segment<'d, D>>,
+    ) -> Block<'d, D> {
         let mut remaining_indentation = &indentation;
-        let mut indent_segments = Vec::new();
         while let Some(indent_node) = remaining_indentation {
             indent_segments.push(indent_node.segment.clone());
             remaining_indentation = &indent_node.parent;
@@ -242,33 +1821,220 @@ impl<'d, D: PrettyDoc<'d>> Block<'d, D> {
         indent_segments.reverse();
 
         Block {
+            start_id,
             prefix_len: indent_segments.iter().map(|seg| seg.width).sum(),
             segments: indent_segments,
             at_eol: false,
+            trailing_space: false,
+            pending_pad: None,
             chunks,
+            landmarks: Vec::new(),
+        }
+    }
+
+    /// Whether any text (not counting indentation or empty strings) has been pushed.
+    fn has_content(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|seg| !seg.is_indentation() && !seg.str.is_empty())
+    }
+
+    /// The width of the line if it ended here, which excludes any trailing soft space.
+    fn printed_len(&self) -> Width {
+        if self.trailing_space {
+            self.prefix_len - 1
+        } else {
+            self.prefix_len
         }
     }
 
     fn push_text(&mut self, textual: Textual<'d, D>) -> Result<(), PrintingError<D::Error>> {
-        if self.at_eol {
+        if let Some(col) = textual.pad_to {
+            // Like a soft space, drop it after an `EndOfLine`.
+            let is_larger = match &self.pending_pad {
+                Some(pad) => pad.pad_to.unwrap() < col,
+                None => true,
+            };
+            if !self.at_eol && is_larger {
+                self.pending_pad = Some(textual);
+            }
+            return Ok(());
+        } else if textual.is_soft_space {
+            // Collapse with an adjacent soft space, and drop it at the start of a line or after an
+            // `EndOfLine` (where it could only be followed by a newline or an error).
+            if self.at_eol || self.trailing_space || !self.has_content() {
+                return Ok(());
+            }
+            self.trailing_space = true;
+        } else if self.at_eol {
             return Err(PrintingError::TextAfterEndOfLine);
+        } else if textual.str.is_empty() && self.trailing_space {
+            // Keep the soft space last, so that it can still be removed.
+            return Ok(());
+        } else {
+            self.trailing_space = false;
+        }
+        if !textual.is_soft_space && !textual.str.is_empty() {
+            if let Some(pad) = self.pending_pad.take() {
+                self.push_padding(pad);
+            }
         }
         self.segments.push(Segment {
             str: textual.str,
             width: textual.width,
+            direction: Direction::of_str(textual.str),
+            link: textual.link,
+            style_label: textual.style_label,
             style: textual.style,
+            kind: if textual.is_soft_space {
+                SegmentKind::Separator
+            } else if textual.is_from_text {
+                SegmentKind::Text
+            } else {
+                SegmentKind::Literal
+            },
         });
         self.prefix_len += textual.width;
         Ok(())
     }
 
-    fn print(self) -> Line<'d, D> {
+    /// Pad the line with spaces up to the `pad`'s column.
+    fn push_padding(&mut self, pad: Textual<'d, D>) {
+        const SPACES: &str = "                                                                ";
+
+        let mut remaining = pad.pad_to.unwrap().saturating_sub(self.prefix_len);
+        while remaining > 0 {
+            let str = &SPACES[..(remaining as usize).min(SPACES.len())];
+            let width = str.len() as Width;
+            self.segments.push(Segment {
+                str,
+                width,
+                direction: Direction::Neutral,
+                link: None,
+                style_label: pad.style_label,
+                style: pad.style.clone(),
+                kind: SegmentKind::Separator,
+            });
+            self.prefix_len += width;
+            remaining -= width;
+        }
+    }
+
+    /// Whether this finished block contains nothing but whitespace.
+    fn is_blank(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|seg| seg.str.chars().all(char::is_whitespace))
+    }
+
+    /// Like `resolve`, but without separating the indentation, which saves allocating.
+    fn into_line(mut self) -> Line<'d, D> {
         assert!(self.chunks.is_empty());
 
+        if self.trailing_space {
+            self.segments.pop();
+        }
         Line {
             segments: self.segments,
         }
     }
+
+    fn resolve(mut self) -> ResolvedBlock<'d, D> {
+        assert!(self.chunks.is_empty());
+
+        if self.trailing_space {
+            self.segments.pop();
+        }
+        let num_indent_segs = self
+            .segments
+            .iter()
+            .take_while(|seg| seg.is_indentation())
+            .count();
+        let segments = self.segments.split_off(num_indent_segs);
+        ResolvedBlock {
+            start_id: self.start_id,
+            indentation: self.segments,
+            segments,
+        }
+    }
+}
+
+/// The settings that a `Printer` gets from its `PrintOptions`. They're shared by the printers that
+/// it's split into.
+struct Config<'d, D: PrettyDoc<'d>> {
+    /// Memoized first lines of `Notation::Cache` subtrees, used by `fits`.
+    first_lines: LayoutCache<D::Id>,
+    /// The most chunks after the focus that `fits` may look at.
+    max_lookahead: Option<usize>,
+    /// The style of `ERROR_PLACEHOLDER`, if errors should be substituted instead of returned.
+    error_style: Option<Rc<D::Style>>,
+    /// How to measure the width of text.
+    width_policy: &'d dyn WidthPolicy,
+    /// How to pick between the options of a choice.
+    choice_strategy: &'d dyn ChoiceStrategy,
+    /// What to do when neither option of a choice fits.
+    tie_break: ChoiceTieBreak,
+    /// The nodes to display in a "match" style.
+    match_style: Option<Rc<MatchStyle<D::Id, D::Style>>>,
+    /// What to add to each printed line.
+    line_decorator: Option<Rc<dyn LineDecorator<'d, D> + 'd>>,
+    /// The most blank lines to print in a row.
+    collapse_blank_lines: Option<usize>,
+}
+
+impl<'d, D: PrettyDoc<'d>> Config<'d, D> {
+    fn new(
+        doc: D,
+        options: &PrintOptions<'d, D>,
+    ) -> Result<Config<'d, D>, PrintingError<D::Error>> {
+        let match_style = match &options.matches {
+            None => None,
+            Some(matches) => Some(Rc::new(matches.resolve(doc)?)),
+        };
+        Ok(Config {
+            first_lines: options.layout_cache.clone().unwrap_or_default(),
+            max_lookahead: options.max_lookahead,
+            error_style: options.on_error.clone().into_error_style(),
+            width_policy: options.width_policy,
+            choice_strategy: options.choice_strategy,
+            tie_break: options.tie_break,
+            match_style,
+            line_decorator: options.line_decorator.clone(),
+            collapse_blank_lines: options.collapse_blank_lines,
+        })
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Default for Config<'d, D> {
+    fn default() -> Self {
+        Config {
+            first_lines: LayoutCache::new(),
+            max_lookahead: None,
+            error_style: None,
+            width_policy: &UnicodeWidth,
+            choice_strategy: &FirstFits,
+            tie_break: ChoiceTieBreak::default(),
+            match_style: None,
+            line_decorator: None,
+            collapse_blank_lines: None,
+        }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for Config<'d, D> {
+    fn clone(&self) -> Self {
+        Config {
+            first_lines: self.first_lines.clone(),
+            max_lookahead: self.max_lookahead,
+            error_style: self.error_style.clone(),
+            width_policy: self.width_policy,
+            choice_strategy: self.choice_strategy,
+            tie_break: self.tie_break,
+            match_style: self.match_style.clone(),
+            line_decorator: self.line_decorator.clone(),
+            collapse_blank_lines: self.collapse_blank_lines,
+        }
+    }
 }
 
 /// While seeking, the Printer has a "focus" at some position in the text. This focus is defined as
@@ -285,32 +2051,306 @@ struct Printer<'d, D: PrettyDoc<'d>> {
     prev_blocks: Vec<Block<'d, D>>,
     /// Stack of blocks after the focus. The last element is the next line.
     next_blocks: Vec<Block<'d, D>>,
+    /// Settings from the `PrintOptions`.
+    config: Config<'d, D>,
+    /// Secondary focus targets to look out for, from `pretty_print_multi`.
+    targets: Vec<Target<D::Id>>,
+    /// The landmarks found on the most recently printed line.
+    landmarks: Vec<(usize, Landmark, Col)>,
+    /// Counters for `DownwardPrinter::stats`.
+    stats: PrintStats,
+    /// The ancestor that was focused instead, if the path didn't exist and
+    /// `fall_back_on_invalid_path` was set.
+    path_fallback: Option<PathFallback>,
+    /// Where the printer sought to, if it's from `seek_origin`, for `checkpoint`.
+    origin: Option<Origin>,
+    /// The row of the next line that `print_next_line` will print, relative to the focused line.
+    /// (`print_prev_line` prints the row above this.)
+    row: isize,
+    /// A hash of the choices made so far, for `layout_fingerprint`.
+    choice_hash: Option<u64>,
+    /// Empty segment vectors to reuse for new blocks, from `pretty_print_into`.
+    spare_segments: Vec<Vec<Segment<'d, D>>>,
+    /// The number of blank lines in a row just above the next line that `print_next_line` will
+    /// print, including ones that were collapsed.
+    blank_run: usize,
+    /// Blocks that `print_prev_line` has already finished, after collapsing blank lines. The last
+    /// element is the previous line.
+    prev_queue: Vec<Block<'d, D>>,
 }
 
 impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
     fn new(width: Width) -> Result<Printer<'d, D>, PrintingError<D::Error>> {
-        let empty_block = Block::new(None, Vec::new());
+        let empty_block = Block::new(None, None, Vec::new(), Vec::new());
         Ok(Printer {
             width,
             prev_blocks: Vec::new(),
             next_blocks: vec![empty_block],
+            config: Config::default(),
+            targets: Vec::new(),
+            landmarks: Vec::new(),
+            stats: PrintStats::default(),
+            path_fallback: None,
+            origin: None,
+            row: 0,
+            choice_hash: None,
+            spare_segments: Vec::new(),
+            blank_run: 0,
+            prev_queue: Vec::new(),
         })
     }
 
+    fn with_options(
+        doc: D,
+        width: Width,
+        options: &PrintOptions<'d, D>,
+    ) -> Result<Printer<'d, D>, PrintingError<D::Error>> {
+        let mut printer = Printer::new(width)?;
+        printer.config = Config::new(doc, options)?;
+        Ok(printer)
+    }
+
+    /// Split a printer that has finished seeking into one that prints the lines above the focus,
+    /// and one that prints the lines below it.
+    fn split(self) -> (Printer<'d, D>, Printer<'d, D>) {
+        let upward_printer = Printer {
+            width: self.width,
+            prev_blocks: self.prev_blocks,
+            next_blocks: Vec::new(),
+            config: self.config.clone(),
+            targets: self.targets.clone(),
+            landmarks: Vec::new(),
+            stats: PrintStats::default(),
+            path_fallback: self.path_fallback.clone(),
+            origin: self.origin.clone(),
+            row: 0,
+            choice_hash: self.choice_hash,
+            spare_segments: Vec::new(),
+            blank_run: 0,
+            prev_queue: Vec::new(),
+        };
+        let downward_printer = Printer {
+            width: self.width,
+            prev_blocks: Vec::new(),
+            next_blocks: self.next_blocks,
+            config: self.config,
+            targets: self.targets,
+            landmarks: Vec::new(),
+            stats: self.stats,
+            path_fallback: self.path_fallback,
+            origin: self.origin,
+            row: self.row,
+            choice_hash: self.choice_hash,
+            spare_segments: self.spare_segments,
+            blank_run: self.blank_run,
+            prev_queue: Vec::new(),
+        };
+        (upward_printer, downward_printer)
+    }
+
+    /// Save where the printer is in the document, for `restore_state`.
+    fn save_state(&self) -> PrinterState<'d, D> {
+        PrinterState {
+            prev_blocks: self.prev_blocks.clone(),
+            next_blocks: self.next_blocks.clone(),
+            prev_queue: self.prev_queue.clone(),
+            row: self.row,
+            blank_run: self.blank_run,
+            choice_hash: self.choice_hash,
+        }
+    }
+
+    /// Where the printer is, as plain data that `Checkpoint::seek` can seek to again.
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            width: self.width,
+            origin: self
+                .origin
+                .clone()
+                .expect("bug in checkpoint: printer not from seek_origin"),
+            row: self.row,
+        }
+    }
+
+    /// Go back to where the printer was when `save_state` was called.
+    fn restore_state(&mut self, state: PrinterState<'d, D>) {
+        self.prev_blocks = state.prev_blocks;
+        self.next_blocks = state.next_blocks;
+        self.prev_queue = state.prev_queue;
+        self.row = state.row;
+        self.blank_run = state.blank_run;
+        self.choice_hash = state.choice_hash;
+    }
+
     /// Returns `None` if it already reached the bottom of the document.
     fn print_next_line(&mut self) -> Result<Option<Line<'d, D>>, PrintingError<D::Error>> {
+        let block = match self.finish_next_uncollapsed_block()? {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        let line = self.decorate(self.row, block);
+        self.row += 1;
+        Ok(Some(line))
+    }
+
+    /// Returns `None` if it already reached the top of the document.
+    fn print_prev_line(&mut self) -> Result<Option<Line<'d, D>>, PrintingError<D::Error>> {
+        if self.config.collapse_blank_lines.is_some() && self.prev_queue.is_empty() {
+            self.queue_prev_blocks()?;
+        }
+        let block = match self.prev_queue.pop() {
+            Some(block) => block,
+            None => match self.finish_prev_block()? {
+                Some(block) => block,
+                None => return Ok(None),
+            },
+        };
+        self.row -= 1;
+        Ok(Some(self.decorate(self.row, block)))
+    }
+
+    /// Like `finish_next_block`, but skip blank lines past the first `collapse_blank_lines` in a
+    /// row.
+    fn finish_next_uncollapsed_block(
+        &mut self,
+    ) -> Result<Option<Block<'d, D>>, PrintingError<D::Error>> {
+        loop {
+            let block = match self.finish_next_block()? {
+                Some(block) => block,
+                None => return Ok(None),
+            };
+            let max_blank_lines = match self.config.collapse_blank_lines {
+                Some(max_blank_lines) => max_blank_lines,
+                None => return Ok(Some(block)),
+            };
+            if !block.is_blank() {
+                self.blank_run = 0;
+                return Ok(Some(block));
+            }
+            self.blank_run += 1;
+            if self.blank_run <= max_blank_lines {
+                return Ok(Some(block));
+            }
+        }
+    }
+
+    /// Finish the run of blank blocks above the focus (if any) and the block above it, and queue
+    /// them up for `print_prev_line`, keeping only the first `collapse_blank_lines` blank blocks of
+    /// the run. Returns the length of the run, including the blocks that were dropped.
+    fn queue_prev_blocks(&mut self) -> Result<usize, PrintingError<D::Error>> {
+        let max_blank_lines = self.config.collapse_blank_lines.unwrap_or(usize::MAX);
+        // From bottom to top.
+        let mut run = Vec::new();
+        let mut above_run = None;
+        while let Some(block) = self.finish_prev_block()? {
+            if block.is_blank() {
+                run.push(block);
+            } else {
+                above_run = Some(block);
+                break;
+            }
+        }
+        let run_len = run.len();
+        self.prev_queue.extend(above_run);
+        let kept = run.split_off(run_len.saturating_sub(max_blank_lines));
+        self.prev_queue.extend(kept.into_iter().rev());
+        Ok(run_len)
+    }
+
+    /// Finish up to `budget` of the blocks above the focus ahead of time, and queue them up for
+    /// `print_prev_line`. If finishing one fails, put it back as it was, so that `print_prev_line`
+    /// returns the error when it gets there.
+    fn queue_prev_blocks_eagerly(&mut self, budget: usize) {
+        // From bottom to top.
+        let mut finished = Vec::new();
+        while finished.len() < budget {
+            let num_prev_blocks = self.prev_blocks.len();
+            let prev_block = match self.prev_blocks.last() {
+                Some(block) => block.clone(),
+                None => break,
+            };
+            match self.finish_prev_block() {
+                Ok(Some(block)) => finished.push(block),
+                Ok(None) => break,
+                Err(_) => {
+                    self.prev_blocks.truncate(num_prev_blocks - 1);
+                    self.prev_blocks.push(prev_block);
+                    break;
+                }
+            }
+        }
+        self.prev_queue.splice(0..0, finished.into_iter().rev());
+    }
+
+    /// Turn a finished block into a line, with the `line_decorator`'s decorations (if any).
+    fn decorate(&self, row: isize, block: Block<'d, D>) -> Line<'d, D> {
+        let start_id = block.start_id;
+        let line = block.into_line();
+        if self.config.line_decorator.is_none() {
+            return line;
+        }
+        let (mut segments, suffix) = self.decorations(row, start_id, &line.segments);
+        segments.extend(line.segments);
+        segments.extend(suffix);
+        Line { segments }
+    }
+
+    /// The segments that the `line_decorator` puts before and after the given line.
+    #[allow(clippy::type_complexity)]
+    fn decorations(
+        &self,
+        row: isize,
+        start_id: Option<D::Id>,
+        segments: &[Segment<'d, D>],
+    ) -> (Vec<Segment<'d, D>>, Vec<Segment<'d, D>>) {
+        let mut prefix = Vec::new();
+        let mut suffix = Vec::new();
+        if let Some(line_decorator) = &self.config.line_decorator {
+            let info = LineInfo {
+                row,
+                start_id,
+                segments,
+            };
+            line_decorator.decorate(&info, &mut prefix, &mut suffix);
+        }
+        (prefix, suffix)
+    }
+
+    /// Returns `None` if it already reached the bottom of the document.
+    fn print_next_block(
+        &mut self,
+    ) -> Result<Option<ResolvedBlock<'d, D>>, PrintingError<D::Error>> {
+        Ok(self.finish_next_block()?.map(Block::resolve))
+    }
+
+    /// Returns `None` if it already reached the top of the document.
+    fn print_prev_block(
+        &mut self,
+    ) -> Result<Option<ResolvedBlock<'d, D>>, PrintingError<D::Error>> {
+        Ok(self.finish_prev_block()?.map(Block::resolve))
+    }
+
+    /// An empty vector for a new block's segments, reusing a spare one if there is one.
+    fn take_spare_segments(&mut self) -> Vec<Segment<'d, D>> {
+        self.spare_segments.pop().unwrap_or_default()
+    }
+
+    /// Take the next block and expand all of its chunks, so that it's ready to resolve. Returns
+    /// `None` if it already reached the bottom of the document.
+    fn finish_next_block(&mut self) -> Result<Option<Block<'d, D>>, PrintingError<D::Error>> {
         use ConsolidatedNotation::*;
-        span!("print_next_line");
+        span!("finish_next_block");
 
         let mut block = match self.next_blocks.pop() {
             None => return Ok(None),
             Some(block) => block,
         };
         while let Some(chunk) = block.chunks.pop() {
+            self.track(&mut block, &chunk)?;
             match chunk.notation {
-                FocusMark => (),
-                Empty | Newline(_) | Concat(_, _) => {
-                    panic!("bug in print_next_line: unexpanded chunk")
+                Empty | FocusMark => (),
+                Newline(_) | Concat(_, _) | Cache(_) => {
+                    panic!("bug in finish_next_block: unexpanded chunk")
                 }
                 EndOfLine => block.at_eol = true,
                 Textual(textual) => block.push_text(textual)?,
@@ -323,23 +2363,26 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
                 }
             }
         }
-        Ok(Some(block.print()))
+        self.landmarks = mem::take(&mut block.landmarks);
+        Ok(Some(block))
     }
 
+    /// Take the previous block and expand all of its chunks, so that it's ready to resolve.
     /// Returns `None` if it already reached the top of the document.
-    fn print_prev_line(&mut self) -> Result<Option<Line<'d, D>>, PrintingError<D::Error>> {
+    fn finish_prev_block(&mut self) -> Result<Option<Block<'d, D>>, PrintingError<D::Error>> {
         use ConsolidatedNotation::*;
-        span!("print_prev_line");
+        span!("finish_prev_block");
 
         let mut block = match self.prev_blocks.pop() {
             None => return Ok(None),
             Some(block) => block,
         };
         while let Some(chunk) = block.chunks.pop() {
+            self.track(&mut block, &chunk)?;
             match chunk.notation {
-                FocusMark => (),
-                Empty | Newline(_) | Concat(_, _) => {
-                    panic!("bug in print_prev_line: unexpanded chunk")
+                Empty | FocusMark => (),
+                Newline(_) | Concat(_, _) | Cache(_) => {
+                    panic!("bug in finish_prev_block: unexpanded chunk")
                 }
                 EndOfLine => block.at_eol = true,
                 Textual(textual) => block.push_text(textual)?,
@@ -350,7 +2393,25 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
                 }
             }
         }
-        Ok(Some(block.print()))
+        self.landmarks = mem::take(&mut block.landmarks);
+        Ok(Some(block))
+    }
+
+    /// Move the focus down by `num_lines` lines, to the start of a line, without printing the lines
+    /// it passes. Stop at the last line of the document. Call this after `seek`.
+    fn skip_lines(&mut self, num_lines: usize) -> Result<(), PrintingError<D::Error>> {
+        span!("skip_lines");
+
+        for _ in 0..num_lines {
+            let block = self.finish_next_block()?.unwrap();
+            if self.next_blocks.is_empty() {
+                // This was the last line, so keep the focus on it, at its end.
+                self.next_blocks.push(block);
+                break;
+            }
+            self.prev_blocks.push(block);
+        }
+        Ok(())
     }
 
     /// Focus relative to the node at the given path.
@@ -364,16 +2425,33 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
     ) -> Result<(), PrintingError<D::Error>> {
         span!("seek");
 
-        let note = DelayedConsolidatedNotation::with_optional_style(doc, root_style)?;
+        let mut note = DelayedConsolidatedNotation::with_optional_style(doc, root_style)?;
+        note.set_error_style(self.config.error_style.clone());
+        note.set_width_policy(self.config.width_policy);
+        note.set_match_style(self.config.match_style.clone())?;
         let mut chunk = Chunk::new(note)?;
-        for child_index in path {
-            chunk = self.seek_child(chunk, *child_index)?;
+        let mut block = self.next_blocks.pop().unwrap();
+        self.track_child(&mut block, chunk.id);
+        self.next_blocks.push(block);
+        validate_path(doc, path)?;
+        let mut node = doc;
+        for (depth, child_index) in path.iter().enumerate() {
+            chunk = self.seek_child(chunk, node, &path[..depth], *child_index)?;
+            node = node.unwrap_child(*child_index)?;
         }
         match focus_target {
             FocusTarget::Start => self.seek_start(chunk),
             FocusTarget::End => self.seek_end(chunk),
-            FocusTarget::Text(pos) => self.seek_text(chunk, pos),
-            FocusTarget::Mark => self.seek_mark(chunk),
+            FocusTarget::Text(_) | FocusTarget::TextEnd | FocusTarget::TextOffset(_, _) => {
+                let (pos, kind) = focus_target.text_offset().unwrap();
+                self.seek_text(chunk, pos, kind)
+            }
+            FocusTarget::Mark => self.seek_mark(chunk, 0),
+            FocusTarget::NthMark(n) => self.seek_mark(chunk, n),
+            FocusTarget::LastMark => match self.count_marks(chunk.clone())? {
+                0 => Err(PrintingError::MissingFocusMark),
+                num_marks => self.seek_mark(chunk, num_marks - 1),
+            },
         }
     }
 
@@ -391,9 +2469,10 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
 
         while block.chunks.len() > num_chunks_after {
             let chunk = block.chunks.pop().unwrap();
+            self.track(&mut block, &chunk)?;
             match chunk.notation {
-                FocusMark => (),
-                Empty | Newline(_) | Concat(_, _) => {
+                Empty | FocusMark => (),
+                Newline(_) | Concat(_, _) | Cache(_) => {
                     panic!("bug in seek: unexpanded chunk")
                 }
                 EndOfLine => block.at_eol = true,
@@ -420,9 +2499,13 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
         Ok(())
     }
 
-    /// Given an _unexpanded_ chunk that belongs at the focus, move the focus to the first
+    /// Given an _unexpanded_ chunk that belongs at the focus, move the focus to the n'th
     /// `FocusMark` in its notation.
-    fn seek_mark(&mut self, chunk: Chunk<'d, D>) -> Result<(), PrintingError<D::Error>> {
+    fn seek_mark(
+        &mut self,
+        chunk: Chunk<'d, D>,
+        mut mark_index: usize,
+    ) -> Result<(), PrintingError<D::Error>> {
         use ConsolidatedNotation::*;
         span!("seek_mark");
 
@@ -433,16 +2516,17 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
 
         while let Some(mut block) = self.next_blocks.pop() {
             while let Some(chunk) = block.chunks.pop() {
+                self.track(&mut block, &chunk)?;
                 match chunk.notation {
-                    Empty | Newline(_) | Concat(_, _) => {
+                    Newline(_) | Concat(_, _) | Cache(_) => {
                         panic!("bug in print_next_line: unexpanded chunk")
                     }
-                    FocusMark => {
-                        if chunk.id == id {
-                            self.next_blocks.push(block);
-                            return Ok(());
-                        }
+                    FocusMark if chunk.id == id && mark_index == 0 => {
+                        self.next_blocks.push(block);
+                        return Ok(());
                     }
+                    FocusMark if chunk.id == id => mark_index -= 1,
+                    Empty | FocusMark => (),
                     EndOfLine => block.at_eol = true,
                     Textual(textual) => block.push_text(textual)?,
                     Child(_, note) => {
@@ -454,10 +2538,77 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
                     }
                 }
             }
-            self.prev_blocks.push(block);
+            self.prev_blocks.push(block);
+        }
+
+        Err(PrintingError::MissingFocusMark)
+    }
+
+    /// Given an _unexpanded_ chunk that belongs at the focus, count the `FocusMark`s in its
+    /// notation, without moving the focus. This lays out the chunk on a scratch copy of the
+    /// focused block, making the same choices that seeking through it would.
+    fn count_marks(&self, chunk: Chunk<'d, D>) -> Result<usize, PrintingError<D::Error>> {
+        use ConsolidatedNotation::*;
+        span!("count_marks");
+
+        let id = chunk.id;
+        let mut scratch = Printer {
+            width: self.width,
+            prev_blocks: Vec::new(),
+            next_blocks: vec![self.next_blocks.last().unwrap().clone()],
+            config: Config {
+                first_lines: LayoutCache::new(),
+                line_decorator: None,
+                collapse_blank_lines: None,
+                ..self.config.clone()
+            },
+            targets: Vec::new(),
+            landmarks: Vec::new(),
+            stats: PrintStats::default(),
+            path_fallback: None,
+            origin: None,
+            row: 0,
+            choice_hash: None,
+            spare_segments: Vec::new(),
+            blank_run: 0,
+            prev_queue: Vec::new(),
+        };
+        // The chunks after `chunk` stay at the bottom of the last block. Once only they remain,
+        // we've seen all of `chunk`.
+        let num_chunks_after = scratch.next_blocks[0].chunks.len();
+        let mut first_block = scratch.next_blocks.pop().unwrap();
+        scratch.expand_focusing_first_block(&mut first_block, chunk)?;
+        scratch.next_blocks.push(first_block);
+
+        let mut num_marks = 0;
+        while let Some(mut block) = scratch.next_blocks.pop() {
+            loop {
+                if scratch.next_blocks.is_empty() && block.chunks.len() == num_chunks_after {
+                    return Ok(num_marks);
+                }
+                let chunk = match block.chunks.pop() {
+                    Some(chunk) => chunk,
+                    None => break,
+                };
+                match chunk.notation {
+                    Newline(_) | Concat(_, _) | Cache(_) => {
+                        panic!("bug in count_marks: unexpanded chunk")
+                    }
+                    FocusMark if chunk.id == id => num_marks += 1,
+                    Empty | FocusMark => (),
+                    EndOfLine => block.at_eol = true,
+                    Textual(textual) => block.push_text(textual)?,
+                    Child(_, note) => {
+                        scratch.expand_focusing_first_block(&mut block, Chunk::new(note)?)?
+                    }
+                    Choice(opt1, opt2) => {
+                        let choice = scratch.choose(&block, opt1, opt2)?;
+                        scratch.expand_focusing_first_block(&mut block, choice)?;
+                    }
+                }
+            }
         }
-
-        Err(PrintingError::MissingFocusMark)
+        Ok(num_marks)
     }
 
     /// Given an _unexpanded_ chunk that belongs at the focus, move the focus to the given
@@ -465,7 +2616,8 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
     fn seek_text(
         &mut self,
         chunk: Chunk<'d, D>,
-        text_pos: usize,
+        mut text_pos: usize,
+        offset_kind: OffsetKind,
     ) -> Result<(), PrintingError<D::Error>> {
         use ConsolidatedNotation::*;
         span!("seek_text");
@@ -476,15 +2628,27 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
 
         while let Some(mut block) = self.next_blocks.pop() {
             while let Some(chunk) = block.chunks.pop() {
+                self.track(&mut block, &chunk)?;
                 match chunk.notation {
-                    Empty | Newline(_) | Concat(_, _) => {
+                    Newline(_) | Concat(_, _) | Cache(_) => {
                         panic!("bug in print_next_line: unexpanded chunk")
                     }
-                    FocusMark => (),
+                    Empty | FocusMark => (),
                     EndOfLine => block.at_eol = true,
                     Textual(textual) => {
+                        if textual.is_from_text && textual.text_continues {
+                            // A piece of a `BreakableText`. Is the position in a later piece?
+                            let len = offset_kind.from_char_offset(textual.str, usize::MAX);
+                            if text_pos >= len {
+                                text_pos -= len;
+                                block.push_text(textual)?;
+                                continue;
+                            }
+                        }
                         if textual.is_from_text {
-                            let (left_textual, right_textual) = textual.split_at(text_pos);
+                            let char_pos = offset_kind.to_char_offset(textual.str, text_pos);
+                            let (left_textual, right_textual) =
+                                textual.split_at(char_pos, self.config.width_policy);
                             block.push_text(left_textual)?;
                             block.chunks.push(Chunk {
                                 id: chunk.id,
@@ -516,6 +2680,8 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
     fn seek_child(
         &mut self,
         parent: Chunk<'d, D>,
+        parent_doc: D,
+        parent_path: &[usize],
         child_index: usize,
     ) -> Result<Chunk<'d, D>, PrintingError<D::Error>> {
         use ConsolidatedNotation::*;
@@ -549,15 +2715,22 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
             let mut block = match self.next_blocks.pop() {
                 Some(block) => block,
                 // We reached the end of the document without finding the target child.
-                None => return Err(PrintingError::InvalidPath(child_index)),
+                None => {
+                    return Err(PrintingError::InvalidPath {
+                        valid_prefix: parent_path.to_vec(),
+                        index: child_index,
+                        num_children: parent_doc.num_children()?,
+                    })
+                }
             };
 
             // 2. Resolve the first Child or Choice. If we find `Child(child_index)`
             // belonging to `parent_doc`, success. Otherwise, go back to 1.
             while let Some(chunk) = block.chunks.pop() {
+                self.track(&mut block, &chunk)?;
                 match chunk.notation {
-                    FocusMark => (),
-                    Empty | Newline(_) | Concat(_, _) => {
+                    Empty | FocusMark => (),
+                    Newline(_) | Concat(_, _) | Cache(_) => {
                         panic!("bug in seek_child: unexpanded chunk")
                     }
                     EndOfLine => block.at_eol = true,
@@ -611,12 +2784,19 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
                 }
                 Newline(indentation) => {
                     let chunks = mem::take(&mut block.chunks);
-                    self.next_blocks.push(Block::new(indentation, chunks));
+                    let segments = self.take_spare_segments();
+                    self.next_blocks.push(Block::new(
+                        Some(chunk.id),
+                        indentation,
+                        chunks,
+                        segments,
+                    ));
                 }
                 Concat(left, right) => {
                     stack.push(Chunk::new(left)?);
                     stack.push(Chunk::new(right)?);
                 }
+                Cache(note) => stack.push(Chunk::new(note)?),
             }
         }
         Ok(())
@@ -652,18 +2832,28 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
                 Newline(indentation) => {
                     chunks.reverse();
                     let prev_block = Block {
+                        start_id: block.start_id,
                         segments: mem::take(&mut block.segments),
                         prefix_len: block.prefix_len,
                         at_eol: block.at_eol,
+                        trailing_space: block.trailing_space,
+                        pending_pad: block.pending_pad.take(),
                         chunks: mem::take(&mut chunks),
+                        landmarks: mem::take(&mut block.landmarks),
                     };
                     self.prev_blocks.push(prev_block);
-                    *block = Block::new(indentation, mem::take(&mut block.chunks));
+                    *block = Block::new(
+                        Some(chunk.id),
+                        indentation,
+                        mem::take(&mut block.chunks),
+                        self.take_spare_segments(),
+                    );
                 }
                 Concat(left, right) => {
                     stack.push(Chunk::new(right)?);
                     stack.push(Chunk::new(left)?);
                 }
+                Cache(note) => stack.push(Chunk::new(note)?),
             }
         }
         chunks.reverse();
@@ -671,120 +2861,454 @@ impl<'d, D: PrettyDoc<'d>> Printer<'d, D> {
         Ok(())
     }
 
-    /// Determine which of the two options of the choice to select. Pick the first option if it
-    /// fits. (We also want to pick the first option if we're inside a `Flat`, but
-    /// ConsolidatedNotation already took care of that.)
+    /// Record the positions of any secondary focus targets at the start of `chunk`, which was just
+    /// popped from `block`.
+    fn track(
+        &mut self,
+        block: &mut Block<'d, D>,
+        chunk: &Chunk<'d, D>,
+    ) -> Result<(), PrintingError<D::Error>> {
+        use ConsolidatedNotation::*;
+
+        if self.targets.is_empty() {
+            return Ok(());
+        }
+        match &chunk.notation {
+            Child(_, note) => self.track_child(block, note.doc().id()?),
+            Empty => self.track_landmark(block, chunk.id, Landmark::End),
+            FocusMark => self.track_landmark(block, chunk.id, Landmark::Mark),
+            Textual(textual) if textual.is_from_text => {
+                for (i, target) in self.targets.iter_mut().enumerate() {
+                    if target.id != chunk.id || target.seen_text {
+                        continue;
+                    }
+                    let text_width = match target.focus_target.text_offset() {
+                        None => continue,
+                        // A piece of a `BreakableText`, before the one with the position.
+                        Some((pos, kind))
+                            if textual.text_continues
+                                && pos >= kind.from_char_offset(textual.str, usize::MAX) =>
+                        {
+                            if pos != usize::MAX {
+                                let len = kind.from_char_offset(textual.str, usize::MAX);
+                                target.focus_target = FocusTarget::TextOffset(pos - len, kind);
+                            }
+                            continue;
+                        }
+                        Some((pos, kind)) => {
+                            let char_pos = kind.to_char_offset(textual.str, pos);
+                            textual
+                                .clone()
+                                .split_at(char_pos, self.config.width_policy)
+                                .0
+                                .width
+                        }
+                    };
+                    target.seen_text = true;
+                    block
+                        .landmarks
+                        .push((i, Landmark::Text, block.prefix_len + text_width));
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Record the start of the node with the given `id`, which is about to be expanded in `block`,
+    /// and push an `Empty` chunk to mark its end.
+    fn track_child(&mut self, block: &mut Block<'d, D>, id: D::Id) {
+        if self.targets.iter().any(|target| target.id == id) {
+            self.track_landmark(block, id, Landmark::Start);
+            block.chunks.push(Chunk {
+                id,
+                notation: ConsolidatedNotation::Empty,
+            });
+        }
+    }
+
+    fn track_landmark(&mut self, block: &mut Block<'d, D>, id: D::Id, landmark: Landmark) {
+        for (i, target) in self.targets.iter().enumerate() {
+            if target.id == id {
+                block.landmarks.push((i, landmark, block.prefix_len));
+            }
+        }
+    }
+
+    /// Determine which of the two options of the choice to select, using the `choice_strategy`.
+    /// By default, pick the first option if it fits. (We also want to pick the first option if
+    /// we're inside a `Flat`, but ConsolidatedNotation already took care of that.)
     fn choose(
-        &self,
+        &mut self,
         block: &Block<'d, D>,
         opt1: DelayedConsolidatedNotation<'d, D>,
         opt2: DelayedConsolidatedNotation<'d, D>,
     ) -> Result<Chunk<'d, D>, PrintingError<D::Error>> {
         span!("choose");
 
+        let width = match opt1.max_width() {
+            Some(max_width) => self.width.min(max_width),
+            None => self.width,
+        };
         let chunk1 = Chunk::new(opt1)?;
 
-        if self.width >= block.prefix_len
-            && fits(
-                self.width - block.prefix_len,
-                block.at_eol,
-                chunk1.notation.clone(),
-                &block.chunks,
-            )?
-        {
+        let first_lines = &self.config.first_lines;
+        let max_lookahead = self.config.max_lookahead;
+        let stats = &mut self.stats;
+        let mut error = None;
+        let mut fits_within = |width: Width| {
+            if error.is_some() || width < block.printed_len() {
+                return false;
+            }
+            let remaining = width - block.printed_len();
+            let notation = chunk1.notation.clone();
+            match fits(
+                remaining,
+                block,
+                notation,
+                first_lines,
+                max_lookahead,
+                stats,
+            ) {
+                Ok(fits) => fits,
+                Err(err) => {
+                    error = Some(err);
+                    false
+                }
+            }
+        };
+        let mut context = ChoiceContext::new(width, block.printed_len(), &mut fits_within);
+        let pick_first = self.config.choice_strategy.choose_first(&mut context);
+        if let Some(err) = error {
+            return Err(err);
+        }
+
+        if pick_first {
+            self.record_choice(true);
+            return Ok(chunk1);
+        }
+        let chunk2 = Chunk::new(opt2)?;
+        if self.break_tie(block, width, &chunk1, &chunk2)? {
+            self.record_choice(true);
             Ok(chunk1)
         } else {
-            Chunk::new(opt2)
+            self.record_choice(false);
+            Ok(chunk2)
         }
     }
 
-    #[allow(unused)]
-    fn debug_long(&self) {
-        for block in &self.prev_blocks {
-            for segment in &block.segments {
-                print!("'{}' ", segment.str);
+    /// The choice strategy picked the second option. If neither option fits within `width`,
+    /// determine whether to pick the first option instead, using the `tie_break` policy.
+    fn break_tie(
+        &mut self,
+        block: &Block<'d, D>,
+        width: Width,
+        chunk1: &Chunk<'d, D>,
+        chunk2: &Chunk<'d, D>,
+    ) -> Result<bool, PrintingError<D::Error>> {
+        match self.config.tie_break {
+            ChoiceTieBreak::PreferSecond => Ok(false),
+            ChoiceTieBreak::PreferFewestOverflowColumns => {
+                let overflow1 = self.overflow(block, width, &chunk1.notation)?;
+                if overflow1 == Some(0) {
+                    return Ok(false);
+                }
+                Ok(match self.overflow(block, width, &chunk2.notation)? {
+                    Some(0) => false,
+                    Some(overflow2) => overflow1.is_some_and(|overflow1| overflow1 < overflow2),
+                    None => overflow1.is_some(),
+                })
             }
-            for chunk in &block.chunks {
-                print!("{} ", chunk.notation);
+            ChoiceTieBreak::PreferFewestLines => {
+                if self.fits_at(block, width, &chunk1.notation)?
+                    || self.fits_at(block, width, &chunk2.notation)?
+                {
+                    return Ok(false);
+                }
+                let lines1 = min_newlines(chunk1.notation.clone(), usize::MAX)?;
+                let lines2 = min_newlines(chunk2.notation.clone(), lines1)?;
+                Ok(lines1 < lines2)
             }
         }
-        print!(" / ");
-        for block in self.next_blocks.iter().rev() {
-            for segment in &block.segments {
-                print!("'{}' ", segment.str);
+    }
+
+    /// How many columns past `width` the line would go if `notation` was printed next, or `None`
+    /// if it doesn't fit at any width (because it has text after an `EndOfLine`).
+    fn overflow(
+        &mut self,
+        block: &Block<'d, D>,
+        width: Width,
+        notation: &ConsolidatedNotation<'d, D>,
+    ) -> Result<Option<Width>, PrintingError<D::Error>> {
+        if self.fits_at(block, width, notation)? {
+            return Ok(Some(0));
+        }
+        // Find a width that fits by doubling, then the smallest one by bisecting.
+        let mut too_narrow = width;
+        let mut wide_enough = width.max(1);
+        while !self.fits_at(block, wide_enough, notation)? {
+            if wide_enough == Width::MAX {
+                return Ok(None);
             }
-            for chunk in &block.chunks {
-                print!("{} ", chunk.notation);
+            too_narrow = wide_enough;
+            wide_enough = wide_enough.saturating_mul(2);
+        }
+        while wide_enough - too_narrow > 1 {
+            let mid = too_narrow + (wide_enough - too_narrow) / 2;
+            if self.fits_at(block, mid, notation)? {
+                wide_enough = mid;
+            } else {
+                too_narrow = mid;
             }
         }
-        println!();
+        Ok(Some(wide_enough - width))
+    }
+
+    /// Whether the line would fit within `width` if `notation` was printed next.
+    fn fits_at(
+        &mut self,
+        block: &Block<'d, D>,
+        width: Width,
+        notation: &ConsolidatedNotation<'d, D>,
+    ) -> Result<bool, PrintingError<D::Error>> {
+        if width < block.printed_len() {
+            return Ok(false);
+        }
+        fits(
+            width - block.printed_len(),
+            block,
+            notation.clone(),
+            &self.config.first_lines,
+            self.config.max_lookahead,
+            &mut self.stats,
+        )
+    }
+
+    /// Mix which option of a choice was picked into `choice_hash`, if it's being computed. This
+    /// uses FNV-1a, so that the hash doesn't depend on the platform or the version of Rust.
+    fn record_choice(&mut self, picked_first: bool) {
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        if let Some(hash) = &mut self.choice_hash {
+            *hash = (*hash ^ u64::from(picked_first)).wrapping_mul(FNV_PRIME);
+        }
     }
 
-    #[allow(unused)]
-    pub fn debug_short(&self) {
-        fn print_block<'d, D: PrettyDoc<'d>>(block: &Block<'d, D>) {
-            println!(
-                "{}|{}",
-                block
+    fn snapshot(&self) -> DebugSnapshot {
+        fn snapshot_block<'d, D: PrettyDoc<'d>>(block: &Block<'d, D>) -> DebugBlock {
+            DebugBlock {
+                segments: block
                     .segments
                     .iter()
-                    .map(|seg| seg.str)
-                    .fold(String::new(), |a, b| a + b),
-                block.chunks.len(),
-            );
+                    .map(|seg| seg.str.to_owned())
+                    .collect(),
+                at_eol: block.at_eol,
+                chunks: block
+                    .chunks
+                    .iter()
+                    .rev()
+                    .map(|chunk| chunk.notation.to_string())
+                    .collect(),
+            }
+        }
+
+        DebugSnapshot {
+            prev_blocks: self.prev_blocks.iter().map(snapshot_block).collect(),
+            next_blocks: self.next_blocks.iter().rev().map(snapshot_block).collect(),
+        }
+    }
+}
+
+/// The printer's internal state, from [`debug_snapshot()`]. This is for bug reports and debugging
+/// tools: its format may change between versions.
+///
+/// The printer keeps the document as a sequence of _blocks_, one per line. Each block has the
+/// text that's been resolved so far at its start, followed by the _chunks_ of notation that
+/// haven't been resolved yet. Blocks near the focus are resolved first, so the ones far away are
+/// mostly unresolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugSnapshot {
+    /// The blocks above the focused line, from top to bottom.
+    pub prev_blocks: Vec<DebugBlock>,
+    /// The block containing the focus, followed by the blocks below it, from top to bottom.
+    pub next_blocks: Vec<DebugBlock>,
+}
+
+/// One of the printer's blocks, in a [`DebugSnapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugBlock {
+    /// The strings of the resolved segments, from left to right, including indentation.
+    pub segments: Vec<String>,
+    /// Whether there's an [`EndOfLine`](Notation::EndOfLine) between the segments and the chunks.
+    pub at_eol: bool,
+    /// The unresolved chunks, from left to right, in the debugging format of the printer's
+    /// internal notation.
+    pub chunks: Vec<String>,
+}
+
+impl DebugSnapshot {
+    /// One line per block, showing its resolved text and how many chunks it has left, with the
+    /// focused block marked.
+    pub fn short(&self) -> String {
+        fn write_block(string: &mut String, block: &DebugBlock) {
+            string.push_str(&block.segments.concat());
+            string.push_str(&format!("|{}\n", block.chunks.len()));
+        }
+
+        let mut string = String::new();
+        for block in &self.prev_blocks {
+            write_block(&mut string, block);
+        }
+        string.push_str("---focus---\n");
+        if let Some(focused_block) = self.next_blocks.first() {
+            write_block(&mut string, focused_block);
+        }
+        string.push_str("-----------\n");
+        for block in self.next_blocks.iter().skip(1) {
+            write_block(&mut string, block);
+        }
+        string
+    }
+
+    /// Every segment and chunk on one line, with a `/` where the focused block starts.
+    pub fn long(&self) -> String {
+        fn write_block(string: &mut String, block: &DebugBlock) {
+            for segment in &block.segments {
+                string.push_str(&format!("'{}' ", segment));
+            }
+            if block.at_eol {
+                string.push_str("EOL ");
+            }
+            for chunk in &block.chunks {
+                string.push_str(&format!("{} ", chunk));
+            }
         }
 
+        let mut string = String::new();
         for block in &self.prev_blocks {
-            print_block(block);
+            write_block(&mut string, block);
         }
-        println!("---focus---");
-        if let Some(focused_block) = self.next_blocks.last() {
-            print_block(focused_block);
+        string.push_str(" / ");
+        for block in &self.next_blocks {
+            write_block(&mut string, block);
         }
-        println!("-----------");
-        for block in self.next_blocks.iter().rev().skip(1) {
-            print_block(block);
+        string
+    }
+}
+
+/// A secondary focus target from `pretty_print_multi`.
+#[derive(Debug, Clone)]
+struct Target<Id> {
+    /// The id of the node at the target's path.
+    id: Id,
+    focus_target: FocusTarget,
+    /// Whether the node's text was already found. (`seek_text` can split it in two.)
+    seen_text: bool,
+}
+
+/// A position of interest within a node, which the printer records when it passes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Landmark {
+    Start,
+    End,
+    Mark,
+    /// The position in the node's text that its target asked for.
+    Text,
+}
+
+impl<Id> Target<Id> {
+    /// Find this target's position, given the `landmarks` of its node that were found in the
+    /// printed region, in document order.
+    fn locate(&self, landmarks: &[(Landmark, (isize, Col))]) -> Option<(isize, Col)> {
+        let find = |landmark: Landmark| landmarks.iter().position(|(l, _)| *l == landmark);
+        let marks = |range: &[(Landmark, (isize, Col))]| {
+            range
+                .iter()
+                .filter(|(l, _)| *l == Landmark::Mark)
+                .map(|(_, pos)| *pos)
+                .collect::<Vec<_>>()
+        };
+        let nth_mark = |n: usize| marks(&landmarks[find(Landmark::Start)?..]).get(n).copied();
+
+        match self.focus_target {
+            FocusTarget::Start => find(Landmark::Start).map(|i| landmarks[i].1),
+            FocusTarget::End => find(Landmark::End).map(|i| landmarks[i].1),
+            FocusTarget::Text(_) | FocusTarget::TextEnd | FocusTarget::TextOffset(_, _) => {
+                find(Landmark::Text).map(|i| landmarks[i].1)
+            }
+            FocusTarget::Mark => nth_mark(0),
+            FocusTarget::NthMark(n) => nth_mark(n),
+            FocusTarget::LastMark => marks(&landmarks[..find(Landmark::End)?]).last().copied(),
         }
-        println!();
     }
 }
 
-/// Determine whether the first line of the notations (`notation` followed by `chunks`) fits within
-/// the available `width`, and does not cause there to be an EOL followed by text.
+/// Determine whether the first line of the notations (`notation` followed by the `block`'s chunks)
+/// fits within the available `width`, and does not cause there to be an EOL followed by text. If
+/// `max_lookahead` is given, look at no more than that many of the block's chunks, and
+/// optimistically assume that the rest fits.
 fn fits<'d, D: PrettyDoc<'d>>(
     width: Width,
-    at_eol: bool,
+    block: &Block<'d, D>,
     notation: ConsolidatedNotation<'d, D>,
-    next_chunks: &[Chunk<'d, D>],
+    first_lines: &LayoutCache<D::Id>,
+    max_lookahead: Option<usize>,
+    stats: &mut PrintStats,
 ) -> Result<bool, PrintingError<D::Error>> {
     use ConsolidatedNotation::*;
     span!("fits");
 
-    let mut next_chunks = next_chunks;
+    let mut next_chunks = block.chunks.as_slice();
     let mut remaining = width;
     let mut notations = vec![notation];
-    let mut at_eol = at_eol;
+    let mut at_eol = block.at_eol;
+    // A soft space is only counted once it's followed by text on the same line.
+    let mut pending_space = block.trailing_space;
+    let mut has_content = block.has_content();
+    let mut lookahead = 0;
+    stats.fits_calls += 1;
 
     loop {
         let notation = match notations.pop() {
             Some(notation) => notation,
             None => match next_chunks.split_last() {
                 None => return Ok(true),
+                Some(_) if Some(lookahead) == max_lookahead => {
+                    stats.lookahead_cutoffs += 1;
+                    return Ok(true);
+                }
                 Some((chunk, more)) => {
                     next_chunks = more;
+                    lookahead += 1;
+                    stats.lookahead_chunks += 1;
+                    stats.max_lookahead_chunks = stats.max_lookahead_chunks.max(lookahead);
                     chunk.notation.clone()
                 }
             },
         };
+        stats.fits_steps += 1;
 
         match notation {
             Empty | FocusMark => (),
+            // Padding isn't counted.
+            Textual(textual) if textual.pad_to.is_some() => (),
+            Textual(textual) if textual.is_soft_space => {
+                if has_content && !at_eol {
+                    pending_space = true;
+                }
+            }
             Textual(textual) => {
                 if at_eol {
                     return Ok(false);
                 }
-                if textual.width <= remaining {
-                    remaining -= textual.width;
+                let mut text_width = textual.width;
+                if !textual.str.is_empty() {
+                    text_width += pending_space as Width;
+                    pending_space = false;
+                    has_content = true;
+                }
+                if text_width <= remaining {
+                    remaining -= text_width;
                 } else {
                     return Ok(false);
                 }
@@ -804,12 +3328,241 @@ fn fits<'d, D: PrettyDoc<'d>>(
                 // by picking opt1 if we're in a Flat
                 notations.push(opt2.eval()?);
             }
+            Cache(note) => {
+                let first_line = FirstLine::lookup(note, remaining, first_lines)?;
+                if first_line.has_text_after_eol || (at_eol && first_line.has_text) {
+                    return Ok(false);
+                }
+                let space_width = (first_line.has_content
+                    && (pending_space || (first_line.leading_space && has_content)))
+                    as Width;
+                if first_line.width + space_width > remaining {
+                    return Ok(false);
+                }
+                remaining -= first_line.width + space_width;
+                if first_line.has_content {
+                    pending_space = first_line.trailing_space;
+                    has_content = true;
+                } else if first_line.leading_space && has_content && !at_eol {
+                    pending_space = true;
+                }
+                if first_line.has_newline {
+                    return Ok(true);
+                }
+                at_eol = at_eol || first_line.has_eol;
+            }
+        }
+    }
+}
+
+/// The fewest newlines that `notation` could print, picking whichever option of each choice has
+/// fewer. Stops counting once there are more than `limit`.
+fn min_newlines<'d, D: PrettyDoc<'d>>(
+    notation: ConsolidatedNotation<'d, D>,
+    limit: usize,
+) -> Result<usize, PrintingError<D::Error>> {
+    use ConsolidatedNotation::*;
+
+    let mut count = 0;
+    let mut notations = vec![notation];
+    while let Some(notation) = notations.pop() {
+        if count > limit {
+            break;
+        }
+        match notation {
+            Empty | FocusMark | EndOfLine | Textual(_) => (),
+            Newline(_) => count += 1,
+            Child(_, note) | Cache(note) => notations.push(note.eval()?),
+            Concat(note1, note2) => {
+                notations.push(note2.eval()?);
+                notations.push(note1.eval()?);
+            }
+            Choice(opt1, opt2) => {
+                let remaining = limit - count;
+                let count1 = min_newlines(opt1.eval()?, remaining)?;
+                count += count1.min(min_newlines(opt2.eval()?, count1.min(remaining))?);
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Measurements of the layouts of a document's [`Notation::Cache`] subtrees, which can be kept
+/// across calls by setting [`PrintOptions::layout_cache`]. An editor that redraws a document after
+/// every keystroke can keep one `LayoutCache` per document, so that each redraw only measures the
+/// parts of the document that changed.
+///
+/// Each measurement is keyed by the id of the document node it came from, so when you edit the
+/// document you must [`invalidate()`](LayoutCache::invalidate) the node you edited _and all of its
+/// ancestors_, since their layouts contain it. (Or, if it's simpler, [`clear()`](LayoutCache::clear)
+/// the whole cache.) The measurements don't depend on the printing width. Cloning a `LayoutCache`
+/// gives another handle to the same cache.
+#[derive(Debug)]
+pub struct LayoutCache<Id: Eq + Hash> {
+    first_lines: Rc<RefCell<HashMap<CacheKey<Id>, FirstLine>>>,
+}
+
+impl<Id: Eq + Hash + Copy> LayoutCache<Id> {
+    /// Construct an empty `LayoutCache`.
+    pub fn new() -> LayoutCache<Id> {
+        LayoutCache {
+            first_lines: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Forget every measurement of the node with this `id`. Call this on every node whose contents
+    /// (including its descendants) changed since it was last printed.
+    pub fn invalidate(&self, id: Id) {
+        self.first_lines
+            .borrow_mut()
+            .retain(|key, _| key.id() != id);
+    }
+
+    /// Forget every measurement.
+    pub fn clear(&self) {
+        self.first_lines.borrow_mut().clear();
+    }
+
+    /// The number of measurements in the cache.
+    pub fn len(&self) -> usize {
+        self.first_lines.borrow().len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.first_lines.borrow().is_empty()
+    }
+}
+
+impl<Id: Eq + Hash> Clone for LayoutCache<Id> {
+    fn clone(&self) -> Self {
+        LayoutCache {
+            first_lines: self.first_lines.clone(),
+        }
+    }
+}
+
+impl<Id: Eq + Hash + Copy> Default for LayoutCache<Id> {
+    fn default() -> Self {
+        LayoutCache::new()
+    }
+}
+
+/// Everything that `fits` needs to know about the first line of a `Notation::Cache` subtree. Since
+/// `fits` always picks the second option of a `Choice`, this doesn't depend on the width.
+///
+/// This is all that gets memoized for a `Cache`. Memoizing its resolved blocks by (id, width
+/// remaining, flat) instead wouldn't be sound: the choices on its last line are made by looking at
+/// the chunks that follow it, which differ from one occurrence to the next.
+#[derive(Debug, Clone, Copy, Default)]
+struct FirstLine {
+    /// The total width of the text before the first `Newline`. If `complete` is false, the actual
+    /// width is larger than this.
+    width: Width,
+    /// Whether the whole first line was measured, or measuring stopped early because it was too
+    /// wide.
+    complete: bool,
+    /// Whether there's any `Textual` before the first `Newline`, not counting soft spaces.
+    has_text: bool,
+    /// Whether there's any non-empty text before the first `Newline`, not counting soft spaces.
+    has_content: bool,
+    /// Whether there's a soft space before all of that text. It isn't counted in `width`, since
+    /// whether it's displayed depends on what comes before.
+    leading_space: bool,
+    /// Whether there's a soft space after all of that text. It isn't counted in `width`, since
+    /// whether it's displayed depends on what comes after.
+    trailing_space: bool,
+    /// Whether there's an `EndOfLine` before the first `Newline`.
+    has_eol: bool,
+    /// Whether there's an `EndOfLine` followed by a `Textual` before the first `Newline`.
+    has_text_after_eol: bool,
+    /// Whether there's a `Newline` at all.
+    has_newline: bool,
+}
+
+impl FirstLine {
+    /// Look up the first line of `note` in `first_lines`, measuring it if it isn't there. If it's
+    /// wider than `limit`, it's fine to stop measuring early.
+    fn lookup<'d, D: PrettyDoc<'d>>(
+        note: DelayedConsolidatedNotation<'d, D>,
+        limit: Width,
+        first_lines: &LayoutCache<D::Id>,
+    ) -> Result<FirstLine, PrintingError<D::Error>> {
+        let key = note.cache_key()?;
+        if let Some(first_line) = first_lines.first_lines.borrow().get(&key) {
+            if first_line.complete || first_line.width > limit {
+                return Ok(*first_line);
+            }
+        }
+        let first_line = FirstLine::measure(note.eval()?, limit)?;
+        first_lines.first_lines.borrow_mut().insert(key, first_line);
+        Ok(first_line)
+    }
+
+    fn measure<'d, D: PrettyDoc<'d>>(
+        notation: ConsolidatedNotation<'d, D>,
+        limit: Width,
+    ) -> Result<FirstLine, PrintingError<D::Error>> {
+        use ConsolidatedNotation::*;
+        span!("measure_first_line");
+
+        let mut first_line = FirstLine {
+            complete: true,
+            ..FirstLine::default()
+        };
+        let mut notations = vec![notation];
+        while let Some(notation) = notations.pop() {
+            match notation {
+                Empty | FocusMark => (),
+                Textual(textual) if textual.pad_to.is_some() => (),
+                Textual(textual) if textual.is_soft_space => {
+                    if first_line.has_eol {
+                        // Dropped
+                    } else if first_line.has_content {
+                        first_line.trailing_space = true;
+                    } else {
+                        first_line.leading_space = true;
+                    }
+                }
+                Textual(textual) => {
+                    if first_line.has_eol {
+                        first_line.has_text_after_eol = true;
+                        return Ok(first_line);
+                    }
+                    first_line.has_text = true;
+                    let mut text_width = textual.width;
+                    if !textual.str.is_empty() {
+                        text_width += first_line.trailing_space as Width;
+                        first_line.trailing_space = false;
+                        first_line.has_content = true;
+                    }
+                    first_line.width = first_line.width.saturating_add(text_width);
+                    if first_line.width > limit {
+                        first_line.complete = false;
+                        return Ok(first_line);
+                    }
+                }
+                EndOfLine => first_line.has_eol = true,
+                Newline(_) => {
+                    first_line.has_newline = true;
+                    return Ok(first_line);
+                }
+                Child(_, note) | Cache(note) => notations.push(note.eval()?),
+                Concat(note1, note2) => {
+                    notations.push(note2.eval()?);
+                    notations.push(note1.eval()?);
+                }
+                Choice(_opt1, opt2) => notations.push(opt2.eval()?),
+            }
         }
+        Ok(first_line)
     }
 }
 
-/// An iterator for printing lines above the focused line.
-struct UpwardPrinter<'d, D: PrettyDoc<'d>>(Printer<'d, D>);
+/// An iterator that prints the lines above the focused line, going up. Returned by
+/// [`pretty_print()`] and its variants. Unlike an `impl Iterator`, it can be named, so it can be
+/// kept in a struct (say, to print more lines as the user scrolls).
+pub struct UpwardPrinter<'d, D: PrettyDoc<'d>>(Printer<'d, D>);
 
 impl<'d, D: PrettyDoc<'d>> Iterator for UpwardPrinter<'d, D> {
     type Item = Result<Line<'d, D>, PrintingError<D::Error>>;
@@ -819,8 +3572,9 @@ impl<'d, D: PrettyDoc<'d>> Iterator for UpwardPrinter<'d, D> {
     }
 }
 
-/// An iterator for printing lines below the focused line.
-struct DownwardPrinter<'d, D: PrettyDoc<'d>>(Printer<'d, D>);
+/// An iterator that prints the lines below the focused line, going down. Returned by
+/// [`pretty_print()`] and its variants.
+pub struct DownwardPrinter<'d, D: PrettyDoc<'d>>(Printer<'d, D>);
 
 impl<'d, D: PrettyDoc<'d>> Iterator for DownwardPrinter<'d, D> {
     type Item = Result<Line<'d, D>, PrintingError<D::Error>>;
@@ -829,3 +3583,222 @@ impl<'d, D: PrettyDoc<'d>> Iterator for DownwardPrinter<'d, D> {
         self.0.print_next_line().transpose()
     }
 }
+
+impl<'d, D: PrettyDoc<'d>> UpwardPrinter<'d, D> {
+    /// Save where this printer is in the document, so that it can go back there later with
+    /// [`restore_state()`](UpwardPrinter::restore_state).
+    pub fn save_state(&self) -> UpwardState<'d, D> {
+        UpwardState(self.0.save_state())
+    }
+
+    /// Go back to a state saved by [`save_state()`](UpwardPrinter::save_state), so that this
+    /// printer prints the same lines again from that point. The state must have come from this
+    /// printer (or another `UpwardPrinter` for the same document, width, and options).
+    pub fn restore_state(&mut self, state: UpwardState<'d, D>) {
+        self.0.restore_state(state.0)
+    }
+
+    /// Save where this printer is in the document as plain data, so that printing can be resumed
+    /// later, or on another thread, with [`resume()`](UpwardPrinter::resume).
+    pub fn checkpoint(&self) -> UpwardCheckpoint {
+        UpwardCheckpoint(self.0.checkpoint())
+    }
+
+    /// Construct a printer that prints the same lines as the one that made `checkpoint` would have
+    /// printed next. This prints the document again from its focus up to the checkpoint, so it
+    /// takes time proportional to the number of lines that printer had printed. The `doc`, the
+    /// `root_style`, and the `options` must be the same as when that printer was made.
+    pub fn resume(
+        doc: D,
+        checkpoint: &UpwardCheckpoint,
+        root_style: Option<&D::Style>,
+        options: &PrintOptions<'d, D>,
+    ) -> Result<UpwardPrinter<'d, D>, PrintingError<D::Error>> {
+        let (mut printer, _, _) = print_around_focus(checkpoint.0.seek(doc, root_style, options)?)?;
+        while printer.0.row > checkpoint.0.row {
+            if printer.0.print_prev_line()?.is_none() {
+                break;
+            }
+        }
+        Ok(printer)
+    }
+
+    /// If [`PrintOptions::fall_back_on_invalid_path`] was set and the path didn't exist, which
+    /// ancestor was focused instead.
+    pub fn path_fallback(&self) -> Option<&PathFallback> {
+        self.0.path_fallback.as_ref()
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> DownwardPrinter<'d, D> {
+    /// Save where this printer is in the document, so that it can go back there later with
+    /// [`restore_state()`](DownwardPrinter::restore_state).
+    pub fn save_state(&self) -> DownwardState<'d, D> {
+        DownwardState(self.0.save_state())
+    }
+
+    /// Go back to a state saved by [`save_state()`](DownwardPrinter::save_state), so that this
+    /// printer prints the same lines again from that point. The state must have come from this
+    /// printer (or another `DownwardPrinter` for the same document, width, and options).
+    pub fn restore_state(&mut self, state: DownwardState<'d, D>) {
+        self.0.restore_state(state.0)
+    }
+
+    /// Save where this printer is in the document as plain data, so that printing can be resumed
+    /// later, or on another thread, with [`resume()`](DownwardPrinter::resume).
+    pub fn checkpoint(&self) -> DownwardCheckpoint {
+        DownwardCheckpoint(self.0.checkpoint())
+    }
+
+    /// Construct a printer that prints the same lines as the one that made `checkpoint` would have
+    /// printed next. This prints the document again from its focus down to the checkpoint, so it
+    /// takes time proportional to the number of lines that printer had printed. The `doc`, the
+    /// `root_style`, and the `options` must be the same as when that printer was made.
+    pub fn resume(
+        doc: D,
+        checkpoint: &DownwardCheckpoint,
+        root_style: Option<&D::Style>,
+        options: &PrintOptions<'d, D>,
+    ) -> Result<DownwardPrinter<'d, D>, PrintingError<D::Error>> {
+        let (_, _, mut printer) = print_around_focus(checkpoint.0.seek(doc, root_style, options)?)?;
+        while printer.0.row < checkpoint.0.row {
+            if printer.0.print_next_line()?.is_none() {
+                break;
+            }
+        }
+        Ok(printer)
+    }
+
+    /// If [`PrintOptions::fall_back_on_invalid_path`] was set and the path didn't exist, which
+    /// ancestor was focused instead.
+    pub fn path_fallback(&self) -> Option<&PathFallback> {
+        self.0.path_fallback.as_ref()
+    }
+
+    /// How much work printing has taken so far, including seeking to the focus and printing the
+    /// focused line. Print the rest of the lines first to get the totals for the whole document.
+    pub fn stats(&self) -> PrintStats {
+        self.0.stats
+    }
+}
+
+/// A checkpoint of where an [`UpwardPrinter`] is in the document, from
+/// [`UpwardPrinter::save_state()`].
+///
+/// This is an in-memory copy of the printer's stacks of partially printed lines, which share the
+/// document's notations through reference counting. So it can't outlive the document, can't be
+/// sent to another thread, and can't be stored to resume printing in a later session; for that,
+/// use an [`UpwardCheckpoint`] instead.
+pub struct UpwardState<'d, D: PrettyDoc<'d>>(PrinterState<'d, D>);
+
+/// A checkpoint of where a [`DownwardPrinter`] is in the document, from
+/// [`DownwardPrinter::save_state()`]. It has the same limitations as an [`UpwardState`].
+pub struct DownwardState<'d, D: PrettyDoc<'d>>(PrinterState<'d, D>);
+
+/// A checkpoint of where an [`UpwardPrinter`] is in the document, from
+/// [`UpwardPrinter::checkpoint()`]. Unlike an [`UpwardState`], this is plain data: the path and
+/// focus target that the printer started from, and how many lines it has printed since. So it can
+/// be sent to another thread, or (with the `serialization` feature) stored, and printing resumed
+/// from it with [`UpwardPrinter::resume()`] as long as the document hasn't changed. Resuming
+/// prints those lines again, though.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct UpwardCheckpoint(Checkpoint);
+
+/// A checkpoint of where a [`DownwardPrinter`] is in the document, from
+/// [`DownwardPrinter::checkpoint()`]. It works like an [`UpwardCheckpoint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+pub struct DownwardCheckpoint(Checkpoint);
+
+/// What a `Printer` sought to, from `seek_origin`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+struct Origin {
+    /// The path given to the printing function (before falling back to an ancestor, if it did).
+    path: Vec<usize>,
+    focus_target: FocusTarget,
+    /// How many lines down the focus was moved after seeking, for `pretty_print_near_line`.
+    skipped_lines: usize,
+}
+
+/// Where a `Printer` is in the document, as plain data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
+struct Checkpoint {
+    width: Width,
+    origin: Origin,
+    /// The printer's `row`.
+    row: isize,
+}
+
+impl Checkpoint {
+    /// Construct a printer and seek to where the checkpointed printer sought to.
+    fn seek<'d, D: PrettyDoc<'d>>(
+        &self,
+        doc: D,
+        root_style: Option<&D::Style>,
+        options: &PrintOptions<'d, D>,
+    ) -> Result<Printer<'d, D>, PrintingError<D::Error>> {
+        seek_origin(doc, self.width, self.origin.clone(), root_style, options)
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for UpwardState<'d, D> {
+    fn clone(&self) -> Self {
+        UpwardState(self.0.clone())
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for DownwardState<'d, D> {
+    fn clone(&self) -> Self {
+        DownwardState(self.0.clone())
+    }
+}
+
+/// Where a `Printer` is in the document: its stacks of partially printed lines above and below.
+struct PrinterState<'d, D: PrettyDoc<'d>> {
+    prev_blocks: Vec<Block<'d, D>>,
+    next_blocks: Vec<Block<'d, D>>,
+    prev_queue: Vec<Block<'d, D>>,
+    row: isize,
+    blank_run: usize,
+    choice_hash: Option<u64>,
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for PrinterState<'d, D> {
+    fn clone(&self) -> Self {
+        PrinterState {
+            prev_blocks: self.prev_blocks.clone(),
+            next_blocks: self.next_blocks.clone(),
+            prev_queue: self.prev_queue.clone(),
+            row: self.row,
+            blank_run: self.blank_run,
+            choice_hash: self.choice_hash,
+        }
+    }
+}
+
+/// An iterator that prints the blocks above the focused block, going up. Returned by
+/// [`pretty_print_blocks()`].
+pub struct UpwardBlockPrinter<'d, D: PrettyDoc<'d>>(Printer<'d, D>);
+
+impl<'d, D: PrettyDoc<'d>> Iterator for UpwardBlockPrinter<'d, D> {
+    type Item = Result<ResolvedBlock<'d, D>, PrintingError<D::Error>>;
+
+    fn next(&mut self) -> Option<Result<ResolvedBlock<'d, D>, PrintingError<D::Error>>> {
+        self.0.print_prev_block().transpose()
+    }
+}
+
+/// An iterator that prints the blocks below the focused block, going down. Returned by
+/// [`pretty_print_blocks()`].
+pub struct DownwardBlockPrinter<'d, D: PrettyDoc<'d>>(Printer<'d, D>);
+
+impl<'d, D: PrettyDoc<'d>> Iterator for DownwardBlockPrinter<'d, D> {
+    type Item = Result<ResolvedBlock<'d, D>, PrintingError<D::Error>>;
+
+    fn next(&mut self) -> Option<Result<ResolvedBlock<'d, D>, PrintingError<D::Error>>> {
+        self.0.print_next_block().transpose()
+    }
+}
@@ -0,0 +1,269 @@
+//! Keep paths into a document pointing at the right node after the document is edited, and
+//! convert between paths and node ids (or, roughly, line numbers).
+
+use crate::infra::HashMap;
+use crate::{PrettyDoc, PrintingError};
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+/// A change to the structure of a document, for [`reanchor()`]. Paths are sequences of child
+/// indices starting from the root, as in [`pretty_print()`](crate::pretty_print).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathEdit {
+    /// A new child was inserted into the node at `parent`, so that it's now at `index`.
+    Insert { parent: Vec<usize>, index: usize },
+    /// The child at `index` of the node at `parent` was removed.
+    Delete { parent: Vec<usize>, index: usize },
+    /// The node at `path` was replaced by a different node, so nothing inside of it can be
+    /// trusted to still exist.
+    Replace { path: Vec<usize> },
+}
+
+/// Given a `path` into a document from before the `edit`, find the path to the same node after
+/// the edit. If that node no longer exists, find the path to its nearest surviving ancestor
+/// instead.
+pub fn reanchor(path: &[usize], edit: &PathEdit) -> Vec<usize> {
+    match edit {
+        PathEdit::Insert { parent, index } => match path.strip_prefix(parent.as_slice()) {
+            Some([child, ..]) if child >= index => {
+                let mut new_path = path.to_vec();
+                new_path[parent.len()] += 1;
+                new_path
+            }
+            _ => path.to_vec(),
+        },
+        PathEdit::Delete { parent, index } => match path.strip_prefix(parent.as_slice()) {
+            Some([child, ..]) if child == index => parent.clone(),
+            Some([child, ..]) if child > index => {
+                let mut new_path = path.to_vec();
+                new_path[parent.len()] -= 1;
+                new_path
+            }
+            _ => path.to_vec(),
+        },
+        PathEdit::Replace { path: replaced } => {
+            if path.starts_with(replaced) {
+                replaced.clone()
+            } else {
+                path.to_vec()
+            }
+        }
+    }
+}
+
+/// What the printer did when given a path that didn't exist in the document, with
+/// [`PrintOptions::fall_back_on_invalid_path`](crate::PrintOptions::fall_back_on_invalid_path)
+/// set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathFallback {
+    /// The path to the nearest ancestor that did exist. The focus was put at its start.
+    pub path: Vec<usize>,
+    /// The child index, within that ancestor, that didn't exist.
+    pub invalid_child_index: usize,
+}
+
+/// Check whether `path` leads to a node in `doc`. If it doesn't, the
+/// [`PrintingError::InvalidPath`] says how far it got, so that you can repair the path before
+/// printing.
+pub fn validate_path<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    path: &[usize],
+) -> Result<(), PrintingError<D::Error>> {
+    find_node(doc, path).map(|_| ())
+}
+
+/// The id of the node at `path` in `doc`, or [`PrintingError::InvalidPath`] if there isn't one.
+pub fn id_at_path<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    path: &[usize],
+) -> Result<D::Id, PrintingError<D::Error>> {
+    Ok(find_node(doc, path)?.id()?)
+}
+
+/// The path to the node with the given `id` in `doc`, or `None` if there isn't one. Like every
+/// path, it's made of child indices as in [`PrettyDoc::unwrap_child()`], which are never negative
+/// and don't depend on the order that notations display the children in.
+///
+/// This searches the document, so it takes time linear in its size. To look up many ids, build a
+/// [`PathIndex`] instead.
+pub fn path_of_id<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    id: D::Id,
+) -> Result<Option<Vec<usize>>, PrintingError<D::Error>> {
+    let mut found = None;
+    walk_paths(doc, |node_id, path| {
+        if node_id == id {
+            found = Some(path.to_vec());
+        }
+        found.is_none()
+    })?;
+    Ok(found)
+}
+
+/// A map from node ids to paths, for looking up many ids in a large document, as in
+/// [`path_of_id()`]. It's a snapshot: rebuild it after the document's structure changes.
+#[derive(Debug, Clone)]
+pub struct PathIndex<Id: Eq + Hash> {
+    paths: HashMap<Id, Vec<usize>>,
+}
+
+impl<Id: Eq + Hash> PathIndex<Id> {
+    /// Index every node in `doc`.
+    pub fn new<'d, D: PrettyDoc<'d, Id = Id>>(doc: D) -> Result<Self, PrintingError<D::Error>> {
+        let mut paths = HashMap::new();
+        walk_paths(doc, |id, path| {
+            paths.insert(id, path.to_vec());
+            true
+        })?;
+        Ok(PathIndex { paths })
+    }
+
+    /// The path to the node with the given `id`, or `None` if it wasn't in the document.
+    pub fn path_of(&self, id: &Id) -> Option<&[usize]> {
+        self.paths.get(id).map(|path| path.as_slice())
+    }
+
+    /// The number of nodes in the document.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Whether the document had no nodes. (It always has at least its root.)
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+}
+
+/// Estimate which node `line` (counting from 0 at the top of the document) falls in, without
+/// printing the lines above it. Returns the path to the innermost such node, and roughly how many
+/// lines into that node `line` is. To seek near `line` (for example, when the user drags a
+/// scrollbar), pass the path to [`pretty_print()`](crate::pretty_print) with
+/// [`FocusTarget::Start`](crate::FocusTarget::Start), and scroll down by that many lines.
+///
+/// The estimate assumes that children are displayed in order, each taking up
+/// [`PrettyDoc::approx_height_hint()`] lines. Nodes without a hint are estimated from their
+/// descendants, with one line for each leaf. So if the large subtrees have hints, this takes time
+/// proportional to the depth of the node (times the number of children along the way), instead of
+/// the number of lines above it.
+pub fn path_near_line<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    line: usize,
+) -> Result<(Vec<usize>, usize), PrintingError<D::Error>> {
+    let mut path = Vec::new();
+    let mut node = doc;
+    let mut offset = line;
+    'descend: while let Some(num_children) = node.num_children()? {
+        let mut child_offset = offset;
+        for index in 0..num_children {
+            let child = node.unwrap_child(index)?;
+            let height = approx_height(child, child_offset)?;
+            if child_offset < height {
+                path.push(index);
+                node = child;
+                offset = child_offset;
+                continue 'descend;
+            }
+            child_offset -= height;
+        }
+        // The line is past the end of the children (according to the estimates).
+        break;
+    }
+    Ok((path, offset))
+}
+
+/// Estimate how many lines `doc` takes up, for `path_near_line()`. Stop early once the estimate
+/// is more than `limit`, since then `path_near_line()` descends into `doc` and only needs to know
+/// that. This doesn't recurse, so it works on very deep documents.
+fn approx_height<'d, D: PrettyDoc<'d>>(doc: D, limit: usize) -> Result<usize, D::Error> {
+    let mut height = 0;
+    let mut stack = vec![doc];
+    while let Some(node) = stack.pop() {
+        if height > limit {
+            break;
+        }
+        if let Some(hint) = node.approx_height_hint()? {
+            height += hint;
+            continue;
+        }
+        match node.num_children()? {
+            None | Some(0) => height += 1,
+            Some(num_children) => {
+                for index in 0..num_children {
+                    stack.push(node.unwrap_child(index)?);
+                }
+            }
+        }
+    }
+    Ok(height)
+}
+
+/// Call `visit` with the id and path of every node in `doc`, in depth-first order, until it
+/// returns false. This doesn't recurse, so it works on very deep documents.
+fn walk_paths<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    mut visit: impl FnMut(D::Id, &[usize]) -> bool,
+) -> Result<(), PrintingError<D::Error>> {
+    let mut path = Vec::new();
+    // The nodes whose children are being visited, and the index of the next child to visit.
+    let mut stack = vec![(doc, 0)];
+    if !visit(doc.id()?, &path) {
+        return Ok(());
+    }
+    while let Some((node, index)) = stack.last_mut() {
+        let node = *node;
+        if *index < node.num_children()?.unwrap_or(0) {
+            let child = node.unwrap_child(*index)?;
+            path.push(*index);
+            *index += 1;
+            if !visit(child.id()?, &path) {
+                return Ok(());
+            }
+            stack.push((child, 0));
+        } else {
+            stack.pop();
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+/// Find the node at `path` in `doc`, or fail with [`PrintingError::InvalidPath`].
+pub(crate) fn find_node<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    path: &[usize],
+) -> Result<D, PrintingError<D::Error>> {
+    let mut node = doc;
+    for (depth, child_index) in path.iter().enumerate() {
+        match node.num_children()? {
+            Some(len) if *child_index < len => node = node.unwrap_child(*child_index)?,
+            num_children => {
+                return Err(PrintingError::InvalidPath {
+                    valid_prefix: path[..depth].to_vec(),
+                    index: *child_index,
+                    num_children,
+                })
+            }
+        }
+    }
+    Ok(node)
+}
+
+/// Find the longest prefix of `path` that exists in `doc`. If it's shorter than `path`, return
+/// a `PathFallback` describing it.
+pub(crate) fn find_path_fallback<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    path: &[usize],
+) -> Result<Option<PathFallback>, PrintingError<D::Error>> {
+    match validate_path(doc, path) {
+        Ok(()) => Ok(None),
+        Err(PrintingError::InvalidPath {
+            valid_prefix,
+            index,
+            ..
+        }) => Ok(Some(PathFallback {
+            path: valid_prefix,
+            invalid_child_index: index,
+        })),
+        Err(err) => Err(err),
+    }
+}
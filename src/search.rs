@@ -0,0 +1,182 @@
+//! Step through the results of a search (such as a structural search for nodes matching some
+//! pattern) in the order that they're displayed.
+
+use crate::infra::{HashMap, HashSet};
+use crate::{
+    consolidated_notation::{ConsolidatedNotation, DelayedConsolidatedNotation},
+    reanchor::find_node,
+    PrettyDoc, PrintingError,
+};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Nodes to display in a "match" style, such as the results of a search. Set
+/// [`PrintOptions::matches`](crate::PrintOptions::matches), or
+/// [`PrintingOptions::matches`](crate::pane::PrintingOptions::matches).
+#[derive(Debug, Clone)]
+pub struct Matches<S> {
+    /// The paths to the matching nodes. Paths that don't exist in the document are ignored.
+    pub paths: Vec<Vec<usize>>,
+    /// The style to display each matching node in. It's combined with the node's own style, and
+    /// applies to the whole node, including its descendants.
+    pub style: S,
+}
+
+/// The ids of the nodes in a `Matches`, for the printer to check each node against as it enters
+/// it.
+#[derive(Debug)]
+pub(crate) struct MatchStyle<Id, S> {
+    pub ids: HashSet<Id>,
+    pub style: S,
+}
+
+impl<S: Clone> Matches<S> {
+    pub(crate) fn resolve<'d, D: PrettyDoc<'d, Style = S>>(
+        &self,
+        doc: D,
+    ) -> Result<MatchStyle<D::Id, S>, PrintingError<D::Error>> {
+        let mut ids = HashSet::new();
+        for path in &self.paths {
+            match find_node(doc, path) {
+                Ok(node) => {
+                    ids.insert(node.id()?);
+                }
+                Err(PrintingError::InvalidPath { .. }) => (),
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(MatchStyle {
+            ids,
+            style: self.style.clone(),
+        })
+    }
+}
+
+/// Compare the positions of the nodes at paths `a` and `b` in the printed document, by where they
+/// start. This is the order that the nodes' notations display them in, which may differ from the
+/// order of their child indices: for example, a [`Notation::Fold`](crate::Notation::Fold) can
+/// display its children in reverse. A node comes before its descendants.
+///
+/// If a notation doesn't display one of the two children being compared, that child comes after
+/// the displayed one. Children that are both hidden are compared by index.
+pub fn cmp_layout_order<'d, D: PrettyDoc<'d>>(
+    doc: D,
+    a: &[usize],
+    b: &[usize],
+) -> Result<Ordering, PrintingError<D::Error>> {
+    LayoutOrder::default().cmp(doc, a, b)
+}
+
+/// The first of the `matches` that comes after `path` in the printed document, as ordered by
+/// [`cmp_layout_order()`], or `None` if there isn't one. `path` doesn't need to be one of the
+/// `matches`.
+pub fn next_match_after<'a, 'd, D: PrettyDoc<'d>>(
+    doc: D,
+    matches: &'a [Vec<usize>],
+    path: &[usize],
+) -> Result<Option<&'a [usize]>, PrintingError<D::Error>> {
+    LayoutOrder::default().nearest(doc, matches, path, Ordering::Greater)
+}
+
+/// The last of the `matches` that comes before `path` in the printed document, as ordered by
+/// [`cmp_layout_order()`], or `None` if there isn't one. `path` doesn't need to be one of the
+/// `matches`.
+pub fn prev_match_before<'a, 'd, D: PrettyDoc<'d>>(
+    doc: D,
+    matches: &'a [Vec<usize>],
+    path: &[usize],
+) -> Result<Option<&'a [usize]>, PrintingError<D::Error>> {
+    LayoutOrder::default().nearest(doc, matches, path, Ordering::Less)
+}
+
+/// Compares paths by layout order, remembering the order of the children of each node it looks
+/// at.
+#[derive(Default)]
+struct LayoutOrder {
+    /// For each path to a node, the indices of its children in the order they're displayed.
+    child_orders: HashMap<Vec<usize>, Vec<usize>>,
+}
+
+impl LayoutOrder {
+    /// The match closest to `path` on the `side` of it.
+    fn nearest<'a, 'd, D: PrettyDoc<'d>>(
+        &mut self,
+        doc: D,
+        matches: &'a [Vec<usize>],
+        path: &[usize],
+        side: Ordering,
+    ) -> Result<Option<&'a [usize]>, PrintingError<D::Error>> {
+        let mut nearest: Option<&'a [usize]> = None;
+        for candidate in matches {
+            if self.cmp(doc, candidate, path)? != side {
+                continue;
+            }
+            let is_nearer = match nearest {
+                None => true,
+                Some(nearest) => self.cmp(doc, candidate, nearest)? == side.reverse(),
+            };
+            if is_nearer {
+                nearest = Some(candidate);
+            }
+        }
+        Ok(nearest)
+    }
+
+    fn cmp<'d, D: PrettyDoc<'d>>(
+        &mut self,
+        doc: D,
+        a: &[usize],
+        b: &[usize],
+    ) -> Result<Ordering, PrintingError<D::Error>> {
+        let common_len = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        if common_len == a.len() || common_len == b.len() {
+            return Ok(a.len().cmp(&b.len()));
+        }
+        let parent = &a[..common_len];
+        if !self.child_orders.contains_key(parent) {
+            let order = child_order(find_node(doc, parent)?)?;
+            self.child_orders.insert(parent.to_vec(), order);
+        }
+        let order = &self.child_orders[parent];
+        let position = |index: usize| {
+            let displayed_at = order.iter().position(|i| *i == index);
+            (displayed_at.unwrap_or(order.len()), index)
+        };
+        Ok(position(a[common_len]).cmp(&position(b[common_len])))
+    }
+}
+
+/// The indices of `node`'s children, in the order that its notation first displays them. Children
+/// that are in both options of a `Choice` are ordered as in the first option.
+fn child_order<'d, D: PrettyDoc<'d>>(node: D) -> Result<Vec<usize>, PrintingError<D::Error>> {
+    fn walk<'d, D: PrettyDoc<'d>>(
+        note: DelayedConsolidatedNotation<'d, D>,
+        order: &mut Vec<usize>,
+        seen: &mut HashSet<usize>,
+    ) -> Result<(), PrintingError<D::Error>> {
+        use ConsolidatedNotation::*;
+
+        match note.eval()? {
+            Empty | EndOfLine | Newline(_) | Textual(_) | FocusMark => (),
+            Concat(note1, note2) | Choice(note1, note2) => {
+                walk(note1, order, seen)?;
+                walk(note2, order, seen)?;
+            }
+            Cache(note) => walk(note, order, seen)?,
+            Child(index, _) => {
+                if seen.insert(index) {
+                    order.push(index);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    walk(
+        DelayedConsolidatedNotation::new(node)?,
+        &mut order,
+        &mut HashSet::new(),
+    )?;
+    Ok(order)
+}
@@ -0,0 +1,60 @@
+//! Synthetic documents of adjustable size, for benchmarking.
+
+use crate::doc_examples::json::{
+    json_array, json_comment, json_number, json_object, json_object_pair, json_roots, json_string,
+    Json,
+};
+
+const WORDS: &[&str] = &[
+    "lorem",
+    "ipsum",
+    "dolor",
+    "sit",
+    "amet",
+    "consectetur",
+    "adipiscing",
+    "elit",
+    "sed",
+    "do",
+    "eiusmod",
+    "tempor",
+];
+
+/// Construct a Json document that's nested `depth` levels deep. Each level is an object containing
+/// an `"id"` and a `"child"` (the next level down). At typical widths, it's printed on about
+/// `4 * depth` lines. The path to the innermost object is `[1, 1, ..., 1]` (`2 * depth` ones).
+pub fn make_deep_doc(depth: usize) -> Json {
+    let mut doc = json_object(Vec::new());
+    for id in (0..depth).rev() {
+        doc = json_object(vec![
+            json_object_pair("id", json_number(id as f64)),
+            json_object_pair("child", doc),
+        ]);
+    }
+    doc
+}
+
+/// Construct a Json array containing `n` small objects. At typical widths, each object fits on a
+/// single line, so it's printed on `n + 2` lines. The path to the i'th object is `[i]`.
+pub fn make_wide_doc(n: usize) -> Json {
+    json_array(
+        (0..n)
+            .map(|i| {
+                json_object(vec![
+                    json_object_pair("id", json_number(i as f64)),
+                    json_object_pair("name", json_string(&format!("item_{}", i))),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Construct a Json document containing a single comment with `words` words, which gets
+/// flow-wrapped to fill each line.
+pub fn make_flow_doc(words: usize) -> Json {
+    let text = (0..words)
+        .map(|i| WORDS[i % WORDS.len()])
+        .collect::<Vec<_>>()
+        .join(" ");
+    json_roots(vec![json_comment(&text)])
+}
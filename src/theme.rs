@@ -0,0 +1,211 @@
+use crate::{PrettyDoc, Style, StyleLabel, ValidNotation};
+use alloc::{boxed::Box, vec::Vec};
+use core::ops::Range;
+
+/// A mapping from style labels to concrete styles, which lets you render the same document in
+/// different color schemes without changing its [`PrettyDoc::lookup_style()`] implementation.
+///
+/// To use a theme, wrap your document with [`Theme::apply()`] and pass the result to
+/// [`pretty_print()`](crate::pretty_print) or [`display_pane()`](crate::pane::display_pane) in
+/// place of the document.
+///
+/// When looking up a label, the theme's own entries are checked first, then its fallback theme's
+/// (if any), and finally the document's own [`PrettyDoc::lookup_style()`].
+#[derive(Debug, Clone)]
+pub struct Theme<L: StyleLabel + PartialEq, S: Style> {
+    styles: Vec<(L, S)>,
+    fallback: Option<Box<Theme<L, S>>>,
+}
+
+impl<L: StyleLabel + PartialEq, S: Style> Theme<L, S> {
+    /// Construct a theme with no entries, which defers entirely to the document.
+    pub fn new() -> Self {
+        Theme {
+            styles: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    /// Map `label` to `style`. If `label` was already in the theme, its style is replaced.
+    pub fn with_style(mut self, label: L, style: S) -> Self {
+        self.styles.retain(|(l, _)| *l != label);
+        self.styles.push((label, style));
+        self
+    }
+
+    /// Consult `fallback` for any label that isn't in this theme.
+    pub fn with_fallback(mut self, fallback: Theme<L, S>) -> Self {
+        self.fallback = Some(Box::new(fallback));
+        self
+    }
+
+    /// Look up the style for `label` in this theme or its fallbacks. Returns `None` if none of
+    /// them contain it.
+    pub fn lookup(&self, label: &L) -> Option<&S> {
+        for (l, style) in &self.styles {
+            if l == label {
+                return Some(style);
+            }
+        }
+        self.fallback
+            .as_ref()
+            .and_then(|fallback| fallback.lookup(label))
+    }
+
+    /// Wrap a document so that its style labels get looked up in this theme.
+    pub fn apply<'d, D>(&'d self, doc: D) -> Themed<'d, D>
+    where
+        D: PrettyDoc<'d, StyleLabel = L, Style = S>,
+    {
+        Themed { doc, theme: self }
+    }
+}
+
+impl<L: StyleLabel + PartialEq, S: Style> Default for Theme<L, S> {
+    fn default() -> Self {
+        Theme::new()
+    }
+}
+
+/// A document whose style labels are looked up in a [`Theme`]. Construct one with
+/// [`Theme::apply()`].
+#[derive(Debug)]
+pub struct Themed<'d, D: PrettyDoc<'d>>
+where
+    D::StyleLabel: PartialEq,
+{
+    doc: D,
+    theme: &'d Theme<D::StyleLabel, D::Style>,
+}
+
+impl<'d, D: PrettyDoc<'d>> Themed<'d, D>
+where
+    D::StyleLabel: PartialEq,
+{
+    /// The underlying document node.
+    pub fn inner(self) -> D {
+        self.doc
+    }
+
+    fn wrap(self, doc: D) -> Self {
+        Themed {
+            doc,
+            theme: self.theme,
+        }
+    }
+}
+
+impl<'d, D: PrettyDoc<'d>> Clone for Themed<'d, D>
+where
+    D::StyleLabel: PartialEq,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<'d, D: PrettyDoc<'d>> Copy for Themed<'d, D> where D::StyleLabel: PartialEq {}
+
+impl<'d, D: PrettyDoc<'d>> PrettyDoc<'d> for Themed<'d, D>
+where
+    D::StyleLabel: PartialEq,
+{
+    type Id = D::Id;
+    type Style = D::Style;
+    type StyleLabel = D::StyleLabel;
+    type Condition = D::Condition;
+    type Error = D::Error;
+
+    fn id(self) -> Result<Self::Id, Self::Error> {
+        self.doc.id()
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<Self::StyleLabel, Self::Condition>, Self::Error> {
+        self.doc.notation()
+    }
+
+    fn condition(self, condition: &Self::Condition) -> Result<bool, Self::Error> {
+        self.doc.condition(condition)
+    }
+
+    fn lookup_style(self, style_label: Self::StyleLabel) -> Result<Self::Style, Self::Error> {
+        match self.theme.lookup(&style_label) {
+            Some(style) => Ok(style.clone()),
+            None => self.doc.lookup_style(style_label),
+        }
+    }
+
+    fn lookup_indent_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        match self.theme.lookup(&style_label) {
+            Some(style) => Ok(style.clone()),
+            None => self.doc.lookup_indent_style(style_label, depth),
+        }
+    }
+
+    fn lookup_depth_style(
+        self,
+        style_label: Self::StyleLabel,
+        depth: usize,
+    ) -> Result<Self::Style, Self::Error> {
+        match self.theme.lookup(&style_label) {
+            Some(style) => Ok(style.clone()),
+            None => self.doc.lookup_depth_style(style_label, depth),
+        }
+    }
+
+    fn lookup_link(self, link_label: Self::StyleLabel) -> Result<Option<&'d str>, Self::Error> {
+        self.doc.lookup_link(link_label)
+    }
+
+    fn lookup_text(self, text_label: Self::StyleLabel) -> Result<&'d str, Self::Error> {
+        self.doc.lookup_text(text_label)
+    }
+
+    fn transform_text(
+        self,
+        text_label: Self::StyleLabel,
+        text: &'d str,
+    ) -> Result<&'d str, Self::Error> {
+        self.doc.transform_text(text_label, text)
+    }
+
+    fn approx_height_hint(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.approx_height_hint()
+    }
+
+    fn node_style(self) -> Result<Self::Style, Self::Error> {
+        self.doc.node_style()
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        self.doc.num_children()
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        self.doc.unwrap_text()
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_child(i)?))
+    }
+
+    fn unwrap_last_child(self) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_last_child()?))
+    }
+
+    fn unwrap_prev_sibling(self, parent: Self, i: usize) -> Result<Self, Self::Error> {
+        Ok(self.wrap(self.doc.unwrap_prev_sibling(parent.doc, i)?))
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Self::Error> {
+        Ok(self
+            .doc
+            .unwrap_children(range)?
+            .into_iter()
+            .map(|child| self.wrap(child))
+            .collect())
+    }
+}
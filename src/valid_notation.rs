@@ -1,5 +1,5 @@
 use crate::{notation::normalize_child_index, CheckPos, Condition, Notation, StyleLabel};
-use std::fmt;
+use core::fmt;
 
 /// A notation that has passed validation and does not have any detectable flaws. Obtain one by
 /// constructing a [`Notation`] and then calling [`Notation::validate()`].
@@ -25,6 +25,10 @@ pub enum NotationError {
         "Notation contains a CheckPos::RightChild outside of Fold.join, but it's only meaningful inside of that."
     )]
     CheckPosRightOutsideJoin,
+    #[error(
+        "Notation contains a CheckJoin outside of Fold.join, but it's only meaningful inside of that."
+    )]
+    CheckJoinOutsideJoin,
     #[error("Notation contains a Fold inside a Fold, but those aren't allowed to be nested.")]
     NestedFold,
     #[error("Notation contains a Count inside a Count, but those aren't allowed to be nested.")]
@@ -37,6 +41,10 @@ pub enum NotationError {
     CountZeroCheckPosChild,
     #[error("Notation contains a CheckPos::Child with index {} inside of Count.one, but in this case there's guaranteed to be only one child.", 0)]
     CountOneCheckPosChildIndex(isize),
+    #[error("Notation contains a Child with index {index} inside of an Arity case for {arity} children, which is out of bounds.")]
+    ArityChildIndex { index: isize, arity: usize },
+    #[error("Notation contains a CheckPos::Child with index {index} inside of an Arity case for {arity} children, which is out of bounds.")]
+    ArityCheckPosChildIndex { index: isize, arity: usize },
     #[error(
         "Notation contains a Text inside a Count, but a node can't have both text and children."
     )]
@@ -49,8 +57,6 @@ pub enum NotationError {
         "Notation contains Text or Literal after an EndOfLine, which would cause a printing error."
     )]
     TextAfterEol,
-    #[error("Notation contains multiple FocusMarks, but only the first one would ever be used.")]
-    MultipleFocusMarks,
 }
 
 /// Tracks what notations we are inside of during the validation process.
@@ -64,6 +70,8 @@ struct Context {
 enum CountContext {
     Zero,
     One,
+    /// Exactly this many children, which is at least 2.
+    Exactly(usize),
     Many,
 }
 
@@ -95,6 +103,18 @@ impl Context {
         }
     }
 
+    fn count_exactly(self, arity: usize) -> Self {
+        let count = match arity {
+            0 => CountContext::Zero,
+            1 => CountContext::One,
+            _ => CountContext::Exactly(arity),
+        };
+        Context {
+            count: Some(count),
+            fold: self.fold,
+        }
+    }
+
     fn count_many(self) -> Self {
         Context {
             count: Some(CountContext::Many),
@@ -127,35 +147,19 @@ struct History {
     /// [`EndOfLine`](Notation::EndOfLine). We assume here that no [`Child`](Notation::Child) will
     /// end with [`EndOfLine`](Notation::EndOfLine).
     eol: bool,
-    /// Did the previously visited notations _definitely_ contain a
-    /// [`FocusMark`](Notation::FocusMark)?
-    ///
-    /// (It's not a disaster for a Notation to contain multiple `FocusMark`s, so it's ok if _some_
-    /// possible combinations of choices result in multiple `FocusMark`s, so long as not all of
-    /// them do.)
-    mark: bool,
 }
 
 impl History {
     fn uncertain() -> History {
-        History {
-            eol: false,
-            mark: false,
-        }
+        History { eol: false }
     }
 
     fn choice(self, other: History) -> History {
         History {
             eol: self.eol || other.eol,
-            mark: self.mark && other.mark,
         }
     }
 
-    fn with_mark(mut self) -> History {
-        self.mark = true;
-        self
-    }
-
     fn with_eol(mut self) -> History {
         self.eol = true;
         self
@@ -188,12 +192,20 @@ impl<L: StyleLabel, C: Condition> Notation<L, C> {
         use NotationError::*;
 
         match self {
-            Text if ctx.count.is_some() => Err(TextInsideCount),
-            Text if ctx.fold.is_some() => Err(TextInsideFold),
+            Text | BreakableText { .. } if ctx.count.is_some() => Err(TextInsideCount),
+            Text | BreakableText { .. } if ctx.fold.is_some() => Err(TextInsideFold),
             Empty => Ok(history),
-            Text | Literal(_) if history.eol => Err(TextAfterEol),
-            Text | Literal(_) => Ok(history.without_eol()),
-            Newline => Ok(history.without_eol()),
+            Text | BreakableText { .. } | Literal(_) | Placeholder(_) | HiddenCount
+                if history.eol =>
+            {
+                Err(TextAfterEol)
+            }
+            Text | BreakableText { .. } | Literal(_) | Placeholder(_) | HiddenCount => {
+                Ok(history.without_eol())
+            }
+            // A soft space after an `EndOfLine` is dropped rather than being an error.
+            Space | PadTo(_) => Ok(history),
+            Newline | VerticalSpace(..) => Ok(history.without_eol()),
             EndOfLine => Ok(history.with_eol()),
             Flat(note) => note.validate_rec(history, ctx),
             Indent(_, _, note) => note.validate_rec(history, ctx),
@@ -207,30 +219,25 @@ impl<L: StyleLabel, C: Condition> Notation<L, C> {
                 Ok(history_1.choice(history_2))
             }
             Check(_, pos, note1, note2) => {
-                match &pos {
-                    CheckPos::Here => (),
-                    CheckPos::Child(_) if ctx.count == Some(CountContext::Zero) => {
-                        return Err(CountZeroCheckPosChild)
-                    }
-                    CheckPos::Child(i)
-                        if ctx.count == Some(CountContext::One)
-                            && normalize_child_index(*i, 1).is_none() =>
-                    {
-                        return Err(CountOneCheckPosChildIndex(*i))
-                    }
-                    CheckPos::Child(_) => (),
-                    CheckPos::LeftChild if ctx.fold != Some(InFoldJoin) => {
-                        return Err(CheckPosLeftOutsideJoin)
-                    }
-                    CheckPos::RightChild if ctx.fold != Some(InFoldJoin) => {
-                        return Err(CheckPosRightOutsideJoin)
-                    }
-                    CheckPos::LeftChild | CheckPos::RightChild => (),
+                validate_check_pos(pos, ctx)?;
+                let history_1 = note1.validate_rec(history, ctx)?;
+                let history_2 = note2.validate_rec(history, ctx)?;
+                Ok(history_1.choice(history_2))
+            }
+            CheckExpr(expr, note1, note2) => {
+                for pos in expr.positions() {
+                    validate_check_pos(pos, ctx)?;
                 }
                 let history_1 = note1.validate_rec(history, ctx)?;
                 let history_2 = note2.validate_rec(history, ctx)?;
                 Ok(history_1.choice(history_2))
             }
+            CheckJoin(..) if ctx.fold != Some(InFoldJoin) => Err(CheckJoinOutsideJoin),
+            CheckJoin(_, note1, note2) => {
+                let history_1 = note1.validate_rec(history, ctx)?;
+                let history_2 = note2.validate_rec(history, ctx)?;
+                Ok(history_1.choice(history_2))
+            }
             Child(_) if ctx.count == Some(CountContext::Zero) => Err(CountZeroChild),
             Child(i)
                 if ctx.count == Some(CountContext::One)
@@ -238,10 +245,21 @@ impl<L: StyleLabel, C: Condition> Notation<L, C> {
             {
                 Err(CountOneChildIndex(*i))
             }
-            Child(_) => Ok(history.without_eol()),
+            Child(i) => match ctx.count {
+                Some(CountContext::Exactly(arity))
+                    if normalize_child_index(*i, arity).is_none() =>
+                {
+                    Err(ArityChildIndex { index: *i, arity })
+                }
+                _ => Ok(history.without_eol()),
+            },
             Style(_, note) => note.validate_rec(history, ctx),
-            FocusMark if history.mark => Err(MultipleFocusMarks),
-            FocusMark => Ok(history.with_mark()),
+            DepthStyle(_, note) => note.validate_rec(history, ctx),
+            Link(_, note) => note.validate_rec(history, ctx),
+            MapText(_, note) => note.validate_rec(history, ctx),
+            Cache(note) => note.validate_rec(history, ctx),
+            WithWidth(_, note) => note.validate_rec(history, ctx),
+            FocusMark => Ok(history),
             Count { .. } if ctx.count.is_some() => Err(NestedCount),
             Count { zero, one, many } => {
                 let history_1 = zero.validate_rec(history, ctx.count_zero())?;
@@ -249,6 +267,22 @@ impl<L: StyleLabel, C: Condition> Notation<L, C> {
                 let history_3 = many.validate_rec(history, ctx.count_many())?;
                 Ok(history_1.choice(history_2).choice(history_3))
             }
+            Arity { .. } if ctx.count.is_some() => Err(NestedCount),
+            Arity { cases, fallback } => {
+                let mut result = fallback.validate_rec(history, ctx.count_many())?;
+                for (arity, note) in cases {
+                    let case_history = note.validate_rec(history, ctx.count_exactly(*arity))?;
+                    result = result.choice(case_history);
+                }
+                Ok(result)
+            }
+            Summarize {
+                notation, summary, ..
+            } => {
+                let history_1 = notation.validate_rec(history, ctx)?;
+                let history_2 = summary.validate_rec(history_1, ctx)?;
+                Ok(history_1.choice(history_2))
+            }
             Fold { .. } if ctx.fold.is_some() => Err(NestedFold),
             Fold { first, join } => {
                 // Can't easily check for EOL/mark here
@@ -256,6 +290,8 @@ impl<L: StyleLabel, C: Condition> Notation<L, C> {
                 join.validate_rec(History::uncertain(), ctx.fold_join())?;
                 Ok(History::uncertain())
             }
+            // It displays nothing if there are no children.
+            Columns { .. } => Ok(history.choice(history.without_eol())),
             Left if ctx.fold != Some(InFoldJoin) => Err(LeftOutsideJoin),
             Right if ctx.fold != Some(InFoldJoin) => Err(RightOutsideJoin),
             // Can't easily check for EOL/mark here
@@ -264,6 +300,31 @@ impl<L: StyleLabel, C: Condition> Notation<L, C> {
     }
 }
 
+/// Check that a [`CheckPos`] makes sense in the context it's used in.
+fn validate_check_pos(pos: &CheckPos, ctx: Context) -> Result<(), NotationError> {
+    use FoldContext::*;
+    use NotationError::*;
+
+    match pos {
+        CheckPos::Here => Ok(()),
+        CheckPos::Child(_) if ctx.count == Some(CountContext::Zero) => Err(CountZeroCheckPosChild),
+        CheckPos::Child(i)
+            if ctx.count == Some(CountContext::One) && normalize_child_index(*i, 1).is_none() =>
+        {
+            Err(CountOneCheckPosChildIndex(*i))
+        }
+        CheckPos::Child(i) => match ctx.count {
+            Some(CountContext::Exactly(arity)) if normalize_child_index(*i, arity).is_none() => {
+                Err(ArityCheckPosChildIndex { index: *i, arity })
+            }
+            _ => Ok(()),
+        },
+        CheckPos::LeftChild if ctx.fold != Some(InFoldJoin) => Err(CheckPosLeftOutsideJoin),
+        CheckPos::RightChild if ctx.fold != Some(InFoldJoin) => Err(CheckPosRightOutsideJoin),
+        CheckPos::LeftChild | CheckPos::RightChild => Ok(()),
+    }
+}
+
 impl<L: StyleLabel, C: Condition> fmt::Display for ValidNotation<L, C> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.0)
@@ -0,0 +1,249 @@
+//! Bindings for using the pretty printer from JavaScript, with `wasm-bindgen`. Enable them with
+//! the `wasm` feature.
+//!
+//! Everything crosses the boundary as JSON strings, so that web playgrounds and editor webviews
+//! can use the same layout engine as native code:
+//!
+//! - Each notation is given by name, as the JSON serialization of a
+//!   `Notation<String, JsCondition>`. For example `[1, 2]` could use the notation
+//!   `{"Concat": [{"Literal": "["}, {"Concat": [{"Child": 0}, ...]}]}`. Style labels are CSS
+//!   class names.
+//! - A document is a tree of [`JsNode`]s.
+//! - The result of printing is an array of lines, each of which is an array of [`JsSpan`]s.
+//!
+//! ```js
+//! const notations = new Notations();
+//! notations.add("text", JSON.stringify("Text"));
+//! notations.add("pair", JSON.stringify({Concat: [{Child: 0}, {Concat: [{Literal: ": "}, {Child: 1}]}]}));
+//! const lines = JSON.parse(notations.print(JSON.stringify({
+//!     notation: "pair",
+//!     children: [{notation: "text", text: "key"}, {notation: "text", text: "value"}],
+//! }), 80));
+//! ```
+
+use crate::{
+    pretty_print, FocusTarget, Notation, PrettyDoc, PrintingError, Segment, Style, ValidNotation,
+    Width,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use wasm_bindgen::prelude::*;
+
+/// The conditions that notations given from JavaScript can [check](Notation::Check).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JsCondition {
+    /// Whether the node contains the empty string.
+    IsEmptyText,
+    /// Whether the node has this flag (see [`JsNode::flags`]).
+    Flag(String),
+}
+
+/// A document node, as given from JavaScript.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsNode {
+    /// The name of this node's notation, as passed to [`Notations::add()`].
+    pub notation: String,
+    /// The node's text, if it contains text instead of children.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// The node's children. Ignored if it has `text`.
+    #[serde(default)]
+    pub children: Vec<JsNode>,
+    /// Flags that [`JsCondition::Flag`] can check for.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// CSS classes that apply to this whole node.
+    #[serde(default)]
+    pub classes: Vec<String>,
+}
+
+/// A piece of printed text, as given to JavaScript.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsSpan {
+    pub text: String,
+    /// The CSS classes of the node and of every `Notation::Style` that this text is inside of,
+    /// from outermost to innermost.
+    pub classes: Vec<String>,
+    /// Whether this is indentation, rather than text.
+    pub indentation: bool,
+}
+
+/// The style of a [`JsNode`]: a list of CSS classes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsStyle(pub Vec<String>);
+
+impl Style for JsStyle {
+    fn combine(outer_style: &JsStyle, inner_style: &JsStyle) -> JsStyle {
+        let mut classes = outer_style.0.clone();
+        classes.extend(inner_style.0.iter().cloned());
+        JsStyle(classes)
+    }
+}
+
+/// A set of named notations, which documents given from JavaScript can be printed with.
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct Notations {
+    notations: HashMap<String, ValidNotation<String, JsCondition>>,
+}
+
+#[wasm_bindgen]
+impl Notations {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Notations {
+        Notations::default()
+    }
+
+    /// Add (or replace) the notation called `name`. `descriptor` is the JSON serialization of a
+    /// `Notation<String, JsCondition>`. Fails if it isn't one, or if it's not a valid notation.
+    pub fn add(&mut self, name: &str, descriptor: &str) -> Result<(), JsError> {
+        let notation: Notation<String, JsCondition> = serde_json::from_str(descriptor)?;
+        self.notations.insert(name.to_owned(), notation.validate()?);
+        Ok(())
+    }
+
+    /// Pretty print the document `doc`, given as the JSON serialization of a [`JsNode`], with the
+    /// preferred line `width`. Returns the JSON serialization of the lines, each of which is an
+    /// array of [`JsSpan`]s.
+    pub fn print(&self, doc: &str, width: Width) -> Result<String, JsError> {
+        let root: JsNode = serde_json::from_str(doc)?;
+        let mut nodes = Vec::new();
+        self.flatten(root, &mut nodes)?;
+        let doc = JsDoc {
+            notations: &self.notations,
+            nodes: &nodes,
+            index: 0,
+        };
+        let lines = print_spans(doc, width).map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(serde_json::to_string(&lines)?)
+    }
+}
+
+impl Notations {
+    /// Store `node` and its descendants in `nodes` in preorder, so that they can be referred to by
+    /// index. Returns the index of `node`.
+    fn flatten(&self, node: JsNode, nodes: &mut Vec<FlatNode>) -> Result<usize, JsError> {
+        if !self.notations.contains_key(&node.notation) {
+            return Err(JsError::new(&format!(
+                "Unknown notation: {}",
+                node.notation
+            )));
+        }
+        let index = nodes.len();
+        nodes.push(FlatNode {
+            notation: node.notation,
+            text: node.text,
+            children: Vec::new(),
+            flags: node.flags,
+            style: JsStyle(node.classes),
+        });
+        if nodes[index].text.is_none() {
+            let mut children = Vec::new();
+            for child in node.children {
+                children.push(self.flatten(child, nodes)?);
+            }
+            nodes[index].children = children;
+        }
+        Ok(index)
+    }
+}
+
+/// A [`JsNode`], with its children referred to by index.
+#[derive(Debug)]
+struct FlatNode {
+    notation: String,
+    text: Option<String>,
+    children: Vec<usize>,
+    flags: Vec<String>,
+    style: JsStyle,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct JsDoc<'d> {
+    notations: &'d HashMap<String, ValidNotation<String, JsCondition>>,
+    nodes: &'d [FlatNode],
+    index: usize,
+}
+
+impl<'d> JsDoc<'d> {
+    fn node(self) -> &'d FlatNode {
+        &self.nodes[self.index]
+    }
+}
+
+impl<'d> PrettyDoc<'d> for JsDoc<'d> {
+    type Id = usize;
+    type Style = JsStyle;
+    type StyleLabel = String;
+    type Condition = JsCondition;
+    type Error = Infallible;
+
+    fn id(self) -> Result<usize, Infallible> {
+        Ok(self.index)
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<String, JsCondition>, Infallible> {
+        // `Notations::flatten()` checked that it exists.
+        Ok(&self.notations[&self.node().notation])
+    }
+
+    fn condition(self, condition: &JsCondition) -> Result<bool, Infallible> {
+        Ok(match condition {
+            JsCondition::IsEmptyText => self.node().text.as_deref() == Some(""),
+            JsCondition::Flag(flag) => self.node().flags.contains(flag),
+        })
+    }
+
+    fn lookup_style(self, style_label: String) -> Result<JsStyle, Infallible> {
+        Ok(JsStyle(vec![style_label]))
+    }
+
+    fn node_style(self) -> Result<JsStyle, Infallible> {
+        Ok(self.node().style.clone())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Infallible> {
+        Ok(match &self.node().text {
+            Some(_) => None,
+            None => Some(self.node().children.len()),
+        })
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Infallible> {
+        Ok(self.node().text.as_deref().unwrap())
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Infallible> {
+        Ok(JsDoc {
+            index: self.node().children[i],
+            ..self
+        })
+    }
+}
+
+/// Print all of `doc`, as lines of spans.
+fn print_spans(doc: JsDoc, width: Width) -> Result<Vec<Vec<JsSpan>>, PrintingError<Infallible>> {
+    let (_, focused_line, next_lines) = pretty_print(doc, width, &[], FocusTarget::Start, None)?;
+    let mut first_line = focused_line.left_segments;
+    first_line.extend(focused_line.right_segments);
+    let mut lines = vec![to_spans(first_line)];
+    for line in next_lines {
+        lines.push(to_spans(line?.segments));
+    }
+    Ok(lines)
+}
+
+fn to_spans<'d>(segments: Vec<Segment<'d, JsDoc<'d>>>) -> Vec<JsSpan> {
+    segments
+        .into_iter()
+        .map(|segment| {
+            let indentation = segment.is_indentation();
+            JsSpan {
+                text: segment.str.to_owned(),
+                classes: segment.style.0,
+                indentation,
+            }
+        })
+        .collect()
+}
@@ -3,7 +3,7 @@ use crate::standard::generative_testing::{
 };
 use crate::standard::pretty_testing::{assert_pp_without_expectation, SimpleDoc};
 use partial_pretty_printer::{
-    notation_constructors::{empty, eol, flat, lit, nl},
+    notation_constructors::{cache, empty, eol, flat, lit, nl, space},
     Notation,
 };
 
@@ -16,13 +16,14 @@ impl Generator for NotationGen {
     fn generate<P: Picker>(&self, mut size: u32, picker: &mut P) -> Notation<(), ()> {
         assert_ne!(size, 0);
         if size == 1 {
-            match picker.pick_int(6) {
+            match picker.pick_int(7) {
                 0 => empty(),
                 1 => nl(),
                 2 => eol(),
                 3 => lit("a"),
                 4 => lit("bb"),
                 5 => lit("cccc"),
+                6 => space(),
                 _ => unreachable!(),
             }
         } else if size == 2 {
@@ -82,3 +83,31 @@ fn oracle_tests() {
         valid_count + invalid_count
     );
 }
+
+/// Wrap every operand of every `Concat` and `Choice` in a `Cache`.
+fn cache_everything(notation: Notation<(), ()>) -> Notation<(), ()> {
+    use Notation::*;
+
+    match notation {
+        Concat(x, y) => cache(cache_everything(*x)) + cache(cache_everything(*y)),
+        Choice(x, y) => cache(cache_everything(*x)) | cache(cache_everything(*y)),
+        Flat(x) => flat(cache_everything(*x)),
+        Indent(i, style, x) => Indent(i, style, Box::new(cache_everything(*x))),
+        notation => notation,
+    }
+}
+
+#[test]
+fn oracle_tests_with_cache() {
+    let notations = generate_all_up_to_size(NotationGen, 5)
+        .chain(generate_random(NotationGen, 10, [1; 32]).take(500))
+        .chain(generate_random(NotationGen, 30, [1; 32]).take(500));
+
+    for notation in notations {
+        if let Ok(doc) = SimpleDoc::try_new(cache_everything(notation)) {
+            for width in 1..=8 {
+                assert_pp_without_expectation(&doc, width);
+            }
+        }
+    }
+}
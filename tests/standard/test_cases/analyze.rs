@@ -0,0 +1,104 @@
+use crate::standard::pretty_testing::{assert_pp, SimpleDoc};
+use partial_pretty_printer::notation_constructors::{
+    child, eol, flat, fold, indent, left, lit, nl, right, Fold,
+};
+use partial_pretty_printer::{analyze, ChoiceOption, ChoiceReport, Notation, ValidNotation};
+
+fn valid(notation: Notation<(), ()>) -> ValidNotation<(), ()> {
+    notation.validate().unwrap()
+}
+
+#[test]
+fn test_analyze_choice_widths() {
+    let notation = lit("abc") + (lit(" xy") | (nl() + lit("z"))) + lit(";");
+    let report = analyze(&valid(notation.clone()));
+    assert_eq!(
+        report.choices,
+        vec![ChoiceReport {
+            first_min_width: Some(7),
+            second_reachable: true,
+        }]
+    );
+    assert!(report.unreachable_options().is_empty());
+    assert_pp(&SimpleDoc::new(notation.clone()), 7, &["abc xy;"]);
+    assert_pp(&SimpleDoc::new(notation), 6, &["abc", "z;"]);
+
+    // The suffix stops at a newline
+    let notation = (lit("ab") | lit("a")) + lit("cd") + nl() + lit("efgh");
+    let report = analyze(&valid(notation));
+    assert_eq!(report.choices[0].first_min_width, Some(4));
+}
+
+#[test]
+fn test_analyze_unreachable() {
+    let notation = flat(lit("a") | (lit("bb") | lit("c"))) + (lit("d") | lit("e"));
+    let report = analyze(&valid(notation));
+    assert_eq!(
+        report.choices,
+        vec![
+            ChoiceReport {
+                first_min_width: Some(0),
+                second_reachable: false,
+            },
+            ChoiceReport {
+                first_min_width: None,
+                second_reachable: false,
+            },
+            ChoiceReport {
+                first_min_width: Some(2),
+                second_reachable: true,
+            },
+        ]
+    );
+    assert_eq!(
+        report.unreachable_options(),
+        vec![
+            (0, ChoiceOption::Second),
+            (1, ChoiceOption::First),
+            (1, ChoiceOption::Second),
+        ]
+    );
+}
+
+#[test]
+fn test_analyze_indentation() {
+    let notation = lit("x") + (2 >> indent("// ", None, nl() + lit("y"))) + (4 >> lit("z"));
+    let report = analyze(&valid(notation));
+    assert_eq!(report.max_indent_depth, Some(2));
+    assert_eq!(report.max_indent_width, Some(5));
+
+    let notation = fold(Fold {
+        first: child(0),
+        join: left() + lit(",") + (4 >> right()),
+    });
+    let report = analyze(&valid(notation));
+    assert_eq!(report.max_indent_depth, Some(1));
+    assert_eq!(report.max_indent_width, Some(4));
+
+    let notation = fold(Fold {
+        first: child(0),
+        join: 4 >> (left() + lit(",") + right()),
+    });
+    let report = analyze(&valid(notation));
+    assert_eq!(report.max_indent_depth, None);
+    assert_eq!(report.max_indent_width, None);
+}
+
+#[test]
+fn test_analyze_eol() {
+    let report = analyze(&valid(lit("a") + child(0) + eol() + nl()));
+    assert!(!report.eol_before_child);
+    assert!(!report.may_end_with_eol);
+
+    let report = analyze(&valid(child(0) + (eol() | lit("b"))));
+    assert!(!report.eol_before_child);
+    assert!(report.may_end_with_eol);
+
+    let report = analyze(&valid(eol() + child(0)));
+    assert!(report.eol_before_child);
+    assert!(!report.may_end_with_eol);
+
+    // An EndOfLine in an option that's never chosen doesn't count
+    let report = analyze(&valid(flat(lit("a") | eol()) + child(0)));
+    assert!(!report.eol_before_child);
+}
@@ -0,0 +1,299 @@
+use futures_core::Stream;
+use partial_pretty_printer::doc_examples::json::{json_array, json_number, json_string, Json};
+use partial_pretty_printer::doc_examples::tree::{TreeCondition, TreeNotation, TreeStyleLabel};
+use partial_pretty_printer::doc_examples::BasicStyle;
+use partial_pretty_printer::{
+    pretty_print_async, pretty_print_stream, pretty_print_to_string, PrettyDoc, PrintOptions,
+};
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::ptr;
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+#[derive(Debug, PartialEq)]
+struct NotLoaded(u32);
+
+impl fmt::Display for NotLoaded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "node {} not loaded yet", self.0)
+    }
+}
+
+impl std::error::Error for NotLoaded {}
+
+/// A Json document whose nodes can only be accessed once they've been "loaded".
+#[derive(Debug, Clone, Copy)]
+struct LazyDoc<'d> {
+    node: &'d Json,
+    loaded: &'d RefCell<HashSet<u32>>,
+}
+
+impl<'d> LazyDoc<'d> {
+    fn check(self) -> Result<&'d Json, NotLoaded> {
+        let id = self.node.id().unwrap();
+        if self.loaded.borrow().contains(&id) {
+            Ok(self.node)
+        } else {
+            Err(NotLoaded(id))
+        }
+    }
+}
+
+impl<'d> PrettyDoc<'d> for LazyDoc<'d> {
+    type Id = u32;
+    type Style = BasicStyle;
+    type StyleLabel = TreeStyleLabel;
+    type Condition = TreeCondition;
+    type Error = NotLoaded;
+
+    fn id(self) -> Result<u32, NotLoaded> {
+        Ok(self.node.id().unwrap())
+    }
+
+    fn notation(self) -> Result<&'d TreeNotation, NotLoaded> {
+        Ok(self.check()?.notation().unwrap())
+    }
+
+    fn condition(self, condition: &TreeCondition) -> Result<bool, NotLoaded> {
+        Ok(self.check()?.condition(condition).unwrap())
+    }
+
+    fn lookup_style(self, style_label: TreeStyleLabel) -> Result<BasicStyle, NotLoaded> {
+        Ok(self.check()?.lookup_style(style_label).unwrap())
+    }
+
+    fn node_style(self) -> Result<BasicStyle, NotLoaded> {
+        Ok(self.check()?.node_style().unwrap())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, NotLoaded> {
+        Ok(self.check()?.num_children().unwrap())
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, NotLoaded> {
+        Ok(self.check()?.unwrap_text().unwrap())
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, NotLoaded> {
+        Ok(LazyDoc {
+            node: self.check()?.unwrap_child(i).unwrap(),
+            loaded: self.loaded,
+        })
+    }
+}
+
+fn noop_waker() -> Waker {
+    fn noop_raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            noop_raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+
+    unsafe { Waker::from_raw(noop_raw_waker()) }
+}
+
+/// Run a future to completion. The futures in these tests never wait, so this never needs to be
+/// woken.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(output) = Pin::as_mut(&mut future).poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn test_pretty_print_async() {
+    let json = json_array(vec![json_string("a"), json_number(2.), json_string("c")]);
+    let loaded = RefCell::new(HashSet::new());
+    let doc = LazyDoc {
+        node: &json,
+        loaded: &loaded,
+    };
+    let num_loads = Cell::new(0);
+
+    let string = block_on(pretty_print_async(
+        doc,
+        |doc| pretty_print_to_string(doc, 80),
+        |&NotLoaded(id)| {
+            num_loads.set(num_loads.get() + 1);
+            loaded.borrow_mut().insert(id);
+            async { Ok(()) }
+        },
+    ))
+    .unwrap();
+    assert_eq!(string, "[\"a\", 2, \"c\"]");
+    assert_eq!(num_loads.get(), 4);
+
+    // Everything is loaded now.
+    let string = block_on(pretty_print_async(
+        doc,
+        |doc| pretty_print_to_string(doc, 80),
+        |&NotLoaded(id)| async move { Err(NotLoaded(id)) },
+    ))
+    .unwrap();
+    assert_eq!(string, "[\"a\", 2, \"c\"]");
+}
+
+#[test]
+fn test_pretty_print_async_load_failure() {
+    let json = json_array(vec![json_string("a")]);
+    let loaded = RefCell::new(HashSet::new());
+    let doc = LazyDoc {
+        node: &json,
+        loaded: &loaded,
+    };
+
+    let result = block_on(pretty_print_async(
+        doc,
+        |doc| pretty_print_to_string(doc, 80),
+        |&NotLoaded(id)| async move { Err(NotLoaded(id)) },
+    ));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_pretty_print_async_no_progress() {
+    let json = json_array(vec![json_string("a")]);
+    let loaded = RefCell::new(HashSet::new());
+    let doc = LazyDoc {
+        node: &json,
+        loaded: &loaded,
+    };
+    let num_loads = Cell::new(0);
+
+    // `load` claims to succeed, but doesn't load anything.
+    let result = block_on(pretty_print_async(
+        doc,
+        |doc| pretty_print_to_string(doc, 80),
+        |_| {
+            num_loads.set(num_loads.get() + 1);
+            async { Ok(()) }
+        },
+    ));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        format!(
+            "PrettyDoc error: node {} not loaded yet",
+            json.id().unwrap()
+        )
+    );
+    assert_eq!(num_loads.get(), 1);
+}
+
+/// Collect all of the items in a stream. Its futures never wait, so this never needs to be woken.
+fn collect_stream<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+    let waker = noop_waker();
+    let mut context = Context::from_waker(&waker);
+    let mut items = Vec::new();
+    loop {
+        match Pin::new(&mut stream).poll_next(&mut context) {
+            Poll::Ready(Some(item)) => items.push(item),
+            Poll::Ready(None) => return items,
+            Poll::Pending => (),
+        }
+    }
+}
+
+/// `[["x"], ["y"]]`, and the id of `"y"`. Printed 6 wide, the lines for `["x"]` don't depend on
+/// `"y"`.
+fn nested_json() -> (Json, u32) {
+    let json = json_array(vec![
+        json_array(vec![json_string("x")]),
+        json_array(vec![json_string("y")]),
+    ]);
+    let y_id = json
+        .unwrap_child(1)
+        .unwrap()
+        .unwrap_child(0)
+        .unwrap()
+        .id()
+        .unwrap();
+    (json, y_id)
+}
+
+fn print_stream<'d>(
+    doc: LazyDoc<'d>,
+    load: impl FnMut(&NotLoaded) -> std::future::Ready<Result<(), NotLoaded>>,
+) -> Vec<Result<String, String>> {
+    let stream = pretty_print_stream(doc, 6, PrintOptions::default(), load);
+    collect_stream(stream)
+        .into_iter()
+        .map(|line| {
+            line.map(|line| line.to_string())
+                .map_err(|err| err.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn test_pretty_print_stream() {
+    let (json, _) = nested_json();
+    let loaded = RefCell::new(HashSet::new());
+    let doc = LazyDoc {
+        node: &json,
+        loaded: &loaded,
+    };
+    let num_loads = Cell::new(0);
+
+    let lines = print_stream(doc, |&NotLoaded(id)| {
+        num_loads.set(num_loads.get() + 1);
+        loaded.borrow_mut().insert(id);
+        std::future::ready(Ok(()))
+    });
+    let expected = [
+        "[",
+        "    [",
+        "        \"x\"",
+        "    ],",
+        "    [",
+        "        \"y\"",
+        "    ]",
+        "]",
+    ];
+    assert_eq!(
+        lines,
+        expected
+            .iter()
+            .map(|line| Ok(line.to_string()))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(num_loads.get(), 5);
+}
+
+#[test]
+fn test_pretty_print_stream_no_progress() {
+    let (json, y_id) = nested_json();
+    let loaded = RefCell::new(HashSet::new());
+    let doc = LazyDoc {
+        node: &json,
+        loaded: &loaded,
+    };
+
+    // `load` claims to succeed, but never loads "y".
+    let lines = print_stream(doc, |&NotLoaded(id)| {
+        if id != y_id {
+            loaded.borrow_mut().insert(id);
+        }
+        std::future::ready(Ok(()))
+    });
+    assert_eq!(
+        lines,
+        vec![
+            Ok("[".to_owned()),
+            Ok("    [".to_owned()),
+            Ok("        \"x\"".to_owned()),
+            Ok("    ],".to_owned()),
+            Err(format!("PrettyDoc error: node {} not loaded yet", y_id)),
+        ]
+    );
+}
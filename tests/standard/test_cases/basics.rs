@@ -1,5 +1,8 @@
-use crate::standard::pretty_testing::{all_paths, assert_pp, SimpleDoc};
-use partial_pretty_printer::notation_constructors::{empty, eol, flat, indent, lit, nl};
+use crate::standard::pretty_testing::{all_paths, assert_pp, assert_pp_focus, SimpleDoc};
+use partial_pretty_printer::notation_constructors::{
+    cache, empty, eol, flat, indent, lit, mark, nest, nl, opt, space, with_width,
+};
+use partial_pretty_printer::FocusTarget;
 
 #[test]
 fn basics_empty() {
@@ -37,6 +40,12 @@ fn basics_non_whitespace_indent() {
     assert_pp(&SimpleDoc::new(notation), 80, &["Hello", "// world!"]);
 }
 
+#[test]
+fn basics_nest() {
+    let notation = lit("Hello") + nest(2, lit("world!"));
+    assert_pp(&SimpleDoc::new(notation), 80, &["Hello", "  world!"]);
+}
+
 #[test]
 fn basics_flat() {
     let notation = flat(lit("long") | (lit("a") ^ lit("b")));
@@ -50,6 +59,24 @@ fn basics_choice() {
     assert_pp(&SimpleDoc::new(notation), 11, &["Hello", "world!"]);
 }
 
+#[test]
+fn basics_opt() {
+    let notation = lit("Hello") + opt(lit(" world!"));
+    assert_pp(&SimpleDoc::new(notation.clone()), 12, &["Hello world!"]);
+    assert_pp(&SimpleDoc::new(notation), 11, &["Hello"]);
+}
+
+#[test]
+fn basics_cache() {
+    let notation = cache(lit("Hello world!") | lit("Hello") ^ lit("world!"));
+    assert_pp(&SimpleDoc::new(notation.clone()), 12, &["Hello world!"]);
+    assert_pp(&SimpleDoc::new(notation), 11, &["Hello", "world!"]);
+
+    let hello = cache(lit("Hello") + eol());
+    let notation = (hello.clone() + lit("!")) | (hello ^ lit("!"));
+    assert_pp(&SimpleDoc::cheat_validation(notation), 80, &["Hello", "!"]);
+}
+
 #[test]
 fn basics_eol() {
     let notation = eol() + (lit("a") | nl());
@@ -65,6 +92,50 @@ fn basics_eol() {
     assert_pp(&SimpleDoc::cheat_validation(notation), 80, &["ab"]);
 }
 
+#[test]
+fn basics_space() {
+    let notation = lit("a") + space() + lit("b");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a b"]);
+
+    // Adjacent soft spaces collapse
+    let notation = lit("a") + space() + (space() + empty() + space()) + lit("b");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a b"]);
+
+    // Soft spaces disappear at the start and end of lines
+    let notation = space() + lit("a") + space() + (2 >> (space() + lit("b") + space()));
+    assert_pp(&SimpleDoc::new(notation), 80, &["a", "  b"]);
+
+    // A trailing soft space doesn't count against the width
+    let word = |w| lit(w) + space() + (empty() | nl());
+    let notation = word("aa") + word("bb") + word("cc") + lit("dd");
+    assert_pp(&SimpleDoc::new(notation.clone()), 5, &["aa bb", "cc dd"]);
+    assert_pp(
+        &SimpleDoc::new(cache(notation)),
+        4,
+        &["aa", "bb", "cc", "dd"],
+    );
+
+    let notation = lit("a") + space() + eol() + space() + nl() + lit("b");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a", "b"]);
+}
+
+#[test]
+fn basics_with_width() {
+    let pair = || lit("Hello world!") | lit("Hello") ^ lit("world!");
+    let notation = pair() ^ with_width(8, pair()) ^ pair();
+    assert_pp(
+        &SimpleDoc::new(notation),
+        12,
+        &["Hello world!", "Hello", "world!", "Hello world!"],
+    );
+
+    // The width is measured from the start of the line, and the smallest width applies
+    let notation = lit("abc") + with_width(10, lit("defg") + with_width(20, pair()));
+    assert_pp(&SimpleDoc::new(notation), 80, &["abcdefgHello", "world!"]);
+    let notation = lit("abc") + with_width(20, lit("defg") + with_width(30, pair()));
+    assert_pp(&SimpleDoc::new(notation), 80, &["abcdefgHello world!"]);
+}
+
 #[test]
 fn test_all_paths_fn() {
     use partial_pretty_printer::doc_examples::json::{json_array, json_string};
@@ -104,3 +175,337 @@ fn tricky_suffix() {
     let notation = (lit("a") | lit("bb")) + ((lit("x") + nl() + flat(nl())) | lit("yy"));
     assert_pp(&SimpleDoc::new(notation), 3, &["ax", "", ""]);
 }
+
+#[test]
+fn basics_marks() {
+    let notation = lit("a") + mark() + lit("b") + ((mark() + lit("c")) | (lit("c") ^ mark()));
+    let doc = SimpleDoc::new(notation);
+    assert_pp_focus(&doc, 3, &[], FocusTarget::Mark, &["a|bc"]);
+    assert_pp_focus(&doc, 3, &[], FocusTarget::NthMark(0), &["a|bc"]);
+    assert_pp_focus(&doc, 3, &[], FocusTarget::NthMark(1), &["ab|c"]);
+    assert_pp_focus(&doc, 3, &[], FocusTarget::LastMark, &["ab|c"]);
+    assert_pp_focus(&doc, 2, &[], FocusTarget::LastMark, &["abc", "|"]);
+}
+
+#[test]
+fn basics_breakable_text() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::doc_examples::BasicStyle;
+    use partial_pretty_printer::notation_constructors::breakable_text;
+
+    static URL_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("see ") + breakable_text(&["/"], "  "))
+            .validate()
+            .unwrap()
+    });
+
+    let doc: Tree<BasicStyle> = Tree::new_text(
+        &URL_NOTATION,
+        "https://example.com/docs/guide/intro.html".to_owned(),
+    );
+    assert_pp(&doc, 80, &["see https://example.com/docs/guide/intro.html"]);
+    assert_pp(
+        &doc,
+        25,
+        &["see https://example.com/", "  docs/guide/intro.html"],
+    );
+    assert_pp(
+        &doc,
+        12,
+        &[
+            "see https://",
+            "  example.com/",
+            "  docs/",
+            "  guide/",
+            "  intro.html",
+        ],
+    );
+    assert_pp_focus(
+        &doc,
+        25,
+        &[],
+        FocusTarget::Text(26),
+        &["see https://example.com/", "  docs/g|uide/intro.html"],
+    );
+    assert_pp_focus(
+        &doc,
+        25,
+        &[],
+        FocusTarget::TextEnd,
+        &["see https://example.com/", "  docs/guide/intro.html|"],
+    );
+}
+
+#[test]
+fn basics_segment_kinds() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::doc_examples::BasicStyle;
+    use partial_pretty_printer::notation_constructors::text;
+    use partial_pretty_printer::{pretty_print, Line, SegmentKind};
+
+    static NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("let") + space() + text() + indent("  ", None, nl() + lit("= 1")))
+            .validate()
+            .unwrap()
+    });
+
+    let doc: Tree<BasicStyle> = Tree::new_text(&NOTATION, "x".to_owned());
+    let (_, focused_line, next_lines) =
+        pretty_print(&doc, 80, &[], FocusTarget::Start, None).unwrap();
+    let mut lines = vec![Line::from(focused_line)];
+    lines.extend(next_lines.map(|line| line.unwrap()));
+    let kinds = lines
+        .iter()
+        .map(|line| {
+            line.segments
+                .iter()
+                .map(|seg| (seg.str, seg.kind))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            vec![
+                ("let", SegmentKind::Literal),
+                (" ", SegmentKind::Separator),
+                ("x", SegmentKind::Text),
+            ],
+            vec![
+                ("  ", SegmentKind::Indentation),
+                ("= 1", SegmentKind::Literal),
+            ],
+        ]
+    );
+}
+
+#[test]
+fn basics_line_decorator() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::doc_examples::BasicStyle;
+    use partial_pretty_printer::notation_constructors::{child, text};
+    use partial_pretty_printer::{
+        pretty_print_with_options, LineDecorator, LineInfo, PrintOptions, Segment, SegmentKind,
+    };
+    use std::rc::Rc;
+
+    static ROOT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("a") + nl() + child(0) + nl() + lit("c"))
+            .validate()
+            .unwrap()
+    });
+    static TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+    fn to_string<'d>(segments: &[Segment<'d, &'d Tree<BasicStyle>>]) -> String {
+        segments.iter().map(|seg| seg.str).collect()
+    }
+
+    struct Gutter;
+
+    impl<'d> LineDecorator<'d, &'d Tree<BasicStyle>> for Gutter {
+        fn decorate(
+            &self,
+            line: &LineInfo<'_, 'd, &'d Tree<BasicStyle>>,
+            prefix: &mut Vec<Segment<'d, &'d Tree<BasicStyle>>>,
+            suffix: &mut Vec<Segment<'d, &'d Tree<BasicStyle>>>,
+        ) {
+            let marker = match line.row {
+                -1 => "-1| ",
+                0 => " 0| ",
+                1 => " 1| ",
+                _ => panic!("unexpected row {}", line.row),
+            };
+            prefix.push(Segment::decoration(marker, BasicStyle::new()));
+            if line.row == 0 {
+                suffix.push(Segment::decoration(" <", BasicStyle::new()));
+            }
+        }
+    }
+
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &ROOT_NOTATION,
+        vec![Tree::new_text(&TEXT_NOTATION, "b".to_owned())],
+    );
+    let options = PrintOptions {
+        line_decorator: Some(Rc::new(Gutter)),
+        ..PrintOptions::default()
+    };
+    let (prev_lines, focused_line, next_lines) =
+        pretty_print_with_options(&doc, 80, &[0], FocusTarget::End, None, &options).unwrap();
+
+    let prev_lines = prev_lines
+        .map(|line| to_string(&line.unwrap().segments))
+        .collect::<Vec<_>>();
+    assert_eq!(prev_lines, vec!["-1| a"]);
+    assert_eq!(to_string(&focused_line.left_segments), " 0| b");
+    assert_eq!(to_string(&focused_line.right_segments), " <");
+    assert_eq!(focused_line.left_segments[0].kind, SegmentKind::Decoration);
+    let next_lines = next_lines
+        .map(|line| to_string(&line.unwrap().segments))
+        .collect::<Vec<_>>();
+    assert_eq!(next_lines, vec![" 1| c"]);
+}
+
+#[test]
+fn basics_collapse_blank_lines() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::doc_examples::BasicStyle;
+    use partial_pretty_printer::notation_constructors::{child, text};
+    use partial_pretty_printer::{pretty_print_with_options, PrintOptions};
+
+    static ROOT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (child(0) ^ child(1) ^ child(2) ^ child(3) ^ child(4) ^ child(5) ^ child(6))
+            .validate()
+            .unwrap()
+    });
+    static TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+    fn print(doc: &Tree<BasicStyle>, path: &[usize], max_blank_lines: usize) -> Vec<String> {
+        let options = PrintOptions {
+            collapse_blank_lines: Some(max_blank_lines),
+            ..PrintOptions::default()
+        };
+        let (prev_lines, focused_line, next_lines) =
+            pretty_print_with_options(doc, 80, path, FocusTarget::Start, None, &options).unwrap();
+        let mut lines = prev_lines
+            .map(|line| line.unwrap().to_string())
+            .collect::<Vec<_>>();
+        lines.reverse();
+        lines.push(focused_line.to_string());
+        lines.extend(next_lines.map(|line| line.unwrap().to_string()));
+        lines
+    }
+
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &ROOT_NOTATION,
+        ["a", "", "", "", "b", "", "c"]
+            .iter()
+            .map(|s| Tree::new_text(&TEXT_NOTATION, s.to_string()))
+            .collect(),
+    );
+    // The focus doesn't matter, unless it's on a blank line that would be collapsed.
+    for path in [&[][..], &[0], &[4], &[6]] {
+        assert_eq!(print(&doc, path, 0), vec!["a", "b", "c"]);
+        assert_eq!(print(&doc, path, 1), vec!["a", "", "b", "", "c"]);
+        assert_eq!(print(&doc, path, 2), vec!["a", "", "", "b", "", "c"]);
+    }
+    for path in [&[1][..], &[5]] {
+        assert_eq!(print(&doc, path, 1), vec!["a", "", "b", "", "c"]);
+        assert_eq!(print(&doc, path, 2), vec!["a", "", "", "b", "", "c"]);
+    }
+    assert_eq!(print(&doc, &[2], 2), vec!["a", "", "", "b", "", "c"]);
+    // The focused line is always printed.
+    assert_eq!(print(&doc, &[3], 1), vec!["a", "", "", "b", "", "c"]);
+    assert_eq!(print(&doc, &[3], 2), vec!["a", "", "", "", "b", "", "c"]);
+    assert_eq!(print(&doc, &[1], 0), vec!["a", "", "b", "c"]);
+}
+
+#[test]
+fn basics_tie_break() {
+    use partial_pretty_printer::{pretty_print_with_options, ChoiceTieBreak, PrintOptions};
+
+    fn print(doc: &SimpleDoc, width: u16, tie_break: ChoiceTieBreak) -> Vec<String> {
+        let options = PrintOptions {
+            tie_break,
+            ..PrintOptions::default()
+        };
+        let mut results = Vec::new();
+        for focus_target in [FocusTarget::Start, FocusTarget::End] {
+            let (prev_lines, focused_line, next_lines) =
+                pretty_print_with_options(doc, width, &[], focus_target, None, &options).unwrap();
+            let mut lines = prev_lines
+                .map(|line| line.unwrap().to_string())
+                .collect::<Vec<_>>();
+            lines.reverse();
+            lines.push(focused_line.to_string());
+            lines.extend(next_lines.map(|line| line.unwrap().to_string()));
+            results.push(lines);
+        }
+        // The layout doesn't depend on the focus.
+        assert_eq!(results[0], results[1]);
+        results.pop().unwrap()
+    }
+
+    // The first option overflows by 4 columns and takes 2 more lines. The second overflows by 7
+    // and takes 1 more line.
+    let notation = lit("aaaaaa") + ((lit(" b") ^ empty() ^ lit("c")) | (lit(" bbbb") ^ lit("c")));
+    let doc = SimpleDoc::new(notation);
+    assert_eq!(
+        print(&doc, 4, ChoiceTieBreak::PreferSecond),
+        vec!["aaaaaa bbbb", "c"]
+    );
+    assert_eq!(
+        print(&doc, 4, ChoiceTieBreak::PreferFewestOverflowColumns),
+        vec!["aaaaaa b", "", "c"]
+    );
+    assert_eq!(
+        print(&doc, 4, ChoiceTieBreak::PreferFewestLines),
+        vec!["aaaaaa bbbb", "c"]
+    );
+
+    // The first option overflows by 2 columns and takes no more lines.
+    let notation = lit("aaaaaa") + (lit(" b") | (lit(" bbbb") ^ lit("c")));
+    let doc = SimpleDoc::new(notation);
+    assert_eq!(
+        print(&doc, 6, ChoiceTieBreak::PreferSecond),
+        vec!["aaaaaa bbbb", "c"]
+    );
+    assert_eq!(
+        print(&doc, 6, ChoiceTieBreak::PreferFewestOverflowColumns),
+        vec!["aaaaaa b"]
+    );
+    assert_eq!(
+        print(&doc, 6, ChoiceTieBreak::PreferFewestLines),
+        vec!["aaaaaa b"]
+    );
+    // If an option fits, the tie break isn't used.
+    assert_eq!(
+        print(&doc, 8, ChoiceTieBreak::PreferFewestLines),
+        vec!["aaaaaa b"]
+    );
+    assert_eq!(
+        print(&doc, 7, ChoiceTieBreak::PreferFewestOverflowColumns),
+        vec!["aaaaaa b"]
+    );
+}
+
+#[test]
+fn basics_pad_to() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::doc_examples::BasicStyle;
+    use partial_pretty_printer::notation_constructors::{child, pad_to, text};
+
+    let notation = lit("a") + pad_to(4) + lit("b") + pad_to(2) + lit("c");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a   bc"]);
+    // Trailing padding is left out.
+    let notation = (lit("a") + pad_to(3) + pad_to(6) + space() + lit("b") + pad_to(9)) ^ lit("c");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a     b", "c"]);
+    let notation = lit("a") + space() + pad_to(4) + lit("b") + eol() + pad_to(8);
+    assert_pp(&SimpleDoc::new(notation), 80, &["a   b"]);
+
+    static ROOT_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| (child(0) ^ child(1) ^ child(2)).validate().unwrap());
+    static LINE_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (text() + pad_to(10) + lit("// c") + eol())
+            .validate()
+            .unwrap()
+    });
+
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &ROOT_NOTATION,
+        ["a", "abcdefghijkl", "xyz"]
+            .iter()
+            .map(|s| Tree::new_text(&LINE_NOTATION, s.to_string()))
+            .collect(),
+    );
+    assert_pp(
+        &doc,
+        12,
+        &["a         // c", "abcdefghijkl// c", "xyz       // c"],
+    );
+}
@@ -0,0 +1,86 @@
+use partial_pretty_printer::doc_examples::json::{
+    json_array, json_number, json_object, json_object_pair, Json,
+};
+use partial_pretty_printer::{
+    pretty_print_blocks, FocusTarget, Line, PrettyDoc, PrintOptions, ResolvedBlock, Segment,
+};
+
+fn to_string<'d>(segments: &[Segment<'d, &'d Json>]) -> String {
+    segments.iter().map(|seg| seg.str).collect()
+}
+
+#[test]
+fn test_resolved_blocks() {
+    let doc = json_object(vec![
+        json_object_pair("Cats", json_array(vec![json_number(1.), json_number(2.)])),
+        json_object_pair("Dogs", json_array(Vec::new())),
+    ]);
+    let object_id = (&doc).id().unwrap();
+    let cats_id = (&doc)
+        .unwrap_child(0)
+        .unwrap()
+        .unwrap_child(1)
+        .unwrap()
+        .id()
+        .unwrap();
+
+    let (prev_blocks, focused_block, next_blocks) = pretty_print_blocks(
+        &doc,
+        12,
+        &[0, 1],
+        FocusTarget::Start,
+        None,
+        &PrintOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        to_string(&focused_block.block.segments[..focused_block.focus_index]),
+        "\"Cats\": "
+    );
+
+    let mut blocks = prev_blocks.map(Result::unwrap).collect::<Vec<_>>();
+    blocks.reverse();
+    blocks.push(focused_block.block);
+    blocks.extend(next_blocks.map(Result::unwrap));
+
+    let summary = blocks
+        .iter()
+        .map(|block: &ResolvedBlock<&Json>| {
+            (
+                block.start_id,
+                block.indentation.len(),
+                block.indent_width(),
+                to_string(&block.segments),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            (None, 0, 0, "{".to_owned()),
+            (Some(object_id), 1, 4, "\"Cats\": [".to_owned()),
+            (Some(cats_id), 2, 8, "1,".to_owned()),
+            (Some(cats_id), 2, 8, "2".to_owned()),
+            (Some(cats_id), 1, 4, "],".to_owned()),
+            (Some(object_id), 1, 4, "\"Dogs\": []".to_owned()),
+            (Some(object_id), 0, 0, "}".to_owned()),
+        ]
+    );
+
+    let lines = blocks
+        .into_iter()
+        .map(|block| Line::from(block).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            "{",
+            "    \"Cats\": [",
+            "        1,",
+            "        2",
+            "    ],",
+            "    \"Dogs\": []",
+            "}"
+        ]
+    );
+}
@@ -0,0 +1,142 @@
+use partial_pretty_printer::pane::{Bookmark, Bookmarks, PrintingOptions, WidthStrategy, Zoom};
+use partial_pretty_printer::{ErrorPolicy, FocusTarget, PathEdit, UnicodeWidth};
+
+fn bookmark(label: &'static str, path: &[usize]) -> Bookmark<&'static str> {
+    Bookmark {
+        label,
+        path: path.to_vec(),
+        focus_target: FocusTarget::Start,
+    }
+}
+
+#[test]
+fn test_jump_list() {
+    let mut bookmarks = Bookmarks::new(3);
+    assert_eq!(bookmarks.back(bookmark("a", &[0])), None);
+
+    let mut bookmarks = Bookmarks::new(3);
+    bookmarks.record_jump(bookmark("a", &[0]));
+    bookmarks.record_jump(bookmark("a", &[1]));
+    bookmarks.record_jump(bookmark("a", &[1]));
+    assert_eq!(
+        bookmarks.jumps(),
+        &[bookmark("a", &[0]), bookmark("a", &[1])]
+    );
+
+    assert_eq!(bookmarks.forward(), None);
+    assert_eq!(
+        bookmarks.back(bookmark("b", &[])),
+        Some(&bookmark("a", &[1]))
+    );
+    assert_eq!(
+        bookmarks.back(bookmark("a", &[1])),
+        Some(&bookmark("a", &[0]))
+    );
+    assert_eq!(bookmarks.back(bookmark("a", &[0])), None);
+    assert_eq!(bookmarks.forward(), Some(&bookmark("a", &[1])));
+    assert_eq!(bookmarks.forward(), Some(&bookmark("b", &[])));
+    assert_eq!(bookmarks.forward(), None);
+
+    // Jumping after going back forgets the forward entries
+    assert_eq!(
+        bookmarks.back(bookmark("b", &[])),
+        Some(&bookmark("a", &[1]))
+    );
+    bookmarks.record_jump(bookmark("c", &[2]));
+    assert_eq!(
+        bookmarks.jumps(),
+        &[
+            bookmark("a", &[0]),
+            bookmark("a", &[1]),
+            bookmark("c", &[2])
+        ]
+    );
+
+    // The oldest entries are dropped when it's full
+    bookmarks.record_jump(bookmark("c", &[3]));
+    assert_eq!(
+        bookmarks.jumps(),
+        &[
+            bookmark("a", &[1]),
+            bookmark("c", &[2]),
+            bookmark("c", &[3])
+        ]
+    );
+}
+
+#[test]
+fn test_marks_and_reanchor() {
+    let mut bookmarks = Bookmarks::new(10);
+    bookmarks.record_jump(Bookmark {
+        focus_target: FocusTarget::Text(3),
+        ..bookmark("a", &[1, 2])
+    });
+    bookmarks.record_jump(bookmark("b", &[1, 2]));
+    bookmarks.set_mark("last_edit", bookmark("a", &[2, 0]));
+    assert_eq!(bookmarks.mark("last_edit"), Some(&bookmark("a", &[2, 0])));
+    assert_eq!(bookmarks.mark("other"), None);
+
+    bookmarks.reanchor(
+        &"a",
+        &PathEdit::Insert {
+            parent: vec![],
+            index: 0,
+        },
+    );
+    let jumps = bookmarks.jumps();
+    assert_eq!(jumps[0].path, vec![2, 2]);
+    assert_eq!(jumps[0].focus_target, FocusTarget::Text(3));
+    assert_eq!(jumps[1], bookmark("b", &[1, 2]));
+    assert_eq!(bookmarks.mark("last_edit"), Some(&bookmark("a", &[3, 0])));
+
+    bookmarks.reanchor(
+        &"a",
+        &PathEdit::Delete {
+            parent: vec![2],
+            index: 2,
+        },
+    );
+    assert_eq!(bookmarks.jumps()[0], bookmark("a", &[2]));
+    assert_eq!(
+        bookmarks.remove_mark("last_edit"),
+        Some(bookmark("a", &[3, 0]))
+    );
+    assert_eq!(bookmarks.mark("last_edit"), None);
+}
+
+#[test]
+fn test_bookmark_options() {
+    let mut options = PrintingOptions::<()> {
+        focus_path: vec![1, 0],
+        focus_target: FocusTarget::End,
+        focus_height: 0.5,
+        width_strategy: WidthStrategy::Full,
+        set_focus: true,
+        visible_whitespace: None,
+        reorder_bidi: false,
+        fall_back_on_invalid_path: true,
+        sticky_headers: 0,
+        clamp_focus_to_content: false,
+        on_error: ErrorPolicy::Fail,
+        width_policy: &UnicodeWidth,
+        matches: None,
+        blank_style: None,
+        zoom: Zoom::Normal,
+    };
+    let saved = Bookmark::from_options("doc", &options);
+    assert_eq!(
+        saved,
+        Bookmark {
+            label: "doc",
+            path: vec![1, 0],
+            focus_target: FocusTarget::End,
+        }
+    );
+
+    bookmark("doc", &[3]).restore(&mut options);
+    assert_eq!(options.focus_path, vec![3]);
+    assert_eq!(options.focus_target, FocusTarget::Start);
+    saved.restore(&mut options);
+    assert_eq!(options.focus_path, vec![1, 0]);
+    assert_eq!(options.focus_target, FocusTarget::End);
+}
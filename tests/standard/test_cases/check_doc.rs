@@ -0,0 +1,65 @@
+use once_cell::sync::Lazy;
+use partial_pretty_printer::doc_examples::json::{json_array, json_number, json_string};
+use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+use partial_pretty_printer::doc_examples::BasicStyle;
+use partial_pretty_printer::notation_constructors::{child, count, lit, text, Count};
+use partial_pretty_printer::{check_doc, DocNotationMismatch, MismatchKind};
+
+#[test]
+fn test_check_doc_valid() {
+    let doc = json_array(vec![
+        json_number(1.),
+        json_array(vec![json_string("a")]),
+        json_array(vec![]),
+    ]);
+    assert_eq!(check_doc(&doc).unwrap(), vec![]);
+}
+
+#[test]
+fn test_check_doc_mismatches() {
+    static PAIR_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("(") + child(0) + lit(", ") + child(2) + lit(")"))
+            .validate()
+            .unwrap()
+    });
+    static LIST_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        count(Count {
+            zero: lit("[]"),
+            one: child(0),
+            many: child(0) + lit("...") + child(-1),
+        })
+        .validate()
+        .unwrap()
+    });
+    static TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+    let word = |s: &str| Tree::new_text(&TEXT_NOTATION, s.to_owned());
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &PAIR_NOTATION,
+        vec![
+            Tree::new_branch(&TEXT_NOTATION, vec![word("a")]),
+            Tree::new_text(&LIST_NOTATION, "b".to_owned()),
+        ],
+    );
+    assert_eq!(
+        check_doc(&doc).unwrap(),
+        vec![
+            DocNotationMismatch {
+                path: vec![],
+                kind: MismatchKind::ChildIndexOutOfBounds { index: 2, len: 2 },
+            },
+            DocNotationMismatch {
+                path: vec![0],
+                kind: MismatchKind::TextNotationOnTextlessDoc,
+            },
+            DocNotationMismatch {
+                path: vec![1],
+                kind: MismatchKind::CountNotationOnChildlessDoc,
+            },
+        ]
+    );
+
+    // Only the case of a Count that applies is checked.
+    let doc: Tree<BasicStyle> = Tree::new_branch(&LIST_NOTATION, vec![word("a"), word("b")]);
+    assert_eq!(check_doc(&doc).unwrap(), vec![]);
+}
@@ -1,11 +1,18 @@
 use crate::standard::pretty_testing::assert_pp;
 use once_cell::sync::Lazy;
-use partial_pretty_printer::doc_examples::tree::{Tree, TreeCondition, TreeNotation};
+use partial_pretty_printer::doc_examples::tree::{
+    Tree, TreeCondition, TreeNotation, TreeStyleLabel,
+};
 use partial_pretty_printer::doc_examples::BasicStyle;
 use partial_pretty_printer::notation_constructors::{
-    check, child, count, empty, fold, left, lit, right, text, Count, Fold,
+    arity, check, check_join, child, count, empty, fold, left, lit, right, text, Arity, Count, Fold,
+};
+use partial_pretty_printer::{
+    pretty_print_to_string, CheckPos, JoinCondition, NotationError, PrettyDoc,
 };
-use partial_pretty_printer::CheckPos;
+use std::cell::Cell;
+use std::convert::Infallible;
+use std::ops::Range;
 
 static TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
 
@@ -154,3 +161,219 @@ fn test_negative_child_indices() {
         &["cba"],
     );
 }
+
+#[test]
+fn test_check_join() {
+    static JOIN_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        let sep = check_join(
+            JoinCondition::IsLastJoin,
+            lit(" and "),
+            check_join(JoinCondition::IsFirstJoin, lit("; "), lit(", ")),
+        );
+        fold(Fold {
+            first: child(0),
+            join: left() + sep + right(),
+        })
+        .validate()
+        .unwrap()
+    });
+
+    let list = |elems: &[&str]| {
+        Tree::new_branch(
+            &JOIN_NOTATION,
+            elems.iter().map(|s| element(s)).collect::<Vec<_>>(),
+        )
+    };
+    assert_pp(&list(&["a"]), 80, &["a"]);
+    assert_pp(&list(&["a", "b"]), 80, &["a and b"]);
+    assert_pp(&list(&["a", "b", "c"]), 80, &["a; b and c"]);
+    assert_pp(&list(&["a", "b", "c", "d"]), 80, &["a; b, c and d"]);
+
+    let outside_fold = check_join::<(), ()>(JoinCondition::IsFirstJoin, lit("a"), lit("b"));
+    assert!(matches!(
+        outside_fold.validate(),
+        Err(NotationError::CheckJoinOutsideJoin)
+    ));
+}
+
+#[test]
+fn test_arity() {
+    static MINUS_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        arity(Arity {
+            cases: vec![
+                (1, lit("-") + child(0)),
+                (2, child(0) + lit(" - ") + child(1)),
+            ],
+            fallback: lit("minus(")
+                + fold(Fold {
+                    first: child(0),
+                    join: left() + lit(", ") + right(),
+                })
+                + lit(")"),
+        })
+        .validate()
+        .unwrap()
+    });
+
+    let minus = |elems: &[&str]| {
+        Tree::new_branch(
+            &MINUS_NOTATION,
+            elems.iter().map(|s| element(s)).collect::<Vec<_>>(),
+        )
+    };
+    assert_pp(&minus(&[]), 80, &["minus()"]);
+    assert_pp(&minus(&["x"]), 80, &["-x"]);
+    assert_pp(&minus(&["x", "y"]), 80, &["x - y"]);
+    assert_pp(&minus(&["x", "y", "z"]), 80, &["minus(x, y, z)"]);
+
+    let out_of_bounds = arity::<(), ()>(Arity {
+        cases: vec![(2, child(0) + child(2))],
+        fallback: empty(),
+    });
+    assert!(matches!(
+        out_of_bounds.validate(),
+        Err(NotationError::ArityChildIndex { index: 2, arity: 2 })
+    ));
+}
+
+/// A document that counts how it's accessed, to check that `Fold` fetches its children in a
+/// single call to `unwrap_children()`.
+#[derive(Debug, Clone, Copy)]
+struct CountingDoc<'d> {
+    tree: &'d Tree<BasicStyle>,
+    /// The number of calls to `unwrap_child()` and to `unwrap_children()`.
+    calls: &'d Cell<(usize, usize)>,
+}
+
+impl<'d> CountingDoc<'d> {
+    fn wrap(self, tree: &'d Tree<BasicStyle>) -> Self {
+        CountingDoc {
+            tree,
+            calls: self.calls,
+        }
+    }
+}
+
+impl<'d> PrettyDoc<'d> for CountingDoc<'d> {
+    type Id = u32;
+    type Style = BasicStyle;
+    type StyleLabel = TreeStyleLabel;
+    type Condition = TreeCondition;
+    type Error = Infallible;
+
+    fn id(self) -> Result<u32, Infallible> {
+        self.tree.id()
+    }
+
+    fn notation(self) -> Result<&'d TreeNotation, Infallible> {
+        self.tree.notation()
+    }
+
+    fn condition(self, condition: &TreeCondition) -> Result<bool, Infallible> {
+        self.tree.condition(condition)
+    }
+
+    fn lookup_style(self, style_label: TreeStyleLabel) -> Result<BasicStyle, Infallible> {
+        self.tree.lookup_style(style_label)
+    }
+
+    fn node_style(self) -> Result<BasicStyle, Infallible> {
+        self.tree.node_style()
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Infallible> {
+        self.tree.num_children()
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Infallible> {
+        self.tree.unwrap_text()
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Infallible> {
+        let (child_calls, children_calls) = self.calls.get();
+        self.calls.set((child_calls + 1, children_calls));
+        Ok(self.wrap(self.tree.unwrap_child(i)?))
+    }
+
+    fn unwrap_children(self, range: Range<usize>) -> Result<Vec<Self>, Infallible> {
+        let (child_calls, children_calls) = self.calls.get();
+        self.calls.set((child_calls, children_calls + 1));
+        range
+            .map(|i| Ok(self.wrap(self.tree.unwrap_child(i)?)))
+            .collect()
+    }
+}
+
+#[test]
+fn test_unwrap_children() {
+    static LIST_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        fold(Fold {
+            first: child(0),
+            join: left()
+                + check(
+                    TreeCondition::IsEmptyText,
+                    CheckPos::LeftChild,
+                    empty(),
+                    lit(", "),
+                )
+                + right(),
+        })
+        .validate()
+        .unwrap()
+    });
+
+    let tree = Tree::new_branch(
+        &LIST_NOTATION,
+        vec![element("a"), element(""), element("b"), element("c")],
+    );
+    let calls = Cell::new((0, 0));
+    let doc = CountingDoc {
+        tree: &tree,
+        calls: &calls,
+    };
+    assert_eq!(pretty_print_to_string(doc, 80).unwrap(), "a, b, c");
+    // The only other lookup is `Fold.first`'s `child(0)`.
+    assert_eq!(calls.get(), (1, 1));
+}
+
+#[test]
+fn test_summarize() {
+    use partial_pretty_printer::notation_constructors::{hidden_count, summarize, Summarize};
+
+    static LIST_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        let elements = fold(Fold {
+            first: child(0),
+            join: left() + lit(", ") + right(),
+        });
+        (lit("[")
+            + summarize(Summarize {
+                limit: 3,
+                notation: elements + lit(" (last: ") + child(-1) + lit(")"),
+                summary: lit(", … ") + hidden_count() + lit(" more"),
+            })
+            + lit("]"))
+        .validate()
+        .unwrap()
+    });
+    // A notation that fails if it's ever displayed.
+    static BROKEN_NOTATION: Lazy<TreeNotation> = Lazy::new(|| child(5).validate().unwrap());
+
+    let list = |elements: Vec<Tree<BasicStyle>>| Tree::new_branch(&LIST_NOTATION, elements);
+
+    let doc = list(vec![element("a"), element("b"), element("c")]);
+    assert_pp(&doc, 80, &["[a, b, c (last: c)]"]);
+
+    // The hidden children are never displayed.
+    let mut elements = vec![element("a"), element("b"), element("c")];
+    for _ in 0..12 {
+        elements.push(Tree::new_branch(&BROKEN_NOTATION, vec![]));
+    }
+    // (`assert_pp` would try to seek to them.)
+    assert_eq!(
+        pretty_print_to_string(&list(elements), 80).unwrap(),
+        "[a, b, c (last: c), … 12 more]"
+    );
+
+    let doc = list(vec![element("a"), element("b")]);
+    assert_pp(&doc, 80, &["[a, b (last: b)]"]);
+}
@@ -0,0 +1,126 @@
+use crate::standard::pretty_testing::assert_pp;
+use once_cell::sync::Lazy;
+use partial_pretty_printer::notation_constructors::{columns, lit, text};
+use partial_pretty_printer::{PrettyDoc, ValidNotation};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    id: usize,
+    data: ItemData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ItemData {
+    Word(String),
+    List(Vec<Item>),
+    /// Can't be displayed flat.
+    Broken,
+}
+
+static WORD_NOTATION: Lazy<ValidNotation<(), ()>> = Lazy::new(|| text().validate().unwrap());
+static LIST_NOTATION: Lazy<ValidNotation<(), ()>> = Lazy::new(|| columns(3, 2).validate().unwrap());
+static BROKEN_NOTATION: Lazy<ValidNotation<(), ()>> =
+    Lazy::new(|| (lit("x") ^ lit("y")).validate().unwrap());
+
+impl<'d> PrettyDoc<'d> for &'d Item {
+    type Id = usize;
+    type Style = ();
+    type StyleLabel = ();
+    type Condition = ();
+    type Error = std::convert::Infallible;
+
+    fn id(self) -> Result<usize, Self::Error> {
+        Ok(self.id)
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<(), ()>, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(_) => &WORD_NOTATION,
+            ItemData::List(_) => &LIST_NOTATION,
+            ItemData::Broken => &BROKEN_NOTATION,
+        })
+    }
+
+    fn condition(self, _condition: &()) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn node_style(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn lookup_style(self, _label: ()) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(_) => None,
+            ItemData::List(items) => Some(items.len()),
+            ItemData::Broken => Some(0),
+        })
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(word) => word,
+            ItemData::List(_) | ItemData::Broken => unreachable!(),
+        })
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(_) | ItemData::Broken => unreachable!(),
+            ItemData::List(items) => &items[i],
+        })
+    }
+}
+
+static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn new_node(data: ItemData) -> Item {
+    Item {
+        id: ID_COUNTER.fetch_add(1, Ordering::SeqCst),
+        data,
+    }
+}
+
+fn word(word: &str) -> Item {
+    new_node(ItemData::Word(word.to_owned()))
+}
+
+fn list(items: Vec<Item>) -> Item {
+    new_node(ItemData::List(items))
+}
+
+fn words(words: &[&str]) -> Item {
+    list(words.iter().map(|w| word(w)).collect())
+}
+
+#[test]
+fn columns_balanced() {
+    let doc = words(&["alpha", "beta", "gamma", "delta", "epsilon"]);
+    assert_pp(&doc, 80, &["alpha  gamma  epsilon", "beta   delta"]);
+    assert_pp(&doc, 20, &["alpha  delta", "beta   epsilon", "gamma"]);
+    assert_pp(&doc, 10, &["alpha", "beta", "gamma", "delta", "epsilon"]);
+}
+
+#[test]
+fn columns_skip_empty_last_column() {
+    // Three columns of two rows would leave the last column empty, so there are only two.
+    let doc = words(&["a", "b", "c", "d"]);
+    assert_pp(&doc, 80, &["a  c", "b  d"]);
+    assert_pp(&words(&["a"]), 80, &["a"]);
+    assert_pp(&words(&[]), 80, &[""]);
+}
+
+#[test]
+fn columns_nested_and_unflattenable() {
+    // A nested list is displayed flat, on one line.
+    let doc = list(vec![words(&["a", "b", "c"]), word("d"), word("e")]);
+    assert_pp(&doc, 80, &["a  b  c  d  e"]);
+
+    let doc = list(vec![word("a"), new_node(ItemData::Broken), word("b")]);
+    assert_pp(&doc, 80, &["a", "x", "y", "b"]);
+}
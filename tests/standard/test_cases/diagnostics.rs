@@ -0,0 +1,36 @@
+use partial_pretty_printer::diagnostics::{render_diagnostic, Diagnostic, Severity};
+use partial_pretty_printer::doc_examples::json::{json_array, json_number, json_string};
+use partial_pretty_printer::FocusTarget;
+
+#[test]
+fn test_render_diagnostic() {
+    let doc = json_array(vec![json_number(1.0), json_string("two"), json_number(3.0)]);
+    let diagnostic = Diagnostic {
+        path: vec![1],
+        focus_target: FocusTarget::Start,
+        span_len: 5,
+        message: "expected a number".to_owned(),
+        severity: Severity::Error,
+    };
+    assert_eq!(
+        render_diagnostic(&doc, 10, &diagnostic, 0).unwrap(),
+        "error: expected a number\n |\n |     \"two\",\n |     ^^^^^"
+    );
+    assert_eq!(
+        render_diagnostic(&doc, 10, &diagnostic, 5).unwrap(),
+        "error: expected a number\n |\n | [\n |     1,\n |     \"two\",\n |     ^^^^^"
+    );
+
+    // The underline stops at the end of the line.
+    let diagnostic = Diagnostic {
+        path: vec![2],
+        focus_target: FocusTarget::Text(0),
+        span_len: 100,
+        message: "too big".to_owned(),
+        severity: Severity::Warning,
+    };
+    assert_eq!(
+        render_diagnostic(&doc, 80, &diagnostic, 0).unwrap(),
+        "warning: too big\n |\n | [1, \"two\", 3]\n |            ^^"
+    );
+}
@@ -0,0 +1,173 @@
+use crate::standard::pretty_testing::assert_pp;
+use partial_pretty_printer::doc_examples::json::{json_array, json_number, json_string, Json};
+use partial_pretty_printer::doc_examples::{BasicStyle, Color};
+use partial_pretty_printer::doc_view::{
+    FilterChildren, MapStyle, MultiDoc, StyleSource, TraceStyles,
+};
+use partial_pretty_printer::notation_constructors::nl;
+use partial_pretty_printer::{pretty_print_to_annotated_string, PrettyDoc};
+
+fn make_doc() -> Json {
+    json_array(vec![
+        json_number(1.),
+        json_string("a"),
+        json_array(vec![json_number(2.), json_string("b")]),
+        json_string("c"),
+        json_number(3.),
+    ])
+}
+
+fn is_not_string(node: &Json) -> bool {
+    match node.num_children().unwrap() {
+        Some(_) => true,
+        None => node.unwrap_text().unwrap().parse::<f64>().is_ok(),
+    }
+}
+
+#[test]
+fn test_filter_children() {
+    let doc = make_doc();
+    let numbers_only = FilterChildren::new(&doc, &is_not_string);
+    assert_pp(numbers_only, 80, &["[1, [2], 3]"]);
+    let flat_doc = json_array(vec![json_string("a"), json_number(1.), json_number(2.)]);
+    assert_pp(
+        FilterChildren::new(&flat_doc, &is_not_string),
+        5,
+        &["[", "    1,", "    2", "]"],
+    );
+    assert_eq!(numbers_only.num_children().unwrap(), Some(3));
+    assert_eq!(
+        numbers_only.unwrap_child(1).unwrap().id().unwrap(),
+        (&doc).unwrap_child(2).unwrap().id().unwrap()
+    );
+
+    let keep_nothing = |_: &Json| false;
+    assert_pp(FilterChildren::new(&doc, &keep_nothing), 80, &["[]"]);
+}
+
+#[test]
+fn test_map_style() {
+    let doc = make_doc();
+    let strike = |_: &Json, style: BasicStyle| style.strikethrough();
+    let (string, annotations) =
+        pretty_print_to_annotated_string(MapStyle::new(&doc, &strike), 80).unwrap();
+    assert_eq!(string, r#"[1, "a", [2, "b"], "c", 3]"#);
+    assert!(!annotations.is_empty());
+    assert!(annotations
+        .iter()
+        .all(|annotation| annotation.style.strikethrough));
+
+    let (_, annotations) = pretty_print_to_annotated_string(&doc, 80).unwrap();
+    assert!(annotations
+        .iter()
+        .all(|annotation| !annotation.style.strikethrough));
+}
+
+#[test]
+fn test_trace_styles() {
+    let doc = json_array(vec![json_number(1.), json_string("a")]);
+    let root_id = doc.id().unwrap();
+    let string_id = doc.unwrap_child(1).unwrap().id().unwrap();
+    let (string, annotations) =
+        pretty_print_to_annotated_string(TraceStyles::new(&doc), 80).unwrap();
+    assert_eq!(string, r#"[1, "a"]"#);
+
+    let a = annotations
+        .iter()
+        .find(|annotation| &string[annotation.range.clone()] == "a")
+        .unwrap();
+    let sources = a
+        .style
+        .sources()
+        .iter()
+        .map(|(source, _)| source.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sources,
+        vec![
+            StyleSource::Node(root_id),
+            StyleSource::Node(string_id),
+            StyleSource::Label("magenta", string_id),
+        ]
+    );
+    assert!(matches!(a.style.effective_style().color, Color::Magenta));
+}
+
+#[test]
+fn test_multi_doc() {
+    let docs = [
+        json_array(vec![json_number(1.), json_number(2.)]),
+        json_string("a"),
+        json_number(3.),
+    ];
+    let multi_doc = MultiDoc::new(docs.iter().collect(), nl() + nl()).unwrap();
+    assert_pp(multi_doc.root(), 80, &["[1, 2]", "", "\"a\"", "", "3"]);
+    assert_pp(
+        multi_doc.root(),
+        5,
+        &["[", "    1,", "    2", "]", "", "\"a\"", "", "3"],
+    );
+    assert_eq!(multi_doc.root().id().unwrap(), None);
+    assert_eq!(
+        multi_doc.root().unwrap_child(1).unwrap().id().unwrap(),
+        Some(docs[1].id().unwrap())
+    );
+
+    let single = MultiDoc::new(vec![&docs[2]], nl() + nl()).unwrap();
+    assert_pp(single.root(), 80, &["3"]);
+}
+
+#[test]
+fn test_embed_docs() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::simple_tree::{node, text, SimpleTree};
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::doc_view::{EmbedDocs, EmbedNode};
+    use partial_pretty_printer::notation_constructors::{child, lit};
+
+    static QUERY_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| (lit("sql ") + child(0)).validate().unwrap());
+
+    let query = Tree::new_branch(&QUERY_NOTATION, Vec::new());
+    let doc = json_array(vec![json_number(1.), query, json_string("b")]);
+    let select = node(
+        (lit("SELECT ") + child(0) + lit(" FROM ") + child(1))
+            | ((lit("SELECT ") + child(0)) ^ (lit("FROM ") + child(1))),
+        vec![text("x"), text("t")],
+    );
+    let query_id = (&doc).unwrap_child(1).unwrap().id().unwrap();
+    let lookup = |node: &Json| -> Option<&SimpleTree> {
+        if node.id().unwrap() == query_id {
+            Some(&select)
+        } else {
+            None
+        }
+    };
+    let embedded = EmbedDocs::new(&doc, &lookup);
+
+    assert_pp(embedded, 80, &[r#"[1, sql SELECT x FROM t, "b"]"#]);
+    assert_pp(
+        embedded,
+        20,
+        &[
+            "[",
+            "    1,",
+            "    sql SELECT x",
+            "    FROM t,",
+            r#"    "b""#,
+            "]",
+        ],
+    );
+
+    let query = embedded.unwrap_child(1).unwrap();
+    assert_eq!(query.num_children().unwrap(), Some(1));
+    assert_eq!(
+        query.unwrap_child(0).unwrap().id().unwrap(),
+        EmbedNode::Embedded((&select).id().unwrap())
+    );
+    assert_eq!(
+        query.unwrap_children(0..1).unwrap()[0].id().unwrap(),
+        EmbedNode::Embedded((&select).id().unwrap())
+    );
+    assert_eq!(embedded.num_children().unwrap(), Some(3));
+}
@@ -0,0 +1,180 @@
+use partial_pretty_printer::doc_examples::json::{json_array, json_number, json_string, Json};
+use partial_pretty_printer::doc_examples::tree::{TreeCondition, TreeNotation, TreeStyleLabel};
+use partial_pretty_printer::doc_examples::{BasicStyle, Color};
+use partial_pretty_printer::{
+    pretty_print_with_options, ErrorPolicy, FocusTarget, Line, PrettyDoc, PrintOptions,
+    ERROR_PLACEHOLDER,
+};
+
+/// A Json document in which one node can't be loaded.
+#[derive(Debug, Clone, Copy)]
+struct FlakyDoc<'d> {
+    node: &'d Json,
+    broken_id: u32,
+}
+
+fn broken() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, "node not loaded")
+}
+
+impl<'d> FlakyDoc<'d> {
+    fn check(self) -> Result<&'d Json, std::io::Error> {
+        if self.node.id().unwrap() == self.broken_id {
+            Err(broken())
+        } else {
+            Ok(self.node)
+        }
+    }
+}
+
+impl<'d> PrettyDoc<'d> for FlakyDoc<'d> {
+    type Id = u32;
+    type Style = BasicStyle;
+    type StyleLabel = TreeStyleLabel;
+    type Condition = TreeCondition;
+    type Error = std::io::Error;
+
+    fn id(self) -> Result<u32, Self::Error> {
+        Ok(self.node.id().unwrap())
+    }
+
+    fn notation(self) -> Result<&'d TreeNotation, Self::Error> {
+        Ok(self.check()?.notation().unwrap())
+    }
+
+    fn condition(self, condition: &TreeCondition) -> Result<bool, Self::Error> {
+        Ok(self.check()?.condition(condition).unwrap())
+    }
+
+    fn lookup_style(self, style_label: TreeStyleLabel) -> Result<BasicStyle, Self::Error> {
+        Ok(self.check()?.lookup_style(style_label).unwrap())
+    }
+
+    fn node_style(self) -> Result<BasicStyle, Self::Error> {
+        Ok(self.check()?.node_style().unwrap())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        Ok(self.check()?.num_children().unwrap())
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        Ok(self.check()?.unwrap_text().unwrap())
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(FlakyDoc {
+            node: self.node.unwrap_child(i).unwrap(),
+            broken_id: self.broken_id,
+        })
+    }
+}
+
+fn print_all<'d>(
+    doc: FlakyDoc<'d>,
+    width: u16,
+    on_error: ErrorPolicy<BasicStyle>,
+) -> Result<Vec<Line<'d, FlakyDoc<'d>>>, String> {
+    let options = PrintOptions {
+        on_error,
+        ..PrintOptions::default()
+    };
+    let (prev_lines, focused_line, next_lines) =
+        pretty_print_with_options(doc, width, &[], FocusTarget::Start, None, &options)
+            .map_err(|err| err.to_string())?;
+    let mut lines = prev_lines
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    lines.reverse();
+    lines.push(Line::from(focused_line));
+    for line in next_lines {
+        lines.push(line.map_err(|err| err.to_string())?);
+    }
+    Ok(lines)
+}
+
+fn make_doc() -> Json {
+    json_array(vec![json_string("a"), json_string("b"), json_number(3.)])
+}
+
+#[test]
+fn test_error_policy_fail() {
+    let json = make_doc();
+    let doc = FlakyDoc {
+        node: &json,
+        broken_id: json.unwrap_child(1).unwrap().id().unwrap(),
+    };
+    assert!(print_all(doc, 80, ErrorPolicy::Fail).is_err());
+}
+
+#[test]
+fn test_error_policy_substitute() {
+    let json = make_doc();
+    let doc = FlakyDoc {
+        node: &json,
+        broken_id: json.unwrap_child(1).unwrap().id().unwrap(),
+    };
+    let error_style = BasicStyle::new().color(Color::Red);
+
+    let lines = print_all(doc, 80, ErrorPolicy::Substitute(error_style)).unwrap();
+    let lines = lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(lines, vec![format!("[\"a\", {}, 3]", ERROR_PLACEHOLDER)]);
+
+    // The broken node can't say whether it needs a separator, so it doesn't get one.
+    let lines = print_all(doc, 8, ErrorPolicy::Substitute(error_style)).unwrap();
+    let placeholder = lines[2]
+        .segments
+        .iter()
+        .find(|seg| seg.str == ERROR_PLACEHOLDER)
+        .unwrap();
+    assert!(matches!(placeholder.style.color, Color::Red));
+    let lines = lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec![
+            "[".to_owned(),
+            "    \"a\",".to_owned(),
+            format!("    {}", ERROR_PLACEHOLDER),
+            "    3".to_owned(),
+            "]".to_owned(),
+        ]
+    );
+}
+
+#[test]
+fn test_error_above_focus() {
+    let json = json_array(vec![
+        json_array(vec![
+            json_string("aaaaaaaaaaaa"),
+            json_array(vec![json_string("b")]),
+        ]),
+        json_number(3.),
+    ]);
+    let doc = FlakyDoc {
+        node: &json,
+        broken_id: json
+            .unwrap_child(0)
+            .unwrap()
+            .unwrap_child(1)
+            .unwrap()
+            .unwrap_child(0)
+            .unwrap()
+            .id()
+            .unwrap(),
+    };
+
+    // Seeking doesn't need the broken node, so the error comes from the upward printer, at the line
+    // that contains it.
+    let (mut prev_lines, focused_line, _) = pretty_print_with_options(
+        doc,
+        10,
+        &[1],
+        FocusTarget::Start,
+        None,
+        &PrintOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(focused_line.to_left_string(), "    ");
+    assert_eq!(prev_lines.next().unwrap().unwrap().to_string(), "    ],");
+    assert!(prev_lines.next().unwrap().is_err());
+}
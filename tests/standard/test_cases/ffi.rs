@@ -0,0 +1,225 @@
+use partial_pretty_printer::ffi::*;
+use std::ffi::{c_void, CString};
+use std::ptr;
+
+const WORDS: [&str; 4] = ["alpha", "beta", "gamma", "delta"];
+
+// Node 0 is a list of words, and node `i` is `WORDS[i - 1]`.
+extern "C" fn notation(_: *mut c_void, node: u64) -> u32 {
+    if node == 0 {
+        0
+    } else {
+        1
+    }
+}
+
+extern "C" fn num_children(_: *mut c_void, node: u64) -> i64 {
+    if node == 0 {
+        WORDS.len() as i64
+    } else {
+        -1
+    }
+}
+
+extern "C" fn text(_: *mut c_void, node: u64, len: *mut usize) -> *const u8 {
+    let word = WORDS[node as usize - 1];
+    unsafe { *len = word.len() };
+    word.as_ptr()
+}
+
+extern "C" fn child(_: *mut c_void, _node: u64, index: usize) -> u64 {
+    index as u64 + 1
+}
+
+extern "C" fn condition(_: *mut c_void, _node: u64, _condition: u32) -> bool {
+    false
+}
+
+const CALLBACKS: FfiDocCallbacks = FfiDocCallbacks {
+    user_data: ptr::null_mut(),
+    notation,
+    num_children,
+    text,
+    child,
+    condition,
+};
+
+unsafe fn words_notations() -> *mut FfiNotations {
+    let sep = CString::new(" ").unwrap();
+    let list = ppp_fold(
+        ppp_child(0),
+        ppp_concat(
+            ppp_concat(ppp_left(), ppp_choice(ppp_lit(sep.as_ptr()), ppp_nl())),
+            ppp_right(),
+        ),
+    );
+    let notations = ppp_notations_new();
+    let mut index = 99;
+    assert_eq!(ppp_notations_add(notations, list, &mut index), PPP_OK);
+    assert_eq!(index, 0);
+    assert_eq!(ppp_notations_add(notations, ppp_text(), &mut index), PPP_OK);
+    assert_eq!(index, 1);
+    notations
+}
+
+unsafe fn print(
+    notations: *const FfiNotations,
+    path: &[usize],
+    buffer_len: usize,
+) -> (i32, String, usize, usize) {
+    let mut buffer = vec![0; buffer_len];
+    let mut out_len = 0;
+    let mut focus_row = 0;
+    let status = ppp_print(
+        notations,
+        &CALLBACKS,
+        0,
+        12,
+        path.as_ptr(),
+        path.len(),
+        1,
+        1,
+        buffer.as_mut_ptr(),
+        buffer_len,
+        &mut out_len,
+        &mut focus_row,
+    );
+    buffer.truncate(out_len.min(buffer_len));
+    (
+        status,
+        String::from_utf8(buffer).unwrap(),
+        out_len,
+        focus_row,
+    )
+}
+
+#[test]
+fn ffi_print() {
+    unsafe {
+        let notations = words_notations();
+        assert_eq!(
+            print(notations, &[2], 100),
+            (PPP_OK, "alpha beta\ngamma delta".to_owned(), 22, 1)
+        );
+        assert_eq!(
+            print(notations, &[2], 10),
+            (PPP_BUFFER_TOO_SMALL, "\0".repeat(10), 22, 1)
+        );
+        ppp_notations_free(notations);
+    }
+}
+
+#[test]
+fn ffi_invalid_notation() {
+    unsafe {
+        let notations = ppp_notations_new();
+        let mut index = 0;
+        assert_eq!(
+            ppp_notations_add(notations, ppp_left(), &mut index),
+            PPP_INVALID_NOTATION
+        );
+        ppp_notations_free(notations);
+    }
+}
+
+#[test]
+fn ffi_null_arguments() {
+    unsafe {
+        // Constructors return null if any argument is null, freeing the others.
+        assert!(ppp_lit(ptr::null()).is_null());
+        assert!(ppp_flat(ptr::null_mut()).is_null());
+        assert!(ppp_concat(ppp_nl(), ptr::null_mut()).is_null());
+        assert!(ppp_choice(ptr::null_mut(), ppp_nl()).is_null());
+        assert!(ppp_indent(ptr::null(), ppp_nl()).is_null());
+        assert!(ppp_count(ppp_empty(), ptr::null_mut(), ppp_empty()).is_null());
+        assert!(ppp_fold(ppp_child(0), ptr::null_mut()).is_null());
+        assert!(ppp_check(0, ptr::null(), ptr::null_mut(), ppp_empty()).is_null());
+        // So a null from a failed constructor propagates up to `ppp_notations_add`.
+        let notations = ppp_notations_new();
+        let mut index = 99;
+        let broken = ppp_concat(ppp_lit(ptr::null()), ppp_text());
+        assert_eq!(
+            ppp_notations_add(notations, broken, &mut index),
+            PPP_NULL_ARGUMENT
+        );
+        assert_eq!(
+            ppp_notations_add(ptr::null_mut(), ppp_text(), &mut index),
+            PPP_NULL_ARGUMENT
+        );
+        assert_eq!(
+            ppp_notations_add(notations, ppp_text(), ptr::null_mut()),
+            PPP_NULL_ARGUMENT
+        );
+        assert_eq!(index, 99);
+        ppp_notations_free(notations);
+
+        let mut buffer = [0; 10];
+        let mut out_len = 0;
+        let mut focus_row = 0;
+        assert_eq!(
+            ppp_print(
+                ptr::null(),
+                &CALLBACKS,
+                0,
+                12,
+                ptr::null(),
+                0,
+                1,
+                1,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut out_len,
+                &mut focus_row,
+            ),
+            PPP_NULL_ARGUMENT
+        );
+        let notations = words_notations();
+        assert_eq!(
+            ppp_print(
+                notations,
+                &CALLBACKS,
+                0,
+                12,
+                ptr::null(),
+                1,
+                1,
+                1,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut out_len,
+                &mut focus_row,
+            ),
+            PPP_NULL_ARGUMENT
+        );
+        ppp_notations_free(notations);
+
+        // A null buffer is fine if it's 0 bytes long, even if there's nothing to write.
+        let notations = ppp_notations_new();
+        assert_eq!(
+            ppp_notations_add(notations, ppp_empty(), &mut index),
+            PPP_OK
+        );
+        assert_eq!(
+            ppp_print(
+                notations,
+                &CALLBACKS,
+                0,
+                12,
+                ptr::null(),
+                0,
+                1,
+                1,
+                ptr::null_mut(),
+                0,
+                &mut out_len,
+                &mut focus_row,
+            ),
+            PPP_OK
+        );
+        assert_eq!(out_len, 0);
+        ppp_notations_free(notations);
+
+        ppp_notation_free(ptr::null_mut());
+        ppp_notations_free(ptr::null_mut());
+    }
+}
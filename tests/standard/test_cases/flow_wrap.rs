@@ -1,9 +1,11 @@
 use crate::standard::pretty_testing::{assert_pp, assert_pp_seek};
 use once_cell::sync::Lazy;
+use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+use partial_pretty_printer::doc_examples::BasicStyle;
 use partial_pretty_printer::notation_constructors::{
-    child, count, fold, left, lit, nl, right, text, Count, Fold,
+    child, count, empty, flow, fold, left, lit, nl, right, text, Count, Fold,
 };
-use partial_pretty_printer::{PrettyDoc, ValidNotation};
+use partial_pretty_printer::{pretty_print_to_string, PrettyDoc, ValidNotation};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -372,3 +374,52 @@ fn flow_wrap() {
         ],
     );
 }
+
+#[test]
+fn flow_matches_count_and_fold() {
+    static FLOW_NOTATION: Lazy<TreeNotation> = Lazy::new(|| flow(2, lit(";")).validate().unwrap());
+    static COUNT_AND_FOLD_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        count(Count {
+            zero: empty(),
+            one: child(0),
+            many: fold(Fold {
+                first: child(0),
+                join: left() + lit(";") + (lit("  ") | nl()) + right(),
+            }),
+        })
+        .validate()
+        .unwrap()
+    });
+    static TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+    let words = [
+        "Oh",
+        "woe",
+        "is",
+        "me",
+        "the",
+        "turbofish",
+        "remains",
+        "undefeated",
+    ];
+    let make_doc = |notation: &'static TreeNotation, num_words: usize| {
+        let children = words[..num_words]
+            .iter()
+            .map(|word| Tree::new_text(&TEXT_NOTATION, (*word).to_owned()))
+            .collect();
+        Tree::<BasicStyle>::new_branch(notation, children)
+    };
+    for num_words in 0..=words.len() {
+        let flowed = make_doc(&FLOW_NOTATION, num_words);
+        let hand_built = make_doc(&COUNT_AND_FOLD_NOTATION, num_words);
+        for width in 0..30 {
+            assert_eq!(
+                pretty_print_to_string(&flowed, width).unwrap(),
+                pretty_print_to_string(&hand_built, width).unwrap(),
+                "{} words at width {}",
+                num_words,
+                width
+            );
+        }
+    }
+}
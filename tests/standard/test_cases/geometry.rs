@@ -0,0 +1,32 @@
+use partial_pretty_printer::{Pos, Rectangle, Size};
+
+#[test]
+fn test_saturating_geometry() {
+    let size = |width, height| Size { width, height };
+    let pos = |row, col| Pos { row, col };
+
+    assert_eq!(size(5, 3).clamp(size(4, 10)), size(4, 3));
+    assert_eq!(size(5, 3).saturating_sub(size(2, 7)), size(3, 0));
+    assert_eq!(pos(1, 2).offset(pos(3, 4)), pos(4, 6));
+    assert_eq!(pos(1, u16::MAX - 1).offset(pos(0, 5)), pos(1, u16::MAX));
+
+    let rect = Rectangle::new(pos(2, 3), size(10, 4));
+    assert_eq!(rect.pos(), pos(2, 3));
+    assert_eq!(rect.size(), size(10, 4));
+    assert_eq!(
+        rect.shrink(size(4, 20)),
+        Rectangle::new(pos(2, 3), size(4, 4))
+    );
+    assert_eq!(
+        Rectangle::new(pos(0, u16::MAX - 2), size(10, 1)).size(),
+        size(2, 1)
+    );
+
+    let inverted = Rectangle {
+        min_row: 5,
+        max_row: 2,
+        min_col: 5,
+        max_col: 2,
+    };
+    assert_eq!(inverted.size(), size(0, 0));
+}
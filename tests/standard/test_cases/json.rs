@@ -2,9 +2,10 @@ use crate::standard::pretty_testing::{
     assert_pp, assert_pp_focus, assert_pp_region, assert_pp_seek,
 };
 use partial_pretty_printer::doc_examples::json::{
-    json_array, json_bool, json_comment, json_null, json_number, json_object, json_object_pair,
-    json_roots, json_string, Json,
+    escape_json_string, escape_json_string_ascii, json_array, json_bool, json_comment, json_null,
+    json_number, json_object, json_object_pair, json_roots, json_string, json_string_ascii, Json,
 };
+use partial_pretty_printer::doc_examples::tree::LayoutHint;
 use partial_pretty_printer::FocusTarget;
 
 static NUMERALS: &[&str] = &[
@@ -18,6 +19,29 @@ fn json_constants() {
     assert_pp(&doc, 80, &["[true, null, false]"]);
 }
 
+#[test]
+fn json_string_escapes() {
+    assert_eq!(escape_json_string("plain"), "plain");
+    assert_eq!(
+        escape_json_string("say \"hi\"\\\n\t\u{1}"),
+        r#"say \"hi\"\\\n\t\u0001"#
+    );
+    assert_eq!(escape_json_string("一🦀"), "一🦀");
+    assert_eq!(escape_json_string_ascii("a一🦀"), r"a\u4e00\ud83e\udd80");
+
+    let doc = json_array(vec![json_string("a\"b"), json_string("一")]);
+    assert_pp(&doc, 80, &[r#"["a\"b", "一"]"#]);
+    assert_pp(&doc, 9, &["[", r#"    "a\"b","#, r#"    "一""#, "]"]);
+
+    let doc = json_array(vec![json_string_ascii("一"), json_string_ascii("🦀")]);
+    assert_pp(&doc, 26, &[r#"["\u4e00", "\ud83e\udd80"]"#]);
+    assert_pp(
+        &doc,
+        25,
+        &["[", r#"    "\u4e00","#, r#"    "\ud83e\udd80""#, "]"],
+    );
+}
+
 #[test]
 fn json_flow() {
     let doc = json_roots(vec![
@@ -178,6 +202,20 @@ fn json_focus() {
         ],
     );
 
+    assert_pp_focus(
+        refn,
+        17,
+        &[0, 1],
+        FocusTarget::LastMark,
+        &[
+            // force rustfmt
+            "{",
+            "    \"Cats\": [|],",
+            "    \"Dogs\": []",
+            "}",
+        ],
+    );
+
     assert_pp_focus(
         refn,
         17,
@@ -205,6 +243,20 @@ fn json_focus() {
             "}",
         ],
     );
+
+    assert_pp_focus(
+        refn,
+        17,
+        &[1, 0],
+        FocusTarget::TextEnd,
+        &[
+            // force rustfmt
+            "{",
+            "    \"Cats\": [],",
+            "    \"Dogs|\": []",
+            "}",
+        ],
+    );
 }
 
 #[test]
@@ -387,6 +439,67 @@ fn json_comment_commas() {
     );
 }
 
+#[test]
+fn json_layout_hints() {
+    let hint = |was_multiline, had_trailing_separator| LayoutHint {
+        was_multiline,
+        had_trailing_separator,
+    };
+    let array = || {
+        json_array(vec![
+            json_number(1.0),
+            json_number(2.0),
+            json_comment("three"),
+        ])
+    };
+
+    assert_pp(
+        &json_array(vec![json_number(1.0), json_number(2.0)]).with_layout_hint(hint(true, false)),
+        80,
+        &[
+            // force rustfmt
+            "[", "    1,", "    2", "]",
+        ],
+    );
+    assert_pp(
+        &json_array(vec![json_number(1.0), json_number(2.0)]).with_layout_hint(hint(true, true)),
+        80,
+        &[
+            // force rustfmt
+            "[", "    1,", "    2,", "]",
+        ],
+    );
+    // The trailing comma is only kept on multiple lines.
+    assert_pp(
+        &json_array(vec![json_number(1.0), json_number(2.0)]).with_layout_hint(hint(false, true)),
+        80,
+        &["[1, 2]"],
+    );
+    // A trailing comma goes after the last non-comment element.
+    assert_pp(
+        &array().with_layout_hint(hint(false, true)),
+        80,
+        &[
+            // force rustfmt
+            "[",
+            "    1,",
+            "    2,",
+            "    // three",
+            "]",
+        ],
+    );
+    assert_pp(
+        &json_object(vec![json_object_pair("a", json_null())]).with_layout_hint(hint(true, true)),
+        80,
+        &[
+            // force rustfmt
+            "{",
+            "    \"a\": null,",
+            "}",
+        ],
+    );
+}
+
 fn make_json_tree(id: u32, size: usize) -> Json {
     let children = (0..size)
         .map(|n| make_json_tree(2u32.pow(n as u32) + id, n))
@@ -489,3 +602,469 @@ fn time_json() {
     );
     panic!("Success!");
 }
+
+#[test]
+fn synthetic_docs() {
+    use partial_pretty_printer::testing::{make_deep_doc, make_flow_doc, make_wide_doc};
+    use partial_pretty_printer::{pretty_print, pretty_print_to_string};
+
+    let doc = make_wide_doc(3);
+    assert_pp(
+        &doc,
+        80,
+        &[
+            r#"["#,
+            r#"    {"id": 0, "name": "item_0"},"#,
+            r#"    {"id": 1, "name": "item_1"},"#,
+            r#"    {"id": 2, "name": "item_2"}"#,
+            r#"]"#,
+        ],
+    );
+
+    let doc = make_deep_doc(2);
+    assert_pp(
+        &doc,
+        40,
+        &[
+            r#"{"#,
+            r#"    "id": 0,"#,
+            r#"    "child": {"id": 1, "child": {}}"#,
+            r#"}"#,
+        ],
+    );
+    let (_, focused_line, _) =
+        pretty_print(&doc, 40, &[1, 1, 1, 1], FocusTarget::Start, None).unwrap();
+    assert_eq!(focused_line.to_right_string(), "{}}");
+
+    let doc = make_flow_doc(14);
+    assert_eq!(
+        pretty_print_to_string(&doc, 40).unwrap(),
+        [
+            "// lorem ipsum dolor sit amet",
+            "// consectetur adipiscing elit sed do",
+            "// eiusmod tempor lorem ipsum",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn json_print_stats() {
+    use crate::standard::pretty_testing::SimpleDoc;
+    use partial_pretty_printer::notation_constructors::lit;
+    use partial_pretty_printer::testing::make_wide_doc;
+    use partial_pretty_printer::{
+        pretty_print_to_string, pretty_print_with_options, FocusTarget, Line, PrettyDoc,
+        PrintOptions, PrintStats, Width,
+    };
+
+    fn print_with_stats<'d, D: PrettyDoc<'d>>(
+        doc: D,
+        width: Width,
+        options: &PrintOptions<'d, D>,
+    ) -> (Vec<Line<'d, D>>, PrintStats) {
+        let (_, focused_line, mut lines_below) =
+            pretty_print_with_options(doc, width, &[], FocusTarget::Start, None, options).unwrap();
+        let mut lines = vec![Line::from(focused_line)];
+        lines.extend(lines_below.by_ref().map(Result::unwrap));
+        (lines, lines_below.stats())
+    }
+
+    let doc = make_wide_doc(20);
+    let (lines, stats) = print_with_stats(&doc, 80, &PrintOptions::default());
+    let printed = lines
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(printed, pretty_print_to_string(&doc, 80).unwrap());
+    assert!(stats.fits_calls > 0);
+    assert!(stats.fits_steps >= stats.fits_calls);
+    assert!(stats.max_lookahead_chunks <= stats.lookahead_chunks);
+    assert_eq!(stats.lookahead_cutoffs, 0);
+
+    // Each `+ lit("x")` leaves another chunk after the choice for `fits` to look through.
+    let notation = (0..10).fold(lit("aa") | lit("b"), |note, _| note + lit("x"));
+    let doc = SimpleDoc::new(notation);
+    let lookahead_2 = PrintOptions {
+        max_lookahead: Some(2),
+        ..PrintOptions::default()
+    };
+    let (_, unlimited) = print_with_stats(&doc, 100, &PrintOptions::default());
+    assert_eq!(unlimited.max_lookahead_chunks, 10);
+    assert_eq!(unlimited.lookahead_cutoffs, 0);
+    let (lines, limited) = print_with_stats(&doc, 100, &lookahead_2);
+    assert_eq!(limited.max_lookahead_chunks, 2);
+    assert_eq!(limited.lookahead_cutoffs, 1);
+    assert_eq!(lines[0].to_string(), "aaxxxxxxxxxx");
+
+    // Cutting off the lookahead can pick a layout that's too wide.
+    let (lines, _) = print_with_stats(&doc, 5, &PrintOptions::default());
+    assert_eq!(lines[0].to_string(), "bxxxxxxxxxx");
+    let (lines, _) = print_with_stats(&doc, 5, &lookahead_2);
+    assert_eq!(lines[0].to_string(), "aaxxxxxxxxxx");
+}
+
+#[test]
+fn json_layout_fingerprint() {
+    use partial_pretty_printer::layout_fingerprint;
+
+    let numbers = |n: usize| json_array((0..n).map(|i| json_number(i as f64)).collect());
+    let strings = |n: usize| json_array((0..n).map(|_| json_string("x")).collect());
+
+    // Same doc and width, same fingerprint.
+    let fingerprint = layout_fingerprint(&numbers(3), 80).unwrap();
+    assert_eq!(fingerprint, layout_fingerprint(&numbers(3), 80).unwrap());
+
+    // Different text with the same layout has the same fingerprint.
+    assert_eq!(
+        layout_fingerprint(&numbers(3), 9).unwrap(),
+        layout_fingerprint(&strings(3), 15).unwrap()
+    );
+
+    // A different layout of the same doc has a different fingerprint.
+    assert_ne!(fingerprint, layout_fingerprint(&numbers(3), 8).unwrap());
+}
+
+#[test]
+fn json_analyze_layout() {
+    use partial_pretty_printer::analyze_layout;
+
+    let doc = json_array(vec![
+        json_number(1.0),
+        json_string("abcdefgh"),
+        json_number(22.0),
+    ]);
+    // [
+    //     1,
+    //     "abcdefgh",
+    //     22
+    // ]
+    let metrics = analyze_layout(&doc, 10).unwrap();
+    assert_eq!(metrics.num_lines, 5);
+    assert_eq!(metrics.max_line_width, 15);
+    assert_eq!(metrics.overflowing_lines, 1);
+    let mut width_counts = vec![0; 16];
+    width_counts[1] = 2;
+    width_counts[6] = 2;
+    width_counts[15] = 1;
+    assert_eq!(metrics.width_counts, width_counts);
+    assert!((metrics.average_fill - 29.0 / 50.0).abs() < 1e-9);
+
+    let metrics = analyze_layout(&doc, 80).unwrap();
+    assert_eq!(metrics.num_lines, 1);
+    assert_eq!(metrics.overflowing_lines, 0);
+    assert!((metrics.average_fill - 19.0 / 80.0).abs() < 1e-9);
+}
+
+#[test]
+fn json_width_policy() {
+    use crate::standard::pretty_testing::WideGreek;
+    use partial_pretty_printer::{
+        pretty_print_with_options, PrintOptions, UnicodeWidth, WidthPolicy,
+    };
+
+    fn print(doc: &Json, width: u16, width_policy: &'static dyn WidthPolicy) -> Vec<String> {
+        let options = PrintOptions {
+            width_policy,
+            ..PrintOptions::default()
+        };
+        let (_, focused_line, next_lines) =
+            pretty_print_with_options(doc, width, &[], FocusTarget::Start, None, &options).unwrap();
+        let mut lines = vec![focused_line.to_string()];
+        lines.extend(next_lines.map(|line| line.unwrap().to_string()));
+        lines
+    }
+
+    let doc = json_array(vec![json_string("αβγ"), json_string("δ")]);
+    assert_eq!(WideGreek.str_width("\"αβγ\""), 8);
+    assert_eq!(print(&doc, 12, &UnicodeWidth), vec!["[\"αβγ\", \"δ\"]"]);
+    assert_eq!(
+        print(&doc, 12, &WideGreek),
+        vec!["[", "    \"αβγ\",", "    \"δ\"", "]"]
+    );
+    assert_eq!(print(&doc, 16, &WideGreek), vec!["[\"αβγ\", \"δ\"]"]);
+}
+
+#[test]
+fn json_layout_cache() {
+    use partial_pretty_printer::{
+        pretty_print_to_string, pretty_print_with_options, LayoutCache, PrettyDoc, PrintOptions,
+    };
+
+    fn print(doc: &Json, width: u16, layout_cache: &LayoutCache<u32>) -> String {
+        let options = PrintOptions {
+            layout_cache: Some(layout_cache.clone()),
+            ..PrintOptions::default()
+        };
+        let (_, focused_line, next_lines) =
+            pretty_print_with_options(doc, width, &[], FocusTarget::Start, None, &options).unwrap();
+        let mut lines = vec![focused_line.to_string()];
+        for line in next_lines {
+            lines.push(line.unwrap().to_string());
+        }
+        lines.join("\n")
+    }
+
+    let doc = json_array(vec![make_object(), favorites_array(), make_object()]);
+    let layout_cache = LayoutCache::new();
+    assert!(layout_cache.is_empty());
+
+    // Reusing the cache, including at other widths, doesn't change the layout.
+    for width in [80, 40, 20, 80] {
+        assert_eq!(
+            print(&doc, width, &layout_cache),
+            pretty_print_to_string(&doc, width).unwrap()
+        );
+    }
+    let num_entries = layout_cache.len();
+    assert!(num_entries > 0);
+
+    // Invalidating a node forgets its measurements, and only its.
+    let favorites = (&doc).unwrap_child(1).unwrap();
+    layout_cache.invalidate(favorites.id().unwrap());
+    assert!(layout_cache.len() < num_entries);
+    assert!(!layout_cache.is_empty());
+    assert_eq!(
+        print(&doc, 40, &layout_cache),
+        pretty_print_to_string(&doc, 40).unwrap()
+    );
+
+    layout_cache.clear();
+    assert!(layout_cache.is_empty());
+}
+
+#[test]
+fn json_print_into() {
+    use partial_pretty_printer::{pretty_print_into, pretty_print_to_string, Line};
+
+    fn to_strings<'d>(lines: &[Line<'d, &'d Json>]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    let doc = json_array((0..5).map(|_| make_object()).collect());
+    let all_lines = pretty_print_to_string(&doc, 30)
+        .unwrap()
+        .lines()
+        .map(|line| line.to_owned())
+        .collect::<Vec<_>>();
+
+    let mut lines = Vec::new();
+    let (row, col) =
+        pretty_print_into(&doc, 30, &[2], FocusTarget::Start, 3, 4, None, &mut lines).unwrap();
+    assert_eq!((row, col), (3, 4));
+    // Each object takes the same number of lines, between the array's brackets.
+    let object_height = (all_lines.len() - 2) / 5;
+    let focus_row = 1 + 2 * object_height;
+    assert_eq!(to_strings(&lines), all_lines[focus_row - 3..=focus_row + 4]);
+
+    // Printing again into the same buffer reuses its segment vectors.
+    let old_segments = lines
+        .iter()
+        .map(|line| line.segments.as_ptr())
+        .collect::<Vec<_>>();
+    let (row, col) =
+        pretty_print_into(&doc, 30, &[], FocusTarget::Start, 3, 2, None, &mut lines).unwrap();
+    assert_eq!((row, col), (0, 0));
+    assert_eq!(to_strings(&lines), all_lines[..3]);
+    assert!(lines
+        .iter()
+        .any(|line| old_segments.contains(&line.segments.as_ptr())));
+}
+
+#[test]
+fn json_choice_strategy() {
+    use partial_pretty_printer::{
+        pretty_print_to_string, pretty_print_with_options, ChoiceContext, ChoiceStrategy,
+        FirstFits, PrintOptions,
+    };
+
+    /// Let lines overflow the width by a few columns before breaking them.
+    #[derive(Debug)]
+    struct Slack(u16);
+
+    impl ChoiceStrategy for Slack {
+        fn choose_first(&self, choice: &mut ChoiceContext) -> bool {
+            let width = choice.width() + self.0;
+            choice.first_fits_within(width)
+        }
+    }
+
+    fn print(doc: &Json, strategy: &dyn ChoiceStrategy) -> String {
+        let options = PrintOptions {
+            choice_strategy: strategy,
+            ..PrintOptions::default()
+        };
+        let (_, focused_line, next_lines) =
+            pretty_print_with_options(doc, 10, &[], FocusTarget::Start, None, &options).unwrap();
+        let mut lines = vec![focused_line.to_string()];
+        lines.extend(next_lines.map(|line| line.unwrap().to_string()));
+        lines.join("\n")
+    }
+
+    let doc = json_array(vec![
+        json_number(1.0),
+        json_number(22.0),
+        json_number(333.0),
+    ]);
+    assert_eq!(
+        print(&doc, &FirstFits),
+        pretty_print_to_string(&doc, 10).unwrap()
+    );
+    assert_eq!(print(&doc, &FirstFits), "[\n    1,\n    22,\n    333\n]");
+    assert_eq!(print(&doc, &Slack(4)), "[1, 22, 333]");
+}
+
+#[test]
+fn json_debug_snapshot() {
+    use partial_pretty_printer::debug_snapshot;
+
+    let doc = json_array(vec![json_number(1.0), json_number(2.0), json_number(3.0)]);
+    let snapshot = debug_snapshot(&doc, 4, &[1], FocusTarget::Start, None).unwrap();
+    assert_eq!(
+        snapshot.short(),
+        "|1\n    |2\n---focus---\n    |2\n-----------\n    |1\n|1\n"
+    );
+    assert_eq!(
+        snapshot.long(),
+        "'[' '    ' $0 ','  / '    ' '2' ',' '    ' $2 ']' "
+    );
+    let focused_block = &snapshot.next_blocks[0];
+    assert_eq!(focused_block.segments, vec!["    "]);
+    assert_eq!(focused_block.chunks, vec!["'2'", "','"]);
+}
+
+#[test]
+fn json_stored_printers() {
+    use partial_pretty_printer::{pretty_print, DownwardPrinter, UpwardPrinter};
+
+    // A scrollable view that keeps the printers around to print more lines on demand.
+    struct View<'d> {
+        above: UpwardPrinter<'d, &'d Json>,
+        below: DownwardPrinter<'d, &'d Json>,
+    }
+
+    let doc = json_array((1..=5).map(|n| json_number(n as f64)).collect());
+    let (above, focused_line, below) =
+        pretty_print(&doc, 4, &[2], FocusTarget::Start, None).unwrap();
+    assert_eq!(focused_line.to_string(), "    3,");
+    let mut view = View { above, below };
+    assert_eq!(view.above.next().unwrap().unwrap().to_string(), "    2,");
+    assert_eq!(view.below.next().unwrap().unwrap().to_string(), "    4,");
+    assert_eq!(view.above.next().unwrap().unwrap().to_string(), "    1,");
+    assert_eq!(view.above.next().unwrap().unwrap().to_string(), "[");
+    assert!(view.above.next().is_none());
+}
+
+#[test]
+fn json_printer_state() {
+    use partial_pretty_printer::pretty_print;
+
+    let doc = json_array((1..=5).map(|n| json_number(n as f64)).collect());
+    let (mut above, _, mut below) = pretty_print(&doc, 4, &[2], FocusTarget::Start, None).unwrap();
+
+    let below_state = below.save_state();
+    assert_eq!(below.next().unwrap().unwrap().to_string(), "    4,");
+    assert_eq!(below.next().unwrap().unwrap().to_string(), "    5");
+    below.restore_state(below_state.clone());
+    assert_eq!(below.next().unwrap().unwrap().to_string(), "    4,");
+    below.restore_state(below_state);
+    let rest = below
+        .map(|line| line.unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(rest, vec!["    4,", "    5", "]"]);
+
+    assert_eq!(above.next().unwrap().unwrap().to_string(), "    2,");
+    let above_state = above.save_state();
+    assert_eq!(above.next().unwrap().unwrap().to_string(), "    1,");
+    assert_eq!(above.next().unwrap().unwrap().to_string(), "[");
+    assert!(above.next().is_none());
+    above.restore_state(above_state);
+    assert_eq!(above.next().unwrap().unwrap().to_string(), "    1,");
+}
+
+#[test]
+fn json_printer_checkpoint() {
+    use partial_pretty_printer::{
+        pretty_print_near_line, pretty_print_with_options, DownwardPrinter, PrintOptions,
+        UpwardPrinter,
+    };
+
+    let doc = json_array((1..=5).map(|n| json_number(n as f64)).collect());
+    let options = PrintOptions::default();
+    let (mut above, _, mut below) =
+        pretty_print_with_options(&doc, 4, &[2], FocusTarget::Start, None, &options).unwrap();
+
+    assert_eq!(below.next().unwrap().unwrap().to_string(), "    4,");
+    let checkpoint = below.checkpoint();
+    let mut resumed = DownwardPrinter::resume(&doc, &checkpoint, None, &options).unwrap();
+    assert_eq!(resumed.checkpoint(), checkpoint);
+    assert_eq!(resumed.next().unwrap().unwrap().to_string(), "    5");
+    assert_eq!(resumed.next().unwrap().unwrap().to_string(), "]");
+    assert!(resumed.next().is_none());
+
+    assert_eq!(above.next().unwrap().unwrap().to_string(), "    2,");
+    let checkpoint = above.checkpoint();
+    let mut resumed = UpwardPrinter::resume(&doc, &checkpoint, None, &options).unwrap();
+    assert_eq!(resumed.next().unwrap().unwrap().to_string(), "    1,");
+    assert_eq!(resumed.next().unwrap().unwrap().to_string(), "[");
+    assert!(resumed.next().is_none());
+
+    // A checkpoint remembers where `pretty_print_near_line` moved the focus to.
+    let (_, focused_line, below) = pretty_print_near_line(&doc, 4, 3, None, &options).unwrap();
+    assert_eq!(focused_line.to_string(), "    4,");
+    let mut resumed = DownwardPrinter::resume(&doc, &below.checkpoint(), None, &options).unwrap();
+    assert_eq!(resumed.next().unwrap().unwrap().to_string(), "    5");
+
+    #[cfg(feature = "serialization")]
+    {
+        let json = serde_json::to_string(&below.checkpoint()).unwrap();
+        let checkpoint = serde_json::from_str(&json).unwrap();
+        let mut resumed = DownwardPrinter::resume(&doc, &checkpoint, None, &options).unwrap();
+        assert_eq!(resumed.next().unwrap().unwrap().to_string(), "    5");
+    }
+}
+
+#[test]
+fn json_fits_first_line() {
+    use partial_pretty_printer::fits_first_line;
+
+    let string = json_string("abc");
+    assert!(fits_first_line(&string, 5, 0, false).unwrap());
+    assert!(!fits_first_line(&string, 4, 0, false).unwrap());
+    assert!(fits_first_line(&string, 8, 3, false).unwrap());
+    assert!(!fits_first_line(&string, 8, 4, false).unwrap());
+    assert!(!fits_first_line(&string, 80, 0, true).unwrap());
+
+    // Only the `[` has to fit, since the array could be split across lines.
+    let array = json_array(vec![json_number(1.0), json_number(2.0)]);
+    assert!(fits_first_line(&array, 1, 0, false).unwrap());
+    assert!(!fits_first_line(&array, 1, 1, false).unwrap());
+}
+
+#[test]
+fn json_prefetcher() {
+    use partial_pretty_printer::{pretty_print, Prefetcher};
+
+    let doc = json_array((1..=5).map(|n| json_number(n as f64)).collect());
+    let (_, _, below) = pretty_print(&doc, 4, &[2], FocusTarget::Start, None).unwrap();
+    let mut below = Prefetcher::new(below);
+    assert_eq!(below.num_prefetched(), 0);
+    below.prefetch(2);
+    assert_eq!(below.num_prefetched(), 2);
+    assert_eq!(below.get(1).unwrap().as_ref().unwrap().to_string(), "    5");
+    assert!(!below.is_finished());
+    assert_eq!(below.next().unwrap().unwrap().to_string(), "    4,");
+    assert_eq!(below.num_prefetched(), 1);
+    below.prefetch(10);
+    assert_eq!(below.num_prefetched(), 2);
+    assert!(below.is_finished());
+
+    let (_, _, new_below) = pretty_print(&doc, 4, &[3], FocusTarget::Start, None).unwrap();
+    below.invalidate(new_below);
+    assert_eq!(below.num_prefetched(), 0);
+    let rest = below
+        .map(|line| line.unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(rest, vec!["    5", "]"]);
+}
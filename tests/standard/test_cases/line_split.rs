@@ -0,0 +1,332 @@
+use partial_pretty_printer::doc_examples::{
+    json::{json_array, json_string, Json},
+    BasicStyle, Color,
+};
+use partial_pretty_printer::{
+    pretty_print, Direction, FocusTarget, Line, Pos, SplitRounding, SplitSide, Width,
+};
+
+fn print_line(doc: &Json, width: Width) -> Line<&Json> {
+    let (_, focused_line, _) = pretty_print(doc, width, &[], FocusTarget::Start, None).unwrap();
+    Line::from(focused_line)
+}
+
+fn strings<'d>(line: &Line<'d, &'d Json>) -> Vec<String> {
+    line.segments
+        .iter()
+        .map(|seg| seg.str.to_owned())
+        .collect::<Vec<_>>()
+}
+
+#[test]
+fn test_split_line_at_segment_boundary() {
+    let doc = json_array(vec![json_string("ab"), json_string("cd")]);
+    let line = print_line(&doc, 80);
+    assert_eq!(line.to_string(), r#"["ab", "cd"]"#);
+
+    let split = line.split_at_width(5, SplitRounding::Exact).unwrap();
+    assert_eq!(split.left.to_string(), r#"["ab""#);
+    assert_eq!(split.right.to_string(), r#", "cd"]"#);
+    assert_eq!(split.straddler, None);
+    assert_eq!(split.left.width(), 5);
+}
+
+#[test]
+fn test_split_line_inside_segment() {
+    let doc = json_array(vec![json_string("abc")]);
+    let line = print_line(&doc, 80);
+    let split = line.split_at_width(3, SplitRounding::Exact).unwrap();
+    assert_eq!(strings(&split.left), vec!["[", "\"", "a"]);
+    assert_eq!(strings(&split.right), vec!["bc", "\"", "]"]);
+}
+
+#[test]
+fn test_split_line_past_end() {
+    let doc = json_string("abc");
+    let line = print_line(&doc, 80);
+    let split = line.split_at_width(100, SplitRounding::Exact).unwrap();
+    assert_eq!(split.left.to_string(), "\"abc\"");
+    assert_eq!(split.right.to_string(), "");
+    assert_eq!(split.straddler, None);
+}
+
+#[test]
+fn test_split_line_full_width() {
+    let doc = json_string("一二三");
+
+    let line = print_line(&doc, 80);
+    assert!(line.split_at_width(2, SplitRounding::Exact).is_none());
+
+    let line = print_line(&doc, 80);
+    let split = line.split_at_width(2, SplitRounding::Floor).unwrap();
+    assert_eq!(split.left.to_string(), "\"");
+    assert_eq!(split.right.to_string(), "一二三\"");
+    assert_eq!(split.straddler, Some(SplitSide::Right));
+
+    let line = print_line(&doc, 80);
+    let split = line.split_at_width(2, SplitRounding::Ceil).unwrap();
+    assert_eq!(split.left.to_string(), "\"一");
+    assert_eq!(split.right.to_string(), "二三\"");
+    assert_eq!(split.straddler, Some(SplitSide::Left));
+
+    let line = print_line(&doc, 80);
+    let split = line.split_at_width(3, SplitRounding::Exact).unwrap();
+    assert_eq!(split.left.to_string(), "\"一");
+    assert_eq!(split.straddler, None);
+}
+
+#[test]
+fn test_split_line_preserves_styles() {
+    let doc = json_array(vec![json_string("red").with_style(BasicStyle::new().bold())]);
+    let line = print_line(&doc, 80);
+    let split = line.split_at_width(3, SplitRounding::Exact).unwrap();
+    let left_last = split.left.segments.last().unwrap();
+    let right_first = split.right.segments.first().unwrap();
+    assert_eq!(left_last.str, "r");
+    assert_eq!(right_first.str, "ed");
+    assert!(left_last.style.bold);
+    assert!(right_first.style.bold);
+    assert!(matches!(left_last.style.color, Color::Magenta));
+    assert!(matches!(right_first.style.color, Color::Magenta));
+}
+
+#[test]
+fn test_visible_whitespace() {
+    use crate::standard::pretty_testing::SimpleDoc;
+    use partial_pretty_printer::notation_constructors::{indent, lit, nl};
+
+    let notation =
+        lit("x ") + (2 >> lit("y  z  ")) + indent("- ", None, nl() + lit(" w")) + nl() + lit("   ");
+    let doc = SimpleDoc::new(notation);
+    let (_, focused_line, lines) = pretty_print(&doc, 80, &[], FocusTarget::Start, None).unwrap();
+    let visible = std::iter::once(Line::from(focused_line))
+        .chain(lines.map(|line| line.unwrap()))
+        .map(|line| line.with_visible_whitespace(&()).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(visible, vec!["x·", "··y  z··", "-· w", "···"]);
+
+    let long = SimpleDoc::new(lit("a") + lit(&" ".repeat(100)));
+    let (_, focused_line, _) = pretty_print(&long, 80, &[], FocusTarget::Start, None).unwrap();
+    let line = Line::from(focused_line).with_visible_whitespace(&());
+    assert_eq!(line.to_string(), format!("a{}", "·".repeat(100)));
+    assert_eq!(line.width(), 101);
+}
+
+#[test]
+fn test_trim_trailing_whitespace() {
+    use crate::standard::pretty_testing::SimpleDoc;
+    use partial_pretty_printer::notation_constructors::{empty, lit, nl};
+    use partial_pretty_printer::pretty_print_to_trimmed_string;
+
+    let notation = lit("x ") + lit(" ") + (4 >> empty()) + (2 >> lit("y  z")) + nl() + lit("w");
+    let doc = SimpleDoc::new(notation);
+    let (_, focused_line, lines) = pretty_print(&doc, 80, &[], FocusTarget::Start, None).unwrap();
+    let mut line = Line::from(focused_line);
+    assert_eq!(line.trim_trailing_whitespace(), 2);
+    assert_eq!(line.to_string(), "x");
+    assert_eq!(line.width(), 1);
+    let mut line = lines.map(|line| line.unwrap()).next().unwrap();
+    assert_eq!(line.trim_trailing_whitespace(), 4);
+    assert_eq!(line.to_string(), "");
+    assert!(line.segments.is_empty());
+
+    let (string, trimmed) = pretty_print_to_trimmed_string(&doc, 80).unwrap();
+    assert_eq!(string, "x\n\n  y  z\nw");
+    assert_eq!(trimmed, 6);
+}
+
+#[test]
+fn test_tab_indentation() {
+    use crate::standard::pretty_testing::SimpleDoc;
+    use partial_pretty_printer::notation_constructors::{indent, lit};
+    use partial_pretty_printer::pretty_print_to_tabbed_string;
+
+    let notation =
+        lit("a") + (6 >> (lit("b") + indent("// ", None, 2 >> lit("c")))) + (8 >> lit("d"));
+    let doc = SimpleDoc::new(notation);
+    let string = pretty_print_to_tabbed_string(&doc, 80, 4).unwrap();
+    assert_eq!(string, "a\n\t  b\n\t  //   c\n\t\td");
+    let string = pretty_print_to_tabbed_string(&doc, 80, 3).unwrap();
+    assert_eq!(string, "a\n\t\tb\n\t\t//   c\n\t\t  d");
+    let string = pretty_print_to_tabbed_string(&doc, 80, 0).unwrap();
+    assert_eq!(string, "a\n      b\n      //   c\n        d");
+
+    let (_, _, lines) = pretty_print(&doc, 80, &[], FocusTarget::Start, None).unwrap();
+    let widths = lines
+        .map(|line| line.unwrap().with_tab_indentation(4).width())
+        .collect::<Vec<_>>();
+    assert_eq!(widths, vec![7, 12, 9]);
+}
+
+#[test]
+fn test_visual_order() {
+    assert_eq!(Direction::of_str("  (abc)"), Direction::Ltr);
+    assert_eq!(Direction::of_str("\"שלום\""), Direction::Rtl);
+    assert_eq!(Direction::of_str(", "), Direction::Neutral);
+
+    let doc = json_array(vec![json_string("שלום עולם"), json_string("abc")]);
+    let line = print_line(&doc, 80);
+    let width = line.width();
+    let visual = line.into_visual_order();
+    assert_eq!(visual.to_string(), r#"["םלוע םולש", "abc"]"#);
+    assert_eq!(visual.width(), width);
+    assert!(visual
+        .segments
+        .iter()
+        .all(|seg| seg.direction != Direction::Neutral));
+
+    let doc = json_string("abc אב 12 גד");
+    assert_eq!(
+        print_line(&doc, 80).into_visual_order().to_string(),
+        r#""abc בא 12 דג""#
+    );
+
+    // Combining marks stay attached to the character they modify.
+    let doc = json_string("אָב");
+    let visual = print_line(&doc, 80).into_visual_order();
+    assert_eq!(visual.to_string(), "\"בא\u{05B8}\"");
+}
+
+#[test]
+fn test_highlight_changes() {
+    let old_doc = json_array(vec![json_string("red fox"), json_string("cd")]);
+    let new_doc = json_array(vec![
+        json_string("red cat"),
+        json_string("cd"),
+        json_string("e"),
+    ]);
+    let old_line = print_line(&old_doc, 80);
+    let line = print_line(&new_doc, 80).highlight_changes(&old_line, &BasicStyle::new().bold());
+    assert_eq!(line.to_string(), r#"["red cat", "cd", "e"]"#);
+    let changed = line
+        .segments
+        .iter()
+        .filter(|seg| seg.style.bold)
+        .map(|seg| seg.str)
+        .collect::<Vec<_>>();
+    assert_eq!(changed, vec!["cat", ", ", "\"", "e", "\""]);
+    assert!(line
+        .segments
+        .iter()
+        .filter(|seg| seg.str == "red ")
+        .all(|seg| !seg.style.bold));
+}
+
+#[test]
+fn test_wrapped_focus_pos() {
+    let doc = json_string("一二三");
+    let pos = |row, col| Pos { row, col };
+
+    // Focus between "一二" and "三".
+    let (_, focused_line, _) = pretty_print(&doc, 80, &[], FocusTarget::Text(2), None).unwrap();
+    assert_eq!(focused_line.to_left_string(), "\"一二");
+    assert_eq!(focused_line.wrapped_focus_pos(80), pos(0, 5));
+    assert_eq!(focused_line.wrapped_focus_pos(0), pos(0, 5));
+    // "二" doesn't fit in the first row, and "三" still fits after it.
+    assert_eq!(focused_line.wrapped_focus_pos(4), pos(1, 2));
+    // "三" doesn't fit after "二", so the focus moves to the start of the next row.
+    assert_eq!(focused_line.wrapped_focus_pos(3), pos(2, 0));
+    // The first row is exactly full.
+    assert_eq!(focused_line.wrapped_focus_pos(5), pos(1, 0));
+    // Full-width characters wider than the row get a row each.
+    assert_eq!(focused_line.wrapped_focus_pos(1), pos(3, 0));
+
+    // Focus at the end of the line, where nothing follows.
+    let (_, focused_line, _) = pretty_print(&doc, 80, &[], FocusTarget::End, None).unwrap();
+    assert_eq!(focused_line.wrapped_focus_pos(4), pos(2, 1));
+    assert_eq!(focused_line.wrapped_focus_pos(8), pos(0, 8));
+}
+
+#[test]
+fn test_focus_text_offset() {
+    use partial_pretty_printer::OffsetKind;
+
+    // Chars: 'a', 'e', combining acute, crab, 'b'. Bytes: 1, 1, 2, 4, 1. UTF-16: 1, 1, 1, 2, 1.
+    let text = "ae\u{301}🦀b";
+    let doc = json_string(text);
+    let left = |pos, kind| {
+        let target = FocusTarget::TextOffset(pos, kind);
+        let (_, focused_line, _) = pretty_print(&doc, 80, &[], target, None).unwrap();
+        focused_line.to_left_string()
+    };
+
+    assert_eq!(left(2, OffsetKind::Chars), "\"ae");
+    assert_eq!(left(4, OffsetKind::Bytes), "\"ae\u{301}");
+    // In the middle of the crab, so before it.
+    assert_eq!(left(6, OffsetKind::Bytes), "\"ae\u{301}");
+    assert_eq!(left(8, OffsetKind::Bytes), "\"ae\u{301}🦀");
+    assert_eq!(left(4, OffsetKind::Utf16), "\"ae\u{301}");
+    assert_eq!(left(5, OffsetKind::Utf16), "\"ae\u{301}🦀");
+    assert_eq!(left(1, OffsetKind::Graphemes), "\"a");
+    assert_eq!(left(2, OffsetKind::Graphemes), "\"ae\u{301}");
+    assert_eq!(left(100, OffsetKind::Graphemes), "\"ae\u{301}🦀b");
+
+    assert_eq!(OffsetKind::Bytes.from_char_offset(text, 4), 8);
+    assert_eq!(OffsetKind::Utf16.from_char_offset(text, 4), 5);
+    assert_eq!(OffsetKind::Graphemes.from_char_offset(text, 4), 3);
+    assert_eq!(OffsetKind::Graphemes.to_char_offset(text, 2), 3);
+
+    // A family emoji (three people joined by two zero-width joiners) and a flag (two regional
+    // indicators) are one grapheme each.
+    let text = "a👨\u{200d}👩\u{200d}👧🇯🇵b";
+    let doc = json_string(text);
+    let left = |pos| {
+        let target = FocusTarget::TextOffset(pos, OffsetKind::Graphemes);
+        let (_, focused_line, _) = pretty_print(&doc, 80, &[], target, None).unwrap();
+        focused_line.to_left_string()
+    };
+    assert_eq!(left(2), "\"a👨\u{200d}👩\u{200d}👧");
+    assert_eq!(left(3), "\"a👨\u{200d}👩\u{200d}👧🇯🇵");
+    assert_eq!(OffsetKind::Graphemes.to_char_offset(text, 2), 6);
+    assert_eq!(OffsetKind::Graphemes.to_char_offset(text, 3), 8);
+    assert_eq!(OffsetKind::Graphemes.from_char_offset(text, 6), 2);
+    assert_eq!(OffsetKind::Graphemes.from_char_offset(text, 8), 3);
+    // In the middle of the flag, so it counts.
+    assert_eq!(OffsetKind::Graphemes.from_char_offset(text, 7), 3);
+}
+
+#[test]
+fn test_print_node_to_string() {
+    use partial_pretty_printer::{pretty_print_to_string, print_node_to_string, PrintingError};
+
+    let doc = json_array(vec![
+        json_string("a"),
+        json_array(vec![json_string("b"), json_string("c")]),
+    ]);
+    assert_eq!(
+        pretty_print_to_string(&doc, 12).unwrap(),
+        "[\n    \"a\",\n    [\n        \"b\",\n        \"c\"\n    ]\n]"
+    );
+    assert_eq!(
+        print_node_to_string(&doc, 12, &[1]).unwrap(),
+        "[\"b\", \"c\"]"
+    );
+    assert_eq!(
+        print_node_to_string(&doc, 5, &[1]).unwrap(),
+        "[\n    \"b\",\n    \"c\"\n]"
+    );
+    assert_eq!(print_node_to_string(&doc, 80, &[1, 0]).unwrap(), "\"b\"");
+    assert!(matches!(
+        print_node_to_string(&doc, 80, &[2]),
+        Err(PrintingError::InvalidPath { index: 2, .. })
+    ));
+}
+
+#[test]
+fn test_pretty_print_context() {
+    use partial_pretty_printer::pretty_print_context;
+
+    let doc = json_array((0..10).map(|i| json_string(&i.to_string())).collect());
+    // Too wide for one line, so each element is on its own line after the `[`.
+    let context = pretty_print_context(&doc, 10, &[4], 2, 1, "⋯").unwrap();
+    assert_eq!(
+        context,
+        "⋯\n    \"2\",\n    \"3\",\n    \"4\",\n    \"5\",\n⋯"
+    );
+
+    let context = pretty_print_context(&doc, 10, &[1], 5, 0, "...").unwrap();
+    assert_eq!(context, "[\n    \"0\",\n    \"1\",\n...");
+
+    let context = pretty_print_context(&doc, 10, &[9], 0, 5, "⋯").unwrap();
+    assert_eq!(context, "⋯\n    \"9\"\n]");
+}
@@ -0,0 +1,27 @@
+use crate::standard::pretty_testing::{assert_pp, SimpleDoc};
+use partial_pretty_printer::nota;
+use partial_pretty_printer::notation_constructors::{flat, indent, lit, nl, space};
+use partial_pretty_printer::Notation;
+
+#[test]
+fn macros_nota_matches_constructors() {
+    let elems: Notation<(), ()> = lit("a") + lit(",") + space() + lit("b");
+    let from_macro: Notation<(), ()> =
+        nota! { "[" ++ (flat(elems) | indent("    ", nl ++ elems) ++ nl) ++ "]" };
+    let by_hand = lit("[")
+        + (flat(elems.clone()) | (indent("    ", None, nl() + elems.clone()) + nl()))
+        + lit("]");
+    assert_eq!(format!("{:?}", from_macro), format!("{:?}", by_hand));
+
+    let from_macro: Notation<(), ()> = nota! { "a" ++ "b" ^ "c" | { lit("d") } };
+    let by_hand: Notation<(), ()> = ((lit("a") + lit("b")) ^ lit("c")) | lit("d");
+    assert_eq!(format!("{:?}", from_macro), format!("{:?}", by_hand));
+}
+
+#[test]
+fn macros_nota_prints() {
+    let elems: Notation<(), ()> = nota! { "one" ++ "," ++ space ++ "two" };
+    let notation = nota! { "[" ++ (flat(elems) | indent("  ", nl ++ elems) ++ nl) ++ "]" };
+    assert_pp(&SimpleDoc::new(notation.clone()), 80, &["[one, two]"]);
+    assert_pp(&SimpleDoc::new(notation), 5, &["[", "  one, two", "]"]);
+}
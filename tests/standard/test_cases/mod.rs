@@ -1,9 +1,33 @@
+mod analyze;
+#[cfg(feature = "async")]
+mod async_print;
 mod basics;
+mod blocks;
+mod bookmarks;
+mod check_doc;
 mod children;
+mod columns;
+mod diagnostics;
+mod doc_view;
+mod error_policy;
+#[cfg(feature = "ffi")]
+mod ffi;
 mod flow_wrap;
+mod geometry;
 mod iter_chain;
 mod json;
+mod line_split;
+#[cfg(feature = "macros")]
+mod macros;
+mod multi_focus;
+mod notation_loop;
 mod panes;
+mod present_children;
+mod reanchor;
 mod regressions;
 mod ruby_loop;
+mod search;
+mod simple_tree;
 mod styles;
+mod vertical_space;
+mod xml;
@@ -0,0 +1,168 @@
+use partial_pretty_printer::doc_examples::json::{
+    json_array, json_comment, json_object, json_object_pair, json_roots, Json,
+};
+use partial_pretty_printer::{
+    pretty_print, pretty_print_multi, FocusTarget, Line, SelectionSegment,
+};
+
+fn make_doc() -> Json {
+    json_object(vec![
+        json_object_pair("Cats", json_array(Vec::new())),
+        json_object_pair("Dogs", json_array(Vec::new())),
+    ])
+}
+
+#[test]
+fn test_multi_focus_lines() {
+    let doc = make_doc();
+    let printed =
+        pretty_print_multi(&doc, 17, &[(&[1, 1], FocusTarget::Start)], 5, 5, None).unwrap();
+    let lines = printed
+        .lines_above
+        .iter()
+        .map(|line| line.to_string())
+        .chain([Line::from(printed.focused_line).to_string()])
+        .chain(printed.lines_below.iter().map(|line| line.to_string()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        vec!["{", "    \"Cats\": [],", "    \"Dogs\": []", "}"]
+    );
+    assert_eq!(printed.positions, vec![Some((0, 12))]);
+}
+
+#[test]
+fn test_multi_focus_positions() {
+    let doc = make_doc();
+    let focuses: &[(&[usize], FocusTarget)] = &[
+        (&[1, 1], FocusTarget::Start),
+        (&[1, 0], FocusTarget::Text(2)),
+        (&[1, 0], FocusTarget::TextEnd),
+        (&[0, 1], FocusTarget::End),
+        (&[0, 1], FocusTarget::Mark),
+        (&[0], FocusTarget::Start),
+        (&[], FocusTarget::Start),
+        (&[], FocusTarget::End),
+        (&[1, 1], FocusTarget::LastMark),
+        (&[1], FocusTarget::End),
+        (&[0], FocusTarget::Mark),
+    ];
+    let printed = pretty_print_multi(&doc, 17, focuses, 5, 5, None).unwrap();
+    assert_eq!(
+        printed.positions,
+        vec![
+            Some((0, 12)),
+            Some((0, 7)),
+            Some((0, 9)),
+            Some((-1, 14)),
+            Some((-1, 13)),
+            Some((-1, 4)),
+            Some((-2, 0)),
+            Some((1, 1)),
+            Some((0, 13)),
+            Some((0, 14)),
+            None,
+        ]
+    );
+
+    // Each position should agree with printing that focus on its own.
+    for (i, (path, focus_target)) in focuses.iter().enumerate().take(10) {
+        let (upward, focused_line, _) = pretty_print(&doc, 17, path, *focus_target, None).unwrap();
+        let (row, col) = printed.positions[i].unwrap();
+        assert_eq!(col, focused_line.left_width());
+        assert_eq!(upward.count() as isize, row + 2);
+    }
+}
+
+#[test]
+fn test_multi_focus_outside_region() {
+    let doc = make_doc();
+    let focuses: &[(&[usize], FocusTarget)] = &[
+        (&[1, 1], FocusTarget::Start),
+        (&[], FocusTarget::Start),
+        (&[], FocusTarget::End),
+        (&[0, 1], FocusTarget::Start),
+    ];
+    let printed = pretty_print_multi(&doc, 17, focuses, 1, 0, None).unwrap();
+    assert_eq!(printed.lines_above.len(), 1);
+    assert!(printed.lines_below.is_empty());
+    assert_eq!(
+        printed.positions,
+        vec![Some((0, 12)), None, None, Some((-1, 12))]
+    );
+}
+
+#[test]
+fn test_multi_focus_flow_text() {
+    let doc = json_roots(vec![json_comment("one two three four five six")]);
+    let focuses: &[(&[usize], FocusTarget)] = &[
+        (&[], FocusTarget::Start),
+        (&[0, 3], FocusTarget::Text(2)),
+        (&[0, 5], FocusTarget::TextEnd),
+        (&[0], FocusTarget::End),
+    ];
+    let printed = pretty_print_multi(&doc, 16, focuses, 0, 5, None).unwrap();
+    assert_eq!(
+        printed.positions,
+        vec![Some((0, 0)), Some((1, 5)), Some((1, 16)), Some((1, 16))]
+    );
+}
+
+fn show_selection<'d>(lines: Vec<Vec<SelectionSegment<'d, &'d Json>>>) -> Vec<String> {
+    lines
+        .into_iter()
+        .map(|line| {
+            let mut string = String::new();
+            let mut selected = false;
+            for seg in line {
+                if seg.selected != selected {
+                    string.push(if seg.selected { '<' } else { '>' });
+                    selected = seg.selected;
+                }
+                string.push_str(seg.segment.str);
+            }
+            if selected {
+                string.push('>');
+            }
+            string
+        })
+        .collect()
+}
+
+#[test]
+fn test_selection() {
+    let doc = make_doc();
+    let focuses: &[(&[usize], FocusTarget)] = &[
+        (&[1, 0], FocusTarget::Text(2)),
+        (&[0, 0], FocusTarget::Text(3)),
+        (&[1, 1], FocusTarget::Mark),
+    ];
+
+    let printed = pretty_print_multi(&doc, 17, focuses, 5, 5, None).unwrap();
+    assert_eq!(
+        show_selection(printed.into_selection(1, 0)),
+        vec!["{", "    \"Cat<s\": [],>", "<    \"Do>gs\": []", "}",]
+    );
+
+    let printed = pretty_print_multi(&doc, 17, focuses, 5, 5, None).unwrap();
+    assert_eq!(
+        show_selection(printed.into_selection(0, 2)),
+        vec!["{", "    \"Cats\": [],", "    \"Do<gs\": [>]", "}"]
+    );
+
+    // The start of the selection is above the printed region
+    let printed = pretty_print_multi(&doc, 17, focuses, 0, 1, None).unwrap();
+    assert_eq!(
+        show_selection(printed.into_selection(1, 0)),
+        vec!["<    \"Do>gs\": []", "}"]
+    );
+
+    // The end of the selection is below the printed region
+    let focuses: &[(&[usize], FocusTarget)] =
+        &[(&[0, 0], FocusTarget::Start), (&[], FocusTarget::End)];
+    let printed = pretty_print_multi(&doc, 17, focuses, 0, 1, None).unwrap();
+    assert_eq!(
+        show_selection(printed.into_selection(0, 1)),
+        vec!["    <\"Cats\": [],>", "<    \"Dogs\": []>"]
+    );
+}
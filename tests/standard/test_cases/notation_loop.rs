@@ -0,0 +1,91 @@
+use partial_pretty_printer::notation_constructors::{child, lit, text};
+use partial_pretty_printer::{pretty_print_to_string, PrettyDoc, PrintingError, ValidNotation};
+use std::convert::Infallible;
+
+/// A document in which node `i` has node `children[i]` as its only child, or is text if that's
+/// `None`. Nodes can be their own descendants.
+#[derive(Debug, Clone, Copy)]
+struct LinkedDoc<'d> {
+    children: &'d [Option<usize>],
+    notations: &'d [ValidNotation<(), ()>; 2],
+    index: usize,
+}
+
+impl<'d> PrettyDoc<'d> for LinkedDoc<'d> {
+    type Id = usize;
+    type Style = ();
+    type StyleLabel = ();
+    type Condition = ();
+    type Error = Infallible;
+
+    fn id(self) -> Result<usize, Infallible> {
+        Ok(self.index)
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<(), ()>, Infallible> {
+        Ok(match self.children[self.index] {
+            Some(_) => &self.notations[0],
+            None => &self.notations[1],
+        })
+    }
+
+    fn condition(self, _condition: &()) -> Result<bool, Infallible> {
+        Ok(false)
+    }
+
+    fn lookup_style(self, _style_label: ()) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn node_style(self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Infallible> {
+        Ok(self.children[self.index].map(|_| 1))
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Infallible> {
+        Ok("leaf")
+    }
+
+    fn unwrap_child(self, _i: usize) -> Result<Self, Infallible> {
+        Ok(LinkedDoc {
+            index: self.children[self.index].unwrap(),
+            ..self
+        })
+    }
+}
+
+fn print(children: &[Option<usize>]) -> Result<String, PrintingError<Infallible>> {
+    let notations = [
+        (lit("(") + child(0) + lit(")")).validate().unwrap(),
+        text().validate().unwrap(),
+    ];
+    let doc = LinkedDoc {
+        children,
+        notations: &notations,
+        index: 0,
+    };
+    pretty_print_to_string(doc, 80)
+}
+
+#[test]
+fn notation_loop_detected() {
+    match print(&[Some(1), Some(2), Some(3), Some(1)]) {
+        Err(PrintingError::NotationLoop { path, cycle }) => {
+            assert_eq!(path, vec![0]);
+            assert_eq!(cycle, vec![0, 0, 0]);
+        }
+        result => panic!("expected NotationLoop, got {:?}", result),
+    }
+}
+
+#[test]
+fn notation_loop_deep_doc() {
+    // A deep document that isn't a loop prints fine.
+    let mut children = (1..200).map(Some).collect::<Vec<_>>();
+    children.push(None);
+    let expected = format!("{}leaf{}", "(".repeat(199), ")".repeat(199));
+    assert_eq!(print(&children).unwrap(), expected);
+}
@@ -1,14 +1,19 @@
-use crate::standard::pretty_testing::SimpleDoc;
+use crate::standard::pretty_testing::{SimpleDoc, WideGreek};
 use partial_pretty_printer::{
     doc_examples::{
-        json::{json_array, json_number, json_string, Json},
+        json::{json_array, json_number, json_object, json_object_pair, json_string, Json},
         BasicStyle,
     },
     pane::{
-        display_pane, DocLabel, PaneNotation, PaneSize, PlainText, PrintingOptions, WidthStrategy,
+        display_pane, display_pane_region, display_pane_with_images, ActivePane, DocLabel,
+        GutterContent, Image, OverlayAnchor, OverlayPlacement, PaneError, PaneNotation, PaneRunner,
+        PaneSize, PlainText, PrintingOptions, WidthStrategy, Zoom,
     },
-    FocusTarget, Pos, PrettyDoc, Size, Style,
+    FocusTarget, PathFallback, Pos, PrettyDoc, PrintingError, Rectangle, Size, Style, UnicodeWidth,
+    ValidNotation,
 };
+use std::cell::Cell;
+use std::convert::Infallible;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
@@ -16,14 +21,14 @@ type NoStyle = ();
 
 #[derive(Debug, Clone)]
 struct SimpleLabel<'d, D: PrettyDoc<'d> + Clone + Debug>(
-    Option<(D, PrintingOptions)>,
+    Option<(D, PrintingOptions<D::Style>)>,
     PhantomData<&'d D>,
 );
 
 fn get_content<'d, D: PrettyDoc<'d> + Clone + Debug>(
     label: SimpleLabel<'d, D>,
-) -> Option<(D, PrintingOptions)> {
-    label.0
+) -> Result<Option<(D, PrintingOptions<D::Style>)>, D::Error> {
+    Ok(label.0)
 }
 
 #[track_caller]
@@ -48,7 +53,7 @@ fn pane_test_with_size<'d, S: Style + Default, D: PrettyDoc<'d, Style = S> + Clo
     expected: &str,
 ) {
     let mut screen = PlainText::new(size.width, size.height);
-    display_pane(&mut screen, &notation, &S::default(), &get_content).unwrap();
+    display_pane(&mut screen, &notation, &S::default(), &get_content, None).unwrap();
     let actual = screen.to_string();
     if actual != expected {
         eprintln!("ACTUAL:\n{}", actual);
@@ -65,7 +70,7 @@ fn pane_test_with_focus<'d, S: Style + Default, D: PrettyDoc<'d, Style = S> + Cl
     expected_pos: Pos,
 ) {
     let mut screen = PlainText::new(size.width, size.height);
-    display_pane(&mut screen, &notation, &S::default(), &get_content).unwrap();
+    display_pane(&mut screen, &notation, &S::default(), &get_content, None).unwrap();
     let actual = screen.to_string();
     if actual != expected {
         eprintln!("ACTUAL:\n{}", actual);
@@ -159,6 +164,26 @@ fn test_horz_split_pane() {
     );
 }
 
+#[test]
+fn test_zero_proportional_panes() {
+    use PaneSize::{Fixed, Proportional};
+
+    // With no hunger between them, the proportional panes get nothing, instead of dividing by 0.
+    pane_test_with_size::<NoStyle, &SimpleDoc>(
+        Size {
+            width: 4,
+            height: 2,
+        },
+        PaneNotation::Horz(vec![
+            (Proportional(0), fill('a')),
+            (Fixed(2), fill('b')),
+            (Proportional(0), fill('c')),
+        ]),
+        "bb\n\
+         bb\n",
+    );
+}
+
 #[test]
 fn test_vert_split_pane() {
     use PaneSize::{Fixed, Proportional};
@@ -225,14 +250,243 @@ fn test_mixed_split_pane() {
 }
 
 #[test]
-fn test_doc_pane() {
-    let options = PrintingOptions {
-        focus_path: Vec::new(),
-        focus_height: 0.0,
-        width_strategy: WidthStrategy::Full,
-        focus_target: FocusTarget::Start,
-        set_focus: false,
+fn test_display_pane_region() {
+    use PaneSize::Proportional;
+
+    fn make_note(chars: [char; 3]) -> PaneNotation<SimpleLabel<'static, &'static SimpleDoc>, ()> {
+        PaneNotation::Vert(vec![
+            (
+                Proportional(1),
+                PaneNotation::Horz(vec![
+                    (Proportional(1), fill(chars[0])),
+                    (Proportional(1), fill(chars[1])),
+                ]),
+            ),
+            (Proportional(1), fill(chars[2])),
+        ])
+    }
+
+    let mut screen = PlainText::new(6, 4);
+    display_pane(
+        &mut screen,
+        &make_note(['a', 'b', 'c']),
+        &(),
+        &get_content,
+        None,
+    )
+    .unwrap();
+    assert_eq!(screen.to_string(), "aaabbb\naaabbb\ncccccc\ncccccc\n");
+
+    // Only the pane in the upper right intersects the region.
+    let region = Rectangle {
+        min_row: 1,
+        max_row: 2,
+        min_col: 4,
+        max_col: 5,
+    };
+    display_pane_region(
+        &mut screen,
+        &make_note(['x', 'y', 'z']),
+        &(),
+        &get_content,
+        None,
+        region,
+    )
+    .unwrap();
+    assert_eq!(screen.to_string(), "aaayyy\naaayyy\ncccccc\ncccccc\n");
+
+    // This region straddles the upper left and bottom panes.
+    let region = Rectangle {
+        min_row: 1,
+        max_row: 3,
+        min_col: 0,
+        max_col: 1,
+    };
+    display_pane_region(
+        &mut screen,
+        &make_note(['x', 'y', 'z']),
+        &(),
+        &get_content,
+        None,
+        region,
+    )
+    .unwrap();
+    assert_eq!(screen.to_string(), "xxxyyy\nxxxyyy\nzzzzzz\nzzzzzz\n");
+}
+
+#[test]
+fn test_if_fits_pane() {
+    use PaneSize::{Fixed, Proportional};
+
+    fn if_fits<L: DocLabel>(
+        min_width: u16,
+        min_height: u32,
+        then: PaneNotation<L, NoStyle>,
+        otherwise: PaneNotation<L, NoStyle>,
+    ) -> PaneNotation<L, NoStyle> {
+        PaneNotation::IfFits {
+            min_width,
+            min_height,
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        }
+    }
+
+    let with_sidebar = || {
+        if_fits(
+            7,
+            0,
+            PaneNotation::Horz(vec![(Proportional(1), fill('m')), (Fixed(2), fill('s'))]),
+            fill('m'),
+        )
+    };
+
+    pane_test::<NoStyle, &SimpleDoc>(
+        PaneNotation::Vert(vec![
+            (Fixed(2), with_sidebar()),
+            (
+                Proportional(1),
+                PaneNotation::Horz(vec![
+                    (Proportional(1), with_sidebar()),
+                    (Fixed(1), fill('|')),
+                ]),
+            ),
+        ]),
+        "mmmmmss\n\
+         mmmmmss\n\
+         mmmmmm|\n\
+         mmmmmm|\n\
+         mmmmmm|\n\
+         mmmmmm|\n\
+         mmmmmm|\n",
+    );
+
+    pane_test::<NoStyle, &SimpleDoc>(
+        PaneNotation::Vert(vec![
+            (Fixed(3), if_fits(0, 3, fill('a'), fill('b'))),
+            (Proportional(1), if_fits(0, 5, fill('c'), fill('d'))),
+        ]),
+        "aaaaaaa\n\
+         aaaaaaa\n\
+         aaaaaaa\n\
+         ddddddd\n\
+         ddddddd\n\
+         ddddddd\n\
+         ddddddd\n",
+    );
+}
+
+#[test]
+fn test_overlay_pane() {
+    fn overlay<L: DocLabel>(
+        anchor: OverlayAnchor,
+        width: u16,
+        height: u32,
+    ) -> PaneNotation<L, NoStyle> {
+        PaneNotation::Overlay {
+            base: Box::new(fill('.')),
+            overlay: Box::new(PaneNotation::Vert(vec![
+                (PaneSize::Fixed(1), fill('-')),
+                (PaneSize::Proportional(1), fill('#')),
+            ])),
+            placement: OverlayPlacement {
+                anchor,
+                size: Size { width, height },
+            },
+        }
+    }
+
+    pane_test::<NoStyle, &SimpleDoc>(
+        overlay(OverlayAnchor::Center, 4, 3),
+        ".......\n\
+         .......\n\
+         .----..\n\
+         .####..\n\
+         .####..\n\
+         .......\n\
+         .......\n",
+    );
+
+    pane_test::<NoStyle, &SimpleDoc>(
+        overlay(OverlayAnchor::At(Pos { row: 4, col: 1 }), 9, 9),
+        ".......\n\
+         .......\n\
+         .......\n\
+         .......\n\
+         .------\n\
+         .######\n\
+         .######\n",
+    );
+
+    pane_test::<NoStyle, &SimpleDoc>(
+        overlay(OverlayAnchor::Center, 20, 2),
+        ".......\n\
+         .......\n\
+         -------\n\
+         #######\n\
+         .......\n\
+         .......\n\
+         .......\n",
+    );
+}
+
+#[test]
+fn test_image_pane() {
+    let notation = PaneNotation::Vert(vec![
+        (PaneSize::Fixed(1), PaneNotation::Image { label: "missing" }),
+        (
+            PaneSize::Proportional(1),
+            PaneNotation::Horz(vec![
+                (PaneSize::Fixed(2), fill('#')),
+                (
+                    PaneSize::Proportional(1),
+                    PaneNotation::Image { label: "logo" },
+                ),
+            ]),
+        ),
+    ]);
+    let logo = Image {
+        bytes: vec![1, 2, 3],
+        size: Size {
+            width: 4,
+            height: 5,
+        },
+    };
+    let get_content = |_label: &str| Ok::<Option<(&SimpleDoc, PrintingOptions<NoStyle>)>, _>(None);
+    let get_image = |label: &str| match label {
+        "logo" => Ok(Some(logo.clone())),
+        _ => Ok(None),
     };
+
+    let mut screen = PlainText::new(8, 3);
+    let layout =
+        display_pane_with_images(&mut screen, &notation, &(), &get_content, &get_image, None)
+            .unwrap();
+    assert_eq!(screen.to_string(), "        \n##      \n##      \n");
+    // The image is shrunk to fit in its pane.
+    assert_eq!(
+        layout.images,
+        vec![(
+            Rectangle {
+                min_row: 1,
+                max_row: 3,
+                min_col: 2,
+                max_col: 6,
+            },
+            logo
+        )]
+    );
+
+    // Without `get_image`, image panes are left blank.
+    let mut screen = PlainText::new(8, 3);
+    let layout = display_pane(&mut screen, &notation, &(), &get_content, None).unwrap();
+    assert_eq!(screen.to_string(), "        \n##      \n##      \n");
+    assert_eq!(layout.images, vec![]);
+}
+
+#[test]
+fn test_doc_pane() {
+    let options = PrintingOptions::default();
     let doc = json_array(vec![json_string("Hello"), json_string("world")]);
     let contents = SimpleLabel(Some((&doc, options)), PhantomData);
     pane_test(
@@ -252,14 +506,32 @@ fn test_doc_pane() {
 }
 
 #[test]
-fn test_doc_pane_full_width_cutoff() {
+fn test_doc_pane_visible_whitespace() {
     let options = PrintingOptions {
-        focus_path: Vec::new(),
-        focus_height: 0.0,
-        width_strategy: WidthStrategy::Full,
-        focus_target: FocusTarget::Start,
-        set_focus: false,
+        visible_whitespace: Some(BasicStyle::new()),
+        ..PrintingOptions::default()
     };
+    let doc = json_array(vec![json_string("Hello"), json_string("world")]);
+    let contents = SimpleLabel(Some((&doc, options)), PhantomData);
+    pane_test(
+        PaneNotation::Doc { label: contents },
+        &[
+            "[      ",  // force rustfmt
+            "····\"He", // force rustfmt
+            "····\"wo", // force rustfmt
+            "]      ",  // force rustfmt
+            "       ",  // force rustfmt
+            "       ",  // force rustfmt
+            "       ",  // force rustfmt
+            "",
+        ]
+        .join("\n"),
+    );
+}
+
+#[test]
+fn test_doc_pane_full_width_cutoff() {
+    let options = PrintingOptions::default();
     let doc = json_string("一二三");
     let contents = SimpleLabel(Some((&doc, options)), PhantomData);
     let note = PaneNotation::Doc { label: contents };
@@ -303,11 +575,8 @@ fn test_pane_cursor_heights() {
     #[track_caller]
     fn test_at_height(focus_height: f32, expected: &str) {
         let options = PrintingOptions {
-            focus_path: Vec::new(),
             focus_height,
-            width_strategy: WidthStrategy::Full,
-            focus_target: FocusTarget::Start,
-            set_focus: false,
+            ..PrintingOptions::default()
         };
         let doc = json_string("Hi");
         let contents = SimpleLabel(Some((&doc, options)), PhantomData);
@@ -335,11 +604,8 @@ fn test_pane_widths() {
     #[track_caller]
     fn test_with_width(width_strategy: WidthStrategy, expected: &str) {
         let options = PrintingOptions {
-            focus_path: Vec::new(),
-            focus_height: 0.0,
             width_strategy,
-            focus_target: FocusTarget::Start,
-            set_focus: false,
+            ..PrintingOptions::default()
         };
         let doc = json_array(vec![json_string("Hello"), json_string("world")]);
         let contents = SimpleLabel(Some((&doc, options)), PhantomData);
@@ -390,9 +656,8 @@ fn test_seek() {
         let options = PrintingOptions {
             focus_path: path.to_owned(),
             focus_height: 0.5,
-            width_strategy: WidthStrategy::Full,
             focus_target,
-            set_focus: false,
+            ..PrintingOptions::default()
         };
 
         PaneNotation::Doc {
@@ -459,13 +724,7 @@ fn test_seek() {
 #[test]
 fn test_dynamic() {
     fn make_note(doc: &Json) -> PaneNotation<SimpleLabel<&Json>, BasicStyle> {
-        let options = PrintingOptions {
-            focus_path: Vec::new(),
-            focus_height: 0.0,
-            width_strategy: WidthStrategy::Full,
-            focus_target: FocusTarget::Start,
-            set_focus: false,
-        };
+        let options = PrintingOptions::default();
 
         PaneNotation::Doc {
             label: SimpleLabel(Some((&doc, options)), PhantomData),
@@ -480,7 +739,7 @@ fn test_dynamic() {
     pane_test(
         PaneNotation::Vert(vec![
             (PaneSize::Proportional(1), make_note(&doc8)),
-            (PaneSize::Dynamic, make_note(&doc5)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc5)),
         ]),
         &[
             "[      ", // force rustfmt
@@ -497,7 +756,7 @@ fn test_dynamic() {
     pane_test(
         PaneNotation::Vert(vec![
             (PaneSize::Proportional(1), make_note(&doc5)),
-            (PaneSize::Dynamic, make_note(&doc8)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc8)),
         ]),
         &[
             "[      ", // force rustfmt
@@ -511,10 +770,27 @@ fn test_dynamic() {
         .join("\n"),
     );
 
+    pane_test(
+        PaneNotation::Vert(vec![
+            (PaneSize::Fixed(7), make_note(&doc5)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc8)),
+        ]),
+        &[
+            "[      ", // force rustfmt
+            "    6, ", // force rustfmt
+            "    7, ", // force rustfmt
+            "    8  ", // force rustfmt
+            "]      ", // force rustfmt
+            "       ", // force rustfmt
+            "       \n",
+        ]
+        .join("\n"),
+    );
+
     pane_test(
         PaneNotation::Horz(vec![
             (PaneSize::Proportional(1), make_note(&doc8)),
-            (PaneSize::Dynamic, make_note(&doc_num)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc_num)),
         ]),
         &[
             "[    42", // force rustfmt
@@ -535,7 +811,7 @@ fn test_dynamic() {
         },
         PaneNotation::Horz(vec![
             (PaneSize::Proportional(1), make_note(&doc8)),
-            (PaneSize::Dynamic, make_note(&doc_unicode)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc_unicode)),
         ]),
         &[
             "[     \"一1\"", // force rustfmt
@@ -556,7 +832,7 @@ fn test_dynamic() {
         },
         PaneNotation::Horz(vec![
             (PaneSize::Proportional(1), make_note(&doc8)),
-            (PaneSize::Dynamic, make_note(&doc_unicode)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc_unicode)),
         ]),
         &[
             "[    \"一1\"", // force rustfmt
@@ -569,6 +845,253 @@ fn test_dynamic() {
         ]
         .join("\n"),
     );
+
+    // Without a max, the first dynamic pane starves the second.
+    pane_test(
+        PaneNotation::Vert(vec![
+            (PaneSize::Dynamic { max: None }, make_note(&doc8)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc5)),
+        ]),
+        &[
+            "[      ", // force rustfmt
+            "    0, ", // force rustfmt
+            "    1, ", // force rustfmt
+            "    2, ", // force rustfmt
+            "    3, ", // force rustfmt
+            "    4, ", // force rustfmt
+            "    5  \n",
+        ]
+        .join("\n"),
+    );
+
+    pane_test(
+        PaneNotation::Vert(vec![
+            (PaneSize::Dynamic { max: Some(3) }, make_note(&doc8)),
+            (PaneSize::Dynamic { max: None }, make_note(&doc5)),
+        ]),
+        &[
+            "[      ", // force rustfmt
+            "    0, ", // force rustfmt
+            "    1, ", // force rustfmt
+            "[      ", // force rustfmt
+            "    6, ", // force rustfmt
+            "    7, ", // force rustfmt
+            "    8  \n",
+        ]
+        .join("\n"),
+    );
+
+    pane_test(
+        PaneNotation::Horz(vec![
+            (PaneSize::Dynamic { max: Some(5) }, make_note(&doc5)),
+            (PaneSize::Proportional(1), fill('*')),
+        ]),
+        &[
+            "[    **", // force rustfmt
+            "    6**", // force rustfmt
+            "    7**", // force rustfmt
+            "    8**", // force rustfmt
+            "]    **", // force rustfmt
+            "     **", // force rustfmt
+            "     **\n",
+        ]
+        .join("\n"),
+    );
+}
+
+#[test]
+fn test_zoom() {
+    let options = |zoom| PrintingOptions {
+        focus_target: FocusTarget::End,
+        set_focus: true,
+        zoom,
+        ..PrintingOptions::default()
+    };
+    let doc = json_array(vec![json_number(1.0), json_number(2.0)]);
+    let size = Size {
+        width: 13,
+        height: 5,
+    };
+
+    let contents = SimpleLabel(Some((&doc, options(Zoom::Wide))), PhantomData);
+    pane_test_with_focus(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "[[11,,  22]] ", // force rustfmt
+            "             ", // force rustfmt
+            "             ", // force rustfmt
+            "             ", // force rustfmt
+            "             ", // force rustfmt
+            "",
+        ]
+        .join("\n"),
+        Pos { row: 0, col: 12 },
+    );
+
+    // Each line takes up two rows, and the last pane row is left over.
+    let contents = SimpleLabel(Some((&doc, options(Zoom::Double))), PhantomData);
+    pane_test_with_focus(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "[[11,,  22]] ", // force rustfmt
+            "[[11,,  22]] ", // force rustfmt
+            "             ", // force rustfmt
+            "             ", // force rustfmt
+            "             ", // force rustfmt
+            "",
+        ]
+        .join("\n"),
+        Pos { row: 0, col: 12 },
+    );
+
+    // The document is printed 5 columns wide, so it doesn't fit on one line. A zoomed character
+    // that doesn't entirely fit in the pane isn't displayed.
+    let clamped_options = PrintingOptions {
+        clamp_focus_to_content: true,
+        ..options(Zoom::Double)
+    };
+    let contents = SimpleLabel(Some((&doc, clamped_options)), PhantomData);
+    pane_test_with_focus(
+        Size {
+            width: 11,
+            height: 8,
+        },
+        PaneNotation::Doc { label: contents },
+        &[
+            "[[         ", // force rustfmt
+            "[[         ", // force rustfmt
+            "        11 ", // force rustfmt
+            "        11 ", // force rustfmt
+            "        22 ", // force rustfmt
+            "        22 ", // force rustfmt
+            "]]         ", // force rustfmt
+            "]]         ", // force rustfmt
+            "",
+        ]
+        .join("\n"),
+        Pos { row: 6, col: 2 },
+    );
+}
+
+#[test]
+fn test_hit_test() {
+    let options = |zoom| PrintingOptions {
+        zoom,
+        ..PrintingOptions::default()
+    };
+    let doc = json_number(1.0);
+    let get_content = |label: usize| {
+        let zoom = if label == 0 { Zoom::Wide } else { Zoom::Normal };
+        Ok::<_, Infallible>(Some((&doc, options(zoom))))
+    };
+    let notation = PaneNotation::Horz(vec![
+        (PaneSize::Fixed(2), fill('#')),
+        (PaneSize::Proportional(1), PaneNotation::Doc { label: 0 }),
+        (PaneSize::Fixed(3), PaneNotation::Doc { label: 1 }),
+    ]);
+
+    let mut screen = PlainText::new(10, 3);
+    let layout = display_pane(
+        &mut screen,
+        &notation,
+        &BasicStyle::default(),
+        &get_content,
+        None,
+    )
+    .unwrap();
+    assert_eq!(screen.to_string(), "##11   1  \n##        \n##        \n");
+    assert_eq!(layout.hit_test(Pos { row: 0, col: 1 }), None);
+    // The first document is zoomed, so each of its cells is two columns wide.
+    assert_eq!(layout.hit_test(Pos { row: 0, col: 2 }), Some((0, 0, 0)));
+    assert_eq!(layout.hit_test(Pos { row: 1, col: 5 }), Some((0, 1, 1)));
+    assert_eq!(layout.hit_test(Pos { row: 2, col: 6 }), Some((0, 2, 2)));
+    assert_eq!(layout.hit_test(Pos { row: 2, col: 8 }), Some((1, 2, 1)));
+    assert_eq!(layout.hit_test(Pos { row: 3, col: 8 }), None);
+}
+
+#[test]
+fn test_pane_transition() {
+    use partial_pretty_printer::pane::display_pane_transition;
+
+    let options = PrintingOptions::default();
+    let docs = [json_number(1.0), json_number(2.0)];
+    let get_content = |label: usize| Ok::<_, Infallible>(Some((&docs[label], options.clone())));
+    let style = BasicStyle::default();
+
+    // Open a second pane to the right of the first.
+    let mut screen = PlainText::new(10, 2);
+    let one_pane = PaneNotation::Doc { label: 0 };
+    let old_layout = display_pane(&mut screen, &one_pane, &style, &get_content, None).unwrap();
+    let two_panes = PaneNotation::Horz(vec![
+        (PaneSize::Proportional(1), PaneNotation::Doc { label: 0 }),
+        (PaneSize::Proportional(1), PaneNotation::Doc { label: 1 }),
+    ]);
+    let new_layout = display_pane(&mut screen, &two_panes, &style, &get_content, None).unwrap();
+    let new_screen = screen.to_string();
+
+    let frame = |t: f32| {
+        let mut screen = PlainText::new(10, 2);
+        let layout = display_pane_transition(
+            &mut screen,
+            &old_layout,
+            &new_layout,
+            t,
+            &style,
+            &get_content,
+        )
+        .unwrap();
+        let rects = layout
+            .docs
+            .iter()
+            .map(|doc| (doc.label, doc.rect))
+            .collect::<Vec<_>>();
+        (screen.to_string(), rects)
+    };
+    let rect = |min_row, max_row, min_col, max_col| Rectangle {
+        min_row,
+        max_row,
+        min_col,
+        max_col,
+    };
+
+    // At the start, the new pane is empty and isn't displayed.
+    assert_eq!(
+        frame(0.0),
+        (
+            "1         \n          \n".to_owned(),
+            vec![(0, rect(0, 2, 0, 10))]
+        )
+    );
+    // Halfway, the new pane has grown out of the center of where it's going, over the old one.
+    assert_eq!(
+        frame(0.5),
+        (
+            "1         \n      2   \n".to_owned(),
+            vec![(0, rect(0, 2, 0, 8)), (1, rect(1, 2, 6, 9))]
+        )
+    );
+    assert_eq!(frame(1.0).0, new_screen);
+    assert_eq!(
+        frame(1.0).1,
+        vec![(0, rect(0, 2, 0, 5)), (1, rect(0, 2, 5, 10))]
+    );
+
+    // Closing the pane again shrinks it back.
+    let mut screen = PlainText::new(10, 2);
+    let layout = display_pane_transition(
+        &mut screen,
+        &new_layout,
+        &old_layout,
+        0.5,
+        &style,
+        &get_content,
+    )
+    .unwrap();
+    assert_eq!(layout.docs.len(), 2);
+    assert_eq!(layout.docs[0].label, 1);
+    assert_eq!(layout.docs[1].rect, rect(0, 2, 0, 8));
 }
 
 #[test]
@@ -576,9 +1099,9 @@ fn test_focus_point() {
     let options = PrintingOptions {
         focus_path: vec![2, 0],
         focus_height: 0.5,
-        width_strategy: WidthStrategy::Full,
         focus_target: FocusTarget::End,
         set_focus: true,
+        ..PrintingOptions::default()
     };
     let doc = json_array(vec![
         json_string("Hello"),
@@ -625,3 +1148,694 @@ fn test_focus_point() {
         Pos { col: 11, row: 7 },
     );
 }
+
+#[test]
+fn test_sticky_headers() {
+    let doc = json_object(vec![json_object_pair(
+        "Cats",
+        json_array((1..=6).map(|n| json_number(n as f64)).collect()),
+    )]);
+    let options = |focus_path, sticky_headers| PrintingOptions {
+        focus_path,
+        focus_height: 1.0,
+        sticky_headers,
+        ..PrintingOptions::default()
+    };
+    let size = Size {
+        width: 14,
+        height: 4,
+    };
+
+    // The object pair and its array start on the same line, so it's only shown once.
+    let contents = SimpleLabel(Some((&doc, options(vec![0, 1, 4], 3))), PhantomData);
+    pane_test_with_size(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "    \"Cats\": [ ",
+            "        3,    ",
+            "        4,    ",
+            "        5,    ",
+            "",
+        ]
+        .join("\n"),
+    );
+
+    let contents = SimpleLabel(Some((&doc, options(vec![0, 1, 4], 0))), PhantomData);
+    pane_test_with_size(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "        2,    ",
+            "        3,    ",
+            "        4,    ",
+            "        5,    ",
+            "",
+        ]
+        .join("\n"),
+    );
+
+    // No headers are needed if the ancestors' lines are still visible.
+    let contents = SimpleLabel(Some((&doc, options(vec![0, 1, 2], 3))), PhantomData);
+    pane_test_with_size(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "    \"Cats\": [ ",
+            "        1,    ",
+            "        2,    ",
+            "        3,    ",
+            "",
+        ]
+        .join("\n"),
+    );
+}
+
+#[test]
+fn test_clamp_focus_to_content() {
+    let doc = json_array((1..=8).map(|n| json_number(n as f64)).collect());
+    let options = |focus_path, focus_height, clamp_focus_to_content| PrintingOptions {
+        focus_path,
+        focus_height,
+        set_focus: true,
+        clamp_focus_to_content,
+        ..PrintingOptions::default()
+    };
+    let size = Size {
+        width: 8,
+        height: 4,
+    };
+    let test = |focus_path, focus_height, clamp, expected: &[&str], focus_row| {
+        let contents = SimpleLabel(
+            Some((&doc, options(focus_path, focus_height, clamp))),
+            PhantomData,
+        );
+        pane_test_with_focus(
+            size,
+            PaneNotation::Doc { label: contents },
+            &(expected.join("\n") + "\n"),
+            Pos {
+                row: focus_row,
+                col: 4,
+            },
+        );
+    };
+
+    // Near the top
+    test(
+        vec![0],
+        1.0,
+        false,
+        &["        ", "        ", "[       ", "    1,  "],
+        3,
+    );
+    test(
+        vec![0],
+        1.0,
+        true,
+        &["[       ", "    1,  ", "    2,  ", "    3,  "],
+        1,
+    );
+
+    // Near the bottom
+    test(
+        vec![7],
+        0.0,
+        false,
+        &["    8   ", "]       ", "        ", "        "],
+        0,
+    );
+    test(
+        vec![7],
+        0.0,
+        true,
+        &["    6,  ", "    7,  ", "    8   ", "]       "],
+        2,
+    );
+}
+
+#[test]
+fn test_gutter() {
+    let doc = json_array((1..=9).map(|n| json_number(n as f64)).collect());
+    let options = |focus_path, focus_height| PrintingOptions {
+        focus_path,
+        focus_height,
+        ..PrintingOptions::default()
+    };
+    let size = Size {
+        width: 10,
+        height: 4,
+    };
+    let test = |focus_path, focus_height, content, expected: &[&str]| {
+        let contents = SimpleLabel(Some((&doc, options(focus_path, focus_height))), PhantomData);
+        pane_test_with_size(
+            size,
+            PaneNotation::Gutter {
+                label: contents,
+                width: 3,
+                content,
+            },
+            &(expected.join("\n") + "\n"),
+        );
+    };
+
+    test(
+        vec![5],
+        1.0,
+        GutterContent::LineNumbers,
+        &[" 4     3, ", " 5     4, ", " 6     5, ", " 7     6, "],
+    );
+    test(
+        vec![8],
+        0.0,
+        GutterContent::LineNumbers,
+        &["10     9  ", "11 ]      ", "          ", "          "],
+    );
+    test(
+        vec![5],
+        1.0,
+        GutterContent::RelativeLineNumbers,
+        &[" 3     3, ", " 2     4, ", " 1     5, ", " 0     6, "],
+    );
+    let markers = vec![(4, ('+', BasicStyle::new())), (6, ('~', BasicStyle::new()))];
+    test(
+        vec![5],
+        1.0,
+        GutterContent::Markers(markers.into_iter().collect()),
+        &["       3, ", "+      4, ", "       5, ", "~      6, "],
+    );
+}
+
+#[test]
+fn test_path_fallback() {
+    let doc = json_array(vec![json_string("a"), json_string("b")]);
+    let options = |fall_back_on_invalid_path| PrintingOptions {
+        focus_path: vec![5, 0],
+        focus_target: FocusTarget::End,
+        set_focus: true,
+        fall_back_on_invalid_path,
+        ..PrintingOptions::default()
+    };
+    let notation = PaneNotation::Vert(vec![
+        (PaneSize::Fixed(1), fill('=')),
+        (
+            PaneSize::Proportional(1),
+            PaneNotation::Doc { label: "doc" },
+        ),
+    ]);
+
+    let mut screen = PlainText::<BasicStyle>::new(12, 3);
+    let layout = display_pane(
+        &mut screen,
+        &notation,
+        &BasicStyle::default(),
+        &|_| Ok::<_, Infallible>(Some((&doc, options(true)))),
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        screen.to_string(),
+        "============\n[\"a\", \"b\"]  \n            \n"
+    );
+    assert_eq!(screen.focus_points(), &[Pos { row: 1, col: 0 }]);
+    assert_eq!(
+        layout.path_fallbacks,
+        vec![(
+            Pos { row: 1, col: 0 },
+            PathFallback {
+                path: vec![],
+                invalid_child_index: 5,
+            }
+        )]
+    );
+
+    let mut screen = PlainText::<BasicStyle>::new(12, 3);
+    let result = display_pane(
+        &mut screen,
+        &notation,
+        &BasicStyle::default(),
+        &|_| Ok::<_, Infallible>(Some((&doc, options(false)))),
+        None,
+    );
+    assert!(matches!(
+        result,
+        Err(PaneError::PrintingError(PrintingError::InvalidPath {
+            index: 5,
+            ..
+        }))
+    ));
+}
+
+/// A document that failed to load.
+#[derive(Debug, Clone, Copy)]
+struct UnloadedDoc;
+
+fn unloaded() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, "buffer not found")
+}
+
+impl<'d> PrettyDoc<'d> for UnloadedDoc {
+    type Id = ();
+    type Style = ();
+    type StyleLabel = ();
+    type Condition = ();
+    type Error = std::io::Error;
+
+    fn id(self) -> Result<(), Self::Error> {
+        Err(unloaded())
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<(), ()>, Self::Error> {
+        Err(unloaded())
+    }
+
+    fn condition(self, _condition: &()) -> Result<bool, Self::Error> {
+        Err(unloaded())
+    }
+
+    fn lookup_style(self, _style_label: ()) -> Result<(), Self::Error> {
+        Err(unloaded())
+    }
+
+    fn node_style(self) -> Result<(), Self::Error> {
+        Err(unloaded())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        Err(unloaded())
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        Err(unloaded())
+    }
+
+    fn unwrap_child(self, _i: usize) -> Result<Self, Self::Error> {
+        Err(unloaded())
+    }
+}
+
+#[test]
+fn test_content_error() {
+    let notation = PaneNotation::Vert(vec![
+        (PaneSize::Fixed(1), fill('*')),
+        (PaneSize::Fixed(1), PaneNotation::Doc { label: "buffer" }),
+    ]);
+    let mut screen = PlainText::new(5, 2);
+    let result = display_pane(
+        &mut screen,
+        &notation,
+        &(),
+        &|label: &str| {
+            assert_eq!(label, "buffer");
+            Err::<Option<(UnloadedDoc, PrintingOptions<()>)>, _>(unloaded())
+        },
+        None,
+    );
+    match result {
+        Err(PaneError::ContentError(err)) => assert_eq!(err.to_string(), "buffer not found"),
+        _ => panic!("expected a ContentError"),
+    }
+}
+
+#[test]
+fn test_active_pane() {
+    let doc_left = make_array(0, 3);
+    let doc_right = json_number(42.0);
+    let options = || PrintingOptions::default();
+    let get_content = |label: &str| {
+        Ok::<_, Infallible>(match label {
+            "left" => Some((&doc_left, options())),
+            "right" => Some((&doc_right, options())),
+            _ => None,
+        })
+    };
+    let notation = |right_size| {
+        PaneNotation::Horz(vec![
+            (
+                PaneSize::Proportional(1),
+                PaneNotation::Doc { label: "left" },
+            ),
+            (PaneSize::Fixed(1), fill('|')),
+            (right_size, PaneNotation::Doc { label: "right" }),
+        ])
+    };
+    let restyled = Cell::new(0);
+    let restyle = |style: &BasicStyle| {
+        restyled.set(restyled.get() + 1);
+        BasicStyle {
+            bold: true,
+            ..*style
+        }
+    };
+
+    for (active_label, right_size, expected) in [
+        (
+            "left",
+            PaneSize::Proportional(1),
+            Some((
+                Pos { row: 0, col: 0 },
+                Size {
+                    width: 3,
+                    height: 4,
+                },
+            )),
+        ),
+        (
+            "right",
+            PaneSize::Proportional(1),
+            Some((
+                Pos { row: 0, col: 4 },
+                Size {
+                    width: 3,
+                    height: 4,
+                },
+            )),
+        ),
+        (
+            "right",
+            PaneSize::Dynamic { max: None },
+            Some((
+                Pos { row: 0, col: 5 },
+                Size {
+                    width: 2,
+                    height: 4,
+                },
+            )),
+        ),
+        ("nowhere", PaneSize::Dynamic { max: None }, None),
+    ] {
+        let mut screen = PlainText::new(7, 4);
+        restyled.set(0);
+        let is_active = |label: &&str| *label == active_label;
+        let active = ActivePane {
+            is_active: &is_active,
+            style: &restyle,
+        };
+        let layout = display_pane(
+            &mut screen,
+            &notation(right_size),
+            &BasicStyle::default(),
+            &get_content,
+            Some(&active),
+        )
+        .unwrap();
+        assert_eq!(layout.active, expected);
+        assert_eq!(restyled.get(), expected.iter().count());
+    }
+}
+
+#[test]
+fn test_pane_runner() {
+    let docs = [json_number(1.0), json_number(2.0)];
+    let options = PrintingOptions::default();
+    let revision = Cell::new(0);
+    let get_content = |label: usize| Ok(Some((&docs[label + revision.get()], options.clone())));
+    let notation = |ch| {
+        PaneNotation::Vert(vec![
+            (PaneSize::Fixed(1), PaneNotation::Doc { label: 0 }),
+            (PaneSize::Fixed(1), fill(ch)),
+        ])
+    };
+    let style = BasicStyle::default();
+
+    let mut runner = PaneRunner::new();
+    let mut screen = PlainText::new(3, 2);
+    let redraw = |runner: &mut PaneRunner<usize>, screen: &mut PlainText<BasicStyle>, ch| {
+        runner
+            .maybe_redraw(
+                screen,
+                &notation(ch),
+                &style,
+                revision.get() as u64,
+                &get_content,
+                None,
+            )
+            .unwrap()
+    };
+    assert!(redraw(&mut runner, &mut screen, '*'));
+    assert_eq!(screen.to_string(), "1  \n***\n");
+    assert!(!redraw(&mut runner, &mut screen, '*'));
+
+    // Changing the notation redraws.
+    assert!(redraw(&mut runner, &mut screen, '-'));
+    assert!(!redraw(&mut runner, &mut screen, '-'));
+
+    // Changing the content fingerprint redraws.
+    revision.set(1);
+    assert!(redraw(&mut runner, &mut screen, '-'));
+    assert!(screen.to_string().starts_with('2'));
+    assert!(!redraw(&mut runner, &mut screen, '-'));
+
+    // Changing the window size redraws.
+    let mut screen = PlainText::new(4, 2);
+    assert!(redraw(&mut runner, &mut screen, '-'));
+    assert_eq!(screen.to_string(), "2   \n----\n");
+
+    runner.invalidate();
+    assert!(redraw(&mut runner, &mut screen, '-'));
+}
+
+#[test]
+fn test_width_policy() {
+    let doc = json_array(vec![json_string("αβ"), json_string("γ")]);
+    let options = |width_policy| PrintingOptions {
+        width_policy,
+        ..PrintingOptions::default()
+    };
+    let size = Size {
+        width: 12,
+        height: 4,
+    };
+
+    let contents = SimpleLabel(Some((&doc, options(&UnicodeWidth))), PhantomData);
+    pane_test_with_size(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "[\"αβ\", \"γ\"] ",
+            "            ",
+            "            ",
+            "            \n",
+        ]
+        .join("\n"),
+    );
+
+    // Greek letters are 2 columns wide, so the document no longer fits on one line.
+    let contents = SimpleLabel(Some((&doc, options(&WideGreek))), PhantomData);
+    pane_test_with_size(
+        size,
+        PaneNotation::Doc { label: contents },
+        &[
+            "[           ",
+            "    \"αβ\", ",
+            "    \"γ\"    ",
+            "]           \n",
+        ]
+        .join("\n"),
+    );
+}
+
+#[test]
+fn test_diff() {
+    use partial_pretty_printer::doc_examples::tree::Contents;
+    use partial_pretty_printer::pane::{diff, DiffKind, DiffSide, DiffStyles, DocDiff};
+
+    let old_doc = make_array(1, 7);
+    let mut children = match &old_doc.contents {
+        Contents::Children(children) => children.clone(),
+        Contents::Text(_) => unreachable!(),
+    };
+    let old_ids = children.iter().map(|child| child.id).collect::<Vec<_>>();
+    children.remove(0);
+    children[1].contents = Contents::Text("30".to_owned());
+    children.push(json_number(7.0));
+    let new_id = children[5].id;
+    // Keep the root's id, so that the roots are aligned.
+    let mut new_doc = json_array(children);
+    new_doc.id = old_doc.id;
+
+    let styles = DiffStyles {
+        inserted: BasicStyle::default(),
+        deleted: BasicStyle::default(),
+        changed: BasicStyle::default(),
+    };
+    let doc_diff = diff(&old_doc, &new_doc, styles).unwrap();
+    assert_eq!(
+        doc_diff.kind(DiffSide::Old, old_ids[0]),
+        Some(DiffKind::Deleted)
+    );
+    assert_eq!(doc_diff.kind(DiffSide::Old, old_ids[1]), None);
+    assert_eq!(
+        doc_diff.kind(DiffSide::Old, old_ids[2]),
+        Some(DiffKind::Changed)
+    );
+    assert_eq!(
+        doc_diff.kind(DiffSide::New, old_ids[2]),
+        Some(DiffKind::Changed)
+    );
+    assert_eq!(
+        doc_diff.kind(DiffSide::New, new_id),
+        Some(DiffKind::Inserted)
+    );
+    assert_eq!(doc_diff.kind(DiffSide::New, old_ids[0]), None);
+
+    assert_eq!(
+        doc_diff.sync_path(DiffSide::Old, &[4]).unwrap(),
+        Some((vec![3], true))
+    );
+    assert_eq!(
+        doc_diff.sync_path(DiffSide::Old, &[0]).unwrap(),
+        Some((vec![], false))
+    );
+    assert_eq!(doc_diff.sync_path(DiffSide::Old, &[9]).unwrap(), None);
+
+    let options = PrintingOptions {
+        focus_path: vec![4],
+        focus_height: 0.5,
+        ..PrintingOptions::default()
+    };
+    let get_content = |side: DiffSide| doc_diff.content(side, DiffSide::Old, &options).map(Some);
+    let notation = DocDiff::<&Json>::pane_notation(DiffSide::Old, DiffSide::New);
+    let mut screen = PlainText::new(21, 9);
+    display_pane(
+        &mut screen,
+        &notation,
+        &BasicStyle::default(),
+        &get_content,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        screen.to_string(),
+        [
+            "    1,    │[         ",
+            "    2,    │    2,    ",
+            "    3,    │    30,   ",
+            "    4,    │    4,    ",
+            "    5,    │    5,    ",
+            "    6     │    6,    ",
+            "]         │    7     ",
+            "          │]         ",
+            "          │          ",
+            "",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+fn test_fake_window_resize() {
+    use partial_pretty_printer::testing::{FakeWindow, WindowCall};
+
+    let doc = make_array(0, 3);
+    let options = PrintingOptions {
+        focus_path: vec![1],
+        focus_target: FocusTarget::End,
+        set_focus: true,
+        ..PrintingOptions::default()
+    };
+    let get_content = |_label: ()| Ok::<_, Infallible>(Some((&doc, options.clone())));
+    let notation = PaneNotation::Horz(vec![
+        (PaneSize::Proportional(1), PaneNotation::Doc { label: () }),
+        (
+            PaneSize::Fixed(1),
+            PaneNotation::Style {
+                style: BasicStyle {
+                    bold: true,
+                    ..BasicStyle::default()
+                },
+                notation: Box::new(fill('|')),
+            },
+        ),
+    ]);
+    let style_name = |style: &BasicStyle| {
+        if style.bold {
+            Some("b".to_owned())
+        } else {
+            None
+        }
+    };
+
+    let mut window = FakeWindow::new(Size {
+        width: 10,
+        height: 2,
+    });
+    display_pane(
+        &mut window,
+        &notation,
+        &BasicStyle::default(),
+        &get_content,
+        None,
+    )
+    .unwrap();
+    assert_eq!(window.cells_not_written_once(), vec![]);
+    assert_eq!(window.focus_points(), vec![Pos { row: 0, col: 5 }]);
+    assert_eq!(
+        window.to_markup(style_name),
+        "[0, 1, 2]<b>|</b>\n         <b>|</b>\n"
+    );
+
+    let size = Size {
+        width: 8,
+        height: 4,
+    };
+    window.resize(size);
+    window.clear_calls();
+    display_pane(
+        &mut window,
+        &notation,
+        &BasicStyle::default(),
+        &get_content,
+        None,
+    )
+    .unwrap();
+    assert_eq!(window.cells_not_written_once(), vec![]);
+    assert_eq!(window.focus_points(), vec![Pos { row: 0, col: 5 }]);
+    assert_eq!(
+        window.to_string(),
+        ["    1, |", "    2  |", "]      |", "       |", ""].join("\n")
+    );
+    assert!(!window
+        .calls()
+        .iter()
+        .any(|call| matches!(call, WindowCall::Resize(_))));
+}
+
+#[test]
+fn test_blank_style() {
+    use partial_pretty_printer::{doc_examples::Color, testing::FakeWindow};
+
+    let doc = json_number(42.0);
+    let options = PrintingOptions {
+        blank_style: Some(BasicStyle::new().background(Color::Blue)),
+        ..PrintingOptions::default()
+    };
+    let get_content = |_label: ()| Ok::<_, Infallible>(Some((&doc, options.clone())));
+    // The blank style is combined with the pane's bold style.
+    let notation = PaneNotation::Style {
+        style: BasicStyle::new().bold(),
+        notation: Box::new(PaneNotation::Doc { label: () }),
+    };
+    let style_name = |style: &BasicStyle| match (style.bold, style.background) {
+        (true, Some(Color::Blue)) => Some("bold_blue".to_owned()),
+        (true, None) => Some("bold".to_owned()),
+        _ => None,
+    };
+
+    let mut window = FakeWindow::new(Size {
+        width: 5,
+        height: 2,
+    });
+    display_pane(
+        &mut window,
+        &notation,
+        &BasicStyle::default(),
+        &get_content,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        window.to_markup(style_name),
+        "<bold>42</bold><bold_blue>   </bold_blue>\n<bold_blue>     </bold_blue>\n"
+    );
+}
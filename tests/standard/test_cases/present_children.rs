@@ -0,0 +1,115 @@
+use crate::standard::pretty_testing::assert_pp;
+use once_cell::sync::Lazy;
+use partial_pretty_printer::notation_constructors::{join_present_children, lit, nl, text};
+use partial_pretty_printer::{PrettyDoc, ValidNotation};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    id: usize,
+    data: ItemData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ItemData {
+    Word(String),
+    List(Vec<Item>),
+}
+
+static WORD_NOTATION: Lazy<ValidNotation<(), ()>> = Lazy::new(|| text().validate().unwrap());
+static LIST_NOTATION: Lazy<ValidNotation<(), ()>> = Lazy::new(|| {
+    (lit("[") + join_present_children(lit(",") + (lit(" ") | nl())) + lit("]"))
+        .validate()
+        .unwrap()
+});
+
+impl<'d> PrettyDoc<'d> for &'d Item {
+    type Id = usize;
+    type Style = ();
+    type StyleLabel = ();
+    type Condition = ();
+    type Error = std::convert::Infallible;
+
+    fn id(self) -> Result<usize, Self::Error> {
+        Ok(self.id)
+    }
+
+    fn notation(self) -> Result<&'d ValidNotation<(), ()>, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(_) => &WORD_NOTATION,
+            ItemData::List(_) => &LIST_NOTATION,
+        })
+    }
+
+    fn condition(self, _condition: &()) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn node_style(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn lookup_style(self, _label: ()) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn num_children(self) -> Result<Option<usize>, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(_) => None,
+            ItemData::List(items) => Some(items.len()),
+        })
+    }
+
+    fn unwrap_text(self) -> Result<&'d str, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(word) => word,
+            ItemData::List(_) => unreachable!(),
+        })
+    }
+
+    fn unwrap_child(self, i: usize) -> Result<Self, Self::Error> {
+        Ok(match &self.data {
+            ItemData::Word(_) => unreachable!(),
+            ItemData::List(items) => &items[i],
+        })
+    }
+}
+
+static ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn new_node(data: ItemData) -> Item {
+    Item {
+        id: ID_COUNTER.fetch_add(1, Ordering::SeqCst),
+        data,
+    }
+}
+
+fn word(word: &str) -> Item {
+    new_node(ItemData::Word(word.to_owned()))
+}
+
+fn list(items: Vec<Item>) -> Item {
+    new_node(ItemData::List(items))
+}
+
+fn words(words: &[&str]) -> Item {
+    list(words.iter().map(|w| word(w)).collect())
+}
+
+#[test]
+fn present_children_skip_empty() {
+    assert_pp(&words(&["a", "", "b"]), 80, &["[a, b]"]);
+    assert_pp(&words(&["", "a", "", "", "b", ""]), 80, &["[a, b]"]);
+    assert_pp(&words(&["", ""]), 80, &["[]"]);
+    assert_pp(&words(&["a", ""]), 80, &["[a]"]);
+    assert_pp(&words(&["a"]), 80, &["[a]"]);
+    assert_pp(&words(&[]), 80, &["[]"]);
+}
+
+#[test]
+fn present_children_nested() {
+    // An empty list displays as `[]`, so it isn't skipped.
+    let doc = list(vec![word(""), words(&["", ""]), word("c"), word("")]);
+    assert_pp(&doc, 80, &["[[], c]"]);
+    assert_pp(&words(&["aaaa", "", "bbbb", ""]), 6, &["[aaaa,", "bbbb]"]);
+}
@@ -0,0 +1,193 @@
+use partial_pretty_printer::doc_examples::json::{json_array, json_number, json_string};
+use partial_pretty_printer::{
+    id_at_path, path_near_line, path_of_id, pretty_print, pretty_print_near_line,
+    pretty_print_to_string, pretty_print_with_options, reanchor, validate_path, FocusTarget,
+    PathEdit, PathFallback, PathIndex, PrettyDoc, PrintOptions, PrintingError,
+};
+
+#[test]
+fn test_reanchor() {
+    let insert = PathEdit::Insert {
+        parent: vec![1],
+        index: 2,
+    };
+    assert_eq!(reanchor(&[1, 1, 0], &insert), vec![1, 1, 0]);
+    assert_eq!(reanchor(&[1, 2, 0], &insert), vec![1, 3, 0]);
+    assert_eq!(reanchor(&[1, 5], &insert), vec![1, 6]);
+    assert_eq!(reanchor(&[1], &insert), vec![1]);
+    assert_eq!(reanchor(&[0, 2], &insert), vec![0, 2]);
+
+    let delete = PathEdit::Delete {
+        parent: vec![1],
+        index: 2,
+    };
+    assert_eq!(reanchor(&[1, 1, 0], &delete), vec![1, 1, 0]);
+    assert_eq!(reanchor(&[1, 2, 0], &delete), vec![1]);
+    assert_eq!(reanchor(&[1, 3, 0], &delete), vec![1, 2, 0]);
+    assert_eq!(reanchor(&[2, 3], &delete), vec![2, 3]);
+
+    let replace = PathEdit::Replace { path: vec![0, 4] };
+    assert_eq!(reanchor(&[0, 4, 1, 1], &replace), vec![0, 4]);
+    assert_eq!(reanchor(&[0, 4], &replace), vec![0, 4]);
+    assert_eq!(reanchor(&[0, 3, 1], &replace), vec![0, 3, 1]);
+}
+
+#[test]
+fn test_pretty_print_with_fallback() {
+    let doc = json_array(vec![
+        json_number(1.0),
+        json_array(vec![json_string("a"), json_string("b")]),
+    ]);
+
+    let options = PrintOptions {
+        fall_back_on_invalid_path: true,
+        ..PrintOptions::default()
+    };
+    let (_, focused_line, lines_below) =
+        pretty_print_with_options(&doc, 80, &[1, 1], FocusTarget::End, None, &options).unwrap();
+    assert_eq!(lines_below.path_fallback(), None);
+    assert_eq!(focused_line.to_left_string(), r#"[1, ["a", "b""#);
+
+    let (lines_above, focused_line, lines_below) =
+        pretty_print_with_options(&doc, 80, &[1, 2, 0], FocusTarget::End, None, &options).unwrap();
+    assert_eq!(lines_above.path_fallback(), lines_below.path_fallback());
+    assert_eq!(
+        lines_below.path_fallback(),
+        Some(&PathFallback {
+            path: vec![1],
+            invalid_child_index: 2,
+        })
+    );
+    assert_eq!(focused_line.to_left_string(), "[1, ");
+
+    match pretty_print(&doc, 80, &[1, 2, 0], FocusTarget::End, None) {
+        Err(PrintingError::InvalidPath {
+            valid_prefix,
+            index,
+            num_children,
+        }) => {
+            assert_eq!(valid_prefix, vec![1]);
+            assert_eq!(index, 2);
+            assert_eq!(num_children, Some(2));
+        }
+        _ => panic!("expected InvalidPath"),
+    }
+    assert!(validate_path(&doc, &[1, 1]).is_ok());
+    match validate_path(&doc, &[1, 1, 0]) {
+        Err(PrintingError::InvalidPath {
+            valid_prefix,
+            index,
+            num_children,
+        }) => {
+            assert_eq!(valid_prefix, vec![1, 1]);
+            assert_eq!(index, 0);
+            assert_eq!(num_children, None);
+        }
+        _ => panic!("expected InvalidPath"),
+    }
+}
+
+#[test]
+fn test_paths_and_ids() {
+    use partial_pretty_printer::testing::{all_paths, make_deep_doc};
+
+    let doc = json_array(vec![
+        json_number(1.0),
+        json_array(vec![json_string("a"), json_string("b")]),
+    ]);
+    let index = PathIndex::new(&doc).unwrap();
+    assert_eq!(index.len(), 5);
+    for path in all_paths(&doc) {
+        let id = id_at_path(&doc, &path).unwrap();
+        assert_eq!(path_of_id(&doc, id).unwrap(), Some(path.clone()));
+        assert_eq!(index.path_of(&id), Some(path.as_slice()));
+    }
+    assert_eq!(
+        id_at_path(&doc, &[1, 1]).unwrap(),
+        (&doc)
+            .unwrap_child(1)
+            .unwrap()
+            .unwrap_child(1)
+            .unwrap()
+            .id()
+            .unwrap()
+    );
+
+    let other_doc = json_number(2.0);
+    let other_id = (&other_doc).id().unwrap();
+    assert_eq!(path_of_id(&doc, other_id).unwrap(), None);
+    assert_eq!(index.path_of(&other_id), None);
+    assert!(matches!(
+        id_at_path(&doc, &[1, 2]),
+        Err(PrintingError::InvalidPath { index: 2, .. })
+    ));
+
+    let deep_doc = make_deep_doc(500);
+    let deep_path = vec![1; 1000];
+    let id = id_at_path(&deep_doc, &deep_path).unwrap();
+    assert_eq!(path_of_id(&deep_doc, id).unwrap(), Some(deep_path));
+}
+
+#[test]
+fn test_path_near_line() {
+    let doc = json_array(vec![
+        json_number(1.0),
+        json_array(vec![json_string("a"), json_string("b")]).with_height_hint(100),
+        json_number(2.0),
+    ]);
+    assert_eq!(path_near_line(&doc, 0).unwrap(), (vec![0], 0));
+    assert_eq!(path_near_line(&doc, 1).unwrap(), (vec![1, 0], 0));
+    assert_eq!(path_near_line(&doc, 2).unwrap(), (vec![1, 1], 0));
+    // Past the children of the hinted array, but still inside of it.
+    assert_eq!(path_near_line(&doc, 50).unwrap(), (vec![1], 49));
+    assert_eq!(path_near_line(&doc, 101).unwrap(), (vec![2], 0));
+    assert_eq!(path_near_line(&doc, 500).unwrap(), (vec![], 500));
+
+    // Without hints, every leaf counts as a line.
+    let doc = json_array((0..10).map(|i| json_number(i as f64)).collect());
+    assert_eq!(path_near_line(&doc, 7).unwrap(), (vec![7], 0));
+}
+
+#[test]
+fn test_pretty_print_near_line() {
+    let doc = json_array(vec![
+        json_number(1.0),
+        json_array(vec![json_string("a"), json_string("b")]).with_height_hint(100),
+        json_number(2.0),
+    ]);
+    let print = |line: usize| {
+        let (prev_lines, focused_line, next_lines) =
+            pretty_print_near_line(&doc, 5, line, None, &PrintOptions::default()).unwrap();
+        let mut lines = prev_lines
+            .map(|line| line.unwrap().to_string())
+            .collect::<Vec<_>>();
+        lines.reverse();
+        let focus_row = lines.len();
+        let focus = (
+            focused_line
+                .left_segments
+                .iter()
+                .map(|seg| seg.str)
+                .collect(),
+            focused_line
+                .right_segments
+                .iter()
+                .map(|seg| seg.str)
+                .collect(),
+        );
+        lines.push(focused_line.to_string());
+        lines.extend(next_lines.map(|line| line.unwrap().to_string()));
+        // Skipping lines doesn't change what's printed around them.
+        assert_eq!(lines.join("\n"), pretty_print_to_string(&doc, 5).unwrap());
+        (focus_row, focus)
+    };
+    let focus = |left: &str, right: &str| (left.to_owned(), right.to_owned());
+
+    assert_eq!(print(0), (1, focus("    ", "1,")));
+    assert_eq!(print(2), (4, focus("        ", "\"b\"")));
+    // Two lines into the hinted array, past its children.
+    assert_eq!(print(3), (4, focus("        ", "\"b\"")));
+    // Past the end of the document.
+    assert_eq!(print(50), (7, focus("]", "")));
+    assert_eq!(print(101), (6, focus("    ", "2")));
+}
@@ -0,0 +1,109 @@
+use once_cell::sync::Lazy;
+use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+use partial_pretty_printer::doc_examples::BasicStyle;
+use partial_pretty_printer::notation_constructors::{child, fold, left, lit, right, text, Fold};
+use partial_pretty_printer::{
+    cmp_layout_order, next_match_after, pretty_print_with_options, prev_match_before, FocusTarget,
+    Line, Matches, PrintOptions,
+};
+use std::cmp::Ordering;
+
+static REVERSED_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    fold(Fold {
+        first: child(0),
+        join: right() + lit(" ") + left(),
+    })
+    .validate()
+    .unwrap()
+});
+static LIST_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+    (lit("[")
+        + fold(Fold {
+            first: child(0),
+            join: left() + lit(" ") + right(),
+        })
+        + lit("]"))
+    .validate()
+    .unwrap()
+});
+static TEXT_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+
+fn word(text: &str) -> Tree<BasicStyle> {
+    Tree::new_text(&TEXT_NOTATION, text.to_owned())
+}
+
+/// `[a b] e d [c]`, where the root displays its children in reverse.
+fn make_doc() -> Tree<BasicStyle> {
+    Tree::new_branch(
+        &REVERSED_NOTATION,
+        vec![
+            Tree::new_branch(&LIST_NOTATION, vec![word("c")]),
+            word("d"),
+            word("e"),
+            Tree::new_branch(&LIST_NOTATION, vec![word("a"), word("b")]),
+        ],
+    )
+}
+
+#[test]
+fn search_layout_order() {
+    let doc = make_doc();
+    let cmp = |a: &[usize], b: &[usize]| cmp_layout_order(&doc, a, b).unwrap();
+    assert_eq!(cmp(&[3, 1], &[2]), Ordering::Less);
+    assert_eq!(cmp(&[0], &[1]), Ordering::Greater);
+    assert_eq!(cmp(&[3], &[3, 0]), Ordering::Less);
+    assert_eq!(cmp(&[3, 0], &[3, 1]), Ordering::Less);
+    assert_eq!(cmp(&[2], &[2]), Ordering::Equal);
+
+    let matches = vec![vec![0, 0], vec![2], vec![3, 0]];
+    let next = |path: &[usize]| next_match_after(&doc, &matches, path).unwrap();
+    let prev = |path: &[usize]| prev_match_before(&doc, &matches, path).unwrap();
+    assert_eq!(next(&[]), Some(&[3, 0][..]));
+    assert_eq!(next(&[3, 0]), Some(&[2][..]));
+    assert_eq!(next(&[1]), Some(&[0, 0][..]));
+    assert_eq!(next(&[0, 0]), None);
+    assert_eq!(prev(&[0, 0]), Some(&[2][..]));
+    assert_eq!(prev(&[3, 1]), Some(&[3, 0][..]));
+    assert_eq!(prev(&[3]), None);
+}
+
+#[test]
+fn search_match_style() {
+    let doc = make_doc();
+    let matches = Matches {
+        paths: vec![vec![3], vec![1], vec![7]],
+        style: BasicStyle {
+            bold: true,
+            ..BasicStyle::default()
+        },
+    };
+    let options = PrintOptions {
+        matches: Some(matches),
+        ..PrintOptions::default()
+    };
+    let (_, focused_line, _) =
+        pretty_print_with_options(&doc, 80, &[], FocusTarget::Start, None, &options).unwrap();
+    let bold = Line::from(focused_line)
+        .segments
+        .iter()
+        .map(|seg| (seg.str, seg.style.bold))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        bold,
+        vec![
+            ("[", true),
+            ("a", true),
+            (" ", true),
+            ("b", true),
+            ("]", true),
+            (" ", false),
+            ("e", false),
+            (" ", false),
+            ("d", true),
+            (" ", false),
+            ("[", false),
+            ("c", false),
+            ("]", false),
+        ]
+    );
+}
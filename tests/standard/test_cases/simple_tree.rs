@@ -0,0 +1,129 @@
+use crate::standard::pretty_testing::{assert_pp, assert_pp_focus};
+use partial_pretty_printer::doc_examples::simple_tree::{node, node_with, text};
+use partial_pretty_printer::doc_examples::tree::TreeCondition;
+use partial_pretty_printer::notation_constructors::{
+    check, child, empty, eol, fold, left, lit, right, Fold,
+};
+use partial_pretty_printer::{CheckPos, FocusTarget};
+use std::rc::Rc;
+
+#[test]
+fn simple_tree_text() {
+    assert_pp(&text("Hello world!"), 80, &["Hello world!"]);
+}
+
+#[test]
+fn simple_tree_list() {
+    let separator = check(
+        TreeCondition::NeedsSeparator,
+        CheckPos::LeftChild,
+        lit(","),
+        empty(),
+    );
+    let list = Rc::new(
+        ((lit("[")
+            + (4 >> fold(Fold {
+                first: child(0),
+                join: (left() + separator) ^ right(),
+            })))
+            ^ lit("]"))
+        .validate()
+        .unwrap(),
+    );
+    let doc = node_with(
+        &list,
+        vec![
+            text("one"),
+            node_with(&list, vec![text("two"), text("three")]),
+            node(lit("(") + child(0) + lit(")"), vec![text("four")]),
+        ],
+    );
+    assert_pp(
+        &doc,
+        80,
+        &[
+            // force rustfmt
+            "[",
+            "    one,",
+            "    [",
+            "        two,",
+            "        three",
+            "    ],",
+            "    (four)",
+            "]",
+        ],
+    );
+    assert_pp_focus(
+        &doc,
+        80,
+        &[1, 1],
+        FocusTarget::Text(2),
+        &[
+            // force rustfmt
+            "[",
+            "    one,",
+            "    [",
+            "        two,",
+            "        th|ree",
+            "    ],",
+            "    (four)",
+            "]",
+        ],
+    );
+}
+
+#[test]
+#[should_panic(expected = "SimpleTree: invalid notation")]
+fn simple_tree_invalid_notation() {
+    node(child(0) + eol() + lit("!"), vec![text("a")]);
+}
+
+#[test]
+fn simple_tree_check_expr() {
+    use partial_pretty_printer::doc_examples::tree::TreeStyleLabel;
+    use partial_pretty_printer::notation_constructors::{check_expr, holds};
+    use partial_pretty_printer::{Notation, NotationError};
+
+    // A comma between children, except next to empty ones.
+    let separator = check_expr(
+        holds(TreeCondition::NeedsSeparator, CheckPos::LeftChild)
+            & !holds(TreeCondition::IsEmptyText, CheckPos::LeftChild)
+            & !holds(TreeCondition::IsEmptyText, CheckPos::RightChild),
+        lit(", "),
+        empty(),
+    );
+    let list = Rc::new(
+        fold(Fold {
+            first: child(0),
+            join: left() + separator + right(),
+        })
+        .validate()
+        .unwrap(),
+    );
+    let doc = node_with(&list, vec![text("a"), text(""), text("b"), text("c")]);
+    assert_pp(&doc, 80, &["ab, c"]);
+
+    // Brackets if the first or last child is empty.
+    let brackets = check_expr(
+        holds(TreeCondition::IsEmptyText, CheckPos::Child(0))
+            | holds(TreeCondition::IsEmptyText, CheckPos::Child(-1)),
+        lit("[") + child(0) + lit("..") + child(-1) + lit("]"),
+        child(0) + lit("..") + child(-1),
+    );
+    assert_pp(
+        &node(brackets.clone(), vec![text("1"), text("2")]),
+        80,
+        &["1..2"],
+    );
+    assert_pp(&node(brackets, vec![text("1"), text("")]), 80, &["[1..]"]);
+
+    let outside_join: Notation<TreeStyleLabel, TreeCondition> = check_expr(
+        !holds(TreeCondition::IsComment, CheckPos::LeftChild),
+        lit("a"),
+        empty(),
+    );
+    assert!(matches!(
+        outside_join.validate(),
+        Err(NotationError::CheckPosLeftOutsideJoin)
+    ));
+}
@@ -2,7 +2,7 @@ use partial_pretty_printer::doc_examples::{
     json::{json_array, json_number, json_object, json_object_pair, json_string, Json},
     BasicStyle, Color,
 };
-use partial_pretty_printer::{pretty_print, FocusTarget, Line, Width};
+use partial_pretty_printer::{pretty_print, FocusTarget, Line, PrettyDoc, Style, Theme, Width};
 
 #[derive(Debug)]
 struct RichChar {
@@ -15,7 +15,11 @@ struct RichText {
     lines: Vec<Vec<RichChar>>,
 }
 
-fn print(doc: &Json, width: Width, style: BasicStyle) -> RichText {
+fn print<'d, D: PrettyDoc<'d, Style = BasicStyle>>(
+    doc: D,
+    width: Width,
+    style: BasicStyle,
+) -> RichText {
     let (upward_printer, focused_line, downward_printer) =
         pretty_print(doc, width, &[], FocusTarget::Start, Some(&style)).unwrap();
     let mut rich_text = RichText::new();
@@ -42,6 +46,7 @@ impl RichChar {
             Color::Blue => 'b',
             Color::Magenta => 'm',
             Color::Cyan => 'c',
+            Color::Rgb(..) => '#',
         };
         if self.style.bold {
             ch.to_ascii_uppercase()
@@ -56,7 +61,7 @@ impl RichText {
         RichText { lines: Vec::new() }
     }
 
-    fn push_line<'d>(&mut self, line: Line<'d, &'d Json>) {
+    fn push_line<'d, D: PrettyDoc<'d, Style = BasicStyle>>(&mut self, line: Line<'d, D>) {
         let mut chars = Vec::new();
         for segment in line.segments {
             for ch in segment.str.chars() {
@@ -178,3 +183,328 @@ fn test_json_styles() {
         &rich_text.display_styles(),
     );
 }
+
+#[test]
+fn test_basic_style_combine() {
+    let outer = BasicStyle::new()
+        .color(Color::Red)
+        .background(Color::Rgb(0, 0, 64))
+        .italic();
+    let inner = BasicStyle::new()
+        .color(Color::Rgb(255, 128, 0))
+        .strikethrough();
+
+    let combined = BasicStyle::combine(&outer, &inner);
+    assert!(matches!(combined.color, Color::Rgb(255, 128, 0)));
+    assert!(matches!(combined.background, Some(Color::Rgb(0, 0, 64))));
+    assert!(combined.italic);
+    assert!(combined.strikethrough);
+    assert!(!combined.bold);
+
+    let inner = inner.background(Color::Black);
+    let combined = BasicStyle::combine(&outer, &inner);
+    assert!(matches!(combined.background, Some(Color::Black)));
+}
+
+#[test]
+fn test_theme() {
+    let json = make_json_object();
+    let theme = Theme::new()
+        .with_style("magenta", BasicStyle::new().color(Color::Green))
+        .with_fallback(Theme::new().with_style("open", BasicStyle::new().color(Color::Red)));
+
+    let rich_text = print(theme.apply(&json), 90, BasicStyle::new());
+    assert_str_eq(
+        r#"{"Name": "Alice", "Age": 42, "Pets": [], "Favorites": ["chocolate", "lemon", "almond"]}"#,
+        &rich_text.display_text(),
+    );
+    assert_str_eq(
+        r#"rggggggwwGGGGGGGwwgggggwwbbwwggggggwwrwwwgggggggggggwwRGGGGGGGGGGGRRGGGGGGGRRGGGGGGGGBb"#,
+        &rich_text.display_styles(),
+    );
+}
+
+#[test]
+fn test_links() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::notation_constructors::{child, indent, link, lit, nl, text};
+
+    static CALL_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (link("def", lit("call ") + child(0)) + indent("  ", None, nl() + child(1)))
+            .validate()
+            .unwrap()
+    });
+    static NAME_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| link("name", text()).validate().unwrap());
+
+    let name = |s: &str| Tree::new_text(&NAME_NOTATION, s.to_owned());
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &CALL_NOTATION,
+        vec![
+            name("foo").with_link("name", "file:///foo.rs"),
+            name("bar").with_link("other", "file:///bar.rs"),
+        ],
+    )
+    .with_link("def", "file:///call.rs");
+
+    let (_, focused_line, mut lines) =
+        pretty_print(&doc, 80, &[], FocusTarget::Start, None).unwrap();
+    fn links<'d>(line: Line<'d, &'d Tree<BasicStyle>>) -> Vec<(&'d str, Option<&'d str>)> {
+        line.segments
+            .iter()
+            .map(|seg| (seg.str, seg.link))
+            .collect::<Vec<_>>()
+    }
+    assert_eq!(
+        links(Line::from(focused_line)),
+        vec![
+            ("call ", Some("file:///call.rs")),
+            ("foo", Some("file:///foo.rs")),
+        ]
+    );
+    assert_eq!(
+        links(lines.next().unwrap().unwrap()),
+        vec![("  ", None), ("bar", None)]
+    );
+}
+
+#[test]
+fn test_themed_links() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::notation_constructors::{link, style, text};
+
+    static NAME_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| style("name", link("name", text())).validate().unwrap());
+
+    let doc: Tree<BasicStyle> =
+        Tree::new_text(&NAME_NOTATION, "foo".to_owned()).with_link("name", "file:///foo.rs");
+    let theme = Theme::new().with_style("name", BasicStyle::new().color(Color::Green));
+    let themed = theme.apply(&doc);
+
+    let (_, focused_line, _) = pretty_print(themed, 80, &[], FocusTarget::Start, None).unwrap();
+    let line = Line::from(focused_line);
+    assert_eq!(line.segments.len(), 1);
+    assert_eq!(line.segments[0].str, "foo");
+    assert_eq!(line.segments[0].link, Some("file:///foo.rs"));
+    assert!(matches!(line.segments[0].style.color, Color::Green));
+}
+
+#[test]
+fn test_semantic_tokens() {
+    use partial_pretty_printer::doc_examples::json::json_null;
+    use partial_pretty_printer::{semantic_tokens, SemanticToken};
+
+    let token = |row, col, length, token_type| SemanticToken {
+        row,
+        col,
+        length,
+        token_type,
+    };
+    let doc = json_array(vec![json_string("ab"), json_number(1.0), json_null()]);
+
+    assert_eq!(
+        semantic_tokens(&doc, 80, 0..10).unwrap(),
+        vec![
+            token(0, 0, 1, "open"),
+            token(0, 1, 1, "magenta"),
+            token(0, 2, 2, "magenta"),
+            token(0, 4, 1, "magenta"),
+            token(0, 7, 1, "blue"),
+            token(0, 10, 4, "green_bold"),
+            token(0, 14, 1, "close"),
+        ]
+    );
+
+    assert_eq!(
+        semantic_tokens(&doc, 5, 1..3).unwrap(),
+        vec![
+            token(1, 4, 1, "magenta"),
+            token(1, 5, 2, "magenta"),
+            token(1, 7, 1, "magenta"),
+            token(2, 4, 1, "blue"),
+        ]
+    );
+}
+
+#[test]
+fn test_annotated_string() {
+    use partial_pretty_printer::pretty_print_to_annotated_string;
+
+    let doc = json_array(vec![json_number(1.0), json_string("ab")]);
+    let (string, annotations) = pretty_print_to_annotated_string(&doc, 80).unwrap();
+    assert_eq!(string, r#"[1, "ab"]"#);
+    let spans = annotations
+        .iter()
+        .map(|ann| &string[ann.range.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(spans, vec!["[", "1", ", ", "\"", "ab", "\"", "]"]);
+    assert!(matches!(annotations[1].style.color, Color::Blue));
+    assert!(matches!(annotations[4].style.color, Color::Magenta));
+
+    let (string, annotations) = pretty_print_to_annotated_string(&doc, 5).unwrap();
+    assert_eq!(string, "[\n    1,\n    \"ab\"\n]");
+    let spans = annotations
+        .iter()
+        .map(|ann| &string[ann.range.clone()])
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec!["[", "    ", "1", ",", "    ", "\"", "ab", "\"", "]"]
+    );
+}
+
+#[test]
+fn test_placeholders() {
+    use crate::standard::pretty_testing::assert_pp;
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::notation_constructors::{child, lit, nl, placeholder, style, text};
+
+    static FILE_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("// ") + placeholder("lines") + lit(" lines, saved ") + placeholder("time"))
+            .validate()
+            .unwrap()
+    });
+    static LINE_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (style("gutter", placeholder("num")) + lit(" ") + text())
+            .validate()
+            .unwrap()
+    });
+    static BODY_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| (child(0) + nl() + child(1)).validate().unwrap());
+
+    let line = |num: &str, s: &str| {
+        Tree::new_text(&LINE_NOTATION, s.to_owned()).with_placeholder("num", num)
+    };
+    let header: Tree<BasicStyle> = Tree::new_branch(&FILE_NOTATION, vec![])
+        .with_placeholder("lines", "2")
+        .with_placeholder("time", "12:00");
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &BODY_NOTATION,
+        vec![
+            header,
+            Tree::new_branch(&BODY_NOTATION, vec![line("1", "foo"), line("2", "bar")]),
+        ],
+    );
+    assert_pp(&doc, 80, &["// 2 lines, saved 12:00", "1 foo", "2 bar"]);
+
+    // Labels without an entry display nothing.
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &BODY_NOTATION,
+        vec![
+            line("1", "foo"),
+            Tree::new_text(&LINE_NOTATION, "bar".to_owned()),
+        ],
+    );
+    assert_pp(&doc, 80, &["1 foo", " bar"]);
+}
+
+#[test]
+fn test_map_text() {
+    use crate::standard::pretty_testing::assert_pp;
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::notation_constructors::{child, lit, map_text, nl, text};
+
+    static CONST_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("const ") + map_text("upper", child(0)) + lit(" = ") + child(1))
+            .validate()
+            .unwrap()
+    });
+    static NAME_NOTATION: Lazy<TreeNotation> = Lazy::new(|| text().validate().unwrap());
+    static BODY_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| (child(0) + nl() + child(1)).validate().unwrap());
+
+    let name = |s: &str| {
+        Tree::new_text(&NAME_NOTATION, s.to_owned())
+            .with_text_transform("upper", |text| text.to_uppercase())
+    };
+    let constant = |n: &str, v: &str| Tree::new_branch(&CONST_NOTATION, vec![name(n), name(v)]);
+    let doc: Tree<BasicStyle> = Tree::new_branch(
+        &BODY_NOTATION,
+        vec![
+            constant("max_len", "max_width"),
+            Tree::new_branch(
+                &CONST_NOTATION,
+                vec![
+                    Tree::new_text(&NAME_NOTATION, "min_len".to_owned()),
+                    name("0"),
+                ],
+            ),
+        ],
+    );
+    // Only text inside the `MapText` is transformed. Text without an entry is unchanged.
+    assert_pp(
+        &doc,
+        80,
+        &["const MAX_LEN = max_width", "const min_len = 0"],
+    );
+}
+
+#[test]
+fn test_indent_styles_by_depth() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::notation_constructors::{child, indent, lit, nl};
+
+    static NEST_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (lit("nest") + indent("| ", Some("guide"), nl() + child(0)))
+            .validate()
+            .unwrap()
+    });
+    static LEAF_NOTATION: Lazy<TreeNotation> = Lazy::new(|| lit("leaf").validate().unwrap());
+
+    let nest = |child: Tree<BasicStyle>| {
+        Tree::new_branch(&NEST_NOTATION, vec![child]).with_indent_styles(
+            "guide",
+            vec![
+                BasicStyle::new().color(Color::Red),
+                BasicStyle::new().color(Color::Green),
+            ],
+        )
+    };
+    let doc = nest(nest(nest(Tree::new_branch(&LEAF_NOTATION, vec![]))));
+    let rich_text = print(&doc, 80, BasicStyle::new());
+    assert_str_eq(
+        "nest\n| nest\n| | nest\n| | | leaf",
+        &rich_text.display_text(),
+    );
+    assert_str_eq(
+        "wwww\nrrwwww\nrrggwwww\nrrggrrwwww",
+        &rich_text.display_styles(),
+    );
+}
+
+#[test]
+fn test_depth_styles() {
+    use once_cell::sync::Lazy;
+    use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+    use partial_pretty_printer::notation_constructors::{child, depth_style, lit, style};
+
+    static PAREN_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        (depth_style("paren", lit("(")) + child(0) + depth_style("paren", lit(")")))
+            .validate()
+            .unwrap()
+    });
+    static LEAF_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| style("leaf", lit("x")).validate().unwrap());
+
+    let paren = |child: Tree<BasicStyle>| {
+        Tree::new_branch(&PAREN_NOTATION, vec![child]).with_depth_styles(
+            "paren",
+            vec![
+                BasicStyle::new().color(Color::Red),
+                BasicStyle::new().color(Color::Green),
+                BasicStyle::new().color(Color::Blue),
+            ],
+        )
+    };
+    let leaf = Tree::new_branch(&LEAF_NOTATION, vec![])
+        .with_style_override("leaf", BasicStyle::new().color(Color::Yellow));
+    let doc = paren(paren(paren(paren(leaf))));
+    let rich_text = print(&doc, 80, BasicStyle::new());
+    assert_str_eq("((((x))))", &rich_text.display_text());
+    assert_str_eq("rgbryrbgr", &rich_text.display_styles());
+}
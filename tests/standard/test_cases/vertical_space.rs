@@ -0,0 +1,68 @@
+use crate::standard::pretty_testing::{assert_pp, SimpleDoc};
+use once_cell::sync::Lazy;
+use partial_pretty_printer::doc_examples::tree::{Tree, TreeNotation};
+use partial_pretty_printer::doc_examples::BasicStyle;
+use partial_pretty_printer::notation_constructors::{
+    cache, child, fold, left, lit, right, text, vertical_space, Fold,
+};
+
+#[test]
+fn test_vertical_space_alone() {
+    let notation = lit("a") + vertical_space(1, 3) + lit("b") + vertical_space(0, 3) + lit("c");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a", "", "b", "c"]);
+}
+
+#[test]
+fn test_vertical_space_collapses() {
+    let notation = lit("a") + vertical_space(1, 3) + vertical_space(2, 3) + lit("b");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a", "", "", "b"]);
+
+    let notation = lit("a") + vertical_space(1, 1) + vertical_space(3, 5) + lit("b");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a", "", "b"]);
+}
+
+#[test]
+fn test_vertical_space_not_across_cache() {
+    let notation =
+        lit("a") + vertical_space(1, 1) + cache(vertical_space(1, 1) + lit("b")) + lit("c");
+    assert_pp(&SimpleDoc::new(notation), 80, &["a", "", "", "", "bc"]);
+}
+
+#[test]
+fn test_vertical_space_between_children() {
+    static SEQ_NOTATION: Lazy<TreeNotation> = Lazy::new(|| {
+        fold(Fold {
+            first: child(0),
+            join: left() + vertical_space(0, 1) + right(),
+        })
+        .validate()
+        .unwrap()
+    });
+    static FUNC_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| (lit("fn ") + text()).validate().unwrap());
+    static BLANK_NOTATION: Lazy<TreeNotation> =
+        Lazy::new(|| vertical_space(1, 2).validate().unwrap());
+
+    let func = |name: &str| Tree::<BasicStyle>::new_text(&FUNC_NOTATION, name.to_owned());
+    let blank = || Tree::new_text(&BLANK_NOTATION, String::new());
+    assert_pp(
+        &Tree::new_branch(
+            &SEQ_NOTATION,
+            vec![
+                func("a"),
+                blank(),
+                blank(),
+                blank(),
+                func("b"),
+                func("c"),
+                blank(),
+                func("d"),
+            ],
+        ),
+        80,
+        &[
+            // force rustfmt
+            "fn a", "", "fn b", "fn c", "", "fn d",
+        ],
+    );
+}
@@ -0,0 +1,102 @@
+use crate::standard::pretty_testing::assert_pp;
+use partial_pretty_printer::doc_examples::xml::{
+    xml_attribute, xml_element, xml_text, AttributeLayout, Xml,
+};
+
+fn link(layout: AttributeLayout) -> Xml {
+    xml_element(
+        "a",
+        layout,
+        vec![
+            xml_attribute("href", "https://example.com"),
+            xml_attribute("title", "Example"),
+        ],
+        vec![xml_text("click")],
+    )
+}
+
+#[test]
+fn xml_self_closing() {
+    let doc = xml_element("br", AttributeLayout::Inline, vec![], vec![]);
+    assert_pp(&doc, 80, &["<br/>"]);
+
+    let doc = xml_element(
+        "img",
+        AttributeLayout::Inline,
+        vec![xml_attribute("alt", "\"Tom & Jerry\"")],
+        vec![],
+    );
+    assert_pp(&doc, 80, &[r#"<img alt="&quot;Tom &amp; Jerry&quot;"/>"#]);
+}
+
+#[test]
+fn xml_attribute_layouts() {
+    let all_inline = r#"<a href="https://example.com" title="Example">click</a>"#;
+    for layout in [
+        AttributeLayout::Inline,
+        AttributeLayout::Aligned,
+        AttributeLayout::Hanging,
+    ] {
+        assert_pp(&link(layout), 80, &[all_inline]);
+    }
+
+    assert_pp(
+        &link(AttributeLayout::Inline),
+        40,
+        &[
+            r#"<a href="https://example.com" title="Example">"#,
+            "    click",
+            "</a>",
+        ],
+    );
+    assert_pp(
+        &link(AttributeLayout::Aligned),
+        40,
+        &[
+            r#"<a href="https://example.com""#,
+            r#"   title="Example">click</a>"#,
+        ],
+    );
+    assert_pp(
+        &link(AttributeLayout::Hanging),
+        40,
+        &[
+            r#"<a href="https://example.com""#,
+            r#"    title="Example">click</a>"#,
+        ],
+    );
+}
+
+#[test]
+fn xml_text_flow() {
+    let doc = xml_element(
+        "body",
+        AttributeLayout::Aligned,
+        vec![xml_attribute("class", "main")],
+        vec![
+            xml_element(
+                "p",
+                AttributeLayout::Inline,
+                vec![],
+                vec![xml_text("The quick brown fox jumps over the lazy dog.")],
+            ),
+            xml_element("hr", AttributeLayout::Inline, vec![], vec![]),
+            xml_text("1 < 2"),
+        ],
+    );
+    assert_pp(
+        &doc,
+        24,
+        &[
+            r#"<body class="main">"#,
+            "    <p>",
+            "        The quick brown",
+            "        fox jumps over",
+            "        the lazy dog.",
+            "    </p>",
+            "    <hr/>",
+            "    1 &lt; 2",
+            "</body>",
+        ],
+    );
+}